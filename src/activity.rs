@@ -0,0 +1,229 @@
+use crate::at_slot::SIGNATURES_PAGE_SIZE;
+use crate::config::TokenInfo;
+use crate::diff::SOL_ASSET;
+use crate::fetcher::BalanceFetcher;
+use crate::token_account::{SOL_DECIMALS, TOKEN_PROGRAM_IDS};
+use chrono::{DateTime, Utc};
+use futures::future::try_join_all;
+use serde::{Deserialize, Serialize};
+use solana_client::rpc_client::GetConfirmedSignaturesForAddress2Config;
+use solana_client::rpc_request::TokenAccountsFilter;
+use solana_sdk::clock::Slot;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::Signature;
+use solana_transaction_status_client_types::UiTransactionTokenBalance;
+use std::collections::{BTreeMap, HashMap};
+use std::str::FromStr;
+
+/// A transaction involving a wallet or one of its token accounts.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WalletTransaction {
+    pub signature: String,
+    pub slot: Slot,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub block_time: Option<DateTime<Utc>>,
+    /// Why the transaction failed; failed transactions still pay fees.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// What the transaction moved in and out of the wallet, when transfers
+    /// were decoded.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub transfers: Vec<Transfer>,
+}
+
+impl WalletTransaction {
+    /// Whether the decoded transfers include `asset`.
+    pub fn moves(&self, asset: &str) -> bool {
+        self.transfers
+            .iter()
+            .any(|transfer| transfer.asset == asset)
+    }
+}
+
+/// The net change of one of a wallet's balances in a transaction, positive
+/// for incoming. SOL changes include the fee when the wallet paid it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Transfer {
+    pub asset: String,
+    /// The token mint; `None` for SOL.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mint: Option<String>,
+    pub amount: f64,
+}
+
+impl std::fmt::Display for Transfer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:+} {}", self.amount, self.asset)
+    }
+}
+
+impl BalanceFetcher {
+    /// The latest transactions involving `wallet` or any of `accounts`,
+    /// newest first and at most `limit` of them, leaving out those at or
+    /// before `after_slot`. With `decode_transfers` each transaction is also
+    /// fetched to work out what it moved in and out of the wallet.
+    ///
+    /// Token transfers only mention the token account, not its owner, so
+    /// `accounts` should list the wallet's token accounts of interest.
+    pub async fn get_wallet_transactions(
+        &self,
+        wallet: &Pubkey,
+        accounts: &[Pubkey],
+        after_slot: Option<Slot>,
+        limit: usize,
+        decode_transfers: bool,
+        known_tokens: &[TokenInfo],
+    ) -> Result<Vec<WalletTransaction>, anyhow::Error> {
+        let commitment = self.history_commitment();
+        let mut addresses = vec![*wallet];
+        addresses.extend(accounts.iter().filter(|account| *account != wallet));
+        let pages = try_join_all(addresses.iter().map(|address| async move {
+            let _permit = self.permit().await?;
+            Ok::<_, anyhow::Error>(
+                self.client
+                    .get_signatures_for_address_with_config(
+                        address,
+                        GetConfirmedSignaturesForAddress2Config {
+                            before: None,
+                            until: None,
+                            limit: Some(limit.min(SIGNATURES_PAGE_SIZE)),
+                            commitment: Some(commitment),
+                        },
+                    )
+                    .await?,
+            )
+        }))
+        .await?;
+
+        let mut statuses = BTreeMap::new();
+        for status in pages.into_iter().flatten() {
+            if after_slot.is_some_and(|after| status.slot <= after) {
+                continue;
+            }
+            statuses
+                .entry((std::cmp::Reverse(status.slot), status.signature.clone()))
+                .or_insert(status);
+        }
+
+        let mut transactions: Vec<WalletTransaction> = statuses
+            .into_values()
+            .take(limit)
+            .map(|status| WalletTransaction {
+                signature: status.signature,
+                slot: status.slot,
+                block_time: status
+                    .block_time
+                    .and_then(|time| DateTime::from_timestamp(time, 0)),
+                error: status.err.map(|err| err.to_string()),
+                transfers: Vec::new(),
+            })
+            .collect();
+        if decode_transfers {
+            let transfers = try_join_all(
+                transactions
+                    .iter()
+                    .map(|transaction| self.get_transfers(wallet, &transaction.signature)),
+            )
+            .await?;
+            let mints: Vec<Pubkey> = transfers
+                .iter()
+                .flat_map(|transfers| transfers.keys().flatten().copied())
+                .collect();
+            let labels = self.resolve_tickers(mints.iter(), known_tokens).await?;
+            for (transaction, transfers) in transactions.iter_mut().zip(transfers) {
+                transaction.transfers = transfers
+                    .into_iter()
+                    .map(|(mint, amount)| match mint {
+                        Some(mint) => Transfer {
+                            asset: labels[&mint].ticker.clone(),
+                            mint: Some(mint.to_string()),
+                            amount,
+                        },
+                        None => Transfer {
+                            asset: SOL_ASSET.to_string(),
+                            mint: None,
+                            amount,
+                        },
+                    })
+                    .collect();
+            }
+        }
+        Ok(transactions)
+    }
+
+    /// The latest `limit` transactions involving `wallet` or the token
+    /// accounts it holds now, with what each moved in and out of it.
+    pub async fn get_recent_transactions(
+        &self,
+        wallet: &Pubkey,
+        limit: usize,
+        known_tokens: &[TokenInfo],
+    ) -> Result<Vec<WalletTransaction>, anyhow::Error> {
+        let token_accounts = try_join_all(TOKEN_PROGRAM_IDS.iter().map(|program_id| {
+            self.get_token_accounts(wallet, TokenAccountsFilter::ProgramId(*program_id), None)
+        }))
+        .await?
+        .concat();
+        let accounts = token_accounts
+            .iter()
+            .map(|account| Pubkey::from_str(&account.pubkey))
+            .collect::<Result<Vec<_>, _>>()?;
+        self.get_wallet_transactions(wallet, &accounts, None, limit, true, known_tokens)
+            .await
+    }
+
+    /// The wallet's nonzero balance changes in transaction `signature`, by
+    /// mint (`None` for SOL), from the transaction's pre and post balances.
+    async fn get_transfers(
+        &self,
+        wallet: &Pubkey,
+        signature: &str,
+    ) -> Result<BTreeMap<Option<Pubkey>, f64>, anyhow::Error> {
+        let (keys, meta) = self
+            .get_transaction_meta(&Signature::from_str(signature)?)
+            .await?;
+        let mut transfers = BTreeMap::new();
+        if let Some(index) = keys.iter().position(|key| key == wallet) {
+            let pre = meta.pre_balances.get(index).copied().unwrap_or_default();
+            let post = meta.post_balances.get(index).copied().unwrap_or_default();
+            if pre != post {
+                transfers.insert(None, signed_ui_amount(pre, post, SOL_DECIMALS));
+            }
+        }
+
+        // Owned token amounts per mint before and after, with decimals.
+        let mut tokens: HashMap<Pubkey, (u64, u64, u8)> = HashMap::new();
+        let owner = wallet.to_string();
+        let pre: Option<Vec<UiTransactionTokenBalance>> = meta.pre_token_balances.into();
+        let post: Option<Vec<UiTransactionTokenBalance>> = meta.post_token_balances.into();
+        for (balances, after) in [(pre, false), (post, true)] {
+            for balance in balances.unwrap_or_default() {
+                let balance_owner: Option<String> = balance.owner.into();
+                if balance_owner.as_deref() != Some(owner.as_str()) {
+                    continue;
+                }
+                let amount: u64 = balance.ui_token_amount.amount.parse()?;
+                let entry = tokens.entry(Pubkey::from_str(&balance.mint)?).or_insert((
+                    0,
+                    0,
+                    balance.ui_token_amount.decimals,
+                ));
+                if after {
+                    entry.1 = entry.1.saturating_add(amount);
+                } else {
+                    entry.0 = entry.0.saturating_add(amount);
+                }
+            }
+        }
+        for (mint, (pre, post, decimals)) in tokens {
+            if pre != post {
+                transfers.insert(Some(mint), signed_ui_amount(pre, post, decimals));
+            }
+        }
+        Ok(transfers)
+    }
+}
+
+fn signed_ui_amount(pre: u64, post: u64, decimals: u8) -> f64 {
+    (post as f64 - pre as f64) / 10f64.powi(i32::from(decimals))
+}
@@ -0,0 +1,105 @@
+use crate::config::TokenConfig;
+use crate::diff::SOL_ASSET;
+use crate::report::BalanceReport;
+use serde::Serialize;
+use std::fmt;
+
+/// A balance that dropped below its configured threshold.
+#[derive(Debug, Clone, Serialize)]
+pub struct Alert {
+    pub wallet: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub label: Option<String>,
+    pub asset: String,
+    pub balance: f64,
+    pub threshold: f64,
+}
+
+impl fmt::Display for Alert {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.label {
+            Some(label) => write!(f, "{} ({})", label, self.wallet)?,
+            None => write!(f, "{}", self.wallet)?,
+        }
+        write!(
+            f,
+            ": {} balance {} is below {}",
+            self.asset, self.balance, self.threshold
+        )
+    }
+}
+
+/// Compares every fetched wallet against the thresholds in `config`.
+/// Wallet-specific thresholds, keyed by address, `.sol` domain or label, take
+/// precedence over the global ones; wallets that failed to fetch are not
+/// checked.
+pub fn check_thresholds(config: &TokenConfig, report: &BalanceReport) -> Vec<Alert> {
+    let mut wallets: Vec<&String> = report.wallets.keys().collect();
+    wallets.sort();
+
+    let mut alerts = Vec::new();
+    for wallet in wallets {
+        let result = &report.wallets[wallet];
+        let overrides = [Some(wallet), result.domain.as_ref(), result.label.as_ref()]
+            .into_iter()
+            .flatten()
+            .find_map(|name| config.wallet_thresholds.get(name));
+
+        let min_sol = overrides
+            .and_then(|thresholds| thresholds.min_sol)
+            .or(config.min_sol);
+        if let Some(threshold) = min_sol {
+            if result.sol_balance < threshold {
+                alerts.push(Alert {
+                    wallet: wallet.clone(),
+                    label: result.label.clone(),
+                    asset: SOL_ASSET.to_string(),
+                    balance: result.sol_balance,
+                    threshold,
+                });
+            }
+        }
+
+        let tokens = config
+            .wallets
+            .iter()
+            .find(|info| &info.address == wallet || result.domain.as_ref() == Some(&info.address))
+            .map_or_else(|| config.tokens.clone(), |info| config.tokens_for(info));
+        for token in &tokens {
+            let reported = result
+                .token_balances
+                .iter()
+                .find(|(_, balance)| balance.mint == token.address);
+            let asset = reported
+                .map(|(ticker, _)| ticker.as_str())
+                .or(token.ticker.as_deref())
+                .unwrap_or(&token.address);
+
+            // Per-wallet overrides may name the token by ticker or mint.
+            let threshold = overrides
+                .and_then(|thresholds| {
+                    thresholds
+                        .min_balance
+                        .get(asset)
+                        .or_else(|| thresholds.min_balance.get(&token.address))
+                })
+                .copied()
+                .or(token.min_balance);
+            let Some(threshold) = threshold else {
+                continue;
+            };
+            let balance = reported.map_or(0.0, |(_, balance)| balance.amount);
+            if balance < threshold {
+                alerts.push(Alert {
+                    wallet: wallet.clone(),
+                    label: result.label.clone(),
+                    asset: asset.to_string(),
+                    balance,
+                    threshold,
+                });
+            }
+        }
+    }
+
+    alerts
+}
@@ -0,0 +1,384 @@
+use crate::config::{TokenConfig, TokenInfo, WalletInfo};
+use crate::error::BalanceError;
+use crate::fetcher::{before_deadline, BalanceFetcher};
+use crate::group::summarize_groups;
+use crate::report::{BalanceReport, BalanceResult, TokenBalance};
+use crate::sns::is_sol_domain;
+use crate::token_account::{associated_token_address, TOKEN_PROGRAM_IDS};
+use chrono::{DateTime, Utc};
+use futures::future::{join_all, try_join_all};
+use solana_client::rpc_client::GetConfirmedSignaturesForAddress2Config;
+use solana_client::rpc_config::RpcTransactionConfig;
+use solana_client::rpc_request::TokenAccountsFilter;
+use solana_sdk::clock::Slot;
+use solana_sdk::commitment_config::CommitmentConfig;
+use solana_sdk::native_token::lamports_to_sol;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::Signature;
+use solana_transaction_status_client_types::option_serializer::OptionSerializer;
+use solana_transaction_status_client_types::{
+    UiTransactionEncoding, UiTransactionStatusMeta, UiTransactionTokenBalance,
+};
+use std::collections::btree_map::Entry;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::str::FromStr;
+use std::time::Instant;
+
+/// Most signatures `getSignaturesForAddress` returns per call.
+pub(crate) const SIGNATURES_PAGE_SIZE: usize = 1000;
+
+impl BalanceFetcher {
+    /// Reconstructs balances as they were at the end of `slot` from each
+    /// account's last transaction at or before it. This needs an RPC node
+    /// that keeps the full transaction history.
+    ///
+    /// Token accounts are those the wallet holds now plus the associated
+    /// token accounts of configured mints, so tokens kept in other accounts
+    /// that have since been closed are missed. Stake and USD values are not
+    /// reported, as neither can be read back in time.
+    pub async fn fetch_report_at(
+        &self,
+        config: &TokenConfig,
+        slot: Slot,
+    ) -> Result<BalanceReport, BalanceError> {
+        let started = Instant::now();
+        let first_available = async {
+            let _permit = self.permit().await?;
+            Ok::<_, anyhow::Error>(self.client.get_first_available_block().await?)
+        }
+        .await
+        .map_err(|err| BalanceError::rpc(None, err))?;
+        if slot < first_available {
+            return Err(BalanceError::rpc(
+                None,
+                anyhow::anyhow!(
+                    "the RPC node has no blocks before slot {}; querying older slots needs an archival node",
+                    first_available
+                ),
+            ));
+        }
+
+        let results = join_all(config.wallets.iter().map(|wallet| {
+            before_deadline(
+                config,
+                started,
+                self.get_wallet_balance_at(wallet, config, slot),
+            )
+        }))
+        .await;
+
+        let mut report = BalanceReport::new(HashMap::new());
+        report.slot = Some(slot);
+        report.historical = true;
+        for (wallet, result) in config.wallets.iter().zip(results) {
+            match result {
+                Ok((address, balance)) => {
+                    report.wallet_order.push(address.to_string());
+                    report.wallets.insert(address.to_string(), balance);
+                }
+                Err(err) => {
+                    report
+                        .errors
+                        .insert(wallet.address.clone(), format!("{:#}", err));
+                }
+            }
+        }
+
+        if config.discover_tokens {
+            let configured = config.configured_mints();
+            report.retain_tokens(|ticker, balance| {
+                configured.contains(&balance.mint)
+                    || config
+                        .token_filter
+                        .allows(ticker, balance, self.is_listed(&balance.mint))
+            });
+        }
+        report.retain_tokens(|ticker, balance| config.shows_token(ticker, balance));
+
+        report.groups = summarize_groups(&config.groups, &report);
+        self.save_disk_cache();
+        Ok(report)
+    }
+
+    /// Finds the last block produced at or before `time` by binary search
+    /// over block times.
+    pub async fn slot_at_time(&self, time: DateTime<Utc>) -> Result<Slot, anyhow::Error> {
+        let target = time.timestamp();
+        let (mut low, mut high) = {
+            let _permit = self.permit().await?;
+            (
+                self.client.get_first_available_block().await?,
+                self.client.get_slot().await?,
+            )
+        };
+
+        let mut found = None;
+        while low <= high {
+            let middle = low + (high - low) / 2;
+            // Skipped slots have no block time; probe the next produced block.
+            let block = {
+                let _permit = self.permit().await?;
+                self.client.get_blocks_with_limit(middle, 1).await?
+            };
+            let block_time = match block.first() {
+                Some(block) if *block <= high => {
+                    let _permit = self.permit().await?;
+                    Some((*block, self.client.get_block_time(*block).await?))
+                }
+                _ => None,
+            };
+            match block_time {
+                Some((block, block_time)) if block_time <= target => {
+                    found = Some(block);
+                    low = block + 1;
+                }
+                _ => match middle.checked_sub(1) {
+                    Some(below) => high = below,
+                    None => break,
+                },
+            }
+        }
+
+        found.ok_or_else(|| anyhow::anyhow!("no block found at or before {}", time))
+    }
+
+    async fn get_wallet_balance_at(
+        &self,
+        wallet: &WalletInfo,
+        config: &TokenConfig,
+        slot: Slot,
+    ) -> Result<(Pubkey, BalanceResult), anyhow::Error> {
+        let wallet_str = wallet.address.as_str();
+        let wallet_pubkey = self.resolve_wallet(wallet_str).await?;
+        let tokens = config.tokens_for(wallet);
+
+        let (lamports, mut token_balances) = futures::try_join!(
+            self.lamports_at(&wallet_pubkey, slot),
+            self.token_balances_at(&wallet_pubkey, &tokens, config.discover_tokens, slot)
+        )?;
+        token_balances.retain(|ticker, balance| !wallet.tokens.excludes(ticker, &balance.mint));
+
+        let result = BalanceResult {
+            label: wallet.label.clone(),
+            domain: is_sol_domain(wallet_str).then(|| wallet_str.to_string()),
+            multisig: wallet.multisig.clone(),
+            realm: wallet.realm.clone(),
+            cluster: wallet.cluster.clone(),
+            sol_balance: lamports_to_sol(lamports),
+            sol_lamports: lamports,
+            sol_usd_value: None,
+            wrapped_sol: None,
+            rent: None,
+            stake: None,
+            nfts: None,
+            vesting: None,
+            token_balances,
+            total_usd: None,
+        };
+        Ok((wallet_pubkey, result))
+    }
+
+    async fn lamports_at(&self, address: &Pubkey, slot: Slot) -> Result<u64, anyhow::Error> {
+        let Some((keys, meta)) = self.last_transaction_at(address, slot).await? else {
+            return Ok(0);
+        };
+        Ok(keys
+            .iter()
+            .position(|key| key == address)
+            .and_then(|index| meta.post_balances.get(index))
+            .copied()
+            .unwrap_or(0))
+    }
+
+    async fn token_balances_at(
+        &self,
+        wallet_pubkey: &Pubkey,
+        tokens: &[TokenInfo],
+        discover_tokens: bool,
+        slot: Slot,
+    ) -> Result<HashMap<String, TokenBalance>, anyhow::Error> {
+        let mints = tokens
+            .iter()
+            .map(TokenInfo::mint)
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let filters: Vec<TokenAccountsFilter> = if discover_tokens {
+            TOKEN_PROGRAM_IDS
+                .iter()
+                .map(|program_id| TokenAccountsFilter::ProgramId(*program_id))
+                .collect()
+        } else {
+            mints
+                .iter()
+                .map(|mint| TokenAccountsFilter::Mint(*mint))
+                .collect()
+        };
+        let current = try_join_all(
+            filters
+                .into_iter()
+                .map(|filter| self.get_token_accounts(wallet_pubkey, filter, None)),
+        )
+        .await?
+        .concat();
+
+        let mut accounts = HashSet::new();
+        for account in current {
+            accounts.insert(Pubkey::from_str(&account.pubkey)?);
+        }
+        for mint in &mints {
+            for program_id in &TOKEN_PROGRAM_IDS {
+                accounts.insert(associated_token_address(wallet_pubkey, program_id, mint));
+            }
+        }
+
+        let amounts = try_join_all(
+            accounts
+                .iter()
+                .map(|account| self.token_amount_at(account, wallet_pubkey, slot)),
+        )
+        .await?;
+
+        let mut by_mint = BTreeMap::new();
+        for (mint, amount, decimals) in amounts.into_iter().flatten() {
+            if !discover_tokens && !mints.contains(&mint) {
+                continue;
+            }
+            let total = by_mint.entry(mint).or_insert((0u64, decimals));
+            total.0 = total.0.saturating_add(amount);
+        }
+        for mint in &mints {
+            if let Entry::Vacant(entry) = by_mint.entry(*mint) {
+                entry.insert((0, self.decimals_or_zero(mint).await?));
+            }
+        }
+
+        self.label_balances(by_mint, tokens).await
+    }
+
+    /// The mint, amount and decimals of token account `account` after its
+    /// last transaction at or before `slot`, if `owner` owned it then.
+    async fn token_amount_at(
+        &self,
+        account: &Pubkey,
+        owner: &Pubkey,
+        slot: Slot,
+    ) -> Result<Option<(Pubkey, u64, u8)>, anyhow::Error> {
+        let Some((keys, meta)) = self.last_transaction_at(account, slot).await? else {
+            return Ok(None);
+        };
+        let Some(index) = keys.iter().position(|key| key == account) else {
+            return Ok(None);
+        };
+
+        // Accounts closed by the transaction have no post balance.
+        let balances: Option<Vec<UiTransactionTokenBalance>> = meta.post_token_balances.into();
+        let Some(balance) = balances
+            .unwrap_or_default()
+            .into_iter()
+            .find(|balance| usize::from(balance.account_index) == index)
+        else {
+            return Ok(None);
+        };
+        let owner_then: Option<String> = balance.owner.into();
+        if owner_then.is_some_and(|owner_then| owner_then != owner.to_string()) {
+            return Ok(None);
+        }
+
+        Ok(Some((
+            Pubkey::from_str(&balance.mint)?,
+            balance.ui_token_amount.amount.parse()?,
+            balance.ui_token_amount.decimals,
+        )))
+    }
+
+    /// The account keys and status of the last transaction involving
+    /// `address` at or before `slot`.
+    async fn last_transaction_at(
+        &self,
+        address: &Pubkey,
+        slot: Slot,
+    ) -> Result<Option<(Vec<Pubkey>, UiTransactionStatusMeta)>, anyhow::Error> {
+        let commitment = self.history_commitment();
+
+        let mut before = None;
+        let signature = loop {
+            let page = {
+                let _permit = self.permit().await?;
+                self.client
+                    .get_signatures_for_address_with_config(
+                        address,
+                        GetConfirmedSignaturesForAddress2Config {
+                            before,
+                            until: None,
+                            limit: Some(SIGNATURES_PAGE_SIZE),
+                            commitment: Some(commitment),
+                        },
+                    )
+                    .await?
+            };
+            // Signatures come newest first.
+            if let Some(status) = page.iter().find(|status| status.slot <= slot) {
+                break Signature::from_str(&status.signature)?;
+            }
+            match page.last() {
+                Some(oldest) if page.len() == SIGNATURES_PAGE_SIZE => {
+                    before = Some(Signature::from_str(&oldest.signature)?);
+                }
+                _ => return Ok(None),
+            }
+        };
+
+        Ok(Some(self.get_transaction_meta(&signature).await?))
+    }
+
+    /// The account keys and status metadata of transaction `signature`.
+    pub(crate) async fn get_transaction_meta(
+        &self,
+        signature: &Signature,
+    ) -> Result<(Vec<Pubkey>, UiTransactionStatusMeta), anyhow::Error> {
+        let commitment = self.history_commitment();
+        let transaction = {
+            let _permit = self.permit().await?;
+            self.client
+                .get_transaction_with_config(
+                    signature,
+                    RpcTransactionConfig {
+                        encoding: Some(UiTransactionEncoding::Base64),
+                        commitment: Some(commitment),
+                        max_supported_transaction_version: Some(0),
+                    },
+                )
+                .await?
+        };
+        let meta = transaction
+            .transaction
+            .meta
+            .ok_or_else(|| anyhow::anyhow!("transaction {} has no status metadata", signature))?;
+        let decoded = transaction
+            .transaction
+            .transaction
+            .decode()
+            .ok_or_else(|| anyhow::anyhow!("could not decode transaction {}", signature))?;
+
+        // Balances are indexed over the static keys followed by the keys
+        // loaded from address lookup tables.
+        let mut keys = decoded.message.static_account_keys().to_vec();
+        if let OptionSerializer::Some(loaded) = &meta.loaded_addresses {
+            for address in loaded.writable.iter().chain(&loaded.readonly) {
+                keys.push(Pubkey::from_str(address)?);
+            }
+        }
+
+        Ok((keys, meta))
+    }
+
+    /// Transaction history is not served at `processed` commitment.
+    pub(crate) fn history_commitment(&self) -> CommitmentConfig {
+        let commitment = self.client.commitment();
+        if commitment.is_at_least_confirmed() {
+            commitment
+        } else {
+            CommitmentConfig::confirmed()
+        }
+    }
+}
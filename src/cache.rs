@@ -0,0 +1,202 @@
+use crate::metadata::TokenMetadata;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use solana_sdk::pubkey::Pubkey;
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct CacheConfig {
+    /// Keep mint decimals, token metadata and resolved domains on disk
+    /// between runs.
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+    /// Where cache files are kept [default: the user cache directory].
+    #[serde(default)]
+    pub dir: Option<PathBuf>,
+    /// How long a cached value is used before it is fetched again.
+    #[serde(default = "default_ttl", with = "humantime_serde")]
+    pub ttl: Duration,
+}
+
+impl Default for CacheConfig {
+    fn default() -> Self {
+        CacheConfig {
+            enabled: default_enabled(),
+            dir: None,
+            ttl: default_ttl(),
+        }
+    }
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+fn default_ttl() -> Duration {
+    Duration::from_secs(24 * 60 * 60)
+}
+
+#[derive(Default, Serialize, Deserialize)]
+struct CacheFile {
+    #[serde(default)]
+    decimals: BTreeMap<String, Cached<u8>>,
+    /// `None` for mints without a metadata account.
+    #[serde(default)]
+    metadata: BTreeMap<String, Cached<Option<TokenMetadata>>>,
+    /// `.sol` domains and the wallets they resolved to.
+    #[serde(default)]
+    domains: BTreeMap<String, Cached<String>>,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+struct Cached<T> {
+    value: T,
+    /// Unix time in seconds.
+    cached_at: u64,
+}
+
+/// Mint decimals, token metadata and domain resolutions saved between runs,
+/// in one file per RPC endpoint so clusters don't mix.
+pub struct DiskCache {
+    path: PathBuf,
+    /// The entries read from disk that are younger than the TTL.
+    loaded: CacheFile,
+}
+
+impl DiskCache {
+    /// Reads the cache for `rpc_url`, dropping expired entries. With
+    /// `refresh` nothing is read, so every value is fetched and saved again.
+    /// A missing or unreadable file counts as empty.
+    pub fn open(config: &CacheConfig, rpc_url: &str, refresh: bool) -> Self {
+        let digest = hex::encode(Sha256::digest(rpc_url.as_bytes()));
+        let path = config
+            .dir
+            .clone()
+            .unwrap_or_else(cache_dir)
+            .join(format!("rpc-{}.json", &digest[..16]));
+
+        let mut loaded = CacheFile::default();
+        if !refresh {
+            match fs::read_to_string(&path).map(|content| serde_json::from_str(&content)) {
+                Ok(Ok(file)) => loaded = file,
+                Ok(Err(err)) => {
+                    tracing::debug!(path = %path.display(), %err, "ignoring corrupt cache")
+                }
+                Err(_) => {}
+            }
+        }
+        let oldest = now().saturating_sub(config.ttl.as_secs());
+        loaded.decimals.retain(|_, entry| entry.cached_at >= oldest);
+        loaded.metadata.retain(|_, entry| entry.cached_at >= oldest);
+        loaded.domains.retain(|_, entry| entry.cached_at >= oldest);
+        DiskCache { path, loaded }
+    }
+
+    pub(crate) fn decimals(&self) -> HashMap<Pubkey, u8> {
+        parse_keys(&self.loaded.decimals)
+    }
+
+    pub(crate) fn metadata(&self) -> HashMap<Pubkey, Option<TokenMetadata>> {
+        parse_keys(&self.loaded.metadata)
+    }
+
+    pub(crate) fn domains(&self) -> HashMap<String, Pubkey> {
+        self.loaded
+            .domains
+            .iter()
+            .filter_map(|(domain, entry)| {
+                Some((domain.clone(), Pubkey::from_str(&entry.value).ok()?))
+            })
+            .collect()
+    }
+
+    /// Writes the given values, keeping the age of those that were read from
+    /// disk. Nothing is written when there is nothing new.
+    pub(crate) fn save(
+        &self,
+        decimals: &HashMap<Pubkey, u8>,
+        metadata: &HashMap<Pubkey, Option<TokenMetadata>>,
+        domains: &HashMap<String, Pubkey>,
+    ) -> Result<(), anyhow::Error> {
+        let now = now();
+        let mut fresh = false;
+        let mut entry = |cached: Option<u64>| {
+            fresh |= cached.is_none();
+            cached.unwrap_or(now)
+        };
+        let mut file = CacheFile::default();
+        for (mint, value) in decimals {
+            let key = mint.to_string();
+            let cached_at = entry(self.loaded.decimals.get(&key).map(|e| e.cached_at));
+            file.decimals.insert(
+                key,
+                Cached {
+                    value: *value,
+                    cached_at,
+                },
+            );
+        }
+        for (mint, value) in metadata {
+            let key = mint.to_string();
+            let cached_at = entry(self.loaded.metadata.get(&key).map(|e| e.cached_at));
+            file.metadata.insert(
+                key,
+                Cached {
+                    value: value.clone(),
+                    cached_at,
+                },
+            );
+        }
+        for (domain, owner) in domains {
+            let cached_at = entry(self.loaded.domains.get(domain).map(|e| e.cached_at));
+            file.domains.insert(
+                domain.clone(),
+                Cached {
+                    value: owner.to_string(),
+                    cached_at,
+                },
+            );
+        }
+        if !fresh {
+            return Ok(());
+        }
+
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        // Write a sibling and rename it, so concurrent runs never read half
+        // a file.
+        let temp = self.path.with_extension("json.tmp");
+        fs::write(&temp, serde_json::to_string(&file)?)
+            .map_err(|err| anyhow::anyhow!("writing {}: {}", temp.display(), err))?;
+        fs::rename(&temp, &self.path)
+            .map_err(|err| anyhow::anyhow!("writing {}: {}", self.path.display(), err))
+    }
+}
+
+fn parse_keys<T: Clone>(entries: &BTreeMap<String, Cached<T>>) -> HashMap<Pubkey, T> {
+    entries
+        .iter()
+        .filter_map(|(key, entry)| Some((Pubkey::from_str(key).ok()?, entry.value.clone())))
+        .collect()
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_secs())
+        .unwrap_or_default()
+}
+
+/// `$XDG_CACHE_HOME/solana-balance`, or `~/.cache/solana-balance`.
+pub(crate) fn cache_dir() -> PathBuf {
+    let cache_dir = std::env::var_os("XDG_CACHE_HOME")
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".cache")))
+        .unwrap_or_default();
+    cache_dir.join("solana-balance")
+}
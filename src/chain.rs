@@ -0,0 +1,219 @@
+use crate::config::TokenInfo;
+use crate::fetcher::BalanceFetcher;
+use crate::nft::ASSETS_PAGE_SIZE;
+use crate::report::TokenBalance;
+use async_trait::async_trait;
+use serde::Deserialize;
+use serde_json::json;
+use solana_sdk::clock::Slot;
+use solana_sdk::pubkey::Pubkey;
+use std::collections::btree_map::Entry;
+use std::collections::{BTreeMap, HashMap};
+use std::str::FromStr;
+
+/// Where wallets' SOL and token balances are read from.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ChainBackend {
+    /// Standard JSON-RPC: `getBalance` plus token account reads.
+    #[default]
+    JsonRpc,
+    /// Helius' DAS API: one `getAssetsByOwner` call per wallet, SOL
+    /// included.
+    Helius,
+    /// Triton's DAS API: `getAssetsByOwner` for tokens, `getBalance` for
+    /// SOL.
+    Triton,
+}
+
+impl ChainBackend {
+    pub fn client(self) -> Box<dyn ChainClient> {
+        match self {
+            ChainBackend::JsonRpc => Box::new(JsonRpcClient),
+            ChainBackend::Helius => Box::new(DasClient {
+                name: "helius",
+                native_balance: true,
+            }),
+            ChainBackend::Triton => Box::new(DasClient {
+                name: "triton",
+                native_balance: false,
+            }),
+        }
+    }
+}
+
+/// What a wallet holds directly: SOL in lamports and its tokens by ticker.
+#[derive(Debug, Clone, Default)]
+pub struct Holdings {
+    pub lamports: u64,
+    pub tokens: HashMap<String, TokenBalance>,
+}
+
+/// Reads one wallet's SOL and token balances. Stake, vesting, rent and NFTs
+/// are read over JSON-RPC whatever the client.
+#[async_trait]
+pub trait ChainClient: Send + Sync {
+    fn name(&self) -> &str;
+
+    /// Whether configured tokens can be read for every wallet at once
+    /// before the per-wallet calls, which then only fetch SOL.
+    fn batches_tokens(&self) -> bool {
+        false
+    }
+
+    /// The balances of `tokens`, or with `discover` of every token the
+    /// wallet holds plus `tokens`, read at `min_context_slot` or later where
+    /// the client can pin reads.
+    async fn holdings(
+        &self,
+        fetcher: &BalanceFetcher,
+        wallet: &Pubkey,
+        tokens: &[TokenInfo],
+        discover: bool,
+        min_context_slot: Option<Slot>,
+    ) -> Result<Holdings, anyhow::Error>;
+}
+
+/// Reads balances with plain JSON-RPC methods any node serves.
+pub struct JsonRpcClient;
+
+#[async_trait]
+impl ChainClient for JsonRpcClient {
+    fn name(&self) -> &str {
+        "json_rpc"
+    }
+
+    fn batches_tokens(&self) -> bool {
+        true
+    }
+
+    async fn holdings(
+        &self,
+        fetcher: &BalanceFetcher,
+        wallet: &Pubkey,
+        tokens: &[TokenInfo],
+        discover: bool,
+        min_context_slot: Option<Slot>,
+    ) -> Result<Holdings, anyhow::Error> {
+        let token_balances = async {
+            if discover {
+                fetcher
+                    .discover_token_balances(wallet, tokens, min_context_slot)
+                    .await
+            } else {
+                fetcher
+                    .get_token_balances(wallet, tokens, min_context_slot)
+                    .await
+            }
+        };
+        let (lamports, tokens) = futures::try_join!(
+            fetcher.get_sol_balance(wallet, min_context_slot),
+            token_balances
+        )?;
+        Ok(Holdings { lamports, tokens })
+    }
+}
+
+/// Reads balances with the DAS `getAssetsByOwner` method of enhanced
+/// providers, a page of up to 1000 assets per call instead of a call per
+/// token program. Frozen amounts and delegations are not reported.
+pub struct DasClient {
+    name: &'static str,
+    /// Whether the provider returns the SOL balance with `showNativeBalance`.
+    native_balance: bool,
+}
+
+#[derive(Deserialize)]
+struct FungiblePage {
+    items: Vec<FungibleAsset>,
+    #[serde(rename = "nativeBalance", default)]
+    native_balance: Option<NativeBalance>,
+}
+
+#[derive(Deserialize)]
+struct FungibleAsset {
+    #[serde(default)]
+    id: String,
+    #[serde(default)]
+    token_info: Option<FungibleInfo>,
+}
+
+#[derive(Deserialize)]
+struct FungibleInfo {
+    #[serde(default)]
+    balance: u64,
+    #[serde(default)]
+    decimals: u8,
+}
+
+#[derive(Deserialize)]
+struct NativeBalance {
+    lamports: u64,
+}
+
+#[async_trait]
+impl ChainClient for DasClient {
+    fn name(&self) -> &str {
+        self.name
+    }
+
+    async fn holdings(
+        &self,
+        fetcher: &BalanceFetcher,
+        wallet: &Pubkey,
+        tokens: &[TokenInfo],
+        discover: bool,
+        min_context_slot: Option<Slot>,
+    ) -> Result<Holdings, anyhow::Error> {
+        let mut lamports = None;
+        let mut by_mint = BTreeMap::new();
+        for page in 1.. {
+            let params = json!({
+                "ownerAddress": wallet.to_string(),
+                "page": page,
+                "limit": ASSETS_PAGE_SIZE,
+                "displayOptions": {
+                    "showFungible": true,
+                    "showNativeBalance": self.native_balance && page == 1,
+                },
+            });
+            let response: FungiblePage = fetcher.get_assets_by_owner(params).await?;
+            if let Some(native) = response.native_balance {
+                lamports = Some(native.lamports);
+            }
+            let full = response.items.len() == ASSETS_PAGE_SIZE;
+            for asset in response.items {
+                let (Some(info), Ok(mint)) = (asset.token_info, Pubkey::from_str(&asset.id)) else {
+                    continue;
+                };
+                let total = by_mint.entry(mint).or_insert((0u64, info.decimals));
+                total.0 = total.0.saturating_add(info.balance);
+            }
+            if !full {
+                break;
+            }
+        }
+
+        let configured = tokens
+            .iter()
+            .map(TokenInfo::mint)
+            .collect::<Result<Vec<_>, _>>()?;
+        if !discover {
+            by_mint.retain(|mint, _| configured.contains(mint));
+        }
+        for mint in configured {
+            if let Entry::Vacant(entry) = by_mint.entry(mint) {
+                entry.insert((0, fetcher.decimals_or_zero(&mint).await?));
+            }
+        }
+
+        let lamports = match lamports {
+            Some(lamports) => lamports,
+            None => fetcher.get_sol_balance(wallet, min_context_slot).await?,
+        };
+        Ok(Holdings {
+            lamports,
+            tokens: fetcher.label_balances(by_mint, tokens).await?,
+        })
+    }
+}
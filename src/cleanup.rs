@@ -0,0 +1,90 @@
+use crate::config::TokenConfig;
+use crate::fetcher::BalanceFetcher;
+use crate::token_account::{TokenAccountDetail, TokenAccountState};
+use futures::future::join_all;
+use serde::Serialize;
+use solana_sdk::native_token::lamports_to_sol;
+use std::collections::BTreeMap;
+
+/// Empty token accounts the wallets could close to reclaim their rent.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct CleanupReport {
+    /// Wallets with at least one closable account, keyed by address.
+    pub wallets: BTreeMap<String, WalletCleanup>,
+    /// SOL returned by closing every listed account.
+    pub recoverable_sol: f64,
+    /// Wallets that could not be fetched, with the reason.
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub errors: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct WalletCleanup {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub label: Option<String>,
+    pub accounts: Vec<TokenAccountDetail>,
+    pub recoverable_sol: f64,
+}
+
+impl CleanupReport {
+    /// Number of closable accounts across all wallets.
+    pub fn accounts(&self) -> usize {
+        self.wallets
+            .values()
+            .map(|wallet| wallet.accounts.len())
+            .sum()
+    }
+}
+
+impl BalanceFetcher {
+    /// Lists each wallet's token accounts holding no tokens. Frozen accounts
+    /// are left out since they cannot be closed until thawed.
+    pub async fn fetch_cleanup_report(
+        &self,
+        config: &TokenConfig,
+    ) -> Result<CleanupReport, anyhow::Error> {
+        let results = join_all(config.wallets.iter().map(|wallet| async {
+            let pubkey = self.resolve_wallet(&wallet.address).await?;
+            let details = self.get_token_account_details(&pubkey, None).await?;
+            Ok::<_, anyhow::Error>((pubkey, details))
+        }))
+        .await;
+
+        let mut report = CleanupReport::default();
+        let mut recoverable = 0u64;
+        for (wallet, result) in config.wallets.iter().zip(results) {
+            let (pubkey, details) = match result {
+                Ok(result) => result,
+                Err(err) => {
+                    report
+                        .errors
+                        .insert(wallet.address.clone(), format!("{:#}", err));
+                    continue;
+                }
+            };
+            let accounts: Vec<TokenAccountDetail> = details
+                .into_iter()
+                .filter(|account| {
+                    account.raw_amount == 0 && account.state != TokenAccountState::Frozen
+                })
+                .collect();
+            if accounts.is_empty() {
+                continue;
+            }
+            let lamports = accounts.iter().fold(0u64, |total, account| {
+                total.saturating_add(account.lamports)
+            });
+            recoverable = recoverable.saturating_add(lamports);
+            report.wallets.insert(
+                pubkey.to_string(),
+                WalletCleanup {
+                    label: wallet.label.clone(),
+                    accounts,
+                    recoverable_sol: lamports_to_sol(lamports),
+                },
+            );
+        }
+        report.recoverable_sol = lamports_to_sol(recoverable);
+        Ok(report)
+    }
+}
@@ -0,0 +1,459 @@
+use crate::logging::LogFormat;
+use chrono::{DateTime, NaiveDate, NaiveTime, Utc};
+use clap::{ArgAction, Args, Parser, Subcommand};
+use solana_sdk::commitment_config::CommitmentLevel;
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+use test_solana::explorer::{Cluster, Explorer};
+use test_solana::output::{Decimals, NumberLocale};
+use test_solana::pricing::Currency;
+use test_solana::rpc::RpcCalls;
+use test_solana::{ConfigFormat, OutputFormat, Schedule, SortOrder, TokenInfo};
+
+#[derive(Debug, Parser)]
+#[command(
+    version,
+    about = "Check SOL and SPL token balances for a set of wallets"
+)]
+pub struct Cli {
+    /// Path to the config file (YAML, TOML or JSON)
+    #[arg(long, global = true, default_value = "config.yaml")]
+    pub config: PathBuf,
+
+    /// Don't read a config file; use defaults plus --wallet and --token
+    #[arg(long, global = true, conflicts_with = "profile")]
+    pub no_config: bool,
+
+    /// Check this wallet too (repeatable); with no config file, only these
+    #[arg(long = "wallet", global = true, value_name = "ADDRESS")]
+    pub wallets: Vec<String>,
+
+    /// Check the wallets listed one per line in this file too, or on stdin
+    /// for `-` (repeatable; blank lines and `#` comments are skipped)
+    #[arg(long, visible_alias = "wallets", global = true, value_name = "PATH")]
+    pub wallets_file: Vec<PathBuf>,
+
+    /// Check this token too, as MINT or MINT:TICKER (repeatable)
+    #[arg(long = "token", global = true, value_name = "MINT[:TICKER]", value_parser = parse_token)]
+    pub tokens: Vec<TokenInfo>,
+
+    /// Use the settings of this entry under `profiles` in the config file
+    #[arg(long, global = true)]
+    pub profile: Option<String>,
+
+    /// Config file format: yaml, toml or json [default: from the file extension]
+    #[arg(long, global = true)]
+    pub config_format: Option<ConfigFormat>,
+
+    /// Override the RPC endpoint from the config file
+    #[arg(long, global = true)]
+    pub rpc_url: Option<String>,
+
+    /// Answer RPC requests from the JSON fixtures in this directory instead
+    /// of the network (DAS and price lookups still go out)
+    #[arg(long, global = true, value_name = "DIR")]
+    pub fixture: Option<PathBuf>,
+
+    /// With --fixture, send requests as usual and save each response there
+    #[arg(long, global = true, requires = "fixture")]
+    pub record: bool,
+
+    /// Override the commitment level: processed, confirmed or finalized
+    #[arg(long, global = true)]
+    pub commitment: Option<CommitmentLevel>,
+
+    /// How long each RPC request may take, e.g. `10s` [default: 30s]
+    #[arg(long, global = true, value_parser = humantime::parse_duration)]
+    pub request_timeout: Option<Duration>,
+
+    /// Stop waiting for wallets after this long, e.g. `1m`, and report the
+    /// rest as errors
+    #[arg(long, global = true, value_parser = humantime::parse_duration)]
+    pub deadline: Option<Duration>,
+
+    /// Fail a report that would make more RPC requests than this
+    #[arg(long, global = true, value_name = "N")]
+    pub max_rpc_calls: Option<u64>,
+
+    /// Print how many RPC requests of each method were made to stderr when
+    /// done
+    #[arg(long, global = true)]
+    pub stats: bool,
+
+    /// The RPC requests of this invocation's fetchers, for --stats.
+    #[arg(skip)]
+    pub rpc_calls: Arc<RpcCalls>,
+
+    /// Link wallets, mints and token accounts in text and JSON output to this
+    /// explorer: solana-explorer, solscan or xray
+    #[arg(long, global = true)]
+    pub explorer: Option<Explorer>,
+
+    /// Cluster explorer links point at: mainnet-beta, devnet or testnet
+    /// [default: guessed from the RPC URL]
+    #[arg(long, global = true)]
+    pub cluster: Option<Cluster>,
+
+    /// Show valuations in this currency, e.g. EUR, instead of USD; turns on
+    /// pricing with the default provider if the config has none
+    #[arg(long, global = true)]
+    pub currency: Option<Currency>,
+
+    /// Number style in text, CSV and HTML output: en (1,234.5), de
+    /// (1.234,5), fr (1 234,5), ch (1'234.5) or plain (1234.5)
+    #[arg(long, global = true)]
+    pub locale: Option<NumberLocale>,
+
+    /// Decimals of balances in text, CSV and HTML output, or `auto` for
+    /// each mint's own [default: 4]
+    #[arg(long, global = true)]
+    pub decimals: Option<Decimals>,
+
+    /// Show balances as integer lamports and token base units in text, CSV
+    /// and JSON output; JSON gets them as strings, which no parser rounds
+    #[arg(long, global = true)]
+    pub raw: bool,
+
+    /// Output format: text, json, csv, prometheus, html, markdown,
+    /// influx, parquet or xlsx
+    #[arg(long, global = true, default_value_t = OutputFormat::Text)]
+    pub format: OutputFormat,
+
+    /// Report every SPL token held by each wallet, not just configured ones
+    #[arg(long, global = true)]
+    pub all_tokens: bool,
+
+    /// Read every wallet at or after the same slot so totals are consistent
+    #[arg(long, global = true)]
+    pub same_slot: bool,
+
+    /// Report balances as of this past slot (needs an archival RPC node)
+    #[arg(long, global = true, conflicts_with = "at_time")]
+    pub at_slot: Option<u64>,
+
+    /// Report balances as of this time, as RFC 3339 or `YYYY-MM-DD` (end of day)
+    #[arg(long, global = true, value_parser = parse_to)]
+    pub at_time: Option<DateTime<Utc>>,
+
+    /// Leave tokens with a zero balance out of the report
+    #[arg(long, global = true)]
+    pub hide_zero: bool,
+
+    /// Only report this token, by ticker or mint (repeatable)
+    #[arg(long = "only", global = true, value_name = "TICKER")]
+    pub only_tokens: Vec<String>,
+
+    /// Include stake accounts the wallets can stake or withdraw from
+    #[arg(long, global = true)]
+    pub stake: bool,
+
+    /// Show how much SOL is rent-exempt reserve, in each wallet and its token accounts
+    #[arg(long, global = true)]
+    pub rent: bool,
+
+    /// Value liquid staking tokens (mSOL, jitoSOL, bSOL) in SOL
+    #[arg(long, global = true)]
+    pub liquid_staking: bool,
+
+    /// Count each wallet's NFTs with the DAS API (the RPC endpoint must support it)
+    #[arg(long, global = true)]
+    pub nfts: bool,
+
+    /// Show the primary .sol domain next to each wallet address
+    #[arg(long, global = true)]
+    pub reverse_lookup: bool,
+
+    /// List each token account (address, ATA or not, program, state and
+    /// balance) under its token's total
+    #[arg(long, global = true)]
+    pub verbose: bool,
+
+    /// Log more: -v for progress, -vv for each RPC request, -vvv for
+    /// everything (RUST_LOG takes precedence)
+    #[arg(short = 'v', action = ArgAction::Count, global = true)]
+    pub log_verbosity: u8,
+
+    /// Log format on stderr: text or json
+    #[arg(long, global = true, default_value_t = LogFormat::Text)]
+    pub log_format: LogFormat,
+
+    /// Download the token list again even if the cached copy is fresh
+    #[arg(long, global = true)]
+    pub refresh_token_list: bool,
+
+    /// Don't read or write the cache of mint decimals, metadata and domains
+    #[arg(long, global = true)]
+    pub no_cache: bool,
+
+    /// Fetch cached mint decimals, metadata, domains and the token list
+    /// again, then save them
+    #[arg(long, global = true, conflicts_with = "no_cache")]
+    pub refresh_cache: bool,
+
+    /// Don't show a progress bar while fetching
+    #[arg(short, long, global = true)]
+    pub quiet: bool,
+
+    /// Exit with an error if any wallet fails, not only when all of them do
+    #[arg(long, global = true)]
+    pub strict: bool,
+
+    /// Render balances through this Tera template instead of --format; the
+    /// template sees the JSON report plus `wallet_list`, the wallets in
+    /// --sort-by order
+    #[arg(long, global = true, value_name = "PATH")]
+    pub template: Option<PathBuf>,
+
+    /// Also email the rendered report to `notifications.email`'s recipients
+    /// (check only); HTML reports are sent as HTML, others as plain text
+    #[arg(long, global = true)]
+    pub email_report: bool,
+
+    /// Print JSON on a single line instead of pretty-printing it
+    #[arg(long, global = true)]
+    pub compact: bool,
+
+    /// Don't color text output (also off when NO_COLOR is set or stdout is not a terminal)
+    #[arg(long, global = true)]
+    pub no_color: bool,
+
+    /// Order wallets in text and CSV output as in the config, or by address,
+    /// label or usd (largest first, which also orders tokens by value)
+    #[arg(long, global = true, default_value_t = SortOrder::Config)]
+    pub sort_by: SortOrder,
+
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    /// Fetch balances once and print them (the default)
+    Check,
+    /// Re-fetch balances periodically; the config file is watched and
+    /// reloaded whenever it is saved
+    Watch(WatchArgs),
+    /// Run in the background, fetching balances on a cron schedule and
+    /// saving each run as a snapshot; the config file is watched and
+    /// reloaded whenever it is saved
+    Daemon(DaemonArgs),
+    /// Check the config file for errors, without querying the chain unless asked
+    Validate(ValidateArgs),
+    /// Check that every RPC endpoint is reachable, healthy and answers balance
+    /// requests, and suggest what to fix when one is not
+    Doctor,
+    /// Serve balances over HTTP, as Prometheus metrics and as JSON
+    Serve(ServeArgs),
+    /// Fetch balances and save them, with timestamp and slot, as JSON
+    Snapshot(SnapshotArgs),
+    /// Show per-wallet, per-asset changes between two snapshots
+    Diff(DiffArgs),
+    /// List empty token accounts and the SOL closing them would recover
+    /// (read-only; no transactions are sent)
+    CleanupReport,
+    /// Sum the staking rewards paid to the wallets' stake accounts, per epoch
+    Rewards(RewardsArgs),
+    /// List a wallet's recent transactions and what each moved in and out
+    /// of it
+    Txs(TxsArgs),
+    /// Compare balances against `expected_balances` and list the ones that
+    /// differ, e.g. to verify cold-storage wallets have not moved
+    Reconcile,
+    /// Browse balances in an interactive dashboard that refreshes itself
+    #[cfg(feature = "tui")]
+    Tui(TuiArgs),
+    /// Show how balances evolved over the runs recorded in `history_db`
+    #[cfg(feature = "history")]
+    History(HistoryArgs),
+}
+
+#[derive(Debug, Args)]
+pub struct WatchArgs {
+    /// Time between refreshes, e.g. `30s` or `5m`
+    #[arg(long, default_value = "30s", value_parser = humantime::parse_duration)]
+    pub interval: Duration,
+
+    /// Number of recent polls drawn in each balance's sparkline (0 to hide them)
+    #[arg(long, default_value_t = 20)]
+    pub trend_length: usize,
+
+    /// Update balances as account notifications arrive over the RPC node's
+    /// WebSocket (`solana_ws_url`), or the `geyser` gRPC endpoint when one
+    /// is configured, instead of polling every interval
+    #[arg(long)]
+    pub subscribe: bool,
+
+    /// List the signatures of the transactions since the previous refresh
+    /// under each balance change
+    #[arg(long)]
+    pub activity: bool,
+
+    /// With --activity, also fetch each transaction and show what it moved
+    /// in and out of the wallet
+    #[arg(long, requires = "activity")]
+    pub transfers: bool,
+}
+
+#[derive(Debug, Args)]
+pub struct DaemonArgs {
+    /// When to fetch balances, as a cron expression in UTC, e.g.
+    /// `0 */6 * * *` for every six hours, or `@hourly` or `@daily`
+    #[arg(long)]
+    pub schedule: Schedule,
+
+    /// Directory snapshots are written to
+    #[arg(long, default_value = ".")]
+    pub snapshot_dir: PathBuf,
+
+    /// Write the process ID to this file while running
+    #[arg(long)]
+    pub pid_file: Option<PathBuf>,
+
+    /// Serve /healthz, on whether the RPC node is reachable, and /readyz, on
+    /// whether the last run succeeded, on this address
+    #[arg(long)]
+    pub listen: Option<SocketAddr>,
+
+    /// Time between the RPC health checks behind /healthz, e.g. `30s`
+    #[arg(
+        long,
+        default_value = "30s",
+        value_parser = humantime::parse_duration,
+        requires = "listen"
+    )]
+    pub health_interval: Duration,
+}
+
+#[derive(Debug, Args)]
+pub struct RewardsArgs {
+    /// Number of most recent completed epochs to cover
+    #[arg(long, default_value_t = 5, conflicts_with_all = ["from_epoch", "to_epoch"])]
+    pub epochs: u64,
+
+    /// First epoch to cover [default: four epochs before --to-epoch]
+    #[arg(long)]
+    pub from_epoch: Option<u64>,
+
+    /// Last epoch to cover [default: the last completed epoch]
+    #[arg(long)]
+    pub to_epoch: Option<u64>,
+}
+
+#[derive(Debug, Args)]
+pub struct TxsArgs {
+    /// Wallet address or `.sol` domain
+    pub wallet: String,
+
+    /// Number of most recent transactions to list
+    #[arg(long, default_value_t = 20)]
+    pub limit: usize,
+}
+
+#[derive(Debug, Args)]
+pub struct ServeArgs {
+    /// Address to listen on
+    #[arg(long, visible_alias = "http", default_value = "0.0.0.0:9090")]
+    pub listen: SocketAddr,
+
+    /// Time between background refreshes, e.g. `30s` or `5m`
+    #[arg(long, default_value = "60s", value_parser = humantime::parse_duration)]
+    pub interval: Duration,
+
+    /// How long balances stay fresh after a successful refresh; the JSON
+    /// endpoints and /readyz fail once they are older [default: 3 × interval]
+    #[arg(long, value_parser = humantime::parse_duration)]
+    pub max_age: Option<Duration>,
+}
+
+#[cfg(feature = "tui")]
+#[derive(Debug, Args)]
+pub struct TuiArgs {
+    /// Time between refreshes, e.g. `30s` or `5m`
+    #[arg(long, default_value = "60s", value_parser = humantime::parse_duration)]
+    pub interval: Duration,
+
+    /// Number of recent polls drawn in each balance's sparkline (0 to hide them)
+    #[arg(long, default_value_t = 20)]
+    pub trend_length: usize,
+}
+
+#[derive(Debug, Args)]
+pub struct ValidateArgs {
+    /// Also check over RPC that every configured mint exists and is a token mint
+    #[arg(long)]
+    pub check_mints: bool,
+}
+
+#[derive(Debug, Args)]
+pub struct SnapshotArgs {
+    /// Where to write the snapshot [default: snapshot-<timestamp>.json]
+    pub path: Option<PathBuf>,
+}
+
+#[derive(Debug, Args)]
+pub struct DiffArgs {
+    /// The older snapshot
+    pub old: PathBuf,
+    /// The newer snapshot
+    pub new: PathBuf,
+}
+
+#[cfg(feature = "history")]
+#[derive(Debug, Args)]
+pub struct HistoryArgs {
+    /// Only show this wallet
+    #[arg(long)]
+    pub wallet: Option<String>,
+
+    /// Only show this asset, by ticker or mint (`SOL` for native SOL)
+    #[arg(long)]
+    pub token: Option<String>,
+
+    /// Start of the range, as RFC 3339 or `YYYY-MM-DD`
+    #[arg(long, value_parser = parse_from)]
+    pub from: Option<DateTime<Utc>>,
+
+    /// End of the range (inclusive), as RFC 3339 or `YYYY-MM-DD`
+    #[arg(long, value_parser = parse_to)]
+    pub to: Option<DateTime<Utc>>,
+
+    /// Database to read instead of `history_db` from the config file
+    #[arg(long)]
+    pub db: Option<PathBuf>,
+}
+
+#[cfg(feature = "history")]
+fn parse_from(s: &str) -> Result<DateTime<Utc>, String> {
+    parse_date(s, NaiveTime::MIN)
+}
+
+fn parse_token(s: &str) -> Result<TokenInfo, String> {
+    let (address, ticker) = match s.split_once(':') {
+        Some((address, ticker)) => (address, Some(ticker.to_string())),
+        None => (s, None),
+    };
+    if address.is_empty() || ticker.as_deref() == Some("") {
+        return Err(format!("expected MINT or MINT:TICKER, got `{}`", s));
+    }
+    Ok(TokenInfo {
+        address: address.to_string(),
+        ticker,
+        min_balance: None,
+    })
+}
+
+fn parse_to(s: &str) -> Result<DateTime<Utc>, String> {
+    parse_date(s, NaiveTime::from_hms_opt(23, 59, 59).unwrap_or_default())
+}
+
+/// Parses an RFC 3339 timestamp, or a bare date at `time_of_day` UTC.
+fn parse_date(s: &str, time_of_day: NaiveTime) -> Result<DateTime<Utc>, String> {
+    if let Ok(timestamp) = DateTime::parse_from_rfc3339(s) {
+        return Ok(timestamp.with_timezone(&Utc));
+    }
+    NaiveDate::parse_from_str(s, "%Y-%m-%d")
+        .map(|date| date.and_time(time_of_day).and_utc())
+        .map_err(|_| format!("expected RFC 3339 or YYYY-MM-DD, got `{}`", s))
+}
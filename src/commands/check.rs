@@ -0,0 +1,90 @@
+use crate::cli::Cli;
+use crate::commands::{
+    connect, fetch_report, load_config, publish_report, record_history, render_options,
+    sol_history, BelowThreshold,
+};
+use std::io::{self, Write};
+use test_solana::notify::{EmailBody, EmailNotifier};
+use test_solana::{check_thresholds, output, OutputFormat};
+
+pub async fn run(cli: &Cli) -> Result<(), anyhow::Error> {
+    let mut config = load_config(cli)?;
+    let email = match &config.notifications.email {
+        Some(email) if cli.email_report => Some(EmailNotifier::new(email.clone())),
+        None if cli.email_report => {
+            anyhow::bail!("--email-report needs `notifications.email` in the config")
+        }
+        _ => None,
+    };
+    let binary = matches!(cli.format, OutputFormat::Parquet | OutputFormat::Xlsx);
+    if email.is_some() && binary && cli.template.is_none() {
+        anyhow::bail!("--email-report cannot send {} output", cli.format);
+    }
+
+    let fetcher = connect(cli, &mut config).await?;
+    let report = fetch_report(cli, &fetcher, &config).await?;
+
+    let mut options = render_options(cli, &config);
+    options.links = config.explorer_links();
+    // The emailed copy is the same bytes, which must not carry ANSI colors.
+    options.color &= email.is_none();
+    let html = match &options.template {
+        Some(template) => template
+            .extension()
+            .is_some_and(|extension| extension == "html" || extension == "htm"),
+        None => options.format == OutputFormat::Html,
+    };
+    let mut rendered = Vec::new();
+    if html && options.template.is_none() {
+        let history = sol_history(&config)?;
+        output::write_html_with_history(&report, &history, &options, &mut rendered)?;
+    } else {
+        output::render(&report, &options, &mut rendered)?;
+    }
+    io::stdout().lock().write_all(&rendered)?;
+    // After the output, so a sink that is down cannot hold back the report
+    // or its exit code.
+    if let Err(err) = record_history(&config, &report) {
+        tracing::error!(error = %format!("{:#}", err), "recording history failed");
+    }
+    if let Err(err) = publish_report(&config, &report).await {
+        tracing::error!(error = %err, "publishing the report failed");
+    }
+
+    if let Some(email) = email {
+        let format = if html {
+            EmailBody::Html
+        } else {
+            EmailBody::Plain
+        };
+        email
+            .send_report(String::from_utf8_lossy(&rendered).into_owned(), format)
+            .await
+            .map_err(|err| anyhow::anyhow!("emailing the report: {:#}", err))?;
+    }
+
+    // The text report already lists errors at the end.
+    if !options.is_text() {
+        for (wallet, error) in &report.errors {
+            eprintln!("error: {}: {}", wallet, error);
+        }
+    }
+
+    if report.all_failed() || (cli.strict && !report.errors.is_empty()) {
+        anyhow::bail!(
+            "{} of {} wallet(s) failed",
+            report.errors.len(),
+            config.wallets.len()
+        );
+    }
+
+    let alerts = check_thresholds(&config, &report);
+    for alert in &alerts {
+        eprintln!("warning: {}", alert);
+    }
+    if !alerts.is_empty() {
+        return Err(BelowThreshold(alerts.len()).into());
+    }
+
+    Ok(())
+}
@@ -0,0 +1,26 @@
+use crate::cli::Cli;
+use crate::commands::{connect, load_config, main_cluster, render_options, require_live};
+use std::io;
+use test_solana::output;
+
+pub async fn run(cli: &Cli) -> Result<(), anyhow::Error> {
+    require_live(cli)?;
+    let mut config = load_config(cli)?;
+    let fetcher = connect(cli, &mut config).await?;
+    let config = main_cluster(&config, "cleanup");
+    let report = fetcher.fetch_cleanup_report(&config).await?;
+    output::write_cleanup(
+        &report,
+        &render_options(cli, &config),
+        &mut io::stdout().lock(),
+    )?;
+
+    if !report.errors.is_empty() && (cli.strict || report.errors.len() == config.wallets.len()) {
+        anyhow::bail!(
+            "{} of {} wallet(s) failed",
+            report.errors.len(),
+            config.wallets.len()
+        );
+    }
+    Ok(())
+}
@@ -0,0 +1,196 @@
+use crate::cli::{Cli, DaemonArgs};
+use crate::commands::health::RefreshStatus;
+use crate::commands::{
+    connect, load_config, probe_fetcher, publish_report, record_history, require_live,
+    some_fetched, Alerting, ConfigReloader,
+};
+use axum::extract::State;
+use axum::response::Response;
+use axum::routing::get;
+use axum::Router;
+use chrono::Utc;
+use std::fs;
+use std::path::Path;
+use std::sync::Arc;
+use test_solana::{check_thresholds, diff_reports, BalanceReport};
+use tokio::sync::RwLock;
+
+pub async fn run(cli: &Cli, args: &DaemonArgs) -> Result<(), anyhow::Error> {
+    require_live(cli)?;
+    let mut config = load_config(cli)?;
+    let mut fetcher = connect(cli, &mut config).await?;
+    let mut probe = probe_fetcher(cli, &config);
+    let mut reloader = ConfigReloader::new(cli);
+    fs::create_dir_all(&args.snapshot_dir)
+        .map_err(|err| anyhow::anyhow!("creating {}: {}", args.snapshot_dir.display(), err))?;
+    // Listen before the first run, so a signal during it is not fatal.
+    let mut shutdown = Shutdown::new()?;
+    let _pid_file = args.pid_file.as_deref().map(PidFile::create).transpose()?;
+
+    let status: Arc<RwLock<RefreshStatus>> = Default::default();
+    // Ticks at once, so /healthz has an answer before the first run.
+    let mut health_checks = tokio::time::interval(args.health_interval);
+    if let Some(listen) = args.listen {
+        let app = Router::new()
+            .route("/healthz", get(healthz))
+            .route("/readyz", get(readyz))
+            .with_state(status.clone());
+        let server = axum::Server::try_bind(&listen)?.serve(app.into_make_service());
+        tokio::spawn(async move {
+            if let Err(err) = server.await {
+                tracing::error!(error = %err, "health server failed");
+            }
+        });
+        tracing::info!(listen = %listen, "serving /healthz and /readyz");
+    }
+
+    let mut alerting = Alerting::new(&config);
+    let mut previous: Option<BalanceReport> = None;
+    loop {
+        let now = Utc::now();
+        let Some(next) = args.schedule.next_after(now) else {
+            anyhow::bail!("schedule `{}` never fires", args.schedule);
+        };
+        tracing::info!(next = %next, "waiting for the next run");
+        let wait = tokio::time::sleep((next - now).to_std().unwrap_or_default());
+        tokio::pin!(wait);
+        loop {
+            tokio::select! {
+                _ = &mut wait => break,
+                _ = shutdown.recv() => {
+                    tracing::info!("shutting down");
+                    return Ok(());
+                }
+                _ = reloader.changed() => {
+                    if let Some(reloaded) = reloader.reload(cli, &config).await {
+                        (config, fetcher) = reloaded;
+                        probe = probe_fetcher(cli, &config);
+                        alerting.reconfigure(&config);
+                    }
+                }
+                _ = health_checks.tick(), if args.listen.is_some() => {
+                    status.write().await.rpc = Some(probe.check_health().await);
+                }
+            }
+        }
+
+        let fetch = fetcher.fetch_report(&config);
+        tokio::pin!(fetch);
+        let fetched = loop {
+            tokio::select! {
+                fetched = &mut fetch => break fetched,
+                _ = health_checks.tick(), if args.listen.is_some() => {
+                    status.write().await.rpc = Some(probe.check_health().await);
+                }
+            }
+        };
+        let fetched = fetched.map_err(anyhow::Error::from).and_then(some_fetched);
+        let report = match fetched {
+            Ok(report) => {
+                status.write().await.succeeded();
+                report
+            }
+            Err(err) => {
+                tracing::error!(error = %format!("{:#}", err), "run failed");
+                status.write().await.failed(&err);
+                continue;
+            }
+        };
+        for (wallet, error) in &report.errors {
+            tracing::error!(wallet = %wallet, error = %error, "fetching wallet failed");
+        }
+        if let Err(err) = record_history(&config, &report) {
+            tracing::error!(error = %format!("{:#}", err), "recording history failed");
+        }
+        if let Err(err) = publish_report(&config, &report).await {
+            tracing::error!(error = %err, "publishing the report failed");
+        }
+        let path = args.snapshot_dir.join(format!(
+            "snapshot-{}.json",
+            report.timestamp.format("%Y%m%dT%H%M%SZ")
+        ));
+        match report.save(&path) {
+            Ok(()) => tracing::info!(
+                path = %path.display(),
+                wallets = report.wallets.len(),
+                "wrote snapshot"
+            ),
+            Err(err) => tracing::error!(
+                path = %path.display(),
+                error = %format!("{:#}", err),
+                "writing the snapshot failed"
+            ),
+        }
+
+        let changes = previous
+            .as_ref()
+            .map(|previous| diff_reports(previous, &report))
+            .unwrap_or_default();
+        let alerts = check_thresholds(&config, &report);
+        for alert in &alerts {
+            tracing::warn!(alert = %alert, "below threshold");
+        }
+        alerting.update(&config, &report, changes, alerts).await;
+        previous = Some(report);
+    }
+}
+
+async fn healthz(State(status): State<Arc<RwLock<RefreshStatus>>>) -> Response {
+    status.read().await.healthz()
+}
+
+async fn readyz(State(status): State<Arc<RwLock<RefreshStatus>>>) -> Response {
+    status.read().await.readyz(None)
+}
+
+/// SIGTERM or Ctrl-C, which end the daemon once its current run is done.
+struct Shutdown {
+    #[cfg(unix)]
+    terminate: tokio::signal::unix::Signal,
+    #[cfg(unix)]
+    interrupt: tokio::signal::unix::Signal,
+}
+
+impl Shutdown {
+    fn new() -> Result<Self, anyhow::Error> {
+        #[cfg(unix)]
+        {
+            use tokio::signal::unix::{signal, SignalKind};
+            Ok(Shutdown {
+                terminate: signal(SignalKind::terminate())?,
+                interrupt: signal(SignalKind::interrupt())?,
+            })
+        }
+        #[cfg(not(unix))]
+        Ok(Shutdown {})
+    }
+
+    async fn recv(&mut self) {
+        #[cfg(unix)]
+        tokio::select! {
+            _ = self.terminate.recv() => {}
+            _ = self.interrupt.recv() => {}
+        }
+        #[cfg(not(unix))]
+        let _ = tokio::signal::ctrl_c().await;
+    }
+}
+
+/// A file holding the daemon's process ID, removed again when dropped.
+struct PidFile<'a>(&'a Path);
+
+impl<'a> PidFile<'a> {
+    fn create(path: &'a Path) -> Result<Self, anyhow::Error> {
+        fs::write(path, format!("{}\n", std::process::id()))
+            .map_err(|err| anyhow::anyhow!("writing {}: {}", path.display(), err))?;
+        Ok(PidFile(path))
+    }
+}
+
+impl Drop for PidFile<'_> {
+    fn drop(&mut self) {
+        if let Err(err) = fs::remove_file(self.0) {
+            tracing::warn!(path = %self.0.display(), error = %err, "removing the PID file failed");
+        }
+    }
+}
@@ -0,0 +1,21 @@
+use crate::cli::{Cli, DiffArgs};
+use crate::commands::{load_optional_config, render_options};
+use std::io;
+use test_solana::{diff_reports, output, BalanceReport};
+
+pub fn run(cli: &Cli, args: &DiffArgs) -> Result<(), anyhow::Error> {
+    let old = BalanceReport::load(&args.old)?;
+    let new = BalanceReport::load(&args.new)?;
+
+    let config = load_optional_config(cli)?;
+    let changes = diff_reports(&old, &new);
+    output::write_changes(
+        &old,
+        &new,
+        &changes,
+        &render_options(cli, &config),
+        &mut io::stdout().lock(),
+    )?;
+
+    Ok(())
+}
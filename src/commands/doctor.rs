@@ -0,0 +1,42 @@
+use crate::cli::Cli;
+use crate::commands::{load_config, probe_fetcher, render_options};
+use solana_sdk::pubkey::Pubkey;
+use std::io;
+use test_solana::output;
+
+pub async fn run(cli: &Cli) -> Result<(), anyhow::Error> {
+    let config = load_config(cli)?;
+
+    let mut diagnoses = Vec::new();
+    for endpoint in config.rpc_urls() {
+        let mut single = config.clone();
+        single.solana_rpc_url = endpoint.clone();
+        single.solana_rpc_urls.clear();
+        let fetcher = probe_fetcher(cli, &single);
+        // Any account will do; the first wallet shows its own balance.
+        let sample = match config.wallets.first() {
+            Some(wallet) => fetcher
+                .resolve_wallet(&wallet.address)
+                .await
+                .unwrap_or_default(),
+            None => Pubkey::default(),
+        };
+        diagnoses.push(fetcher.diagnose(&endpoint, &sample).await);
+    }
+
+    let options = render_options(cli, &config);
+    output::write_diagnoses(&diagnoses, &options, &mut io::stdout().lock())?;
+
+    let unhealthy = diagnoses
+        .iter()
+        .filter(|diagnosis| !diagnosis.is_healthy())
+        .count();
+    if unhealthy > 0 {
+        anyhow::bail!(
+            "{} of {} endpoint(s) failed checks",
+            unhealthy,
+            diagnoses.len()
+        );
+    }
+    Ok(())
+}
@@ -0,0 +1,86 @@
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use axum::Json;
+use chrono::{DateTime, Utc};
+use serde_json::json;
+use std::time::{Duration, SystemTime};
+use test_solana::rpc::redact_urls;
+use test_solana::HealthCheck;
+
+/// How the background refreshes of serve and daemon mode went, for
+/// `/healthz` and `/readyz`.
+#[derive(Debug, Default)]
+pub struct RefreshStatus {
+    pub last_success: Option<SystemTime>,
+    /// Why the last refresh failed, if it did.
+    pub last_error: Option<String>,
+    pub refresh_errors: u64,
+    /// The node's answer to `getHealth` after the last refresh.
+    pub rpc: Option<HealthCheck>,
+}
+
+impl RefreshStatus {
+    pub fn succeeded(&mut self) {
+        self.last_success = Some(SystemTime::now());
+        self.last_error = None;
+    }
+
+    pub fn failed(&mut self, err: &anyhow::Error) {
+        self.refresh_errors += 1;
+        // Served to anyone who can reach the port, so without URLs.
+        self.last_error = Some(redact_urls(&format!("{:#}", err)));
+    }
+
+    /// Why balances are not fresh: older than `max_age`, or, without one,
+    /// not fetched yet or the last refresh failed.
+    pub fn unready(&self, max_age: Option<Duration>) -> Option<String> {
+        let age = self
+            .last_success
+            .and_then(|time| SystemTime::now().duration_since(time).ok());
+        match (max_age, age) {
+            (Some(max_age), Some(age)) if age <= max_age => None,
+            (Some(_), Some(_)) => Some(format!(
+                "balances are stale; last refresh error: {}",
+                self.last_error.as_deref().unwrap_or("none")
+            )),
+            (Some(_), None) => Some("balances have not been fetched yet".to_string()),
+            (None, age) => match &self.last_error {
+                Some(err) => Some(format!("the last refresh failed: {}", err)),
+                None if age.is_none() => Some("balances have not been fetched yet".to_string()),
+                None => None,
+            },
+        }
+    }
+
+    /// 200 while the RPC node answered the last health check, else 503.
+    pub fn healthz(&self) -> Response {
+        let problem = match &self.rpc {
+            Some(rpc) if !rpc.ok => Some(format!("RPC node is unhealthy: {}", rpc.detail)),
+            _ => None,
+        };
+        self.respond(problem)
+    }
+
+    /// 200 while balances are fresh, see [`RefreshStatus::unready`].
+    pub fn readyz(&self, max_age: Option<Duration>) -> Response {
+        self.respond(self.unready(max_age))
+    }
+
+    fn respond(&self, problem: Option<String>) -> Response {
+        let mut body = json!({
+            "status": if problem.is_none() { "ok" } else { "unavailable" },
+            "last_refresh": self.last_success.map(DateTime::<Utc>::from),
+            "last_error": self.last_error,
+            "refresh_errors": self.refresh_errors,
+            "rpc": self.rpc,
+        });
+        let status = match problem {
+            Some(problem) => {
+                body["error"] = problem.into();
+                StatusCode::SERVICE_UNAVAILABLE
+            }
+            None => StatusCode::OK,
+        };
+        (status, Json(body)).into_response()
+    }
+}
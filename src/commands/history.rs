@@ -0,0 +1,36 @@
+use crate::cli::{Cli, HistoryArgs};
+use crate::commands::{load_optional_config, render_options};
+use std::io;
+use test_solana::{output, HistoryQuery, HistoryStore};
+
+pub fn run(cli: &Cli, args: &HistoryArgs) -> Result<(), anyhow::Error> {
+    let config = load_optional_config(cli)?;
+    let path = match &args.db {
+        Some(path) => path.clone(),
+        None => config
+            .history_db
+            .clone()
+            .ok_or_else(|| anyhow::anyhow!("no history_db in {}", cli.config.display()))?,
+    };
+
+    if !path.exists() {
+        anyhow::bail!(
+            "{} does not exist; nothing has been recorded yet",
+            path.display()
+        );
+    }
+    let store = HistoryStore::open(&path)?;
+    let entries = store.query(&HistoryQuery {
+        wallet: args.wallet.clone(),
+        asset: args.token.clone(),
+        from: args.from,
+        to: args.to,
+    })?;
+
+    output::write_history(
+        &entries,
+        &render_options(cli, &config),
+        &mut io::stdout().lock(),
+    )?;
+    Ok(())
+}
@@ -0,0 +1,631 @@
+pub mod check;
+pub mod cleanup;
+pub mod daemon;
+pub mod diff;
+pub mod doctor;
+pub mod health;
+#[cfg(feature = "history")]
+pub mod history;
+pub mod reconcile;
+pub mod rewards;
+pub mod serve;
+pub mod snapshot;
+#[cfg(feature = "tui")]
+pub mod tui;
+pub mod txs;
+pub mod validate;
+pub mod watch;
+
+use crate::cli::Cli;
+use crate::progress::WalletProgress;
+use chrono::{DateTime, Utc};
+use notify::{Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use std::collections::BTreeMap;
+use std::ffi::OsStr;
+use std::io::IsTerminal;
+use std::path::Path;
+use std::time::Duration;
+use std::{fmt, fs, io};
+use test_solana::notify::{Notification, Notifier};
+use test_solana::rpc::{CircuitBreakerConfig, Fixtures, RetryPolicy};
+use test_solana::token_list::TokenList;
+use test_solana::{
+    Alert, BalanceChange, BalanceError, BalanceFetcher, BalanceFetcherBuilder, BalanceReport,
+    ConfigFormat, DiskCache, NftConfig, RenderOptions, TokenConfig, WalletInfo,
+};
+use tokio::sync::mpsc;
+
+/// Exit code used when balances were fetched but some are below their
+/// configured thresholds.
+pub const EXIT_BELOW_THRESHOLD: u8 = 2;
+/// Exit code for an unreadable or invalid config, including bad mints.
+pub const EXIT_CONFIG: u8 = 3;
+/// Exit code when RPC requests failed.
+pub const EXIT_RPC: u8 = 4;
+/// Exit code when fetching prices failed.
+pub const EXIT_PRICE: u8 = 5;
+/// Exit code when `reconcile` found balances other than the expected ones.
+pub const EXIT_DISCREPANCY: u8 = 6;
+
+/// The exit code for a failed command, by the kind of the first
+/// [`BalanceError`] in its chain.
+pub fn exit_code(err: &anyhow::Error) -> u8 {
+    match err
+        .chain()
+        .find_map(|cause| cause.downcast_ref::<BalanceError>())
+    {
+        Some(BalanceError::Config(_) | BalanceError::Parse { .. }) => EXIT_CONFIG,
+        Some(BalanceError::Rpc { .. }) => EXIT_RPC,
+        Some(BalanceError::Price(_)) => EXIT_PRICE,
+        _ => 1,
+    }
+}
+
+/// Prints the RPC requests the command made, by method, for `--stats`.
+pub fn print_rpc_stats(cli: &Cli) {
+    let by_method = cli.rpc_calls.by_method();
+    let width = by_method.keys().map(String::len).max().unwrap_or(0);
+    eprintln!("RPC requests: {}", cli.rpc_calls.total());
+    for (method, count) in &by_method {
+        eprintln!("  {:<width$}  {:>6}", method, count, width = width);
+    }
+}
+
+/// Returned by commands when balances fell below their thresholds, so
+/// `main` can exit with [`EXIT_BELOW_THRESHOLD`].
+#[derive(Debug)]
+pub struct BelowThreshold(pub usize);
+
+impl fmt::Display for BelowThreshold {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} balance(s) below threshold", self.0)
+    }
+}
+
+impl std::error::Error for BelowThreshold {}
+
+/// Returned by `reconcile` when balances differ from the expected ones, so
+/// `main` can exit with [`EXIT_DISCREPANCY`].
+#[derive(Debug)]
+pub struct Discrepancies(pub usize);
+
+impl fmt::Display for Discrepancies {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} balance(s) differ from the expected ones", self.0)
+    }
+}
+
+impl std::error::Error for Discrepancies {}
+
+pub fn load_config(cli: &Cli) -> Result<TokenConfig, anyhow::Error> {
+    let config = if reads_config_file(cli) {
+        let format = cli
+            .config_format
+            .unwrap_or_else(|| ConfigFormat::from_path(&cli.config));
+        TokenConfig::load(&cli.config, format, cli.profile.as_deref())?
+    } else {
+        TokenConfig::from_env()?
+    };
+    apply_overrides(cli, config)
+}
+
+/// Like [`load_config`], for commands that also work without a config
+/// file, such as `diff`: when the file is missing, the defaults with the
+/// command line's overrides.
+pub fn load_optional_config(cli: &Cli) -> Result<TokenConfig, anyhow::Error> {
+    if reads_config_file(cli) && !cli.config.exists() {
+        apply_overrides(cli, TokenConfig::from_env()?)
+    } else {
+        load_config(cli)
+    }
+}
+
+/// Applies the command line's wallets, tokens and settings to `config`.
+fn apply_overrides(cli: &Cli, mut config: TokenConfig) -> Result<TokenConfig, anyhow::Error> {
+    let mut wallets = cli.wallets.clone();
+    for path in &cli.wallets_file {
+        wallets.extend(read_wallets_file(path)?);
+    }
+    for wallet in &wallets {
+        if !config.wallets.iter().any(|entry| entry.is_named(wallet)) {
+            config.wallets.push(WalletInfo::new(wallet.clone()));
+        }
+    }
+    for token in &cli.tokens {
+        match config
+            .tokens
+            .iter_mut()
+            .find(|entry| entry.address == token.address)
+        {
+            Some(entry) => entry.ticker = token.ticker.clone().or(entry.ticker.take()),
+            None => config.tokens.push(token.clone()),
+        }
+    }
+    if let Some(rpc_url) = &cli.rpc_url {
+        config.solana_rpc_url = rpc_url.clone();
+        config.solana_rpc_urls.clear();
+    }
+    if let Some(commitment) = cli.commitment {
+        config.commitment = commitment;
+    }
+    if let Some(timeout) = cli.request_timeout {
+        config.request_timeout = Some(timeout);
+    }
+    if let Some(deadline) = cli.deadline {
+        config.deadline = Some(deadline);
+    }
+    if let Some(max_rpc_calls) = cli.max_rpc_calls {
+        config.max_rpc_calls = Some(max_rpc_calls);
+    }
+    if cli.all_tokens {
+        config.discover_tokens = true;
+    }
+    if cli.hide_zero {
+        config.hide_zero = true;
+    }
+    if !cli.only_tokens.is_empty() {
+        config.only_tokens = cli.only_tokens.clone();
+    }
+    if cli.same_slot {
+        config.same_slot = true;
+    }
+    if cli.stake {
+        config.include_stake = true;
+    }
+    if cli.rent {
+        config.rent_breakdown = true;
+    }
+    if cli.liquid_staking {
+        config.liquid_staking = true;
+    }
+    if cli.nfts && config.nfts.is_none() {
+        config.nfts = Some(NftConfig::default());
+    }
+    if cli.reverse_lookup {
+        config.reverse_lookup = true;
+    }
+    if cli.verbose {
+        config.token_account_details = true;
+    }
+    if cli.explorer.is_some() || cli.cluster.is_some() {
+        let explorer = config.explorer.get_or_insert_with(Default::default);
+        if let Some(provider) = cli.explorer {
+            explorer.provider = provider;
+        }
+        if cli.cluster.is_some() {
+            explorer.cluster = cli.cluster;
+        }
+    }
+    if cli.locale.is_some() {
+        config.number_format.locale = cli.locale;
+    }
+    if cli.decimals.is_some() {
+        config.number_format.decimals = cli.decimals;
+    }
+    if let Some(currency) = &cli.currency {
+        let pricing = config.pricing.get_or_insert_with(Default::default);
+        pricing.display_currency = Some(currency.clone());
+    }
+    if cfg!(not(feature = "history")) && config.history_db.is_some() {
+        tracing::warn!("history_db is ignored; this build lacks the `history` feature");
+    }
+    if cfg!(not(feature = "geyser")) && config.geyser.is_some() {
+        tracing::warn!("geyser is ignored; this build lacks the `geyser` feature");
+    }
+    if cfg!(not(feature = "kafka")) && config.sinks.kafka.is_some() {
+        tracing::warn!("sinks.kafka is ignored; this build lacks the `kafka` feature");
+    }
+    Ok(config)
+}
+
+/// Whether the config file is read, rather than starting from defaults:
+/// always unless `--no-config` is given, or wallets are given on the
+/// command line and the file is missing.
+pub fn reads_config_file(cli: &Cli) -> bool {
+    !cli.no_config
+        && ((cli.wallets.is_empty() && cli.wallets_file.is_empty()) || cli.config.exists())
+}
+
+/// Reads newline-separated wallet addresses from `path`, or stdin for `-`.
+fn read_wallets_file(path: &Path) -> Result<Vec<String>, anyhow::Error> {
+    let content = if path == Path::new("-") {
+        io::read_to_string(io::stdin())?
+    } else {
+        fs::read_to_string(path)
+            .map_err(|err| anyhow::anyhow!("reading {}: {}", path.display(), err))?
+    };
+    Ok(content
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(String::from)
+        .collect())
+}
+
+/// Builds a fetcher for `config`, loading the token list if one is set and
+/// the disk cache unless `--no-cache` or `--fixture` is given, with one for
+/// each of its other clusters.
+pub async fn build_fetcher(
+    cli: &Cli,
+    config: &TokenConfig,
+) -> Result<BalanceFetcher, anyhow::Error> {
+    let mut builder = fetcher_builder(cli, config).await?;
+    for name in config.clusters.keys() {
+        let cluster = config.for_cluster(Some(name));
+        builder = builder.cluster(name, fetcher_builder(cli, &cluster).await?.build());
+    }
+    Ok(builder.build())
+}
+
+async fn fetcher_builder(
+    cli: &Cli,
+    config: &TokenConfig,
+) -> Result<BalanceFetcherBuilder, anyhow::Error> {
+    let mut builder = BalanceFetcher::config_builder(config).rpc_calls(cli.rpc_calls.clone());
+    if let Some(token_list) = &config.token_list {
+        let refresh = cli.refresh_token_list || cli.refresh_cache;
+        builder = builder.token_list(TokenList::load(token_list, refresh).await?);
+    }
+    if let Some(dir) = &cli.fixture {
+        builder = builder.fixtures(if cli.record {
+            Fixtures::Record(dir.clone())
+        } else {
+            Fixtures::Replay(dir.clone())
+        });
+    }
+    // Cached values would skip requests that a replay then lacks.
+    if config.cache.enabled && !cli.no_cache && cli.fixture.is_none() {
+        builder = builder.disk_cache(DiskCache::open(
+            &config.cache,
+            &config.solana_rpc_url,
+            cli.refresh_cache,
+        ));
+    }
+    Ok(builder)
+}
+
+/// Builds a fetcher for checking on `config`'s RPC endpoints, without
+/// retries or circuit breakers, so each check reaches the node and reports
+/// what it answered.
+pub fn probe_fetcher(cli: &Cli, config: &TokenConfig) -> BalanceFetcher {
+    BalanceFetcher::config_builder(config)
+        .rpc_calls(cli.rpc_calls.clone())
+        .retry_policy(RetryPolicy {
+            max_attempts: 1,
+            ..config.retry.clone()
+        })
+        .circuit_breaker(CircuitBreakerConfig {
+            failure_threshold: 0,
+            ..config.circuit_breaker.clone()
+        })
+        .build()
+}
+
+/// Builds the fetcher for `config`, then lists realms' treasuries in place
+/// of the realms, which takes the chain.
+pub async fn connect(cli: &Cli, config: &mut TokenConfig) -> Result<BalanceFetcher, anyhow::Error> {
+    let fetcher = build_fetcher(cli, config).await?;
+    fetcher.expand_realms(config).await?;
+    Ok(fetcher)
+}
+
+/// Notices edits to the config file, so long-running commands can pick up
+/// new wallets, tokens and thresholds without a restart. The file's
+/// directory is watched, as editors often save by replacing the file.
+pub struct ConfigReloader {
+    changes: mpsc::UnboundedReceiver<()>,
+    /// Stops watching when dropped.
+    _watcher: Option<RecommendedWatcher>,
+}
+
+impl ConfigReloader {
+    /// Edits this close together are reloaded once, as a save can take
+    /// several writes.
+    const SETTLE: Duration = Duration::from_millis(200);
+
+    /// Watches the file `--config` names, if it is read at all.
+    pub fn new(cli: &Cli) -> Self {
+        let (sender, changes) = mpsc::unbounded_channel();
+        let watcher = reads_config_file(cli)
+            .then(|| match watch_file(&cli.config, sender) {
+                Ok(watcher) => Some(watcher),
+                Err(err) => {
+                    tracing::warn!(
+                        path = %cli.config.display(),
+                        error = %err,
+                        "cannot watch the config file; edits to it need a restart"
+                    );
+                    None
+                }
+            })
+            .flatten();
+        ConfigReloader {
+            changes,
+            _watcher: watcher,
+        }
+    }
+
+    /// Waits for the config file to change; never returns if it is not
+    /// watched.
+    pub async fn changed(&mut self) {
+        if self.changes.recv().await.is_none() {
+            std::future::pending::<()>().await;
+        }
+        tokio::time::sleep(Self::SETTLE).await;
+        while self.changes.try_recv().is_ok() {}
+    }
+
+    /// Loads the changed config with a fetcher for it, after logging what
+    /// changed. A config that fails to load is logged and skipped, keeping
+    /// `current`.
+    pub async fn reload(
+        &self,
+        cli: &Cli,
+        current: &TokenConfig,
+    ) -> Option<(TokenConfig, BalanceFetcher)> {
+        let reloaded = async {
+            let mut config = load_config(cli)?;
+            let fetcher = connect(cli, &mut config).await?;
+            Ok::<_, anyhow::Error>((config, fetcher))
+        };
+        match reloaded.await {
+            Ok((config, fetcher)) => {
+                let changes = config.changes_since(current);
+                tracing::info!(changes = changes.len(), "reloaded the config");
+                for change in &changes {
+                    tracing::info!("config: {}", change);
+                }
+                Some((config, fetcher))
+            }
+            Err(err) => {
+                tracing::error!(
+                    error = %format!("{:#}", err),
+                    "reloading the config failed; keeping the previous one"
+                );
+                None
+            }
+        }
+    }
+}
+
+/// Watches the directory holding `path`, sending on `changes` whenever
+/// `path` is written, created or replaced.
+fn watch_file(
+    path: &Path,
+    changes: mpsc::UnboundedSender<()>,
+) -> notify::Result<RecommendedWatcher> {
+    let file_name = path.file_name().map(OsStr::to_os_string);
+    let mut watcher = notify::recommended_watcher(move |event: notify::Result<Event>| {
+        let Ok(event) = event else {
+            return;
+        };
+        if matches!(event.kind, EventKind::Access(_) | EventKind::Remove(_)) {
+            return;
+        }
+        if event
+            .paths
+            .iter()
+            .any(|changed| changed.file_name() == file_name.as_deref())
+        {
+            let _ = changes.send(());
+        }
+    })?;
+    let dir = match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    };
+    watcher.watch(dir, RecursiveMode::NonRecursive)?;
+    Ok(watcher)
+}
+
+/// Keeps just the wallets on the main cluster, for commands that do not
+/// support other clusters, warning if any are left out.
+pub fn main_cluster(config: &TokenConfig, command: &str) -> TokenConfig {
+    let main = config.for_cluster(None);
+    let skipped = config.wallets.len() - main.wallets.len();
+    if skipped > 0 {
+        tracing::warn!("{} skips {} wallet(s) on other clusters", command, skipped);
+    }
+    main
+}
+
+/// Fetches balances live, or as of `--at-slot` / `--at-time` when given.
+pub async fn fetch_report(
+    cli: &Cli,
+    fetcher: &BalanceFetcher,
+    config: &TokenConfig,
+) -> Result<BalanceReport, anyhow::Error> {
+    if (cli.at_slot.is_some() || cli.at_time.is_some()) && !config.clusters.is_empty() {
+        anyhow::bail!("--at-slot and --at-time do not support wallets on other clusters");
+    }
+    let slot = match (cli.at_slot, cli.at_time) {
+        (Some(slot), _) => slot,
+        (None, Some(time)) => fetcher.slot_at_time(time).await?,
+        (None, None) => {
+            let progress = WalletProgress::new(cli);
+            return Ok(fetcher
+                .fetch_report_with_progress(config, &progress)
+                .await?);
+        }
+    };
+    Ok(fetcher.fetch_report_at(config, slot).await?)
+}
+
+/// Fails when no wallet of `report` could be fetched, so long-running
+/// commands count the refresh as failed and keep their last good report.
+pub fn some_fetched(report: BalanceReport) -> Result<BalanceReport, anyhow::Error> {
+    match report.errors.iter().next() {
+        Some((wallet, error)) if report.all_failed() => anyhow::bail!(
+            "all {} wallet(s) failed, {} with: {}",
+            report.errors.len(),
+            wallet,
+            error
+        ),
+        _ => Ok(report),
+    }
+}
+
+/// Fails for commands that only make sense on live balances.
+pub fn require_live(cli: &Cli) -> Result<(), anyhow::Error> {
+    if cli.at_slot.is_some() || cli.at_time.is_some() {
+        anyhow::bail!("--at-slot and --at-time only apply to check, snapshot and reconcile");
+    }
+    Ok(())
+}
+
+/// Appends `report` to the configured history database, if any. Reports of
+/// past slots are not recorded, as they would be stored under today's date.
+#[cfg(feature = "history")]
+pub fn record_history(config: &TokenConfig, report: &BalanceReport) -> Result<(), anyhow::Error> {
+    if report.historical {
+        return Ok(());
+    }
+    if let Some(path) = &config.history_db {
+        test_solana::HistoryStore::open(path)?.record(report)?;
+    }
+    Ok(())
+}
+
+/// Writes `report` to every configured sink, trying each even if an earlier
+/// one fails.
+pub async fn publish_report(
+    config: &TokenConfig,
+    report: &BalanceReport,
+) -> Result<(), anyhow::Error> {
+    if report.historical {
+        return Ok(());
+    }
+    let mut failed = Vec::new();
+    for sink in config.sinks.sinks() {
+        if let Err(err) = sink.publish(report).await {
+            failed.push(format!("{}: {}", sink.name(), err));
+        }
+    }
+    if !failed.is_empty() {
+        anyhow::bail!("writing to sinks failed: {}", failed.join("; "));
+    }
+    Ok(())
+}
+
+/// Notifies the configured destinations of balance changes and of
+/// thresholds newly breached or recovered between consecutive reports.
+pub struct Alerting {
+    notifiers: Vec<Box<dyn Notifier>>,
+    /// Thresholds already breached, by wallet and asset, so each breach is
+    /// notified once and its recovery noticed.
+    breached: BTreeMap<(String, String), Alert>,
+}
+
+impl Alerting {
+    pub fn new(config: &TokenConfig) -> Self {
+        Alerting {
+            notifiers: config.notifications.notifiers(),
+            breached: BTreeMap::new(),
+        }
+    }
+
+    /// Switches to the notifiers of a reloaded config, remembering the
+    /// breaches already notified.
+    pub fn reconfigure(&mut self, config: &TokenConfig) {
+        self.notifiers = config.notifications.notifiers();
+    }
+
+    /// Sends out `changes` since the previous report and what changed in
+    /// `alerts`, the breaches in `report`. Failed notifiers are logged.
+    pub async fn update(
+        &mut self,
+        config: &TokenConfig,
+        report: &BalanceReport,
+        changes: Vec<BalanceChange>,
+        alerts: Vec<Alert>,
+    ) {
+        let mut breached: BTreeMap<(String, String), Alert> = alerts
+            .into_iter()
+            .map(|alert| ((alert.wallet.clone(), alert.asset.clone()), alert))
+            .collect();
+        // Wallets that failed to fetch keep their breaches until they are
+        // checked again.
+        for (key, alert) in &self.breached {
+            if !report.wallets.contains_key(&key.0) {
+                breached.insert(key.clone(), alert.clone());
+            }
+        }
+        if !self.notifiers.is_empty() {
+            let notification = Notification {
+                timestamp: report.timestamp,
+                slot: report.slot,
+                changes: changes
+                    .into_iter()
+                    .filter(|change| change.delta().abs() >= config.notifications.min_change)
+                    .collect(),
+                alerts: breached
+                    .iter()
+                    .filter(|(key, _)| !self.breached.contains_key(key))
+                    .map(|(_, alert)| alert.clone())
+                    .collect(),
+                resolved: self
+                    .breached
+                    .iter()
+                    .filter(|(key, _)| !breached.contains_key(key))
+                    .map(|(_, alert)| alert.clone())
+                    .collect(),
+            };
+            if !notification.is_empty() {
+                for notifier in &self.notifiers {
+                    if let Err(err) = notifier.notify(&notification).await {
+                        tracing::error!(
+                            notifier = notifier.name(),
+                            error = %format!("{:#}", err),
+                            "notification failed"
+                        );
+                    }
+                }
+            }
+        }
+        self.breached = breached;
+    }
+}
+
+/// SOL held across all wallets at each run recorded in `history_db`, for
+/// the chart in HTML reports.
+#[cfg(feature = "history")]
+pub fn sol_history(config: &TokenConfig) -> Result<Vec<(DateTime<Utc>, f64)>, anyhow::Error> {
+    let Some(path) = &config.history_db else {
+        return Ok(Vec::new());
+    };
+    let entries = test_solana::HistoryStore::open(path)?.query(&test_solana::HistoryQuery {
+        asset: Some(test_solana::diff::SOL_ASSET.to_string()),
+        ..Default::default()
+    })?;
+    let mut totals: std::collections::BTreeMap<DateTime<Utc>, f64> = Default::default();
+    for entry in entries {
+        *totals.entry(entry.timestamp).or_default() += entry.amount;
+    }
+    Ok(totals.into_iter().collect())
+}
+
+#[cfg(not(feature = "history"))]
+pub fn sol_history(_config: &TokenConfig) -> Result<Vec<(DateTime<Utc>, f64)>, anyhow::Error> {
+    Ok(Vec::new())
+}
+
+#[cfg(not(feature = "history"))]
+pub fn record_history(_config: &TokenConfig, _report: &BalanceReport) -> Result<(), anyhow::Error> {
+    Ok(())
+}
+
+/// How to render output, from the command line and `config`'s number
+/// format, which already has `--locale` and `--decimals` applied.
+pub fn render_options(cli: &Cli, config: &TokenConfig) -> RenderOptions {
+    let no_color = std::env::var_os("NO_COLOR").is_some_and(|value| !value.is_empty());
+    RenderOptions {
+        format: cli.format,
+        compact: cli.compact,
+        color: !cli.no_color && !no_color && io::stdout().is_terminal(),
+        sort_by: cli.sort_by,
+        template: cli.template.clone(),
+        links: None,
+        numbers: config.number_format.clone(),
+        raw: cli.raw,
+    }
+}
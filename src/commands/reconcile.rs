@@ -0,0 +1,26 @@
+use crate::cli::Cli;
+use crate::commands::{connect, fetch_report, load_config, render_options, Discrepancies};
+use std::io;
+use test_solana::{output, reconcile};
+
+pub async fn run(cli: &Cli) -> Result<(), anyhow::Error> {
+    let mut config = load_config(cli)?;
+    if config.expected_balances.is_empty() {
+        anyhow::bail!("reconcile needs `expected_balances` in the config");
+    }
+    let fetcher = connect(cli, &mut config).await?;
+    let report = fetch_report(cli, &fetcher, &config).await?;
+
+    let reconciliation = reconcile(&config, &report);
+    let mut options = render_options(cli, &config);
+    options.links = config.explorer_links();
+    output::write_reconciliation(&reconciliation, &options, &mut io::stdout().lock())?;
+
+    for (wallet, error) in &report.errors {
+        eprintln!("error: {}: {}", wallet, error);
+    }
+    if !reconciliation.discrepancies.is_empty() {
+        return Err(Discrepancies(reconciliation.discrepancies.len()).into());
+    }
+    Ok(())
+}
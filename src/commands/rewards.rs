@@ -0,0 +1,45 @@
+use crate::cli::{Cli, RewardsArgs};
+use crate::commands::{connect, load_config, main_cluster, render_options, require_live};
+use std::io;
+use test_solana::output;
+
+/// Epochs covered when only one end of the range is given.
+const DEFAULT_EPOCHS: u64 = 5;
+
+pub async fn run(cli: &Cli, args: &RewardsArgs) -> Result<(), anyhow::Error> {
+    require_live(cli)?;
+    let mut config = load_config(cli)?;
+    let fetcher = connect(cli, &mut config).await?;
+    let config = main_cluster(&config, "rewards");
+
+    let epochs = match (args.from_epoch, args.to_epoch) {
+        (None, None) => fetcher.completed_epochs(args.epochs).await?,
+        (from, to) => {
+            let to = match to {
+                Some(to) => to,
+                None => *fetcher.completed_epochs(1).await?.end(),
+            };
+            let from = from.unwrap_or_else(|| to.saturating_sub(DEFAULT_EPOCHS - 1));
+            if from > to {
+                anyhow::bail!("--from-epoch {} is after --to-epoch {}", from, to);
+            }
+            from..=to
+        }
+    };
+
+    let report = fetcher.fetch_rewards_report(&config, epochs).await?;
+    output::write_rewards(
+        &report,
+        &render_options(cli, &config),
+        &mut io::stdout().lock(),
+    )?;
+
+    if !report.errors.is_empty() && (cli.strict || report.errors.len() == config.wallets.len()) {
+        anyhow::bail!(
+            "{} of {} wallet(s) failed",
+            report.errors.len(),
+            config.wallets.len()
+        );
+    }
+    Ok(())
+}
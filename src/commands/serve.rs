@@ -0,0 +1,304 @@
+use crate::cli::{Cli, ServeArgs};
+use crate::commands::health::RefreshStatus;
+use crate::commands::{
+    connect, load_config, probe_fetcher, publish_report, record_history, require_live, some_fetched,
+};
+use axum::extract::ws::{Message, WebSocket, WebSocketUpgrade};
+use axum::extract::{Path, State};
+use axum::http::{header, StatusCode};
+use axum::response::{IntoResponse, Response};
+use axum::routing::get;
+use axum::{Json, Router};
+use serde::Serialize;
+use serde_json::json;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+use test_solana::rpc::{BreakerState, EndpointStatus};
+use test_solana::{
+    diff_reports, output, BalanceFetcher, BalanceReport, BalanceResult, TokenConfig,
+};
+use tokio::sync::broadcast::error::RecvError;
+use tokio::sync::{broadcast, RwLock};
+
+const PROMETHEUS_CONTENT_TYPE: &str = "text/plain; version=0.0.4";
+/// Change events buffered per WebSocket client before it starts missing
+/// some.
+const EVENT_BUFFER: usize = 16;
+
+struct ServeState {
+    report: Option<BalanceReport>,
+    status: RefreshStatus,
+    /// The RPC endpoints' circuit breakers after the last refresh.
+    endpoints: Vec<EndpointStatus>,
+    max_age: Duration,
+    /// JSON change events for `/ws` clients.
+    events: broadcast::Sender<String>,
+}
+
+impl ServeState {
+    /// The cached report, or why there is no fresh one.
+    fn fresh_report(&self) -> Result<&BalanceReport, (StatusCode, String)> {
+        match (&self.report, self.status.unready(Some(self.max_age))) {
+            (Some(report), None) => Ok(report),
+            (_, problem) => Err((
+                StatusCode::SERVICE_UNAVAILABLE,
+                problem.unwrap_or_else(|| "balances have not been fetched yet".to_string()),
+            )),
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct WalletResponse<'a> {
+    wallet: &'a str,
+    #[serde(flatten)]
+    result: &'a BalanceResult,
+}
+
+type SharedState = Arc<RwLock<ServeState>>;
+
+pub async fn run(cli: &Cli, args: &ServeArgs) -> Result<(), anyhow::Error> {
+    require_live(cli)?;
+    let mut config = load_config(cli)?;
+    let fetcher = connect(cli, &mut config).await?;
+    let state = Arc::new(RwLock::new(ServeState {
+        report: None,
+        status: RefreshStatus::default(),
+        endpoints: Vec::new(),
+        max_age: args.max_age.unwrap_or(args.interval * 3),
+        events: broadcast::channel(EVENT_BUFFER).0,
+    }));
+
+    let probe = probe_fetcher(cli, &config);
+    tokio::spawn(refresh_loop(
+        fetcher,
+        probe,
+        config,
+        args.interval,
+        state.clone(),
+    ));
+
+    let app = Router::new()
+        .route("/metrics", get(metrics))
+        .route("/balances", get(balances))
+        .route("/balances/:wallet", get(wallet_balances))
+        .route("/healthz", get(healthz))
+        .route("/readyz", get(readyz))
+        .route("/ws", get(ws))
+        .with_state(state);
+
+    eprintln!(
+        "serving /metrics, /balances, /healthz, /readyz and /ws on http://{}",
+        args.listen
+    );
+    axum::Server::bind(&args.listen)
+        .serve(app.into_make_service())
+        .await?;
+
+    Ok(())
+}
+
+/// Refreshes the report every `interval`, checking on the node with
+/// `probe`.
+async fn refresh_loop(
+    fetcher: BalanceFetcher,
+    probe: BalanceFetcher,
+    config: TokenConfig,
+    interval: Duration,
+    state: SharedState,
+) {
+    let mut interval = tokio::time::interval(interval);
+    loop {
+        interval.tick().await;
+        let fetched = fetcher
+            .fetch_report(&config)
+            .await
+            .map_err(anyhow::Error::from)
+            .and_then(some_fetched);
+        let rpc = probe.check_health().await;
+        {
+            let mut state = state.write().await;
+            state.status.rpc = Some(rpc);
+            state.endpoints = fetcher.endpoint_statuses();
+        }
+        match fetched {
+            Ok(report) => {
+                if let Err(err) = record_history(&config, &report) {
+                    tracing::error!(error = %format!("{:#}", err), "recording history failed");
+                }
+                if let Err(err) = publish_report(&config, &report).await {
+                    tracing::error!(error = %err, "publishing the report failed");
+                }
+                let mut state = state.write().await;
+                if let Some(previous) = &state.report {
+                    let changes = diff_reports(previous, &report);
+                    if !changes.is_empty() {
+                        let event = json!({
+                            "type": "changes",
+                            "timestamp": report.timestamp,
+                            "slot": report.slot,
+                            "changes": changes,
+                        });
+                        // Fails only when no client is connected.
+                        let _ = state.events.send(event.to_string());
+                    }
+                }
+                state.report = Some(report);
+                state.status.succeeded();
+            }
+            Err(err) => {
+                tracing::error!(error = %format!("{:#}", err), "refresh failed");
+                state.write().await.status.failed(&err);
+            }
+        }
+    }
+}
+
+async fn balances(State(state): State<SharedState>) -> Response {
+    let state = state.read().await;
+    match state.fresh_report() {
+        Ok(report) => Json(report).into_response(),
+        Err((status, message)) => error_response(status, message),
+    }
+}
+
+/// One wallet's balances, looked up by address, `.sol` domain or label.
+async fn wallet_balances(State(state): State<SharedState>, Path(wallet): Path<String>) -> Response {
+    let state = state.read().await;
+    let report = match state.fresh_report() {
+        Ok(report) => report,
+        Err((status, message)) => return error_response(status, message),
+    };
+
+    let found = report.wallets.iter().find(|(address, result)| {
+        **address == wallet
+            || result.domain.as_ref() == Some(&wallet)
+            || result.label.as_ref() == Some(&wallet)
+    });
+    if let Some((address, result)) = found {
+        return Json(WalletResponse {
+            wallet: address,
+            result,
+        })
+        .into_response();
+    }
+    match report.errors.get(&wallet) {
+        Some(err) => error_response(StatusCode::BAD_GATEWAY, err.clone()),
+        None => error_response(
+            StatusCode::NOT_FOUND,
+            format!("wallet `{}` is not monitored", wallet),
+        ),
+    }
+}
+
+/// Streams a `snapshot` event with the cached report, then a `changes`
+/// event whenever a refresh finds balances that moved.
+async fn ws(State(state): State<SharedState>, upgrade: WebSocketUpgrade) -> Response {
+    upgrade.on_upgrade(move |socket| stream_events(socket, state))
+}
+
+async fn stream_events(mut socket: WebSocket, state: SharedState) {
+    let (mut events, snapshot) = {
+        let state = state.read().await;
+        let snapshot = state
+            .report
+            .as_ref()
+            .map(|report| json!({ "type": "snapshot", "report": report }).to_string());
+        (state.events.subscribe(), snapshot)
+    };
+    if let Some(snapshot) = snapshot {
+        if socket.send(Message::Text(snapshot)).await.is_err() {
+            return;
+        }
+    }
+
+    loop {
+        tokio::select! {
+            event = events.recv() => match event {
+                Ok(event) => {
+                    if socket.send(Message::Text(event)).await.is_err() {
+                        return;
+                    }
+                }
+                Err(RecvError::Lagged(_)) => continue,
+                Err(RecvError::Closed) => return,
+            },
+            message = socket.recv() => match message {
+                Some(Ok(Message::Close(_))) | Some(Err(_)) | None => return,
+                Some(Ok(_)) => {}
+            },
+        }
+    }
+}
+
+/// Whether the RPC node answered its last health check.
+async fn healthz(State(state): State<SharedState>) -> Response {
+    state.read().await.status.healthz()
+}
+
+/// Whether balances are fresh, as the JSON endpoints need them.
+async fn readyz(State(state): State<SharedState>) -> Response {
+    let state = state.read().await;
+    state.status.readyz(Some(state.max_age))
+}
+
+fn error_response(status: StatusCode, message: impl Into<String>) -> Response {
+    (status, Json(json!({ "error": message.into() }))).into_response()
+}
+
+async fn metrics(State(state): State<SharedState>) -> Response {
+    let state = state.read().await;
+
+    let mut body = Vec::new();
+    if let Some(report) = &state.report {
+        if let Err(err) = output::write_prometheus(report, &mut body) {
+            return (StatusCode::INTERNAL_SERVER_ERROR, err.to_string()).into_response();
+        }
+    }
+
+    let last_success = state
+        .status
+        .last_success
+        .and_then(|time| time.duration_since(SystemTime::UNIX_EPOCH).ok())
+        .map_or(0.0, |elapsed| elapsed.as_secs_f64());
+    let mut breakers = String::new();
+    if !state.endpoints.is_empty() {
+        breakers.push_str(
+            "# HELP solana_balance_rpc_circuit_open Whether requests skip the RPC endpoint after repeated failures.\n\
+             # TYPE solana_balance_rpc_circuit_open gauge\n",
+        );
+        for (index, endpoint) in state.endpoints.iter().enumerate() {
+            breakers.push_str(&format!(
+                "solana_balance_rpc_circuit_open{{endpoint=\"{}\",index=\"{}\"}} {}\n",
+                endpoint.host,
+                index,
+                u8::from(endpoint.state == BreakerState::Open)
+            ));
+        }
+        breakers.push_str(
+            "# HELP solana_balance_rpc_circuit_trips_total Times the RPC endpoint's circuit breaker opened.\n\
+             # TYPE solana_balance_rpc_circuit_trips_total counter\n",
+        );
+        for (index, endpoint) in state.endpoints.iter().enumerate() {
+            breakers.push_str(&format!(
+                "solana_balance_rpc_circuit_trips_total{{endpoint=\"{}\",index=\"{}\"}} {}\n",
+                endpoint.host, index, endpoint.trips
+            ));
+        }
+    }
+    let body = format!(
+        "{}{}\
+         # HELP solana_balance_last_refresh_timestamp_seconds Time of the last successful refresh.\n\
+         # TYPE solana_balance_last_refresh_timestamp_seconds gauge\n\
+         solana_balance_last_refresh_timestamp_seconds {}\n\
+         # HELP solana_balance_refresh_errors_total Failed background refreshes.\n\
+         # TYPE solana_balance_refresh_errors_total counter\n\
+         solana_balance_refresh_errors_total {}\n",
+        String::from_utf8_lossy(&body),
+        breakers,
+        last_success,
+        state.status.refresh_errors
+    );
+
+    ([(header::CONTENT_TYPE, PROMETHEUS_CONTENT_TYPE)], body).into_response()
+}
@@ -0,0 +1,48 @@
+use crate::cli::{Cli, SnapshotArgs};
+use crate::commands::{connect, fetch_report, load_config, publish_report, record_history};
+use std::path::PathBuf;
+
+pub async fn run(cli: &Cli, args: &SnapshotArgs) -> Result<(), anyhow::Error> {
+    let mut config = load_config(cli)?;
+
+    let fetcher = connect(cli, &mut config).await?;
+    let report = fetch_report(cli, &fetcher, &config).await?;
+
+    let path = args.path.clone().unwrap_or_else(|| {
+        PathBuf::from(format!(
+            "snapshot-{}.json",
+            report.timestamp.format("%Y%m%dT%H%M%SZ")
+        ))
+    });
+    report.save(&path)?;
+    // After saving, so a sink that is down cannot hold back the report
+    // or its exit code.
+    if let Err(err) = record_history(&config, &report) {
+        tracing::error!(error = %format!("{:#}", err), "recording history failed");
+    }
+    if let Err(err) = publish_report(&config, &report).await {
+        tracing::error!(error = %err, "publishing the report failed");
+    }
+
+    for (wallet, error) in &report.errors {
+        eprintln!("error: {}: {}", wallet, error);
+    }
+    eprintln!(
+        "wrote {} ({} wallets, slot {})",
+        path.display(),
+        report.wallets.len(),
+        report
+            .slot
+            .map_or_else(|| "unknown".to_string(), |slot| slot.to_string())
+    );
+
+    if report.all_failed() || (cli.strict && !report.errors.is_empty()) {
+        anyhow::bail!(
+            "{} of {} wallet(s) failed",
+            report.errors.len(),
+            config.wallets.len()
+        );
+    }
+
+    Ok(())
+}
@@ -0,0 +1,399 @@
+use crate::cli::{Cli, TuiArgs};
+use crate::commands::{connect, load_config, record_history, require_live};
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use ratatui::layout::{Constraint, Layout};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Cell, Row, Table, TableState};
+use ratatui::{DefaultTerminal, Frame};
+use solana_sdk::pubkey::Pubkey;
+use std::collections::HashMap;
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime};
+use test_solana::diff::SOL_ASSET;
+use test_solana::output::format_number;
+use test_solana::pricing::Currency;
+use test_solana::{
+    diff_reports, BalanceFetcher, BalanceReport, BalanceResult, BalanceTrends, SortOrder,
+    TokenAccountDetail, TokenConfig,
+};
+use tokio::sync::mpsc::{self, UnboundedSender};
+
+/// How long a changed value stays highlighted.
+const FLASH_DURATION: Duration = Duration::from_secs(3);
+/// How often the screen is redrawn, so highlights fade without input.
+const REDRAW_INTERVAL: Duration = Duration::from_millis(250);
+
+enum Message {
+    Key(KeyEvent),
+    Report(Result<BalanceReport, String>),
+    Details(String, Result<Vec<TokenAccountDetail>, String>),
+}
+
+pub async fn run(cli: &Cli, args: &TuiArgs) -> Result<(), anyhow::Error> {
+    require_live(cli)?;
+    let mut config = load_config(cli)?;
+    let fetcher = Arc::new(connect(cli, &mut config).await?);
+    let config = Arc::new(config);
+
+    let (sender, receiver) = mpsc::unbounded_channel();
+    let keys = sender.clone();
+    // Reading terminal events blocks, so it gets a thread of its own.
+    std::thread::spawn(move || {
+        while let Ok(event) = event::read() {
+            if let Event::Key(key) = event {
+                if key.kind == KeyEventKind::Press && keys.send(Message::Key(key)).is_err() {
+                    break;
+                }
+            }
+        }
+    });
+
+    let mut terminal = ratatui::init();
+    let mut dashboard = Dashboard {
+        fetcher,
+        config,
+        sender,
+        sort_by: cli.sort_by,
+        report: None,
+        updated_at: None,
+        error: None,
+        refreshing: false,
+        flashes: HashMap::new(),
+        selected: 0,
+        expanded: None,
+        details: HashMap::new(),
+        trends: BalanceTrends::new(args.trend_length),
+        trend_width: match args.trend_length {
+            0 => 0,
+            length => length.clamp(5, u16::MAX.into()) as u16,
+        },
+    };
+    let result = dashboard.run(&mut terminal, receiver, args.interval).await;
+    ratatui::restore();
+    result
+}
+
+struct Dashboard {
+    fetcher: Arc<BalanceFetcher>,
+    config: Arc<TokenConfig>,
+    sender: UnboundedSender<Message>,
+    sort_by: SortOrder,
+    report: Option<BalanceReport>,
+    updated_at: Option<String>,
+    /// Why the last refresh failed, if it did.
+    error: Option<String>,
+    refreshing: bool,
+    /// When each (wallet, asset) balance last changed.
+    flashes: HashMap<(String, String), Instant>,
+    /// Index of the selected wallet.
+    selected: usize,
+    expanded: Option<String>,
+    /// Token accounts of expanded wallets, by wallet address.
+    details: HashMap<String, Result<Vec<TokenAccountDetail>, String>>,
+    trends: BalanceTrends,
+    trend_width: u16,
+}
+
+impl Dashboard {
+    async fn run(
+        &mut self,
+        terminal: &mut DefaultTerminal,
+        mut receiver: mpsc::UnboundedReceiver<Message>,
+        interval: Duration,
+    ) -> Result<(), anyhow::Error> {
+        let mut refresh = tokio::time::interval(interval);
+        let mut redraw = tokio::time::interval(REDRAW_INTERVAL);
+        loop {
+            terminal.draw(|frame| self.draw(frame))?;
+            tokio::select! {
+                _ = refresh.tick() => self.refresh(),
+                _ = redraw.tick() => {}
+                Some(message) = receiver.recv() => match message {
+                    Message::Key(key) => {
+                        let ctrl_c = key.modifiers.contains(KeyModifiers::CONTROL)
+                            && key.code == KeyCode::Char('c');
+                        match key.code {
+                            KeyCode::Char('q') | KeyCode::Esc => return Ok(()),
+                            _ if ctrl_c => return Ok(()),
+                            KeyCode::Up | KeyCode::Char('k') => {
+                                self.selected = self.selected.saturating_sub(1);
+                            }
+                            KeyCode::Down | KeyCode::Char('j') => {
+                                self.selected = (self.selected + 1)
+                                    .min(self.wallets().len().saturating_sub(1));
+                            }
+                            KeyCode::Enter | KeyCode::Char(' ') => self.toggle_expanded(),
+                            KeyCode::Char('r') => {
+                                self.refresh();
+                                refresh.reset();
+                            }
+                            _ => {}
+                        }
+                    }
+                    Message::Report(result) => self.update(result),
+                    Message::Details(wallet, details) => {
+                        self.details.insert(wallet, details);
+                    }
+                },
+            }
+        }
+    }
+
+    fn wallets(&self) -> Vec<(&String, &BalanceResult)> {
+        self.report
+            .as_ref()
+            .map(|report| report.sorted_wallets(self.sort_by))
+            .unwrap_or_default()
+    }
+
+    fn refresh(&mut self) {
+        if self.refreshing {
+            return;
+        }
+        self.refreshing = true;
+        let fetcher = self.fetcher.clone();
+        let config = self.config.clone();
+        let sender = self.sender.clone();
+        tokio::spawn(async move {
+            let report = fetcher
+                .fetch_report(&config)
+                .await
+                .map_err(|err| format!("{:#}", anyhow::Error::from(err)));
+            let _ = sender.send(Message::Report(report));
+        });
+    }
+
+    fn update(&mut self, result: Result<BalanceReport, String>) {
+        self.refreshing = false;
+        let report = match result {
+            Ok(report) => report,
+            Err(err) => {
+                self.error = Some(err);
+                return;
+            }
+        };
+        if let Err(err) = record_history(&self.config, &report) {
+            self.error = Some(format!("recording history failed: {}", err));
+        } else {
+            self.error = None;
+        }
+
+        if let Some(previous) = &self.report {
+            let now = Instant::now();
+            for change in diff_reports(previous, &report) {
+                self.flashes.insert((change.wallet, change.asset), now);
+            }
+        }
+        self.trends.record(&report);
+        self.updated_at = Some(humantime::format_rfc3339_seconds(SystemTime::now()).to_string());
+        self.report = Some(report);
+        self.selected = self.selected.min(self.wallets().len().saturating_sub(1));
+
+        // Token accounts may have changed too.
+        self.details.clear();
+        if let Some(wallet) = self.expanded.clone() {
+            self.load_details(wallet);
+        }
+    }
+
+    fn toggle_expanded(&mut self) {
+        let Some(wallet) = self
+            .wallets()
+            .get(self.selected)
+            .map(|(wallet, _)| wallet.to_string())
+        else {
+            return;
+        };
+        if self.expanded.as_ref() == Some(&wallet) {
+            self.expanded = None;
+            return;
+        }
+        if !self.details.contains_key(&wallet) {
+            self.load_details(wallet.clone());
+        }
+        self.expanded = Some(wallet);
+    }
+
+    fn load_details(&self, wallet: String) {
+        let fetcher = self.fetcher.clone();
+        let sender = self.sender.clone();
+        tokio::spawn(async move {
+            let details = match Pubkey::from_str(&wallet) {
+                Ok(pubkey) => fetcher
+                    .get_token_account_details(&pubkey, None)
+                    .await
+                    .map_err(|err| format!("{:#}", err)),
+                Err(err) => Err(err.to_string()),
+            };
+            let _ = sender.send(Message::Details(wallet, details));
+        });
+    }
+
+    fn is_flashing(&self, wallet: &str, asset: &str) -> bool {
+        self.flashes
+            .get(&(wallet.to_string(), asset.to_string()))
+            .is_some_and(|changed| changed.elapsed() < FLASH_DURATION)
+    }
+
+    fn flash_style(&self, wallet: &str, asset: &str) -> Style {
+        flash_if(self.is_flashing(wallet, asset))
+    }
+
+    fn draw(&self, frame: &mut Frame) {
+        let [main, status] =
+            Layout::vertical([Constraint::Min(0), Constraint::Length(1)]).areas(frame.area());
+
+        let dim = Style::new().add_modifier(Modifier::DIM);
+        let trend = Style::new().fg(Color::Cyan);
+        let currency = self
+            .report
+            .as_ref()
+            .map(BalanceReport::display_currency)
+            .unwrap_or_default();
+        let mut rows = Vec::new();
+        let mut selected_row = None;
+        for (index, (wallet, result)) in self.wallets().into_iter().enumerate() {
+            if index == self.selected {
+                selected_row = Some(rows.len());
+            }
+            let expanded = self.expanded.as_deref() == Some(wallet.as_str());
+            let name = match result.label.as_ref().or(result.domain.as_ref()) {
+                Some(name) => format!("{} ({})", name, wallet),
+                None => wallet.to_string(),
+            };
+            let tokens_changed = result
+                .token_balances
+                .keys()
+                .any(|ticker| self.is_flashing(wallet, ticker));
+            let held = result
+                .token_balances
+                .values()
+                .filter(|balance| balance.raw_amount > 0)
+                .count();
+            rows.push(Row::new([
+                Cell::from(format!("{} {}", if expanded { "▾" } else { "▸" }, name)),
+                Cell::from(Line::from(format_number(result.sol_balance, 4)).right_aligned())
+                    .style(self.flash_style(wallet, SOL_ASSET)),
+                Cell::from(self.trends.sparkline(wallet, SOL_ASSET)).style(trend),
+                Cell::from(Line::from(held.to_string()).right_aligned())
+                    .style(flash_if(tokens_changed)),
+                Cell::from(Line::from(money(&currency, result.total_usd)).right_aligned()),
+            ]));
+            if !expanded {
+                continue;
+            }
+
+            if let Some(stake) = &result.stake {
+                rows.push(Row::new([
+                    Cell::from(format!("    staked SOL ({} accounts)", stake.accounts)),
+                    Cell::from(Line::from(format_number(stake.total, 4)).right_aligned()),
+                ]));
+            }
+            for (ticker, balance) in result.sorted_tokens(self.sort_by) {
+                rows.push(Row::new([
+                    Cell::from(format!("    {}", ticker)),
+                    Cell::from(Line::from(format_number(balance.amount, 4)).right_aligned())
+                        .style(self.flash_style(wallet, ticker)),
+                    Cell::from(self.trends.sparkline(wallet, ticker)).style(trend),
+                    Cell::from(""),
+                    Cell::from(Line::from(money(&currency, balance.usd_value)).right_aligned()),
+                ]));
+                if let Some(Ok(details)) = self.details.get(wallet.as_str()) {
+                    for account in details
+                        .iter()
+                        .filter(|account| account.mint == balance.mint)
+                    {
+                        rows.push(
+                            Row::new([
+                                Cell::from(format!("      {}", account.address)),
+                                Cell::from(
+                                    Line::from(format_number(account.ui_amount(), 4))
+                                        .right_aligned(),
+                                ),
+                                Cell::from(""),
+                                Cell::from(account.program_name()),
+                            ])
+                            .style(dim),
+                        );
+                    }
+                }
+            }
+            match self.details.get(wallet.as_str()) {
+                None => rows.push(Row::new(["      loading token accounts…"]).style(dim)),
+                Some(Err(err)) => rows.push(
+                    Row::new([format!("      token accounts: {}", err)])
+                        .style(Style::new().fg(Color::Red)),
+                ),
+                Some(Ok(_)) => {}
+            }
+        }
+        if let Some(report) = &self.report {
+            for (wallet, error) in &report.errors {
+                rows.push(
+                    Row::new([format!("  {}", wallet), format!("error: {}", error)])
+                        .style(Style::new().fg(Color::Red)),
+                );
+            }
+        }
+
+        let title = match self.report.as_ref().and_then(|report| report.total_usd) {
+            Some(total) => format!(" Balances · total {} ", money(&currency, Some(total))),
+            None => " Balances ".to_string(),
+        };
+        let header = Row::new([
+            Cell::from("Wallet"),
+            Cell::from(Line::from("SOL").right_aligned()),
+            Cell::from("Trend"),
+            Cell::from(Line::from("Tokens").right_aligned()),
+            Cell::from(Line::from(format!("{} Value", currency)).right_aligned()),
+        ])
+        .style(Style::new().add_modifier(Modifier::BOLD));
+        let table = Table::new(
+            rows,
+            [
+                Constraint::Min(30),
+                Constraint::Length(20),
+                Constraint::Length(self.trend_width),
+                Constraint::Length(12),
+                Constraint::Length(16),
+            ],
+        )
+        .header(header)
+        .block(Block::bordered().title(title))
+        .row_highlight_style(Style::new().add_modifier(Modifier::REVERSED));
+        let mut state = TableState::default().with_selected(selected_row);
+        frame.render_stateful_widget(table, main, &mut state);
+
+        let mut spans = Vec::new();
+        match (&self.updated_at, self.refreshing) {
+            (_, true) => spans.push(Span::raw("refreshing…")),
+            (Some(updated_at), false) => spans.push(Span::raw(format!("updated {}", updated_at))),
+            (None, false) => {}
+        }
+        if let Some(err) = &self.error {
+            spans.push(Span::raw("  "));
+            spans.push(Span::styled(err.clone(), Style::new().fg(Color::Red)));
+        }
+        spans.push(Span::styled(
+            "  ↑↓ select  enter expand  r refresh  q quit",
+            dim,
+        ));
+        frame.render_widget(Line::from(spans), status);
+    }
+}
+
+fn flash_if(changed: bool) -> Style {
+    if changed {
+        Style::new().fg(Color::Black).bg(Color::Yellow)
+    } else {
+        Style::new()
+    }
+}
+
+fn money(currency: &Currency, value: Option<f64>) -> String {
+    value
+        .map(|value| currency.format(value))
+        .unwrap_or_default()
+}
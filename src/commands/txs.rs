@@ -0,0 +1,24 @@
+use crate::cli::{Cli, TxsArgs};
+use crate::commands::{connect, load_config, render_options, require_live};
+use std::io;
+use test_solana::output;
+
+pub async fn run(cli: &Cli, args: &TxsArgs) -> Result<(), anyhow::Error> {
+    require_live(cli)?;
+    let mut config = load_config(cli)?;
+    let fetcher = connect(cli, &mut config).await?;
+
+    let wallet = fetcher.resolve_wallet(&args.wallet).await?;
+    let transactions = fetcher
+        .get_recent_transactions(&wallet, args.limit, &config.tokens)
+        .await?;
+    let mut options = render_options(cli, &config);
+    options.links = config.explorer_links();
+    output::write_transactions(
+        &wallet.to_string(),
+        &transactions,
+        &options,
+        &mut io::stdout().lock(),
+    )?;
+    Ok(())
+}
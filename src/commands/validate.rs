@@ -0,0 +1,310 @@
+use crate::cli::{Cli, ValidateArgs};
+use crate::commands::{build_fetcher, load_config, reads_config_file};
+use solana_sdk::pubkey::Pubkey;
+use std::collections::HashMap;
+use std::fs;
+use std::str::FromStr;
+use test_solana::diff::SOL_ASSET;
+use test_solana::{sns, ExpectedBalance};
+
+/// A problem with the config and, when it can be found, the text in the
+/// file it is about.
+struct Problem {
+    message: String,
+    /// Text to point at, and which of its occurrences (from 0).
+    at: Option<(String, usize)>,
+}
+
+impl Problem {
+    fn new(message: impl Into<String>) -> Self {
+        Problem {
+            message: message.into(),
+            at: None,
+        }
+    }
+
+    fn at(mut self, text: &str, occurrence: usize) -> Self {
+        self.at = Some((text.to_string(), occurrence));
+        self
+    }
+}
+
+pub async fn run(cli: &Cli, args: &ValidateArgs) -> Result<(), anyhow::Error> {
+    let (source, content) = if reads_config_file(cli) {
+        (
+            cli.config.display().to_string(),
+            fs::read_to_string(&cli.config)?,
+        )
+    } else {
+        ("command line".to_string(), String::new())
+    };
+    let config = match load_config(cli) {
+        Ok(config) => config,
+        Err(err) => {
+            eprintln!("error: {}", err);
+            if let Some((line, column)) = error_location(&err) {
+                print_snippet(cli, &content, line, Some(column));
+            }
+            anyhow::bail!("{} could not be parsed", source);
+        }
+    };
+
+    let mut problems = Vec::new();
+    let mut seen_wallets: HashMap<&str, usize> = HashMap::new();
+    let mut seen_labels: HashMap<&str, usize> = HashMap::new();
+    for wallet in &config.wallets {
+        let address = wallet.address.as_str();
+        let result = if sns::is_sol_domain(address) {
+            sns::domain_address(address).map(drop)
+        } else {
+            Pubkey::from_str(address).map(drop).map_err(Into::into)
+        };
+        if let Err(err) = result {
+            problems.push(Problem::new(format!("wallet `{}`: {}", address, err)).at(address, 0));
+        }
+
+        let count = seen_wallets.entry(address).or_default();
+        if *count > 0 {
+            problems.push(
+                Problem::new(format!("wallet `{}` is listed more than once", address))
+                    .at(address, *count),
+            );
+        }
+        *count += 1;
+        if let Some(label) = &wallet.label {
+            let count = seen_labels.entry(label).or_default();
+            if *count > 0 {
+                problems.push(
+                    Problem::new(format!("wallet label `{}` is used more than once", label))
+                        .at(label, *count),
+                );
+            }
+            *count += 1;
+        }
+    }
+
+    let mut mints = Vec::new();
+    let mut seen_mints: HashMap<&str, usize> = HashMap::new();
+    let mut seen_tickers: HashMap<String, usize> = HashMap::new();
+    let mut ticker_texts: HashMap<&str, usize> = HashMap::new();
+    for token in &config.tokens {
+        let name = token
+            .ticker
+            .as_ref()
+            .map_or_else(String::new, |ticker| format!("{} ", ticker));
+        match Pubkey::from_str(&token.address) {
+            Ok(mint) => mints.push((mint, token)),
+            Err(err) => problems.push(
+                Problem::new(format!("token {}`{}`: {}", name, token.address, err))
+                    .at(&token.address, 0),
+            ),
+        }
+
+        let count = seen_mints.entry(&token.address).or_default();
+        if *count > 0 {
+            problems.push(
+                Problem::new(format!(
+                    "token {}`{}` is listed more than once",
+                    name, token.address
+                ))
+                .at(&token.address, *count),
+            );
+        }
+        *count += 1;
+        if let Some(ticker) = &token.ticker {
+            // Tickers match case-insensitively, but the file is searched for
+            // the text as written.
+            let written = ticker_texts.entry(ticker).or_default();
+            let count = seen_tickers.entry(ticker.to_lowercase()).or_default();
+            if *count > 0 {
+                problems.push(
+                    Problem::new(format!(
+                        "ticker `{}` is used by more than one token",
+                        ticker
+                    ))
+                    .at(ticker, *written),
+                );
+            }
+            *count += 1;
+            *written += 1;
+        }
+    }
+
+    for (wallet, thresholds) in &config.wallet_thresholds {
+        if !config.wallets.iter().any(|entry| entry.is_named(wallet)) {
+            problems.push(
+                Problem::new(format!(
+                    "wallet_thresholds: `{}` is not in `wallets`",
+                    wallet
+                ))
+                .at(wallet, 0),
+            );
+        }
+        // Tickers read from on-chain metadata are only known at runtime.
+        if config.tokens.iter().any(|token| token.ticker.is_none()) {
+            continue;
+        }
+        for ticker in thresholds.min_balance.keys() {
+            if !config
+                .tokens
+                .iter()
+                .any(|token| token.ticker.as_ref() == Some(ticker) || &token.address == ticker)
+            {
+                problems.push(
+                    Problem::new(format!(
+                        "wallet_thresholds for `{}`: unknown token `{}`",
+                        wallet, ticker
+                    ))
+                    .at(ticker, 0),
+                );
+            }
+        }
+    }
+
+    for (wallet, assets) in &config.expected_balances {
+        let tokens = match config.wallets.iter().find(|entry| entry.is_named(wallet)) {
+            Some(entry) => config.tokens_for(entry),
+            None => {
+                problems.push(
+                    Problem::new(format!(
+                        "expected_balances: `{}` is not in `wallets`",
+                        wallet
+                    ))
+                    .at(wallet, 0),
+                );
+                config.tokens.clone()
+            }
+        };
+        for (asset, expected) in assets {
+            let problem = match *expected {
+                ExpectedBalance::Range {
+                    min: Some(min),
+                    max: Some(max),
+                } if min > max => Some(format!("min {} is above max {}", min, max)),
+                ExpectedBalance::Range {
+                    min: None,
+                    max: None,
+                } => Some("needs an amount, or a min or max".to_string()),
+                _ => None,
+            };
+            if let Some(problem) = problem {
+                problems.push(
+                    Problem::new(format!(
+                        "expected_balances for `{}`: {} {}",
+                        wallet, asset, problem
+                    ))
+                    .at(asset, 0),
+                );
+            }
+            if asset == SOL_ASSET || tokens.iter().any(|token| token.ticker.is_none()) {
+                continue;
+            }
+            if !tokens
+                .iter()
+                .any(|token| token.ticker.as_ref() == Some(asset) || &token.address == asset)
+            {
+                problems.push(
+                    Problem::new(format!(
+                        "expected_balances for `{}`: unknown token `{}`",
+                        wallet, asset
+                    ))
+                    .at(asset, 0),
+                );
+            }
+        }
+    }
+
+    if config.max_rps.is_some_and(|max_rps| max_rps <= 0.0) {
+        problems.push(Problem::new("max_rps must be positive").at("max_rps", 0));
+    }
+
+    if config.token_filter.verified_only && config.token_list.is_none() {
+        problems.push(
+            Problem::new("token_filter.verified_only needs a token_list").at("verified_only", 0),
+        );
+    }
+    if config.token_filter.min_usd_value.is_some() && config.pricing.is_none() {
+        problems
+            .push(Problem::new("token_filter.min_usd_value needs pricing").at("min_usd_value", 0));
+    }
+
+    if args.check_mints && !mints.is_empty() {
+        let fetcher = build_fetcher(cli, &config).await?;
+        let addresses: Vec<Pubkey> = mints.iter().map(|(mint, _)| *mint).collect();
+        let found = fetcher.check_mints(&addresses).await?;
+        for (mint, token) in &mints {
+            if let Some(problem) = found.get(mint) {
+                let name = token
+                    .ticker
+                    .as_ref()
+                    .map_or_else(String::new, |ticker| format!("{} ", ticker));
+                problems.push(
+                    Problem::new(format!("token {}`{}` {}", name, mint, problem))
+                        .at(&token.address, 0),
+                );
+            }
+        }
+    }
+
+    if !problems.is_empty() {
+        for problem in &problems {
+            eprintln!("error: {}", problem.message);
+            let line = problem
+                .at
+                .as_ref()
+                .and_then(|(text, occurrence)| find_line(&content, text, *occurrence));
+            if let Some(line) = line {
+                print_snippet(cli, &content, line, None);
+            }
+        }
+        anyhow::bail!("{} problem(s) found in {}", problems.len(), source);
+    }
+
+    println!(
+        "{}: OK ({} wallets, {} tokens{})",
+        source,
+        config.wallets.len(),
+        config.tokens.len(),
+        if args.check_mints {
+            ", mints verified"
+        } else {
+            ""
+        }
+    );
+    Ok(())
+}
+
+/// The one-based line and column a parse error points at.
+fn error_location(err: &anyhow::Error) -> Option<(usize, usize)> {
+    if let Some(err) = err.downcast_ref::<serde_yaml::Error>() {
+        let location = err.location()?;
+        return Some((location.line(), location.column()));
+    }
+    if let Some(err) = err.downcast_ref::<serde_json::Error>() {
+        return (err.line() > 0).then(|| (err.line(), err.column()));
+    }
+    // TOML errors already quote the offending line.
+    None
+}
+
+/// The one-based line of the `occurrence`th appearance of `text`.
+fn find_line(content: &str, text: &str, occurrence: usize) -> Option<usize> {
+    let (offset, _) = content.match_indices(text).nth(occurrence)?;
+    Some(content[..offset].matches('\n').count() + 1)
+}
+
+fn print_snippet(cli: &Cli, content: &str, line: usize, column: Option<usize>) {
+    let Some(text) = content.lines().nth(line.saturating_sub(1)) else {
+        return;
+    };
+    let gutter = " ".repeat(line.to_string().len());
+    match column {
+        Some(column) => eprintln!("{}--> {}:{}:{}", gutter, cli.config.display(), line, column),
+        None => eprintln!("{}--> {}:{}", gutter, cli.config.display(), line),
+    }
+    eprintln!("{} |", gutter);
+    eprintln!("{} | {}", line, text);
+    if let Some(column) = column {
+        eprintln!("{} | {}^", gutter, " ".repeat(column.saturating_sub(1)));
+    }
+}
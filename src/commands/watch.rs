@@ -0,0 +1,321 @@
+use crate::cli::{Cli, WatchArgs};
+use crate::commands::{
+    connect, load_config, publish_report, record_history, render_options, require_live, Alerting,
+    ConfigReloader,
+};
+use futures::future::join_all;
+use solana_sdk::pubkey::Pubkey;
+use std::collections::{BTreeMap, BTreeSet};
+use std::io::{self, IsTerminal, Write};
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::SystemTime;
+use test_solana::{
+    associated_token_address, check_thresholds, diff_reports, output, BalanceChange,
+    BalanceFetcher, BalanceReport, BalanceTrends, RenderOptions, TokenConfig, WalletTransaction,
+    TOKEN_PROGRAM_IDS,
+};
+use tokio::sync::mpsc;
+
+const CLEAR_SCREEN: &str = "\x1b[2J\x1b[H";
+
+/// Notification updates queued before the screen catches up.
+const UPDATE_BUFFER: usize = 64;
+
+/// Most transactions looked up per wallet and refresh with `--activity`.
+const ACTIVITY_LIMIT: usize = 20;
+
+pub async fn run(cli: &Cli, args: &WatchArgs) -> Result<(), anyhow::Error> {
+    require_live(cli)?;
+    let mut config = load_config(cli)?;
+    let fetcher = connect(cli, &mut config).await?;
+    let mut options = render_options(cli, &config);
+    options.links = config.explorer_links();
+    let mut reloader = ConfigReloader::new(cli);
+    let mut watcher = Watcher {
+        alerting: Alerting::new(&config),
+        config: Arc::new(config),
+        fetcher: Arc::new(fetcher),
+        args,
+        redraw: options.is_text() && io::stdout().is_terminal(),
+        options,
+        previous: None,
+        trends: BalanceTrends::new(args.trend_length),
+    };
+
+    if args.subscribe {
+        // A reloaded config is subscribed to afresh.
+        loop {
+            let config = Arc::clone(&watcher.config);
+            let fetcher = Arc::clone(&watcher.fetcher);
+            if !config.clusters.is_empty() {
+                anyhow::bail!("--subscribe does not support wallets on other clusters");
+            }
+            let (updates, mut received) = mpsc::channel(UPDATE_BUFFER);
+            let subscription = async {
+                #[cfg(feature = "geyser")]
+                if let Some(geyser) = &config.geyser {
+                    return fetcher
+                        .subscribe_reports_geyser(&config, geyser, updates)
+                        .await;
+                }
+                fetcher
+                    .subscribe_reports(&config, &config.ws_url(), updates)
+                    .await
+            };
+            let display = async {
+                while let Some(update) = received.recv().await {
+                    match update {
+                        Ok(report) => watcher.show(report).await?,
+                        Err(err) => {
+                            tracing::warn!(error = %format!("{:#}", err), "resubscribing")
+                        }
+                    }
+                }
+                Ok(())
+            };
+            let reload = async {
+                loop {
+                    reloader.changed().await;
+                    if let Some(reloaded) = reloader.reload(cli, &config).await {
+                        return reloaded;
+                    }
+                }
+            };
+            let reloaded = tokio::select! {
+                result = async { tokio::try_join!(subscription, display) } => {
+                    result?;
+                    return Ok(());
+                }
+                reloaded = reload => reloaded,
+            };
+            watcher.reconfigure(reloaded);
+        }
+    }
+
+    let mut interval = tokio::time::interval(args.interval);
+    loop {
+        tokio::select! {
+            _ = interval.tick() => {}
+            _ = reloader.changed() => {
+                if let Some(reloaded) = reloader.reload(cli, &watcher.config).await {
+                    watcher.reconfigure(reloaded);
+                }
+                continue;
+            }
+        }
+        match watcher
+            .fetcher
+            .fetch_report(&watcher.config)
+            .await
+            .map_err(anyhow::Error::from)
+        {
+            Ok(report) => watcher.show(report).await?,
+            Err(err) => tracing::error!(error = %format!("{:#}", err), "refresh failed"),
+        }
+    }
+}
+
+/// What watch mode remembers between reports.
+struct Watcher<'a> {
+    config: Arc<TokenConfig>,
+    fetcher: Arc<BalanceFetcher>,
+    args: &'a WatchArgs,
+    options: RenderOptions,
+    redraw: bool,
+    alerting: Alerting,
+    previous: Option<BalanceReport>,
+    trends: BalanceTrends,
+}
+
+impl Watcher<'_> {
+    /// Carries on with a reloaded config and its fetcher.
+    fn reconfigure(&mut self, (config, fetcher): (TokenConfig, BalanceFetcher)) {
+        self.options.links = config.explorer_links();
+        self.options.numbers = config.number_format.clone();
+        self.alerting.reconfigure(&config);
+        self.config = Arc::new(config);
+        self.fetcher = Arc::new(fetcher);
+    }
+
+    /// Records, prints and alerts on a new report.
+    async fn show(&mut self, report: BalanceReport) -> Result<(), anyhow::Error> {
+        let config = Arc::clone(&self.config);
+        let now = timestamp();
+        if let Err(err) = record_history(&config, &report) {
+            tracing::error!(error = %format!("{:#}", err), "recording history failed");
+        }
+        if let Err(err) = publish_report(&config, &report).await {
+            tracing::error!(error = %err, "publishing the report failed");
+        }
+
+        self.trends.record(&report);
+        let mut changes = Vec::new();
+        if let Some(previous) = &self.previous {
+            changes = diff_reports(previous, &report);
+            if self.args.activity {
+                self.attach_transactions(previous, &report, &mut changes)
+                    .await;
+            }
+        }
+
+        let mut stdout = io::stdout().lock();
+        if self.options.is_text() {
+            if self.redraw {
+                write!(stdout, "{}", CLEAR_SCREEN)?;
+            }
+            writeln!(stdout, "Last updated: {}\n", now)?;
+            let highlighted = if self.redraw { &changes[..] } else { &[] };
+            let trends = (self.args.trend_length > 0).then_some(&self.trends);
+            output::write_text_with_changes(
+                &report,
+                highlighted,
+                trends,
+                &self.options,
+                &mut stdout,
+            )?;
+            write_transactions(&changes, &mut stdout)?;
+        } else {
+            output::render(&report, &self.options, &mut stdout)?;
+        }
+        stdout.flush()?;
+        drop(stdout);
+
+        let alerts = check_thresholds(&config, &report);
+        for alert in &alerts {
+            eprintln!("[{}] warning: {}", now, alert);
+        }
+
+        self.alerting
+            .update(&config, &report, changes, alerts)
+            .await;
+
+        self.previous = Some(report);
+        Ok(())
+    }
+
+    /// Looks up each changed wallet's transactions since `previous`, on the
+    /// wallet and the associated token accounts of its changed tokens, and
+    /// lists them under its changes. With `--transfers`, a change only lists
+    /// the transactions that moved its asset. Wallets whose lookup fails
+    /// keep their changes without transactions.
+    async fn attach_transactions(
+        &self,
+        previous: &BalanceReport,
+        report: &BalanceReport,
+        changes: &mut [BalanceChange],
+    ) {
+        let Some(after_slot) = previous.slot else {
+            return;
+        };
+        // The previous slot is the main cluster's, so wallets on other
+        // clusters are left out.
+        let wallets: BTreeSet<String> = changes
+            .iter()
+            .filter(|change| {
+                report
+                    .wallets
+                    .get(&change.wallet)
+                    .is_none_or(|result| result.cluster.is_none())
+            })
+            .map(|change| change.wallet.clone())
+            .collect();
+        let changed = &*changes;
+        let lookups = wallets.into_iter().map(|wallet| async move {
+            let pubkey = Pubkey::from_str(&wallet)?;
+            let accounts: Vec<Pubkey> = changed
+                .iter()
+                .filter(|change| change.wallet == wallet)
+                .filter_map(|change| {
+                    let balance = [report, previous].into_iter().find_map(|report| {
+                        report
+                            .wallets
+                            .get(&wallet)?
+                            .token_balances
+                            .get(&change.asset)
+                    })?;
+                    Pubkey::from_str(&balance.mint).ok()
+                })
+                .flat_map(|mint| {
+                    TOKEN_PROGRAM_IDS
+                        .iter()
+                        .map(move |program_id| associated_token_address(&pubkey, program_id, &mint))
+                })
+                .collect();
+            let transactions = self
+                .fetcher
+                .get_wallet_transactions(
+                    &pubkey,
+                    &accounts,
+                    Some(after_slot),
+                    ACTIVITY_LIMIT,
+                    self.args.transfers,
+                    &self.config.tokens,
+                )
+                .await;
+            Ok::<_, anyhow::Error>((wallet, transactions?))
+        });
+
+        let mut by_wallet: BTreeMap<String, Vec<WalletTransaction>> = BTreeMap::new();
+        for lookup in join_all(lookups).await {
+            match lookup {
+                Ok((wallet, transactions)) => {
+                    by_wallet.insert(wallet, transactions);
+                }
+                Err(err) => {
+                    tracing::warn!(error = %format!("{:#}", err), "looking up transactions failed")
+                }
+            }
+        }
+        for change in changes {
+            let Some(transactions) = by_wallet.get(&change.wallet) else {
+                continue;
+            };
+            change.transactions = transactions
+                .iter()
+                .filter(|transaction| !self.args.transfers || transaction.moves(&change.asset))
+                .cloned()
+                .collect();
+        }
+    }
+}
+
+/// Lists the transactions found behind each change, if any.
+fn write_transactions(changes: &[BalanceChange], out: &mut dyn Write) -> io::Result<()> {
+    if changes.iter().all(|change| change.transactions.is_empty()) {
+        return Ok(());
+    }
+    writeln!(out, "\nTransactions since the previous refresh:")?;
+    for change in changes
+        .iter()
+        .filter(|change| !change.transactions.is_empty())
+    {
+        writeln!(
+            out,
+            "  {} {} {:+.4}",
+            change.wallet,
+            change.asset,
+            change.delta()
+        )?;
+        for transaction in &change.transactions {
+            write!(out, "    {}", transaction.signature)?;
+            if let Some(error) = &transaction.error {
+                write!(out, " (failed: {})", error)?;
+            }
+            let transfers: Vec<String> = transaction
+                .transfers
+                .iter()
+                .map(ToString::to_string)
+                .collect();
+            if !transfers.is_empty() {
+                write!(out, " {}", transfers.join(", "))?;
+            }
+            writeln!(out)?;
+        }
+    }
+    Ok(())
+}
+
+fn timestamp() -> humantime::Rfc3339Timestamp {
+    humantime::format_rfc3339_seconds(SystemTime::now())
+}
@@ -0,0 +1,1082 @@
+use crate::cache::CacheConfig;
+use crate::chain::ChainBackend;
+use crate::derive::DerivedWallets;
+use crate::error::BalanceError;
+use crate::explorer::{Cluster, ExplorerConfig, ExplorerLinks};
+use crate::filter::TokenFilter;
+use crate::keypair_file::keypair_file_addresses;
+use crate::metadata::short_address;
+use crate::nft::NftConfig;
+use crate::notify::NotificationConfig;
+use crate::output::NumberFormat;
+use crate::pricing::PricingConfig;
+use crate::reconcile::ExpectedBalance;
+use crate::report::TokenBalance;
+use crate::rpc::{CircuitBreakerConfig, RetryPolicy, RotationStrategy};
+use crate::sink::SinkConfig;
+use crate::squads::vault_address;
+use crate::subscribe::GeyserConfig;
+use crate::token_list::TokenListConfig;
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
+use reqwest::Proxy;
+use serde::{Deserialize, Deserializer};
+use solana_sdk::commitment_config::CommitmentLevel;
+use solana_sdk::pubkey::{Pubkey, MAX_SEEDS, MAX_SEED_LEN};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::time::Duration;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ConfigFormat {
+    #[default]
+    Yaml,
+    Toml,
+    Json,
+}
+
+impl ConfigFormat {
+    /// `.toml` and `.json` files are read as such; anything else as YAML.
+    pub fn from_path(path: &Path) -> Self {
+        match path
+            .extension()
+            .and_then(|extension| extension.to_str())
+            .map(str::to_ascii_lowercase)
+            .as_deref()
+        {
+            Some("toml") => ConfigFormat::Toml,
+            Some("json") => ConfigFormat::Json,
+            _ => ConfigFormat::Yaml,
+        }
+    }
+}
+
+impl FromStr for ConfigFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "yaml" | "yml" => Ok(ConfigFormat::Yaml),
+            "toml" => Ok(ConfigFormat::Toml),
+            "json" => Ok(ConfigFormat::Json),
+            other => anyhow::bail!(
+                "unknown config format `{}` (expected yaml, toml or json)",
+                other
+            ),
+        }
+    }
+}
+
+impl fmt::Display for ConfigFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            ConfigFormat::Yaml => "yaml",
+            ConfigFormat::Toml => "toml",
+            ConfigFormat::Json => "json",
+        })
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct TokenConfig {
+    #[serde(default = "default_rpc_url")]
+    pub solana_rpc_url: String,
+    /// Several endpoints to fail over between; takes precedence over
+    /// `solana_rpc_url` when non-empty.
+    #[serde(default)]
+    pub solana_rpc_urls: Vec<String>,
+    #[serde(default)]
+    pub rpc_rotation: RotationStrategy,
+    /// How wallet balances are read: `json_rpc` (the default), or `helius`
+    /// or `triton` for their DAS API.
+    #[serde(default)]
+    pub backend: ChainBackend,
+    /// PubSub endpoint for `watch --subscribe` [default: derived from the
+    /// first RPC URL].
+    #[serde(default)]
+    pub solana_ws_url: Option<String>,
+    /// Stream `watch --subscribe` updates from this Yellowstone gRPC
+    /// endpoint instead. Needs a build with the `geyser` feature.
+    #[serde(default)]
+    pub geyser: Option<GeyserConfig>,
+    /// Other clusters wallets can be on, by name; wallets name theirs with
+    /// `cluster`, and the rest are on `solana_rpc_url`.
+    #[serde(default)]
+    pub clusters: BTreeMap<String, ClusterConfig>,
+    /// `processed`, `confirmed` or `finalized` (the default).
+    #[serde(default)]
+    pub commitment: CommitmentLevel,
+    #[serde(default = "default_max_concurrency")]
+    pub max_concurrency: usize,
+    /// Requests per second allowed across all endpoints [default: unlimited].
+    #[serde(default)]
+    pub max_rps: Option<f64>,
+    /// RPC requests a report may make before failing [default: unlimited].
+    #[serde(default)]
+    pub max_rpc_calls: Option<u64>,
+    #[serde(default)]
+    pub retry: RetryPolicy,
+    /// When to stop sending requests to an endpoint that keeps failing.
+    #[serde(default)]
+    pub circuit_breaker: CircuitBreakerConfig,
+    /// Mint decimals, token metadata and resolved domains kept on disk.
+    #[serde(default)]
+    pub cache: CacheConfig,
+    /// How long each RPC request may take [default: 30s].
+    #[serde(default, with = "humantime_serde")]
+    pub request_timeout: Option<Duration>,
+    /// Stop waiting for wallets this long into a run, reporting the ones
+    /// still being fetched as errors [default: no deadline].
+    #[serde(default, with = "humantime_serde")]
+    pub deadline: Option<Duration>,
+    /// HTTP, HTTPS or SOCKS5 proxy for RPC requests, e.g.
+    /// `socks5://127.0.0.1:1080`.
+    #[serde(default, deserialize_with = "deserialize_proxy")]
+    pub rpc_proxy: Option<Proxy>,
+    /// Extra headers sent with every RPC request, e.g. `x-api-key`.
+    #[serde(default, deserialize_with = "deserialize_headers")]
+    pub rpc_headers: HeaderMap,
+    /// Read every wallet at or after the slot of the report's first call,
+    /// so totals are not skewed by transfers landing mid-fetch.
+    #[serde(default)]
+    pub same_slot: bool,
+    /// Report every SPL token held by each wallet, not just the configured ones.
+    #[serde(default)]
+    pub discover_tokens: bool,
+    /// Count configured tokens held outside the wallets' associated token
+    /// accounts too, listing each wallet's token accounts.
+    #[serde(default)]
+    pub scan_token_accounts: bool,
+    /// Hides unwanted tokens found by `discover_tokens`.
+    #[serde(default)]
+    pub token_filter: TokenFilter,
+    /// Leave tokens with a zero balance out of reports.
+    #[serde(default)]
+    pub hide_zero: bool,
+    /// Only report these tokens, by ticker (case-insensitive) or mint.
+    #[serde(default)]
+    pub only_tokens: Vec<String>,
+    /// Report stake accounts the wallets can stake or withdraw from.
+    #[serde(default)]
+    pub include_stake: bool,
+    /// Value mSOL, jitoSOL and bSOL balances in SOL from their pools'
+    /// exchange rates.
+    #[serde(default)]
+    pub liquid_staking: bool,
+    /// More SPL stake pools to value tokens of, as pool address by mint.
+    #[serde(default)]
+    pub stake_pools: BTreeMap<String, String>,
+    /// Count wrapped SOL (the native mint) as SOL instead of listing it as a
+    /// separate `wSOL` token.
+    #[serde(default)]
+    pub merge_wrapped_sol: bool,
+    /// Split each wallet's SOL into rent-exempt reserve and spendable SOL,
+    /// and report the rent locked in its token accounts.
+    #[serde(default)]
+    pub rent_breakdown: bool,
+    /// Names, decimals and logos for mints, from a downloaded token list.
+    #[serde(default)]
+    pub token_list: Option<TokenListConfig>,
+    /// Show the primary `.sol` domain of wallets configured by address.
+    #[serde(default)]
+    pub reverse_lookup: bool,
+    /// List the token accounts behind each token balance.
+    #[serde(default)]
+    pub token_account_details: bool,
+    /// Link wallets, mints and token accounts to this explorer in text and
+    /// JSON output. HTML output always links, to Solana Explorer by default.
+    #[serde(default)]
+    pub explorer: Option<ExplorerConfig>,
+    /// Values balances in USD when set.
+    #[serde(default)]
+    pub pricing: Option<PricingConfig>,
+    /// Locale and decimals of numbers in text, CSV and HTML output.
+    #[serde(default)]
+    pub number_format: NumberFormat,
+    /// Counts the NFTs each wallet owns when set, through a DAS-compatible
+    /// RPC endpoint.
+    #[serde(default)]
+    pub nfts: Option<NftConfig>,
+    /// SQLite database every run's balances are appended to; needs a build
+    /// with the `history` feature.
+    #[serde(default)]
+    pub history_db: Option<PathBuf>,
+    /// Warn when any wallet's SOL balance drops below this.
+    #[serde(default)]
+    pub min_sol: Option<f64>,
+    /// Per-wallet thresholds, overriding `min_sol` and the tokens'
+    /// `min_balance`.
+    #[serde(default)]
+    pub wallet_thresholds: HashMap<String, WalletThresholds>,
+    /// Balances the `reconcile` command expects, keyed by wallet (address,
+    /// `.sol` domain or label) and then by `SOL`, ticker or mint.
+    #[serde(default)]
+    pub expected_balances: HashMap<String, HashMap<String, ExpectedBalance>>,
+    /// Where watch mode reports balance changes and threshold breaches.
+    #[serde(default)]
+    pub notifications: NotificationConfig,
+    /// Time-series stores every fetched report is written to.
+    #[serde(default)]
+    pub sinks: SinkConfig,
+    pub wallets: Vec<WalletInfo>,
+    /// Watch-only addresses to derive from a mnemonic or seed and add to
+    /// `wallets`.
+    #[serde(default)]
+    pub derived_wallets: Vec<DerivedWallets>,
+    /// Solana CLI keypair files, directories of them or file name patterns
+    /// like `ids/*.json`, whose addresses are added to `wallets`. Relative
+    /// paths are relative to the config file.
+    #[serde(default)]
+    pub keypair_files: Vec<PathBuf>,
+    /// Named sets of wallets to report subtotals for, by address, domain or
+    /// label. Members not listed in `wallets` are added to it.
+    #[serde(default)]
+    pub groups: BTreeMap<String, Vec<String>>,
+    pub tokens: Vec<TokenInfo>,
+}
+
+/// A wallet to report, given in the config as a plain address (or `.sol`
+/// domain) or as `{address, label}`. A `{type: squads_multisig, address}`
+/// entry stands for the multisig's vaults, which are listed in its place
+/// once the config is loaded, and a `{type: realm, address}` entry for the
+/// DAO's treasuries, listed in its place by
+/// [`BalanceFetcher::expand_realms`](crate::BalanceFetcher::expand_realms).
+/// A `{program_id, seeds}` entry is the program address derived from them.
+/// Detailed entries may replace or adjust the tokens checked for the wallet.
+#[derive(Debug, Deserialize, Clone, PartialEq)]
+#[serde(try_from = "WalletSpec")]
+pub struct WalletInfo {
+    pub address: String,
+    /// Nickname shown next to the address in reports.
+    pub label: Option<String>,
+    /// The Squads multisig this wallet is a vault of.
+    pub multisig: Option<String>,
+    /// The SPL Governance realm this wallet is a treasury of.
+    pub realm: Option<String>,
+    /// Also report the tokens vesting to this wallet.
+    pub vesting: bool,
+    /// Changes to the tokens checked for this wallet.
+    pub tokens: TokenSelection,
+    /// The entry of `clusters` the wallet is on [default: the cluster of
+    /// `solana_rpc_url`].
+    pub cluster: Option<String>,
+    /// What the entry still stands for, until it is expanded.
+    pub(crate) expand: Option<Expansion>,
+}
+
+/// How a wallet's tokens differ from the global `tokens` list.
+#[derive(Debug, Default, Deserialize, Clone, PartialEq)]
+pub struct TokenSelection {
+    /// Checked instead of the global list.
+    #[serde(default)]
+    pub tokens: Option<Vec<TokenInfo>>,
+    /// Checked as well as the global list.
+    #[serde(default)]
+    pub extra_tokens: Vec<TokenInfo>,
+    /// Tickers or mints left out of the wallet's report.
+    #[serde(default)]
+    pub exclude_tokens: Vec<String>,
+}
+
+impl TokenSelection {
+    pub fn is_default(&self) -> bool {
+        self.tokens.is_none() && self.extra_tokens.is_empty() && self.exclude_tokens.is_empty()
+    }
+
+    /// Whether the token `ticker` with mint `mint` is excluded.
+    pub fn excludes(&self, ticker: &str, mint: &str) -> bool {
+        self.exclude_tokens
+            .iter()
+            .any(|excluded| excluded == ticker || excluded == mint)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum Expansion {
+    /// Vault indexes of a Squads multisig.
+    SquadsVaults(Vec<u8>),
+    /// A realm owned by `program_id` [default: the SPL Governance program].
+    Realm { program_id: Option<String> },
+}
+
+#[derive(Debug, Default, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+enum WalletKind {
+    #[default]
+    Wallet,
+    SquadsMultisig,
+    Realm,
+    Vesting,
+}
+
+/// A `wallets` entry: an address, a mapping with `seeds` for a program
+/// derived address, or any other mapping for the detailed form.
+enum WalletSpec {
+    Address(String),
+    Detailed(DetailedWallet),
+    Pda(PdaWallet),
+}
+
+#[derive(Deserialize)]
+struct DetailedWallet {
+    address: String,
+    #[serde(default)]
+    label: Option<String>,
+    #[serde(default, rename = "type")]
+    kind: WalletKind,
+    /// Vault indexes to report for a multisig [default: 0].
+    #[serde(default)]
+    vaults: Option<Vec<u8>>,
+    /// Governance program a realm belongs to, for DAOs that deployed their
+    /// own.
+    #[serde(default)]
+    program_id: Option<String>,
+    #[serde(default)]
+    cluster: Option<String>,
+    #[serde(flatten)]
+    tokens: TokenSelection,
+}
+
+#[derive(Deserialize)]
+struct PdaWallet {
+    program_id: String,
+    seeds: Vec<Seed>,
+    #[serde(default)]
+    label: Option<String>,
+}
+
+/// Picks the shape before deserializing it, so errors name the field at
+/// fault rather than that no shape matched.
+impl<'de> Deserialize<'de> for WalletSpec {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        use serde::de::{Error, Unexpected};
+
+        match serde_json::Value::deserialize(deserializer)? {
+            serde_json::Value::String(address) => Ok(WalletSpec::Address(address)),
+            serde_json::Value::Object(entry) if entry.contains_key("seeds") => {
+                PdaWallet::deserialize(serde_json::Value::Object(entry))
+                    .map(WalletSpec::Pda)
+                    .map_err(D::Error::custom)
+            }
+            serde_json::Value::Object(entry) => {
+                DetailedWallet::deserialize(serde_json::Value::Object(entry))
+                    .map(WalletSpec::Detailed)
+                    .map_err(D::Error::custom)
+            }
+            other => Err(D::Error::invalid_type(
+                match &other {
+                    serde_json::Value::Null => Unexpected::Unit,
+                    serde_json::Value::Bool(value) => Unexpected::Bool(*value),
+                    serde_json::Value::Number(_) => Unexpected::Other("a number"),
+                    _ => Unexpected::Seq,
+                },
+                &"a wallet address or mapping",
+            )),
+        }
+    }
+}
+
+/// A seed of a program derived address.
+enum Seed {
+    /// Used as its UTF-8 bytes.
+    String(String),
+    Typed(TypedSeed),
+}
+
+impl<'de> Deserialize<'de> for Seed {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        match serde_json::Value::deserialize(deserializer)? {
+            serde_json::Value::String(string) => Ok(Seed::String(string)),
+            typed => TypedSeed::deserialize(typed)
+                .map(Seed::Typed)
+                .map_err(serde::de::Error::custom),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum TypedSeed {
+    Pubkey(String),
+    Bytes(Vec<u8>),
+    /// Little-endian, as Anchor programs encode integer seeds.
+    U8(u8),
+    U16(u16),
+    U32(u32),
+    U64(u64),
+}
+
+impl Seed {
+    fn to_bytes(&self) -> Result<Vec<u8>, String> {
+        Ok(match self {
+            Seed::String(string) => string.as_bytes().to_vec(),
+            Seed::Typed(TypedSeed::Pubkey(pubkey)) => Pubkey::from_str(pubkey)
+                .map_err(|err| format!("invalid pubkey seed `{}`: {}", pubkey, err))?
+                .to_bytes()
+                .to_vec(),
+            Seed::Typed(TypedSeed::Bytes(bytes)) => bytes.clone(),
+            Seed::Typed(TypedSeed::U8(value)) => vec![*value],
+            Seed::Typed(TypedSeed::U16(value)) => value.to_le_bytes().to_vec(),
+            Seed::Typed(TypedSeed::U32(value)) => value.to_le_bytes().to_vec(),
+            Seed::Typed(TypedSeed::U64(value)) => value.to_le_bytes().to_vec(),
+        })
+    }
+}
+
+/// Derives the address `seeds` give under `program_id`.
+fn derive_pda(program_id: &str, seeds: &[Seed]) -> Result<Pubkey, String> {
+    let program_id = Pubkey::from_str(program_id)
+        .map_err(|err| format!("invalid program_id `{}`: {}", program_id, err))?;
+    let seeds = seeds
+        .iter()
+        .map(Seed::to_bytes)
+        .collect::<Result<Vec<_>, _>>()?;
+    // The bump seed takes the last place.
+    if seeds.len() >= MAX_SEEDS {
+        return Err(format!("at most {} seeds are allowed", MAX_SEEDS - 1));
+    }
+    if let Some(seed) = seeds.iter().find(|seed| seed.len() > MAX_SEED_LEN) {
+        return Err(format!(
+            "seeds must be at most {} bytes, got {}",
+            MAX_SEED_LEN,
+            seed.len()
+        ));
+    }
+    let seeds: Vec<&[u8]> = seeds.iter().map(Vec::as_slice).collect();
+    Pubkey::try_find_program_address(&seeds, &program_id)
+        .map(|(address, _)| address)
+        .ok_or_else(|| "no valid program address for these seeds".to_string())
+}
+
+impl TryFrom<WalletSpec> for WalletInfo {
+    type Error = String;
+
+    fn try_from(spec: WalletSpec) -> Result<Self, Self::Error> {
+        Ok(match spec {
+            WalletSpec::Address(address) => WalletInfo::new(address),
+            WalletSpec::Detailed(DetailedWallet {
+                address,
+                label,
+                kind,
+                vaults,
+                program_id,
+                cluster,
+                tokens,
+            }) => WalletInfo {
+                address,
+                label,
+                multisig: None,
+                realm: None,
+                vesting: kind == WalletKind::Vesting,
+                tokens,
+                cluster,
+                expand: match kind {
+                    WalletKind::Wallet | WalletKind::Vesting => None,
+                    WalletKind::SquadsMultisig => {
+                        Some(Expansion::SquadsVaults(vaults.unwrap_or_else(|| vec![0])))
+                    }
+                    WalletKind::Realm => Some(Expansion::Realm { program_id }),
+                },
+            },
+            WalletSpec::Pda(PdaWallet {
+                program_id,
+                seeds,
+                label,
+            }) => WalletInfo {
+                label,
+                ..WalletInfo::new(derive_pda(&program_id, &seeds)?.to_string())
+            },
+        })
+    }
+}
+
+impl WalletInfo {
+    pub fn new(address: impl Into<String>) -> Self {
+        WalletInfo {
+            address: address.into(),
+            label: None,
+            multisig: None,
+            realm: None,
+            vesting: false,
+            tokens: TokenSelection::default(),
+            cluster: None,
+            expand: None,
+        }
+    }
+
+    /// Whether `name` refers to this wallet by address or label, or to the
+    /// multisig or realm it belongs to.
+    pub fn is_named(&self, name: &str) -> bool {
+        self.address == name
+            || self.label.as_deref() == Some(name)
+            || self.multisig.as_deref() == Some(name)
+            || self.realm.as_deref() == Some(name)
+    }
+}
+
+#[derive(Debug, Deserialize, Clone, PartialEq)]
+pub struct TokenInfo {
+    pub address: String,
+    /// Defaults to the symbol from the token list or the mint's Metaplex
+    /// metadata.
+    #[serde(default)]
+    pub ticker: Option<String>,
+    /// Warn when a wallet holds less than this many tokens.
+    #[serde(default)]
+    pub min_balance: Option<f64>,
+}
+
+impl TokenInfo {
+    pub fn mint(&self) -> Result<Pubkey, BalanceError> {
+        Pubkey::from_str(&self.address).map_err(|source| BalanceError::Parse {
+            mint: self.address.clone(),
+            source,
+        })
+    }
+}
+
+/// A cluster other than the main one that some wallets are on.
+#[derive(Debug, Clone, Deserialize)]
+pub struct ClusterConfig {
+    pub solana_rpc_url: String,
+    /// Groups whose members are on this cluster.
+    #[serde(default)]
+    pub groups: Vec<String>,
+}
+
+#[derive(Debug, Default, Deserialize, Clone, PartialEq)]
+pub struct WalletThresholds {
+    #[serde(default)]
+    pub min_sol: Option<f64>,
+    /// Minimum token balances, keyed by ticker or mint.
+    #[serde(default)]
+    pub min_balance: HashMap<String, f64>,
+}
+
+fn default_rpc_url() -> String {
+    "https://api.mainnet-beta.solana.com".to_string()
+}
+
+fn env_overrides() -> Vec<(String, String)> {
+    std::env::vars()
+        .filter(|(name, _)| name.starts_with(ENV_PREFIX))
+        .collect()
+}
+
+fn apply_profile(config: &mut serde_json::Value, profile: &str) -> Result<(), anyhow::Error> {
+    let root = config
+        .as_object_mut()
+        .ok_or_else(|| anyhow::anyhow!("the config is not a mapping"))?;
+    let mut profiles = match root.remove("profiles") {
+        Some(serde_json::Value::Object(profiles)) => profiles,
+        _ => anyhow::bail!(
+            "profile `{}` requested but the config has no `profiles`",
+            profile
+        ),
+    };
+    let settings = match profiles.remove(profile) {
+        Some(serde_json::Value::Object(settings)) => settings,
+        Some(_) => anyhow::bail!("profile `{}` is not a mapping", profile),
+        None => {
+            let names: Vec<&str> = profiles.keys().map(String::as_str).collect();
+            anyhow::bail!(
+                "unknown profile `{}` (available: {})",
+                profile,
+                names.join(", ")
+            );
+        }
+    };
+    root.extend(settings);
+    Ok(())
+}
+
+/// Prefix of environment variables that override config values. The rest
+/// of the name is the lowercased key, with `__` between nested keys, e.g.
+/// `SOLANA_BALANCE_MAX_RPS=5` or
+/// `SOLANA_BALANCE_NOTIFICATIONS__TELEGRAM__BOT_TOKEN=...`. Values are
+/// parsed as YAML, so numbers, booleans and `[a, b]` lists work.
+/// `SOLANA_BALANCE_RPC_URL` is short for `solana_rpc_url`.
+pub const ENV_PREFIX: &str = "SOLANA_BALANCE_";
+
+/// What broke a config `value` that deserialized before `profile` or one of
+/// the `overrides` was applied, as ` (in profile `name`)` or
+/// ` (set by SOLANA_BALANCE_...)`; empty when the file itself is at fault.
+fn culprit(
+    mut value: serde_json::Value,
+    profile: Option<&str>,
+    overrides: &[(String, String)],
+) -> String {
+    let parses = |value: &serde_json::Value| TokenConfig::deserialize(value).is_ok();
+    match profile {
+        Some(profile) => {
+            let parsed = parses(&value);
+            if apply_profile(&mut value, profile).is_err() || !parses(&value) {
+                return if parsed {
+                    format!(" (in profile `{}`)", profile)
+                } else {
+                    String::new()
+                };
+            }
+        }
+        None if !parses(&value) => return String::new(),
+        None => {}
+    }
+    for (name, raw) in overrides {
+        apply_env_override(&mut value, &name[ENV_PREFIX.len()..], raw);
+        if !parses(&value) {
+            return format!(" (set by {})", name);
+        }
+    }
+    String::new()
+}
+
+/// The directory of the config file at `path`, empty (the working
+/// directory) for a bare file name.
+fn config_dir(path: &Path) -> &Path {
+    path.parent().unwrap_or(Path::new(""))
+}
+
+fn apply_env_override(config: &mut serde_json::Value, name: &str, raw: &str) {
+    let name = name.to_ascii_lowercase();
+    let path = match name.as_str() {
+        "rpc_url" => "solana_rpc_url",
+        "rpc_urls" => "solana_rpc_urls",
+        "ws_url" => "solana_ws_url",
+        other => other,
+    };
+    let value = serde_yaml::from_str::<serde_json::Value>(raw)
+        .ok()
+        .filter(|value| !value.is_null())
+        .unwrap_or_else(|| serde_json::Value::String(raw.to_string()));
+
+    let mut target = config;
+    for key in path.split("__") {
+        if !target.is_object() {
+            *target = serde_json::Value::Object(Default::default());
+        }
+        target = target
+            .as_object_mut()
+            .expect("just made an object")
+            .entry(key)
+            .or_insert(serde_json::Value::Null);
+    }
+    *target = value;
+}
+
+fn deserialize_proxy<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Option<Proxy>, D::Error> {
+    let url = String::deserialize(deserializer)?;
+    Proxy::all(&url)
+        .map(Some)
+        .map_err(|err| serde::de::Error::custom(format!("invalid proxy `{}`: {}", url, err)))
+}
+
+fn deserialize_headers<'de, D: Deserializer<'de>>(deserializer: D) -> Result<HeaderMap, D::Error> {
+    let mut headers = HeaderMap::new();
+    for (name, value) in BTreeMap::<String, String>::deserialize(deserializer)? {
+        let name = HeaderName::from_bytes(name.as_bytes())
+            .map_err(|_| serde::de::Error::custom(format!("invalid header name `{}`", name)))?;
+        let value = HeaderValue::from_str(&value).map_err(|_| {
+            serde::de::Error::custom(format!("invalid value for header `{}`", name))
+        })?;
+        headers.insert(name, value);
+    }
+    Ok(headers)
+}
+
+fn default_max_concurrency() -> usize {
+    crate::fetcher::DEFAULT_MAX_CONCURRENCY
+}
+
+impl TokenConfig {
+    /// Reads the config, picking the format from the file extension.
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self, BalanceError> {
+        let path = path.as_ref();
+        Self::from_file_with_format(path, ConfigFormat::from_path(path))
+    }
+
+    /// Reads the config in `format`, then applies `SOLANA_BALANCE_*`
+    /// environment overrides (see [`ENV_PREFIX`]).
+    pub fn from_file_with_format(
+        path: impl AsRef<Path>,
+        format: ConfigFormat,
+    ) -> Result<Self, BalanceError> {
+        Self::load(path, format, None)
+    }
+
+    /// Like [`TokenConfig::from_file_with_format`], but first replaces
+    /// top-level settings with those of `profile`, one of the entries under
+    /// `profiles`. Keys a profile leaves out keep their top-level values.
+    pub fn load(
+        path: impl AsRef<Path>,
+        format: ConfigFormat,
+        profile: Option<&str>,
+    ) -> Result<Self, BalanceError> {
+        Self::read(path.as_ref(), format, profile).map_err(BalanceError::Config)
+    }
+
+    fn read(
+        path: &Path,
+        format: ConfigFormat,
+        profile: Option<&str>,
+    ) -> Result<Self, anyhow::Error> {
+        let config_content = fs::read_to_string(path)?;
+        let overrides = env_overrides();
+
+        if !overrides.is_empty() || profile.is_some() {
+            let value: serde_json::Value = match format {
+                ConfigFormat::Yaml => serde_yaml::from_str(&config_content)?,
+                ConfigFormat::Toml => toml::from_str(&config_content)?,
+                ConfigFormat::Json => serde_json::from_str(&config_content)?,
+            };
+            return Self::from_value(value, profile, &overrides, config_dir(path));
+        }
+
+        let mut config: TokenConfig = match format {
+            ConfigFormat::Yaml => serde_yaml::from_str(&config_content)?,
+            ConfigFormat::Toml => toml::from_str(&config_content)?,
+            ConfigFormat::Json => serde_json::from_str(&config_content)?,
+        };
+        config.finish(config_dir(path))?;
+        Ok(config)
+    }
+
+    /// Expands and completes the wallet list once the config is
+    /// deserialized, however it was loaded. Relative paths are resolved
+    /// against `dir`, the config file's directory.
+    fn finish(&mut self, dir: &Path) -> Result<(), anyhow::Error> {
+        self.expand_multisigs()?;
+        self.add_derived_wallets()?;
+        self.add_keypair_wallets(dir)?;
+        self.add_group_members();
+        self.assign_clusters()
+    }
+
+    /// Replaces each Squads multisig entry with its vaults, labelled after
+    /// the multisig.
+    fn expand_multisigs(&mut self) -> Result<(), anyhow::Error> {
+        let mut wallets = Vec::with_capacity(self.wallets.len());
+        for wallet in self.wallets.drain(..) {
+            let Some(Expansion::SquadsVaults(vaults)) = &wallet.expand else {
+                wallets.push(wallet);
+                continue;
+            };
+            let multisig = Pubkey::from_str(&wallet.address).map_err(|err| {
+                anyhow::anyhow!(
+                    "invalid squads_multisig address `{}`: {}",
+                    wallet.address,
+                    err
+                )
+            })?;
+            let label = wallet
+                .label
+                .clone()
+                .unwrap_or_else(|| format!("Squads {}", short_address(&wallet.address)));
+            for &index in vaults {
+                wallets.push(WalletInfo {
+                    label: Some(if vaults.len() > 1 {
+                        format!("{} (vault {})", label, index)
+                    } else {
+                        label.clone()
+                    }),
+                    multisig: Some(wallet.address.clone()),
+                    tokens: wallet.tokens.clone(),
+                    cluster: wallet.cluster.clone(),
+                    ..WalletInfo::new(vault_address(&multisig, index).to_string())
+                });
+            }
+        }
+        self.wallets = wallets;
+        Ok(())
+    }
+
+    /// Adds the addresses derived for each `derived_wallets` entry that are
+    /// not listed already.
+    fn add_derived_wallets(&mut self) -> Result<(), anyhow::Error> {
+        for derived in &self.derived_wallets {
+            for (index, address) in derived.addresses()? {
+                let address = address.to_string();
+                if self.wallets.iter().any(|wallet| wallet.address == address) {
+                    continue;
+                }
+                self.wallets.push(WalletInfo {
+                    label: derived
+                        .label
+                        .as_ref()
+                        .map(|label| format!("{} #{}", label, index)),
+                    ..WalletInfo::new(address)
+                });
+            }
+        }
+        Ok(())
+    }
+
+    /// Adds the addresses of `keypair_files` that are not listed already,
+    /// labelled with their file names.
+    fn add_keypair_wallets(&mut self, dir: &Path) -> Result<(), anyhow::Error> {
+        for pattern in &self.keypair_files {
+            for (label, address) in keypair_file_addresses(&dir.join(pattern))? {
+                let address = address.to_string();
+                if !self.wallets.iter().any(|wallet| wallet.address == address) {
+                    self.wallets.push(WalletInfo {
+                        label: Some(label),
+                        ..WalletInfo::new(address)
+                    });
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// A config with no wallets or tokens and default settings, apart from
+    /// any `SOLANA_BALANCE_*` environment overrides.
+    pub fn from_env() -> Result<Self, BalanceError> {
+        let overrides = env_overrides();
+        Self::from_value(
+            serde_json::json!({ "wallets": [], "tokens": [] }),
+            None,
+            &overrides,
+            Path::new(""),
+        )
+        .map_err(BalanceError::Config)
+    }
+
+    fn from_value(
+        value: serde_json::Value,
+        profile: Option<&str>,
+        overrides: &[(String, String)],
+        dir: &Path,
+    ) -> Result<Self, anyhow::Error> {
+        let mut merged = value.clone();
+        if let Some(profile) = profile {
+            apply_profile(&mut merged, profile)?;
+        }
+        for (name, raw) in overrides {
+            apply_env_override(&mut merged, &name[ENV_PREFIX.len()..], raw);
+        }
+        let mut config: TokenConfig = serde_json::from_value(merged)
+            .map_err(|err| anyhow::anyhow!("{}{}", err, culprit(value, profile, overrides)))?;
+        config.finish(dir)?;
+        Ok(config)
+    }
+
+    fn add_group_members(&mut self) {
+        for member in self.groups.values().flatten() {
+            if !self.wallets.iter().any(|wallet| wallet.is_named(member)) {
+                self.wallets.push(WalletInfo::new(member.clone()));
+            }
+        }
+    }
+
+    /// Puts the members of each cluster's groups on it, unless they name a
+    /// cluster themselves, and checks that every cluster named exists.
+    fn assign_clusters(&mut self) -> Result<(), anyhow::Error> {
+        for (name, cluster) in &self.clusters {
+            for group in &cluster.groups {
+                let Some(members) = self.groups.get(group) else {
+                    anyhow::bail!("cluster `{}` lists unknown group `{}`", name, group);
+                };
+                for wallet in &mut self.wallets {
+                    if wallet.cluster.is_none()
+                        && members.iter().any(|member| wallet.is_named(member))
+                    {
+                        wallet.cluster = Some(name.clone());
+                    }
+                }
+            }
+        }
+        for wallet in &self.wallets {
+            if let Some(cluster) = &wallet.cluster {
+                if !self.clusters.contains_key(cluster) {
+                    anyhow::bail!(
+                        "wallet {} is on unknown cluster `{}`",
+                        wallet.address,
+                        cluster
+                    );
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// The settings and wallets for one cluster: `None` for the wallets on
+    /// `solana_rpc_url`, or an entry of `clusters` with its RPC URL in place
+    /// of the main one.
+    pub fn for_cluster(&self, cluster: Option<&str>) -> TokenConfig {
+        let mut config = self.clone();
+        config.clusters.clear();
+        config
+            .wallets
+            .retain(|wallet| wallet.cluster.as_deref() == cluster);
+        if let Some(settings) = cluster.and_then(|cluster| self.clusters.get(cluster)) {
+            config.solana_rpc_url = settings.solana_rpc_url.clone();
+            config.solana_rpc_urls.clear();
+            config.solana_ws_url = None;
+            config.geyser = None;
+            // The backend and headers are for the main provider.
+            config.backend = ChainBackend::default();
+            config.rpc_headers.clear();
+            // Test clusters' SOL and tokens are worth nothing.
+            if Cluster::guess(&config.solana_rpc_url) != Cluster::MainnetBeta {
+                config.pricing = None;
+            }
+        }
+        config
+    }
+
+    /// The RPC endpoints to use, in priority order.
+    pub fn rpc_urls(&self) -> Vec<String> {
+        if self.solana_rpc_urls.is_empty() {
+            vec![self.solana_rpc_url.clone()]
+        } else {
+            self.solana_rpc_urls.clone()
+        }
+    }
+
+    /// The PubSub endpoint: `solana_ws_url`, or the first RPC URL with its
+    /// scheme switched to WebSocket and the default RPC port 8899 replaced
+    /// by the PubSub port 8900.
+    pub fn ws_url(&self) -> String {
+        if let Some(url) = &self.solana_ws_url {
+            return url.clone();
+        }
+        let rpc_url = &self.rpc_urls()[0];
+        let url = if let Some(rest) = rpc_url.strip_prefix("https://") {
+            format!("wss://{}", rest)
+        } else if let Some(rest) = rpc_url.strip_prefix("http://") {
+            format!("ws://{}", rest)
+        } else {
+            rpc_url.clone()
+        };
+        url.replacen(":8899", ":8900", 1)
+    }
+
+    /// Links for `explorer`, on its cluster or the one `solana_rpc_url`
+    /// suggests.
+    pub fn explorer_links(&self) -> Option<ExplorerLinks> {
+        let explorer = self.explorer.as_ref()?;
+        Some(ExplorerLinks {
+            explorer: explorer.provider,
+            cluster: explorer
+                .cluster
+                .unwrap_or_else(|| Cluster::guess(&self.rpc_urls()[0])),
+        })
+    }
+
+    /// Whether a fetched token passes `hide_zero` and `only_tokens`.
+    pub fn shows_token(&self, ticker: &str, balance: &TokenBalance) -> bool {
+        if self.hide_zero && balance.raw_amount == 0 {
+            return false;
+        }
+        self.only_tokens.is_empty()
+            || self
+                .only_tokens
+                .iter()
+                .any(|only| *only == balance.mint || only.eq_ignore_ascii_case(ticker))
+    }
+
+    /// Mints of the global tokens and of those any wallet checks.
+    pub fn configured_mints(&self) -> HashSet<String> {
+        self.tokens
+            .iter()
+            .cloned()
+            .chain(
+                self.wallets
+                    .iter()
+                    .flat_map(|wallet| self.tokens_for(wallet)),
+            )
+            .map(|token| token.address)
+            .collect()
+    }
+
+    /// The tokens to check for `wallet`: its own list or the global one,
+    /// plus its extra tokens, less those it excludes by mint or configured
+    /// ticker.
+    pub fn tokens_for(&self, wallet: &WalletInfo) -> Vec<TokenInfo> {
+        let selection = &wallet.tokens;
+        let mut tokens = selection.tokens.as_ref().unwrap_or(&self.tokens).clone();
+        for token in &selection.extra_tokens {
+            if !tokens.iter().any(|known| known.address == token.address) {
+                tokens.push(token.clone());
+            }
+        }
+        tokens.retain(|token| {
+            !selection.excludes(token.ticker.as_deref().unwrap_or_default(), &token.address)
+        });
+        tokens
+    }
+
+    /// What differs from `old` in wallets, tokens and thresholds, one line
+    /// per change, for logging a reloaded config.
+    pub fn changes_since(&self, old: &TokenConfig) -> Vec<String> {
+        let mut changes = Vec::new();
+        let wallet_name = |wallet: &WalletInfo| {
+            wallet
+                .label
+                .clone()
+                .unwrap_or_else(|| wallet.address.clone())
+        };
+        for wallet in &self.wallets {
+            match old.wallets.iter().find(|old| old.address == wallet.address) {
+                None => changes.push(format!("added wallet {}", wallet_name(wallet))),
+                Some(old) if old != wallet => {
+                    changes.push(format!("changed wallet {}", wallet_name(wallet)))
+                }
+                Some(_) => {}
+            }
+        }
+        for wallet in &old.wallets {
+            if !self.wallets.iter().any(|new| new.address == wallet.address) {
+                changes.push(format!("removed wallet {}", wallet_name(wallet)));
+            }
+        }
+
+        let name = |token: &TokenInfo| {
+            token
+                .ticker
+                .clone()
+                .unwrap_or_else(|| token.address.clone())
+        };
+        for token in &self.tokens {
+            match old.tokens.iter().find(|old| old.address == token.address) {
+                None => changes.push(format!("added token {}", name(token))),
+                Some(old) if old != token => changes.push(format!("changed token {}", name(token))),
+                Some(_) => {}
+            }
+        }
+        for token in &old.tokens {
+            if !self.tokens.iter().any(|new| new.address == token.address) {
+                changes.push(format!("removed token {}", name(token)));
+            }
+        }
+
+        if self.min_sol != old.min_sol {
+            changes.push(format!(
+                "min_sol {} -> {}",
+                old.min_sol
+                    .map_or("unset".to_string(), |min| min.to_string()),
+                self.min_sol
+                    .map_or("unset".to_string(), |min| min.to_string())
+            ));
+        }
+        let mut wallets: Vec<&String> = self
+            .wallet_thresholds
+            .keys()
+            .chain(old.wallet_thresholds.keys())
+            .collect();
+        wallets.sort();
+        wallets.dedup();
+        for wallet in wallets {
+            if self.wallet_thresholds.get(wallet) != old.wallet_thresholds.get(wallet) {
+                changes.push(format!("changed thresholds of {}", wallet));
+            }
+        }
+        if self.expected_balances != old.expected_balances {
+            changes.push("changed expected_balances".to_string());
+        }
+        changes
+    }
+}
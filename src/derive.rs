@@ -0,0 +1,103 @@
+use bip39::{Language, Mnemonic};
+use serde::Deserialize;
+use solana_sdk::derivation_path::DerivationPath;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::Signer;
+use solana_sdk::signer::keypair::{
+    generate_seed_from_seed_phrase_and_passphrase, keypair_from_seed_and_derivation_path,
+};
+use std::env;
+
+/// Word counts a BIP39 mnemonic can have.
+const MNEMONIC_LENGTHS: [usize; 5] = [12, 15, 18, 21, 24];
+
+/// Watch-only addresses derived from a mnemonic or seed, read from the
+/// environment so the secret never sits in the config file. Keys are derived
+/// in memory and only the addresses kept.
+#[derive(Debug, Clone, Deserialize)]
+pub struct DerivedWallets {
+    /// Variable holding a BIP39 mnemonic.
+    #[serde(default)]
+    pub mnemonic_env: Option<String>,
+    /// Variable holding the optional BIP39 passphrase for `mnemonic_env`.
+    #[serde(default)]
+    pub passphrase_env: Option<String>,
+    /// Variable holding a hex-encoded BIP39 seed, instead of a mnemonic.
+    #[serde(default)]
+    pub seed_env: Option<String>,
+    /// First account index to derive.
+    #[serde(default)]
+    pub start: u32,
+    /// Number of accounts to derive.
+    #[serde(default = "default_count")]
+    pub count: u32,
+    /// Labels the addresses `<label> #<index>`.
+    #[serde(default)]
+    pub label: Option<String>,
+}
+
+fn default_count() -> u32 {
+    5
+}
+
+impl DerivedWallets {
+    /// The addresses at `m/44'/501'/i'/0'` for each account index `i`, the
+    /// path Phantom, Solflare and Ledger wallets use.
+    pub fn addresses(&self) -> Result<Vec<(u32, Pubkey)>, anyhow::Error> {
+        let seed = self.seed()?;
+        (self.start..self.start.saturating_add(self.count))
+            .map(|index| {
+                let path = DerivationPath::new_bip44(Some(index), Some(0));
+                let keypair = keypair_from_seed_and_derivation_path(&seed, Some(path))
+                    .map_err(|err| anyhow::anyhow!("deriving account {}: {}", index, err))?;
+                Ok((index, keypair.pubkey()))
+            })
+            .collect()
+    }
+
+    fn seed(&self) -> Result<Vec<u8>, anyhow::Error> {
+        match (&self.mnemonic_env, &self.seed_env) {
+            (Some(name), None) => {
+                let mnemonic = read_env(name)?;
+                let words: Vec<&str> = mnemonic.split_whitespace().collect();
+                if !MNEMONIC_LENGTHS.contains(&words.len()) {
+                    anyhow::bail!(
+                        "{} holds {} words; a BIP39 mnemonic has 12, 15, 18, 21 or 24",
+                        name,
+                        words.len()
+                    );
+                }
+                // Words outside the list or a bad checksum mean a typo, which
+                // would otherwise derive someone else's addresses.
+                Mnemonic::validate(&words.join(" "), Language::English).map_err(|err| {
+                    anyhow::anyhow!("{} is not a valid BIP39 mnemonic: {}", name, err)
+                })?;
+                let passphrase = match &self.passphrase_env {
+                    Some(name) => read_env(name)?,
+                    None => String::new(),
+                };
+                Ok(generate_seed_from_seed_phrase_and_passphrase(
+                    &words.join(" "),
+                    &passphrase,
+                ))
+            }
+            (None, Some(name)) => {
+                let seed = hex::decode(read_env(name)?.trim())
+                    .map_err(|err| anyhow::anyhow!("{} is not a hex seed: {}", name, err))?;
+                if !(16..=64).contains(&seed.len()) {
+                    anyhow::bail!(
+                        "{} holds a {}-byte seed; expected 16 to 64",
+                        name,
+                        seed.len()
+                    );
+                }
+                Ok(seed)
+            }
+            _ => anyhow::bail!("derived_wallets entries need one of mnemonic_env or seed_env"),
+        }
+    }
+}
+
+fn read_env(name: &str) -> Result<String, anyhow::Error> {
+    env::var(name).map_err(|_| anyhow::anyhow!("environment variable {} is not set", name))
+}
@@ -0,0 +1,87 @@
+use crate::activity::WalletTransaction;
+use crate::report::{BalanceReport, BalanceResult};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+
+/// Asset name used for native SOL in diffs and flattened outputs.
+pub const SOL_ASSET: &str = "SOL";
+/// Asset name used for SOL held in stake accounts.
+pub const STAKED_SOL_ASSET: &str = "SOL (staked)";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BalanceChange {
+    pub wallet: String,
+    pub asset: String,
+    pub old: f64,
+    pub new: f64,
+    /// Transactions since the previous report that may explain the change,
+    /// when watch mode looked them up.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub transactions: Vec<WalletTransaction>,
+}
+
+impl BalanceChange {
+    pub fn delta(&self) -> f64 {
+        self.new - self.old
+    }
+}
+
+/// Lists every SOL and token balance that differs between two reports.
+/// Wallets or tokens missing from one side are treated as a zero balance,
+/// except wallets that failed to fetch in either report, which are skipped.
+pub fn diff_reports(old: &BalanceReport, new: &BalanceReport) -> Vec<BalanceChange> {
+    let wallets: BTreeSet<&String> = old
+        .wallets
+        .keys()
+        .chain(new.wallets.keys())
+        .filter(|wallet| !old.errors.contains_key(*wallet) && !new.errors.contains_key(*wallet))
+        .collect();
+
+    let mut changes = Vec::new();
+    for wallet in wallets {
+        let before = old.wallets.get(wallet);
+        let after = new.wallets.get(wallet);
+
+        let mut push = |asset: &str, old: f64, new: f64| {
+            if old != new {
+                changes.push(BalanceChange {
+                    wallet: wallet.clone(),
+                    asset: asset.to_string(),
+                    old,
+                    new,
+                    transactions: Vec::new(),
+                });
+            }
+        };
+
+        push(
+            SOL_ASSET,
+            before.map_or(0.0, |result| result.sol_balance),
+            after.map_or(0.0, |result| result.sol_balance),
+        );
+
+        push(STAKED_SOL_ASSET, staked(before), staked(after));
+
+        let tokens: BTreeSet<&String> = before
+            .into_iter()
+            .chain(after)
+            .flat_map(|result| result.token_balances.keys())
+            .collect();
+        for token in tokens {
+            let amount = |result: Option<&BalanceResult>| {
+                result
+                    .and_then(|result| result.token_balances.get(token))
+                    .map_or(0.0, |balance| balance.amount)
+            };
+            push(token, amount(before), amount(after));
+        }
+    }
+
+    changes
+}
+
+fn staked(result: Option<&BalanceResult>) -> f64 {
+    result
+        .and_then(|result| result.stake.as_ref())
+        .map_or(0.0, |stake| stake.total)
+}
@@ -0,0 +1,74 @@
+use solana_sdk::pubkey::ParsePubkeyError;
+
+/// The kinds of failure the library reports, for callers that handle them
+/// differently. Details are in each error's source chain.
+#[derive(Debug, thiserror::Error)]
+pub enum BalanceError {
+    /// The config could not be read, parsed or expanded.
+    #[error("loading the config failed")]
+    Config(#[source] anyhow::Error),
+    /// An RPC request failed, for `wallet` if it concerned just one.
+    #[error("{}", match wallet {
+        Some(wallet) => format!("fetching {} failed", wallet),
+        None => "RPC request failed".to_string(),
+    })]
+    Rpc {
+        wallet: Option<String>,
+        #[source]
+        source: anyhow::Error,
+    },
+    /// A configured token's mint is not a valid address.
+    #[error("invalid mint address `{mint}`")]
+    Parse {
+        mint: String,
+        #[source]
+        source: ParsePubkeyError,
+    },
+    /// The price provider failed.
+    #[error("fetching prices failed")]
+    Price(#[source] anyhow::Error),
+    #[error(transparent)]
+    Other(anyhow::Error),
+}
+
+impl From<anyhow::Error> for BalanceError {
+    /// Keeps a `BalanceError` that was wrapped into `err` as it was.
+    fn from(err: anyhow::Error) -> Self {
+        match err.downcast::<BalanceError>() {
+            Ok(err) => err,
+            Err(err) => BalanceError::Other(err),
+        }
+    }
+}
+
+impl BalanceError {
+    /// Classifies a failure while fetching as an RPC error, unless it
+    /// already has a kind.
+    pub(crate) fn rpc(wallet: Option<&str>, source: anyhow::Error) -> Self {
+        match BalanceError::from(source) {
+            BalanceError::Other(source) => BalanceError::Rpc {
+                wallet: wallet.map(str::to_string),
+                source,
+            },
+            err => err,
+        }
+    }
+}
+
+/// `err` and its causes joined with `: `, like `{:#}`, but skipping causes
+/// whose message is already part of the text, as an RPC client error's
+/// kind is both its message and its source.
+pub(crate) fn error_chain(err: &anyhow::Error) -> String {
+    let mut message = String::new();
+    for cause in err.chain() {
+        let cause = cause.to_string();
+        if message.contains(&cause) {
+            continue;
+        }
+        if !message.is_empty() {
+            message.push_str(": ");
+        }
+        message.push_str(&cause);
+    }
+    message
+}
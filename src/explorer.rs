@@ -0,0 +1,156 @@
+use serde::Deserialize;
+use std::fmt;
+use std::str::FromStr;
+
+/// A block explorer that addresses, mints and transactions link to.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Explorer {
+    /// explorer.solana.com
+    #[default]
+    SolanaExplorer,
+    /// solscan.io
+    Solscan,
+    /// Helius' XRAY, xray.helius.xyz
+    Xray,
+}
+
+impl FromStr for Explorer {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().replace('-', "_").as_str() {
+            "solana_explorer" | "solana" => Ok(Explorer::SolanaExplorer),
+            "solscan" => Ok(Explorer::Solscan),
+            "xray" => Ok(Explorer::Xray),
+            other => anyhow::bail!(
+                "unknown explorer `{}` (expected solana-explorer, solscan or xray)",
+                other
+            ),
+        }
+    }
+}
+
+impl fmt::Display for Explorer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Explorer::SolanaExplorer => "solana-explorer",
+            Explorer::Solscan => "solscan",
+            Explorer::Xray => "xray",
+        })
+    }
+}
+
+/// The cluster explorer links point at.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Cluster {
+    #[default]
+    MainnetBeta,
+    Devnet,
+    Testnet,
+}
+
+impl Cluster {
+    /// `devnet` or `testnet` when the RPC URL mentions one, else mainnet.
+    pub fn guess(rpc_url: &str) -> Self {
+        if rpc_url.contains("devnet") {
+            Cluster::Devnet
+        } else if rpc_url.contains("testnet") {
+            Cluster::Testnet
+        } else {
+            Cluster::MainnetBeta
+        }
+    }
+}
+
+impl FromStr for Cluster {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().replace('_', "-").as_str() {
+            "mainnet-beta" | "mainnet" => Ok(Cluster::MainnetBeta),
+            "devnet" => Ok(Cluster::Devnet),
+            "testnet" => Ok(Cluster::Testnet),
+            other => anyhow::bail!(
+                "unknown cluster `{}` (expected mainnet-beta, devnet or testnet)",
+                other
+            ),
+        }
+    }
+}
+
+impl fmt::Display for Cluster {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Cluster::MainnetBeta => "mainnet-beta",
+            Cluster::Devnet => "devnet",
+            Cluster::Testnet => "testnet",
+        })
+    }
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct ExplorerConfig {
+    #[serde(default)]
+    pub provider: Explorer,
+    /// [default: guessed from `solana_rpc_url`]
+    #[serde(default)]
+    pub cluster: Option<Cluster>,
+}
+
+/// Builds links to wallets, mints, token accounts and transactions on one
+/// explorer and cluster.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ExplorerLinks {
+    pub explorer: Explorer,
+    pub cluster: Cluster,
+}
+
+impl ExplorerLinks {
+    /// Links for a wallet on the config's cluster `cluster`, which switch to
+    /// it when it is named after a known one, e.g. `devnet`.
+    pub fn on(self, cluster: Option<&str>) -> Self {
+        match cluster.and_then(|cluster| cluster.parse().ok()) {
+            Some(cluster) => ExplorerLinks { cluster, ..self },
+            None => self,
+        }
+    }
+
+    /// A wallet or token account.
+    pub fn account(&self, address: &str) -> String {
+        match self.explorer {
+            Explorer::SolanaExplorer => self.link("address", address),
+            Explorer::Solscan | Explorer::Xray => self.link("account", address),
+        }
+    }
+
+    pub fn token(&self, mint: &str) -> String {
+        match self.explorer {
+            Explorer::SolanaExplorer => self.link("address", mint),
+            Explorer::Solscan | Explorer::Xray => self.link("token", mint),
+        }
+    }
+
+    pub fn transaction(&self, signature: &str) -> String {
+        self.link("tx", signature)
+    }
+
+    fn link(&self, kind: &str, id: &str) -> String {
+        let base = match self.explorer {
+            Explorer::SolanaExplorer => "https://explorer.solana.com",
+            Explorer::Solscan => "https://solscan.io",
+            Explorer::Xray => "https://xray.helius.xyz",
+        };
+        let mut url = format!("{}/{}/{}", base, kind, id);
+        if self.cluster != Cluster::MainnetBeta {
+            // XRAY calls the cluster a network.
+            let parameter = match self.explorer {
+                Explorer::Xray => "network",
+                _ => "cluster",
+            };
+            url.push_str(&format!("?{}={}", parameter, self.cluster));
+        }
+        url
+    }
+}
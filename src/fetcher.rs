@@ -0,0 +1,1627 @@
+use crate::cache::DiskCache;
+use crate::chain::{ChainClient, Holdings, JsonRpcClient};
+use crate::config::{TokenConfig, TokenInfo, WalletInfo};
+use crate::error::{error_chain, BalanceError};
+use crate::group::summarize_groups;
+use crate::metadata::{metadata_address, parse_metadata, short_address, TokenMetadata};
+use crate::pricing::{Currency, FloorPriceProvider, PriceProvider};
+use crate::report::{BalanceReport, BalanceResult, TokenBalance};
+use crate::rpc::{
+    BoxedSender, Breakers, BudgetSender, CircuitBreakerConfig, EndpointStatus, FixtureSender,
+    Fixtures, PoolSender, RateLimitedSender, RateLimiter, RecordingSender, RetryPolicy,
+    RetrySender, RotationStrategy, RpcCalls, TracingSender,
+};
+use crate::sns::{
+    domain_address, is_sol_domain, parse_owner, parse_primary_domain, parse_reverse_name,
+    primary_domain_address, reverse_address,
+};
+use crate::stake::StakeContext;
+use crate::token_account::{
+    associated_token_address, decode_delegate, decode_mint_decimals, decode_state,
+    decode_token_account, decode_token_account_data, mint_problem, TokenAccountDetail,
+    TokenAccountState, TOKEN_PROGRAM_IDS, WRAPPED_SOL_TICKER,
+};
+use crate::token_list::TokenList;
+use futures::future::{join_all, try_join_all};
+use reqwest::header::HeaderMap;
+use reqwest::Proxy;
+use serde_json::json;
+use solana_account_decoder_client_types::UiAccountEncoding;
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_client::rpc_client::RpcClientConfig;
+use solana_client::rpc_config::{RpcAccountInfoConfig, RpcContextConfig, RpcTokenAccountsFilter};
+use solana_client::rpc_request::{RpcRequest, TokenAccountsFilter};
+use solana_client::rpc_response::{Response, RpcKeyedAccount};
+use solana_rpc_client::http_sender::HttpSender;
+use solana_sdk::clock::Slot;
+use solana_sdk::commitment_config::CommitmentConfig;
+use solana_sdk::native_token::lamports_to_sol;
+use solana_sdk::pubkey::Pubkey;
+use std::collections::btree_map::Entry;
+use std::collections::{BTreeMap, HashMap};
+use std::future::Future;
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::sync::{Semaphore, SemaphorePermit};
+
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(30);
+pub(crate) const DEFAULT_MAX_CONCURRENCY: usize = 8;
+pub(crate) const MAX_MULTIPLE_ACCOUNTS: usize = 100;
+
+/// Runs `fetch`, failing it if `config.deadline` passes first, counted from
+/// `started`.
+pub(crate) async fn before_deadline<T>(
+    config: &TokenConfig,
+    started: Instant,
+    fetch: impl Future<Output = Result<T, anyhow::Error>>,
+) -> Result<T, anyhow::Error> {
+    let Some(deadline) = config.deadline else {
+        return fetch.await;
+    };
+    match tokio::time::timeout_at((started + deadline).into(), fetch).await {
+        Ok(result) => result,
+        Err(_) => anyhow::bail!(
+            "deadline of {} passed before this wallet was fetched",
+            humantime::format_duration(deadline)
+        ),
+    }
+}
+
+/// Told about each wallet as [`BalanceFetcher::fetch_report_with_progress`]
+/// fetches it, e.g. to draw a progress bar. Every method defaults to doing
+/// nothing.
+pub trait FetchProgress: Send + Sync {
+    /// Called once with the number of wallets about to be fetched.
+    fn start(&self, _wallets: usize) {}
+
+    /// Called as each wallet finishes, with its error if it failed.
+    fn wallet_done(&self, _wallet: &WalletInfo, _error: Option<&anyhow::Error>) {}
+
+    /// Called once every wallet has finished.
+    fn finish(&self) {}
+}
+
+impl FetchProgress for () {}
+
+/// Passes on wallets finishing but not the start and finish of one of
+/// several fetches, so the whole run is reported as one.
+struct NestedProgress<'a>(&'a dyn FetchProgress);
+
+impl FetchProgress for NestedProgress<'_> {
+    fn wallet_done(&self, wallet: &WalletInfo, error: Option<&anyhow::Error>) {
+        self.0.wallet_done(wallet, error);
+    }
+}
+
+/// Fetches SOL and SPL token balances for a set of wallets.
+///
+/// Wallets and tokens are fetched concurrently; `max_concurrency` bounds the
+/// number of RPC requests in flight at any time.
+pub struct BalanceFetcher {
+    pub(crate) client: RpcClient,
+    /// Client and endpoint for DAS queries, which take named parameters
+    /// `RpcClient` cannot send.
+    pub(crate) http: reqwest::Client,
+    pub(crate) das_url: String,
+    permits: Semaphore,
+    price_provider: Option<Box<dyn PriceProvider>>,
+    /// The currency of the price provider's prices, when not USD.
+    currency: Option<Currency>,
+    pub(crate) floor_price_provider: Option<Box<dyn FloorPriceProvider>>,
+    chain_client: Box<dyn ChainClient>,
+    decimals_cache: Mutex<HashMap<Pubkey, u8>>,
+    domain_cache: Mutex<HashMap<String, Pubkey>>,
+    metadata_cache: Mutex<HashMap<Pubkey, Option<TokenMetadata>>>,
+    token_list: Option<TokenList>,
+    /// Where the caches above are loaded from and saved to between runs.
+    disk_cache: Option<DiskCache>,
+    /// Fetchers for the wallets on other clusters, by cluster name.
+    clusters: Vec<(String, BalanceFetcher)>,
+    /// The RPC endpoints' circuit breakers, unless replaying fixtures.
+    breakers: Option<Breakers>,
+    rpc_calls: Arc<RpcCalls>,
+    /// RPC requests allowed per report [default: unlimited].
+    pub(crate) max_rpc_calls: Option<u64>,
+    scan_token_accounts: bool,
+}
+
+pub struct BalanceFetcherBuilder {
+    rpc_urls: Vec<String>,
+    rotation: RotationStrategy,
+    commitment: CommitmentConfig,
+    timeout: Duration,
+    max_concurrency: usize,
+    max_rps: Option<f64>,
+    proxy: Option<Proxy>,
+    headers: HeaderMap,
+    retry_policy: RetryPolicy,
+    circuit_breaker: CircuitBreakerConfig,
+    rpc_calls: Arc<RpcCalls>,
+    max_rpc_calls: Option<u64>,
+    scan_token_accounts: bool,
+    price_provider: Option<Box<dyn PriceProvider>>,
+    currency: Option<Currency>,
+    floor_price_provider: Option<Box<dyn FloorPriceProvider>>,
+    chain_client: Box<dyn ChainClient>,
+    token_list: Option<TokenList>,
+    das_url: Option<String>,
+    disk_cache: Option<DiskCache>,
+    sender: Option<BoxedSender>,
+    record: Option<PathBuf>,
+    clusters: Vec<(String, BalanceFetcher)>,
+}
+
+impl BalanceFetcherBuilder {
+    pub fn commitment(mut self, commitment: CommitmentConfig) -> Self {
+        self.commitment = commitment;
+        self
+    }
+
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    pub fn max_concurrency(mut self, max_concurrency: usize) -> Self {
+        self.max_concurrency = max_concurrency.max(1);
+        self
+    }
+
+    /// Limits requests per second, counting every attempt sent to any
+    /// endpoint.
+    pub fn max_rps(mut self, max_rps: f64) -> Self {
+        self.max_rps = Some(max_rps);
+        self
+    }
+
+    /// Sends RPC requests through `proxy`.
+    pub fn proxy(mut self, proxy: Proxy) -> Self {
+        self.proxy = Some(proxy);
+        self
+    }
+
+    /// Adds `headers` to every RPC request, e.g. for providers that
+    /// authenticate with an API key header.
+    pub fn headers(mut self, headers: HeaderMap) -> Self {
+        self.headers.extend(headers);
+        self
+    }
+
+    /// Replaces the endpoint list; requests fail over between them.
+    pub fn endpoints(mut self, rpc_urls: Vec<String>) -> Self {
+        if !rpc_urls.is_empty() {
+            self.rpc_urls = rpc_urls;
+        }
+        self
+    }
+
+    pub fn rotation(mut self, rotation: RotationStrategy) -> Self {
+        self.rotation = rotation;
+        self
+    }
+
+    pub fn retry_policy(mut self, retry_policy: RetryPolicy) -> Self {
+        self.retry_policy = retry_policy;
+        self
+    }
+
+    pub fn circuit_breaker(mut self, circuit_breaker: CircuitBreakerConfig) -> Self {
+        self.circuit_breaker = circuit_breaker;
+        self
+    }
+
+    /// Counts requests in `rpc_calls`, which may be shared with other
+    /// fetchers to see the requests of all of them.
+    pub fn rpc_calls(mut self, rpc_calls: Arc<RpcCalls>) -> Self {
+        self.rpc_calls = rpc_calls;
+        self
+    }
+
+    /// Fails requests beyond the first `max_rpc_calls` of each report.
+    pub fn max_rpc_calls(mut self, max_rpc_calls: u64) -> Self {
+        self.max_rpc_calls = Some(max_rpc_calls);
+        self
+    }
+
+    /// Counts configured tokens in every token account of a wallet, not just
+    /// its associated ones, at two requests per wallet instead of a share of
+    /// the batched ones.
+    pub fn scan_token_accounts(mut self, scan_token_accounts: bool) -> Self {
+        self.scan_token_accounts = scan_token_accounts;
+        self
+    }
+
+    /// Values balances in USD when building reports.
+    pub fn price_provider(mut self, provider: Box<dyn PriceProvider>) -> Self {
+        self.price_provider = Some(provider);
+        self
+    }
+
+    /// The currency the price provider's prices are in, when not USD.
+    pub fn currency(mut self, currency: Currency) -> Self {
+        self.currency = Some(currency);
+        self
+    }
+
+    /// Values NFTs at their collections' floor prices when building reports.
+    pub fn floor_price_provider(mut self, provider: Box<dyn FloorPriceProvider>) -> Self {
+        self.floor_price_provider = Some(provider);
+        self
+    }
+
+    /// Reads wallets' SOL and token balances through `client` instead of
+    /// plain JSON-RPC.
+    pub fn chain_client(mut self, client: Box<dyn ChainClient>) -> Self {
+        self.chain_client = client;
+        self
+    }
+
+    /// Names mints and looks up their decimals from `token_list` before
+    /// falling back to on-chain data.
+    pub fn token_list(mut self, token_list: TokenList) -> Self {
+        self.token_list = Some(token_list);
+        self
+    }
+
+    /// Sends DAS queries (`getAssetsByOwner`) to `das_url` instead of the
+    /// RPC endpoints.
+    pub fn das_url(mut self, das_url: impl Into<String>) -> Self {
+        self.das_url = Some(das_url.into());
+        self
+    }
+
+    /// Seeds the decimals, metadata and domain caches from `cache`, and
+    /// saves what they learn back to it after each report.
+    pub fn disk_cache(mut self, cache: DiskCache) -> Self {
+        self.disk_cache = Some(cache);
+        self
+    }
+
+    /// Replays RPC responses from fixture files instead of sending
+    /// requests, or records them while sending. DAS and price lookups still
+    /// go to the network.
+    pub fn fixtures(mut self, fixtures: Fixtures) -> Self {
+        match fixtures {
+            Fixtures::Replay(dir) => self.sender(Box::new(FixtureSender::new(dir))),
+            Fixtures::Record(dir) => {
+                self.record = Some(dir);
+                self
+            }
+        }
+    }
+
+    /// Sends RPC requests through `sender` instead of the endpoints, such as
+    /// a [`FixtureSender`] or a test double. Retries and the request budget
+    /// still apply; circuit breakers and recording do not.
+    pub fn sender(mut self, sender: BoxedSender) -> Self {
+        self.sender = Some(sender);
+        self
+    }
+
+    /// Fetches the wallets on the config's cluster `name` with `fetcher`,
+    /// built for that cluster's RPC URL.
+    pub fn cluster(mut self, name: impl Into<String>, fetcher: BalanceFetcher) -> Self {
+        self.clusters.push((name.into(), fetcher));
+        self
+    }
+
+    pub fn build(self) -> BalanceFetcher {
+        let mut headers = HttpSender::default_headers();
+        headers.extend(self.headers);
+        let mut client = reqwest::Client::builder()
+            .default_headers(headers)
+            .timeout(self.timeout)
+            .pool_idle_timeout(self.timeout);
+        if let Some(proxy) = self.proxy {
+            client = client.proxy(proxy);
+        }
+        let client = client.build().expect("build rpc client");
+
+        let limiter = self
+            .max_rps
+            .map(|max_rps| Arc::new(RateLimiter::new(max_rps)));
+        let das_url = self.das_url.unwrap_or_else(|| self.rpc_urls[0].clone());
+        let endpoints =
+            self.rpc_urls
+                .into_iter()
+                .map(|url| {
+                    let sender = Box::new(TracingSender::new(Box::new(
+                        HttpSender::new_with_client(url, client.clone()),
+                    ))) as BoxedSender;
+                    let sender = Box::new(BudgetSender::new(
+                        sender,
+                        self.rpc_calls.clone(),
+                        self.max_rpc_calls,
+                    )) as BoxedSender;
+                    match &limiter {
+                        Some(limiter) => Box::new(RateLimitedSender::new(sender, limiter.clone())),
+                        None => sender,
+                    }
+                })
+                .collect();
+        let pool = PoolSender::new(endpoints, self.rotation).circuit_breaker(self.circuit_breaker);
+        let breakers = pool.breakers();
+        let (sender, breakers): (BoxedSender, _) = match (self.sender, self.record) {
+            (Some(sender), _) => (
+                Box::new(BudgetSender::new(
+                    Box::new(TracingSender::new(sender)),
+                    self.rpc_calls.clone(),
+                    self.max_rpc_calls,
+                )),
+                None,
+            ),
+            (None, Some(dir)) => (
+                Box::new(RecordingSender::new(Box::new(pool), dir)),
+                Some(breakers),
+            ),
+            (None, None) => (Box::new(pool), Some(breakers)),
+        };
+        let sender = RetrySender::new(sender, self.retry_policy);
+
+        BalanceFetcher {
+            client: RpcClient::new_sender(
+                sender,
+                RpcClientConfig::with_commitment(self.commitment),
+            ),
+            http: client,
+            das_url,
+            permits: Semaphore::new(self.max_concurrency),
+            price_provider: self.price_provider,
+            currency: self.currency,
+            floor_price_provider: self.floor_price_provider,
+            chain_client: self.chain_client,
+            decimals_cache: Mutex::new(
+                self.disk_cache
+                    .as_ref()
+                    .map(DiskCache::decimals)
+                    .unwrap_or_default(),
+            ),
+            domain_cache: Mutex::new(
+                self.disk_cache
+                    .as_ref()
+                    .map(DiskCache::domains)
+                    .unwrap_or_default(),
+            ),
+            metadata_cache: Mutex::new(
+                self.disk_cache
+                    .as_ref()
+                    .map(DiskCache::metadata)
+                    .unwrap_or_default(),
+            ),
+            token_list: self.token_list,
+            disk_cache: self.disk_cache,
+            clusters: self.clusters,
+            breakers,
+            rpc_calls: self.rpc_calls,
+            max_rpc_calls: self.max_rpc_calls,
+            scan_token_accounts: self.scan_token_accounts,
+        }
+    }
+}
+
+impl BalanceFetcher {
+    pub fn builder(rpc_url: impl Into<String>) -> BalanceFetcherBuilder {
+        BalanceFetcherBuilder {
+            rpc_urls: vec![rpc_url.into()],
+            rotation: RotationStrategy::default(),
+            commitment: CommitmentConfig::default(),
+            timeout: DEFAULT_TIMEOUT,
+            max_concurrency: DEFAULT_MAX_CONCURRENCY,
+            max_rps: None,
+            proxy: None,
+            headers: HeaderMap::new(),
+            retry_policy: RetryPolicy::default(),
+            circuit_breaker: CircuitBreakerConfig::default(),
+            rpc_calls: Arc::default(),
+            max_rpc_calls: None,
+            scan_token_accounts: false,
+            price_provider: None,
+            currency: None,
+            floor_price_provider: None,
+            chain_client: Box::new(JsonRpcClient),
+            token_list: None,
+            das_url: None,
+            disk_cache: None,
+            sender: None,
+            record: None,
+            clusters: Vec::new(),
+        }
+    }
+
+    pub fn new(rpc_url: impl Into<String>) -> Self {
+        Self::builder(rpc_url).build()
+    }
+
+    pub fn from_config(config: &TokenConfig) -> Self {
+        Self::config_builder(config).build()
+    }
+
+    /// The circuit breaker of each RPC endpoint, in pool order; empty when
+    /// requests go through a [`BalanceFetcherBuilder::sender`].
+    pub fn endpoint_statuses(&self) -> Vec<EndpointStatus> {
+        self.breakers
+            .as_ref()
+            .map(Breakers::statuses)
+            .unwrap_or_default()
+    }
+
+    /// The RPC requests made so far, by method.
+    pub fn rpc_calls(&self) -> &RpcCalls {
+        &self.rpc_calls
+    }
+
+    /// A builder preconfigured from `config`, for settings that need more
+    /// than the config file (such as a downloaded token list).
+    pub fn config_builder(config: &TokenConfig) -> BalanceFetcherBuilder {
+        let mut builder = Self::builder(&config.solana_rpc_url)
+            .endpoints(config.rpc_urls())
+            .rotation(config.rpc_rotation)
+            .commitment(CommitmentConfig {
+                commitment: config.commitment,
+            })
+            .max_concurrency(config.max_concurrency)
+            .retry_policy(config.retry.clone())
+            .circuit_breaker(config.circuit_breaker.clone())
+            .scan_token_accounts(config.scan_token_accounts)
+            .chain_client(config.backend.client());
+        if let Some(timeout) = config.request_timeout {
+            builder = builder.timeout(timeout);
+        }
+        if let Some(max_rps) = config.max_rps {
+            builder = builder.max_rps(max_rps);
+        }
+        if let Some(max_rpc_calls) = config.max_rpc_calls {
+            builder = builder.max_rpc_calls(max_rpc_calls);
+        }
+        if let Some(proxy) = &config.rpc_proxy {
+            builder = builder.proxy(proxy.clone());
+        }
+        builder = builder.headers(config.rpc_headers.clone());
+        if let Some(pricing) = &config.pricing {
+            builder = builder.price_provider(pricing.provider());
+            if let Some(currency) = pricing.currency() {
+                builder = builder.currency(currency);
+            }
+        }
+        if let Some(nfts) = &config.nfts {
+            if let Some(das_url) = &nfts.das_url {
+                builder = builder.das_url(das_url.clone());
+            }
+            if let Some(floor_prices) = &nfts.floor_prices {
+                builder = builder.floor_price_provider(floor_prices.provider());
+            }
+        }
+        builder
+    }
+
+    /// Fetches all wallet balances and, if a price provider is configured,
+    /// values them in USD.
+    ///
+    /// A wallet that cannot be fetched is recorded in
+    /// [`BalanceReport::errors`] instead of failing the whole report.
+    pub async fn fetch_report(&self, config: &TokenConfig) -> Result<BalanceReport, BalanceError> {
+        self.fetch_report_with_progress(config, &()).await
+    }
+
+    /// Like [`fetch_report`](Self::fetch_report), telling `progress` about
+    /// each wallet as it is fetched.
+    #[tracing::instrument(skip_all, fields(wallets = config.wallets.len()))]
+    pub async fn fetch_report_with_progress(
+        &self,
+        config: &TokenConfig,
+        progress: &dyn FetchProgress,
+    ) -> Result<BalanceReport, BalanceError> {
+        self.rpc_calls.start_run();
+        let report = if config.clusters.is_empty() {
+            self.fetch_cluster_report(config, progress).await
+        } else {
+            self.fetch_clusters(config, progress).await
+        };
+        // A report missing what the budget refused would look complete.
+        match self.rpc_calls.exceeded() {
+            Some(err) => Err(BalanceError::rpc(None, err.into())),
+            None => report,
+        }
+    }
+
+    /// Fetches the wallets of a config without other clusters.
+    async fn fetch_cluster_report(
+        &self,
+        config: &TokenConfig,
+        progress: &dyn FetchProgress,
+    ) -> Result<BalanceReport, BalanceError> {
+        let started = Instant::now();
+        let stake_context = if config.include_stake {
+            Some(
+                self.get_stake_context()
+                    .await
+                    .map_err(|err| BalanceError::rpc(None, err))?,
+            )
+        } else {
+            None
+        };
+
+        let slot = async {
+            let _permit = self.permit().await?;
+            Ok::<_, anyhow::Error>(self.client.get_slot().await?)
+        }
+        .await;
+        let slot = if config.same_slot {
+            Some(slot.map_err(|err| BalanceError::rpc(None, err))?)
+        } else {
+            slot.ok()
+        };
+
+        // Balance reads must not be answered from before this slot.
+        let min_context_slot = slot.filter(|_| config.same_slot);
+
+        progress.start(config.wallets.len());
+        let results = self
+            .fetch_wallets(
+                config,
+                stake_context.as_ref(),
+                min_context_slot,
+                progress,
+                started,
+            )
+            .await;
+        progress.finish();
+        let results = results.map_err(|err| BalanceError::rpc(None, err))?;
+
+        let mut report = BalanceReport::new(HashMap::new());
+        report.slot = slot;
+        report.same_slot = config.same_slot;
+        for (wallet, result) in config.wallets.iter().zip(results) {
+            match result {
+                Ok((address, balance)) => {
+                    report.wallet_order.push(address.to_string());
+                    report.wallets.insert(address.to_string(), balance);
+                }
+                Err(err) => {
+                    let error = error_chain(&err);
+                    tracing::info!(wallet = %wallet.address, %error, "fetching wallet failed");
+                    report.errors.insert(wallet.address.clone(), error);
+                }
+            }
+        }
+
+        if config.reverse_lookup {
+            self.apply_primary_domains(&mut report).await;
+        }
+        if config.merge_wrapped_sol {
+            report.merge_wrapped_sol();
+        }
+        self.apply_lst_rates(config, &mut report)
+            .await
+            .map_err(|err| BalanceError::rpc(None, err))?;
+        if let Some(nfts) = &config.nfts {
+            self.apply_floor_prices(nfts, &mut report)
+                .await
+                .map_err(BalanceError::Price)?;
+        }
+
+        let filter = &config.token_filter;
+        let configured = config.configured_mints();
+        let discovered =
+            |balance: &TokenBalance| config.discover_tokens && !configured.contains(&balance.mint);
+
+        // Filter before pricing so spam mints are never sent to the provider.
+        report.retain_tokens(|ticker, balance| {
+            config.shows_token(ticker, balance)
+                && (!discovered(balance)
+                    || filter.allows(ticker, balance, self.is_listed(&balance.mint)))
+        });
+
+        if let Some(provider) = &self.price_provider {
+            let prices = provider
+                .usd_prices(&report.mints())
+                .await
+                .map_err(BalanceError::Price)?;
+            report.apply_prices(&prices);
+            report.currency = self.currency.clone();
+            report.retain_tokens(|_, balance| !discovered(balance) || filter.allows_value(balance));
+        }
+
+        report.groups = summarize_groups(&config.groups, &report);
+        tracing::info!(
+            fetched = report.wallets.len(),
+            failed = report.errors.len(),
+            backend = self.chain_client.name(),
+            elapsed_ms = started.elapsed().as_millis() as u64,
+            "fetched balances"
+        );
+        self.save_disk_cache();
+        Ok(report)
+    }
+
+    /// Fetches the wallets of each cluster with its own fetcher and merges
+    /// the reports, main cluster first. The slot is the main cluster's.
+    async fn fetch_clusters(
+        &self,
+        config: &TokenConfig,
+        progress: &dyn FetchProgress,
+    ) -> Result<BalanceReport, BalanceError> {
+        progress.start(config.wallets.len());
+        let progress = NestedProgress(progress);
+        let main = config.for_cluster(None);
+        let main_report = async {
+            if main.wallets.is_empty() {
+                return Ok(BalanceReport::new(HashMap::new()));
+            }
+            self.fetch_cluster_report(&main, &progress).await
+        };
+        let other_reports = try_join_all(config.clusters.keys().map(|name| {
+            let progress = &progress;
+            async move {
+                let fetcher = self
+                    .clusters
+                    .iter()
+                    .find(|(cluster, _)| cluster == name)
+                    .map(|(_, fetcher)| fetcher)
+                    .ok_or_else(|| {
+                        BalanceError::Config(anyhow::anyhow!(
+                            "no fetcher was built for cluster `{}`",
+                            name
+                        ))
+                    })?;
+                let cluster = config.for_cluster(Some(name));
+                if cluster.wallets.is_empty() {
+                    return Ok(None);
+                }
+                fetcher
+                    .fetch_cluster_report(&cluster, progress)
+                    .await
+                    .map(Some)
+            }
+        }));
+        let (mut report, other_reports) = futures::try_join!(main_report, other_reports)?;
+        progress.0.finish();
+
+        for other in other_reports.into_iter().flatten() {
+            report.merge(other);
+        }
+        report.groups = summarize_groups(&config.groups, &report);
+        Ok(report)
+    }
+
+    /// Saves the cached decimals, metadata and domains to the disk cache,
+    /// if there is one.
+    pub(crate) fn save_disk_cache(&self) {
+        let Some(cache) = &self.disk_cache else {
+            return;
+        };
+        let result = cache.save(
+            &self.decimals_cache.lock().unwrap(),
+            &self.metadata_cache.lock().unwrap(),
+            &self.domain_cache.lock().unwrap(),
+        );
+        if let Err(err) = result {
+            tracing::warn!("saving cache: {:#}", err);
+        }
+    }
+
+    /// Fetches every configured wallet, keeping each wallet's outcome.
+    async fn fetch_wallets(
+        &self,
+        config: &TokenConfig,
+        stake_context: Option<&StakeContext>,
+        min_context_slot: Option<Slot>,
+        progress: &dyn FetchProgress,
+        started: Instant,
+    ) -> Result<Vec<Result<(Pubkey, BalanceResult), anyhow::Error>>, anyhow::Error> {
+        let token_balances = self
+            .prefetch_token_balances(config, min_context_slot)
+            .await?;
+        Ok(join_all(config.wallets.iter().map(|wallet| async {
+            let result = before_deadline(
+                config,
+                started,
+                self.get_wallet_balance(
+                    wallet,
+                    config,
+                    stake_context,
+                    token_balances.as_ref(),
+                    min_context_slot,
+                ),
+            )
+            .await;
+            progress.wallet_done(wallet, result.as_ref().err());
+            result
+        }))
+        .await)
+    }
+
+    pub(crate) fn is_listed(&self, mint: &str) -> bool {
+        let Some(token_list) = &self.token_list else {
+            return false;
+        };
+        Pubkey::from_str(mint).is_ok_and(|mint| token_list.get(&mint).is_some())
+    }
+
+    /// Labels wallets configured by address with their primary `.sol`
+    /// domain. Names are cosmetic, so lookup failures leave them unlabeled.
+    async fn apply_primary_domains(&self, report: &mut BalanceReport) {
+        let wallets: Vec<Pubkey> = report
+            .wallets
+            .iter()
+            .filter(|(_, result)| result.domain.is_none())
+            .filter_map(|(wallet, _)| Pubkey::from_str(wallet).ok())
+            .collect();
+        let domains = match self.get_primary_domains(&wallets).await {
+            Ok(domains) => domains,
+            Err(err) => {
+                tracing::debug!(error = %format!("{:#}", err), "primary domain lookup failed");
+                return;
+            }
+        };
+        for (wallet, domain) in domains {
+            if let Some(result) = report.wallets.get_mut(&wallet.to_string()) {
+                result.domain = Some(domain);
+            }
+        }
+    }
+
+    /// Looks up each wallet's primary `.sol` domain. Domains the wallet no
+    /// longer owns are left out.
+    pub async fn get_primary_domains(
+        &self,
+        wallets: &[Pubkey],
+    ) -> Result<HashMap<Pubkey, String>, anyhow::Error> {
+        let mut name_accounts = Vec::new();
+        for chunk in wallets.chunks(MAX_MULTIPLE_ACCOUNTS) {
+            let addresses: Vec<Pubkey> = chunk.iter().map(primary_domain_address).collect();
+            let accounts = {
+                let _permit = self.permit().await?;
+                self.client.get_multiple_accounts(&addresses).await?
+            };
+            for (wallet, account) in chunk.iter().zip(accounts) {
+                if let Some(name_account) =
+                    account.and_then(|account| parse_primary_domain(&account.data))
+                {
+                    name_accounts.push((*wallet, name_account));
+                }
+            }
+        }
+
+        let mut domains = HashMap::new();
+        for chunk in name_accounts.chunks(MAX_MULTIPLE_ACCOUNTS / 2) {
+            let addresses: Vec<Pubkey> = chunk
+                .iter()
+                .flat_map(|(_, name_account)| [*name_account, reverse_address(name_account)])
+                .collect();
+            let accounts = {
+                let _permit = self.permit().await?;
+                self.client.get_multiple_accounts(&addresses).await?
+            };
+            for ((wallet, _), pair) in chunk.iter().zip(accounts.chunks(2)) {
+                let [Some(name), Some(reverse)] = pair else {
+                    continue;
+                };
+                if parse_owner(&name.data) != Some(*wallet) {
+                    continue;
+                }
+                if let Some(domain) = parse_reverse_name(&reverse.data) {
+                    domains.insert(*wallet, domain);
+                }
+            }
+        }
+
+        Ok(domains)
+    }
+
+    /// Waits for a free request slot; hold the permit for the duration of
+    /// one RPC call.
+    pub(crate) async fn permit(&self) -> Result<SemaphorePermit<'_>, anyhow::Error> {
+        Ok(self.permits.acquire().await?)
+    }
+
+    /// Fetches all wallet balances, failing if any single wallet fails.
+    pub async fn get_wallet_balances(
+        &self,
+        config: &TokenConfig,
+    ) -> Result<HashMap<String, BalanceResult>, BalanceError> {
+        let stake_context = if config.include_stake {
+            Some(
+                self.get_stake_context()
+                    .await
+                    .map_err(|err| BalanceError::rpc(None, err))?,
+            )
+        } else {
+            None
+        };
+
+        let token_balances = self
+            .prefetch_token_balances(config, None)
+            .await
+            .map_err(|err| BalanceError::rpc(None, err))?;
+        let results = try_join_all(config.wallets.iter().map(|wallet| async {
+            self.get_wallet_balance(
+                wallet,
+                config,
+                stake_context.as_ref(),
+                token_balances.as_ref(),
+                None,
+            )
+            .await
+            .map_err(|err| BalanceError::rpc(Some(&wallet.address), err))
+        }))
+        .await?;
+
+        Ok(results
+            .into_iter()
+            .map(|(address, balance)| (address.to_string(), balance))
+            .collect())
+    }
+
+    /// Parses a wallet address, resolving `.sol` domains through SNS.
+    /// Resolved domains are cached for the lifetime of the fetcher.
+    pub async fn resolve_wallet(&self, wallet: &str) -> Result<Pubkey, anyhow::Error> {
+        if !is_sol_domain(wallet) {
+            return Pubkey::from_str(wallet)
+                .map_err(|err| anyhow::anyhow!("invalid wallet address: {}", err));
+        }
+
+        if let Some(owner) = self.domain_cache.lock().unwrap().get(wallet) {
+            return Ok(*owner);
+        }
+
+        let account = {
+            let _permit = self.permit().await?;
+            self.client
+                .get_account_with_commitment(&domain_address(wallet)?, self.client.commitment())
+                .await?
+                .value
+        };
+        let owner = account
+            .and_then(|account| parse_owner(&account.data))
+            .ok_or_else(|| anyhow::anyhow!("domain {} is not registered", wallet))?;
+
+        self.domain_cache
+            .lock()
+            .unwrap()
+            .insert(wallet.to_string(), owner);
+        Ok(owner)
+    }
+
+    /// Fetches the configured tokens of every wallet in batches, unless
+    /// tokens are discovered per wallet. Wallets with their own token
+    /// selection, and those that cannot be resolved, are left out and
+    /// fetched later on their own.
+    async fn prefetch_token_balances(
+        &self,
+        config: &TokenConfig,
+        min_context_slot: Option<Slot>,
+    ) -> Result<Option<HashMap<Pubkey, HashMap<String, TokenBalance>>>, anyhow::Error> {
+        if config.discover_tokens || !self.chain_client.batches_tokens() {
+            return Ok(None);
+        }
+        let wallets: Vec<Pubkey> = join_all(
+            config
+                .wallets
+                .iter()
+                .filter(|wallet| wallet.tokens.is_default())
+                .map(|wallet| self.resolve_wallet(&wallet.address)),
+        )
+        .await
+        .into_iter()
+        .filter_map(Result::ok)
+        .collect();
+        Ok(Some(
+            self.get_token_balances_batch(&wallets, &config.tokens, min_context_slot)
+                .await?,
+        ))
+    }
+
+    async fn get_wallet_balance(
+        &self,
+        wallet: &WalletInfo,
+        config: &TokenConfig,
+        stake_context: Option<&StakeContext>,
+        prefetched: Option<&HashMap<Pubkey, HashMap<String, TokenBalance>>>,
+        min_context_slot: Option<Slot>,
+    ) -> Result<(Pubkey, BalanceResult), anyhow::Error> {
+        let wallet_str = wallet.address.as_str();
+        let wallet_pubkey = self.resolve_wallet(wallet_str).await?;
+        let tokens = config.tokens_for(wallet);
+
+        let holdings = async {
+            match prefetched.and_then(|prefetched| prefetched.get(&wallet_pubkey)) {
+                Some(balances) => Ok(Holdings {
+                    lamports: self
+                        .get_sol_balance(&wallet_pubkey, min_context_slot)
+                        .await?,
+                    tokens: balances.clone(),
+                }),
+                None => {
+                    self.chain_client
+                        .holdings(
+                            self,
+                            &wallet_pubkey,
+                            &tokens,
+                            config.discover_tokens,
+                            min_context_slot,
+                        )
+                        .await
+                }
+            }
+        };
+        let stake = async {
+            match stake_context {
+                Some(context) => Ok(Some(
+                    self.get_stake_summary(&wallet_pubkey, context, min_context_slot)
+                        .await?,
+                )),
+                None => Ok(None),
+            }
+        };
+        let details = async {
+            if config.token_account_details {
+                self.get_token_account_details(&wallet_pubkey, min_context_slot)
+                    .await
+            } else {
+                Ok(Vec::new())
+            }
+        };
+        let (holdings, stake, details) = futures::try_join!(holdings, stake, details)?;
+        let sol_balance = holdings.lamports;
+        let mut token_balances = holdings.tokens;
+        token_balances.retain(|ticker, balance| !wallet.tokens.excludes(ticker, &balance.mint));
+        for detail in details {
+            if let Some(balance) = token_balances
+                .values_mut()
+                .find(|balance| balance.mint == detail.mint)
+            {
+                balance.accounts.push(detail);
+            }
+        }
+        let rent = if config.rent_breakdown {
+            Some(
+                self.get_rent_breakdown(&wallet_pubkey, sol_balance, min_context_slot)
+                    .await?,
+            )
+        } else {
+            None
+        };
+        let vesting = if wallet.vesting {
+            Some(
+                self.get_vesting(&wallet_pubkey, &tokens, min_context_slot)
+                    .await?,
+            )
+        } else {
+            None
+        };
+        let nfts = match &config.nfts {
+            Some(nfts) => Some(self.get_nft_summary(&wallet_pubkey, nfts).await?),
+            None => None,
+        };
+
+        let result = BalanceResult {
+            label: wallet.label.clone(),
+            domain: is_sol_domain(wallet_str).then(|| wallet_str.to_string()),
+            multisig: wallet.multisig.clone(),
+            realm: wallet.realm.clone(),
+            cluster: wallet.cluster.clone(),
+            sol_balance: lamports_to_sol(sol_balance),
+            sol_lamports: sol_balance,
+            sol_usd_value: None,
+            wrapped_sol: None,
+            rent,
+            stake,
+            nfts,
+            vesting,
+            token_balances,
+            total_usd: None,
+        };
+        Ok((wallet_pubkey, result))
+    }
+
+    /// The wallet's SOL balance in lamports, read at `min_context_slot` or
+    /// later.
+    pub async fn get_sol_balance(
+        &self,
+        wallet_pubkey: &Pubkey,
+        min_context_slot: Option<Slot>,
+    ) -> Result<u64, anyhow::Error> {
+        let config = RpcContextConfig {
+            commitment: Some(self.client.commitment()),
+            min_context_slot,
+        };
+        let _permit = self.permit().await?;
+        let response: Response<u64> = self
+            .client
+            .send(
+                RpcRequest::GetBalance,
+                json!([wallet_pubkey.to_string(), config]),
+            )
+            .await?;
+        Ok(response.value)
+    }
+
+    /// Like `RpcClient::get_token_accounts_by_owner`, but read at
+    /// `min_context_slot` or later.
+    pub(crate) async fn get_token_accounts(
+        &self,
+        wallet_pubkey: &Pubkey,
+        filter: TokenAccountsFilter,
+        min_context_slot: Option<Slot>,
+    ) -> Result<Vec<RpcKeyedAccount>, anyhow::Error> {
+        let filter = match filter {
+            TokenAccountsFilter::Mint(mint) => RpcTokenAccountsFilter::Mint(mint.to_string()),
+            TokenAccountsFilter::ProgramId(program_id) => {
+                RpcTokenAccountsFilter::ProgramId(program_id.to_string())
+            }
+        };
+        let config = RpcAccountInfoConfig {
+            encoding: Some(UiAccountEncoding::JsonParsed),
+            commitment: Some(self.client.commitment()),
+            data_slice: None,
+            min_context_slot,
+        };
+        let _permit = self.permit().await?;
+        let response: Response<Vec<RpcKeyedAccount>> = self
+            .client
+            .send(
+                RpcRequest::GetTokenAccountsByOwner,
+                json!([wallet_pubkey.to_string(), filter, config]),
+            )
+            .await?;
+        Ok(response.value)
+    }
+
+    pub async fn get_token_balances(
+        &self,
+        wallet_pubkey: &Pubkey,
+        tokens: &[TokenInfo],
+        min_context_slot: Option<Slot>,
+    ) -> Result<HashMap<String, TokenBalance>, anyhow::Error> {
+        Ok(self
+            .get_token_balances_batch(
+                std::slice::from_ref(wallet_pubkey),
+                tokens,
+                min_context_slot,
+            )
+            .await?
+            .remove(wallet_pubkey)
+            .unwrap_or_default())
+    }
+
+    /// Reads the associated token accounts (legacy and Token-2022) of every
+    /// wallet for every configured mint, `MAX_MULTIPLE_ACCOUNTS` per
+    /// request. Tokens held in other accounts are only counted with
+    /// [`BalanceFetcherBuilder::scan_token_accounts`], which lists each
+    /// wallet's token accounts instead, falling back to the associated ones
+    /// for wallets whose listing fails.
+    pub async fn get_token_balances_batch(
+        &self,
+        wallets: &[Pubkey],
+        tokens: &[TokenInfo],
+        min_context_slot: Option<Slot>,
+    ) -> Result<HashMap<Pubkey, HashMap<String, TokenBalance>>, anyhow::Error> {
+        let mints = tokens
+            .iter()
+            .map(TokenInfo::mint)
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let mut accounts = Vec::new();
+        let mut listed_decimals = HashMap::new();
+        let mut unlisted = wallets.to_vec();
+        if self.scan_token_accounts {
+            let listings = join_all(wallets.iter().map(|wallet| async move {
+                let listing = try_join_all(TOKEN_PROGRAM_IDS.iter().map(|program_id| {
+                    self.get_token_accounts(
+                        wallet,
+                        TokenAccountsFilter::ProgramId(*program_id),
+                        min_context_slot,
+                    )
+                }))
+                .await;
+                (*wallet, listing)
+            }))
+            .await;
+            for (wallet, listing) in listings {
+                let token_accounts = match listing {
+                    Ok(token_accounts) => token_accounts.concat(),
+                    Err(err) => {
+                        tracing::warn!(
+                            wallet = %wallet,
+                            error = %format!("{:#}", err),
+                            "listing token accounts failed; reading the associated ones"
+                        );
+                        continue;
+                    }
+                };
+                unlisted.retain(|unlisted| *unlisted != wallet);
+                for account in &token_accounts {
+                    let (Some(decoded), Ok(address)) = (
+                        decode_token_account(account),
+                        Pubkey::from_str(&account.pubkey),
+                    ) else {
+                        continue;
+                    };
+                    if !mints.contains(&decoded.mint) {
+                        continue;
+                    }
+                    if let Some(decimals) = decoded.decimals {
+                        listed_decimals.insert(decoded.mint, decimals);
+                    }
+                    accounts.push((
+                        wallet,
+                        decoded.mint,
+                        address,
+                        decoded.amount,
+                        decoded.state,
+                        decoded.delegate,
+                    ));
+                }
+            }
+        }
+
+        let mut lookups = Vec::new();
+        for wallet in &unlisted {
+            for mint in &mints {
+                for program_id in &TOKEN_PROGRAM_IDS {
+                    lookups.push((
+                        *wallet,
+                        *mint,
+                        associated_token_address(wallet, program_id, mint),
+                    ));
+                }
+            }
+        }
+
+        let config = RpcAccountInfoConfig {
+            encoding: Some(UiAccountEncoding::Base64),
+            commitment: Some(self.client.commitment()),
+            data_slice: None,
+            min_context_slot,
+        };
+        let amounts = try_join_all(lookups.chunks(MAX_MULTIPLE_ACCOUNTS).map(|chunk| {
+            let config = config.clone();
+            async move {
+                let addresses: Vec<Pubkey> = chunk.iter().map(|(_, _, address)| *address).collect();
+                let _permit = self.permit().await?;
+                let accounts = self
+                    .client
+                    .get_multiple_accounts_with_config(&addresses, config)
+                    .await?
+                    .value;
+                Ok::<_, anyhow::Error>(chunk.iter().zip(accounts).filter_map(
+                    |((wallet, mint, address), account)| {
+                        let data = account?.data;
+                        let (owner, account_mint, amount) = decode_token_account_data(&data)?;
+                        (owner == *wallet && account_mint == *mint).then(|| {
+                            let state = decode_state(&data);
+                            (
+                                *wallet,
+                                *mint,
+                                *address,
+                                amount,
+                                state,
+                                decode_delegate(&data),
+                            )
+                        })
+                    },
+                ))
+            }
+        }));
+        let (amounts, mut decimals, labels) = futures::try_join!(
+            amounts,
+            self.get_mint_decimals(&mints),
+            self.resolve_tickers(mints.iter(), tokens)
+        )?;
+        accounts.extend(amounts.into_iter().flatten());
+        for (mint, listed) in listed_decimals {
+            decimals.entry(mint).or_insert(listed);
+        }
+
+        let mut totals: HashMap<(Pubkey, Pubkey), u64> = HashMap::new();
+        let mut frozen: HashMap<(Pubkey, Pubkey), u64> = HashMap::new();
+        let mut delegations = Vec::new();
+        for (wallet, mint, address, amount, state, delegate) in accounts {
+            let total = totals.entry((wallet, mint)).or_default();
+            *total = total.saturating_add(amount);
+            if state == TokenAccountState::Frozen {
+                let total = frozen.entry((wallet, mint)).or_default();
+                *total = total.saturating_add(amount);
+            }
+            if let Some((delegate, at_risk)) = delegate {
+                delegations.push((wallet, mint, address, delegate, at_risk));
+            }
+        }
+
+        let mut balances = HashMap::new();
+        for wallet in wallets {
+            let mut token_balances = HashMap::new();
+            for mint in &mints {
+                let raw_amount = totals.get(&(*wallet, *mint)).copied().unwrap_or(0);
+                let mut balance = TokenBalance::new(
+                    mint.to_string(),
+                    raw_amount,
+                    decimals.get(mint).copied().unwrap_or(0),
+                );
+                if let Some(amount) = frozen.get(&(*wallet, *mint)) {
+                    balance.add_frozen(*amount);
+                }
+                for (_, _, address, delegate, at_risk) in delegations
+                    .iter()
+                    .filter(|(owner, account_mint, ..)| owner == wallet && account_mint == mint)
+                {
+                    balance.add_delegation(address.to_string(), *delegate, *at_risk);
+                }
+                insert_labeled(&mut token_balances, &labels[mint], balance);
+            }
+            balances.insert(*wallet, token_balances);
+        }
+        Ok(balances)
+    }
+
+    /// Returns the balance of every SPL Token and Token-2022 account owned
+    /// by the wallet, keyed by ticker. Configured tokens are always
+    /// reported; mints without a configured ticker are named from their
+    /// Metaplex metadata.
+    pub async fn discover_token_balances(
+        &self,
+        wallet_pubkey: &Pubkey,
+        known_tokens: &[TokenInfo],
+        min_context_slot: Option<Slot>,
+    ) -> Result<HashMap<String, TokenBalance>, anyhow::Error> {
+        let token_accounts = try_join_all(TOKEN_PROGRAM_IDS.iter().map(|program_id| {
+            self.get_token_accounts(
+                wallet_pubkey,
+                TokenAccountsFilter::ProgramId(*program_id),
+                min_context_slot,
+            )
+        }))
+        .await?
+        .concat();
+
+        let mut by_mint = self.sum_by_mint(&token_accounts).await?;
+        for token in known_tokens {
+            let mint = token.mint()?;
+            if let Entry::Vacant(entry) = by_mint.entry(mint) {
+                entry.insert((0, self.decimals_or_zero(&mint).await?));
+            }
+        }
+
+        let mut token_balances = self.label_balances(by_mint, known_tokens).await?;
+        for account in &token_accounts {
+            let Some(decoded) = decode_token_account(account) else {
+                continue;
+            };
+            let mint = decoded.mint.to_string();
+            let Some(balance) = token_balances
+                .values_mut()
+                .find(|balance| balance.mint == mint)
+            else {
+                continue;
+            };
+            if decoded.state == TokenAccountState::Frozen {
+                balance.add_frozen(decoded.amount);
+            }
+            if let Some((delegate, at_risk)) = decoded.delegate {
+                balance.add_delegation(account.pubkey.clone(), delegate, at_risk);
+            }
+        }
+        Ok(token_balances)
+    }
+
+    /// Lists each token account `wallet_pubkey` owns under either token
+    /// program, ordered by mint.
+    pub async fn get_token_account_details(
+        &self,
+        wallet_pubkey: &Pubkey,
+        min_context_slot: Option<Slot>,
+    ) -> Result<Vec<TokenAccountDetail>, anyhow::Error> {
+        let token_accounts = try_join_all(TOKEN_PROGRAM_IDS.iter().map(|program_id| {
+            self.get_token_accounts(
+                wallet_pubkey,
+                TokenAccountsFilter::ProgramId(*program_id),
+                min_context_slot,
+            )
+        }))
+        .await?
+        .concat();
+
+        let decoded: Vec<_> = token_accounts
+            .iter()
+            .filter_map(|account| Some((account, decode_token_account(account)?)))
+            .collect();
+        let mut missing_decimals: Vec<Pubkey> = decoded
+            .iter()
+            .filter(|(_, decoded)| decoded.decimals.is_none())
+            .map(|(_, decoded)| decoded.mint)
+            .collect();
+        missing_decimals.sort();
+        missing_decimals.dedup();
+        let decimals = self.get_mint_decimals(&missing_decimals).await?;
+
+        let mut details: Vec<TokenAccountDetail> = decoded
+            .into_iter()
+            .map(|(account, decoded)| TokenAccountDetail {
+                address: account.pubkey.clone(),
+                mint: decoded.mint.to_string(),
+                program_id: account.account.owner.clone(),
+                associated: Pubkey::from_str(&account.account.owner).is_ok_and(|program_id| {
+                    associated_token_address(wallet_pubkey, &program_id, &decoded.mint).to_string()
+                        == account.pubkey
+                }),
+                state: decoded.state,
+                raw_amount: decoded.amount,
+                decimals: decoded
+                    .decimals
+                    .or_else(|| decimals.get(&decoded.mint).copied())
+                    .unwrap_or(0),
+                lamports: account.account.lamports,
+            })
+            .collect();
+        details.sort_by(|a, b| (&a.mint, &a.address).cmp(&(&b.mint, &b.address)));
+        Ok(details)
+    }
+
+    /// Turns per-mint totals (in base units, with decimals) into balances
+    /// keyed by ticker.
+    pub(crate) async fn label_balances(
+        &self,
+        by_mint: BTreeMap<Pubkey, (u64, u8)>,
+        known_tokens: &[TokenInfo],
+    ) -> Result<HashMap<String, TokenBalance>, anyhow::Error> {
+        let labels = self.resolve_tickers(by_mint.keys(), known_tokens).await?;
+
+        let mut token_balances = HashMap::new();
+        for (mint, (raw_amount, decimals)) in by_mint {
+            insert_labeled(
+                &mut token_balances,
+                &labels[&mint],
+                TokenBalance::new(mint.to_string(), raw_amount, decimals),
+            );
+        }
+
+        Ok(token_balances)
+    }
+
+    /// Sums token accounts per mint in base units, returning each total with
+    /// the mint's decimals. Decimals are looked up for accounts the RPC node
+    /// returned as raw data.
+    async fn sum_by_mint(
+        &self,
+        token_accounts: &[RpcKeyedAccount],
+    ) -> Result<BTreeMap<Pubkey, (u64, u8)>, anyhow::Error> {
+        let decoded: Vec<_> = token_accounts
+            .iter()
+            .filter_map(decode_token_account)
+            .collect();
+
+        let mut missing_decimals: Vec<Pubkey> = decoded
+            .iter()
+            .filter(|account| account.decimals.is_none())
+            .map(|account| account.mint)
+            .collect();
+        missing_decimals.sort();
+        missing_decimals.dedup();
+        let decimals = self.get_mint_decimals(&missing_decimals).await?;
+
+        let mut by_mint = BTreeMap::new();
+        for account in decoded {
+            let Some(decimals) = account
+                .decimals
+                .or_else(|| decimals.get(&account.mint).copied())
+            else {
+                continue;
+            };
+            let total = by_mint.entry(account.mint).or_insert((0u64, decimals));
+            total.0 = total.0.saturating_add(account.amount);
+        }
+
+        Ok(by_mint)
+    }
+
+    pub(crate) async fn decimals_or_zero(&self, mint: &Pubkey) -> Result<u8, anyhow::Error> {
+        Ok(self
+            .get_mint_decimals(std::slice::from_ref(mint))
+            .await?
+            .get(mint)
+            .copied()
+            .unwrap_or(0))
+    }
+
+    /// Looks up mint decimals, caching them for the lifetime of the fetcher.
+    pub async fn get_mint_decimals(
+        &self,
+        mints: &[Pubkey],
+    ) -> Result<HashMap<Pubkey, u8>, anyhow::Error> {
+        let mut decimals = HashMap::new();
+        let mut missing = Vec::new();
+        {
+            let cache = self.decimals_cache.lock().unwrap();
+            for mint in mints {
+                let listed = self
+                    .token_list
+                    .as_ref()
+                    .and_then(|list| list.get(mint))
+                    .map(|entry| entry.decimals);
+                match cache.get(mint).copied().or(listed) {
+                    Some(value) => {
+                        decimals.insert(*mint, value);
+                    }
+                    None => missing.push(*mint),
+                }
+            }
+        }
+
+        for chunk in missing.chunks(MAX_MULTIPLE_ACCOUNTS) {
+            let accounts = {
+                let _permit = self.permit().await?;
+                self.client.get_multiple_accounts(chunk).await?
+            };
+            let mut cache = self.decimals_cache.lock().unwrap();
+            for (mint, account) in chunk.iter().zip(accounts) {
+                if let Some(value) = account.and_then(|account| decode_mint_decimals(&account.data))
+                {
+                    cache.insert(*mint, value);
+                    decimals.insert(*mint, value);
+                }
+            }
+        }
+        Ok(decimals)
+    }
+
+    /// Looks up `mints` on-chain and explains each one that is not an
+    /// initialized token mint.
+    pub async fn check_mints(
+        &self,
+        mints: &[Pubkey],
+    ) -> Result<BTreeMap<Pubkey, &'static str>, anyhow::Error> {
+        let mut problems = BTreeMap::new();
+        for chunk in mints.chunks(MAX_MULTIPLE_ACCOUNTS) {
+            let accounts = {
+                let _permit = self.permit().await?;
+                self.client.get_multiple_accounts(chunk).await?
+            };
+            for (mint, account) in chunk.iter().zip(accounts) {
+                if let Some(problem) = mint_problem(account.as_ref()) {
+                    problems.insert(*mint, problem);
+                }
+            }
+        }
+        Ok(problems)
+    }
+
+    /// Names each mint: configured tickers first, then the token list, then
+    /// the Metaplex metadata symbol (or name), then a shortened mint address.
+    pub(crate) async fn resolve_tickers<'a>(
+        &self,
+        mints: impl Iterator<Item = &'a Pubkey>,
+        known_tokens: &[TokenInfo],
+    ) -> Result<HashMap<Pubkey, TokenLabel>, anyhow::Error> {
+        let mut labels = HashMap::new();
+        let mut unknown = Vec::new();
+        for mint in mints {
+            let address = mint.to_string();
+            match known_tokens
+                .iter()
+                .find(|token| token.address == address)
+                .and_then(|token| token.ticker.as_ref())
+            {
+                Some(ticker) => {
+                    labels.insert(
+                        *mint,
+                        TokenLabel {
+                            ticker: ticker.clone(),
+                            name: None,
+                            logo_uri: None,
+                        },
+                    );
+                }
+                None if *mint == spl_token::native_mint::ID => {
+                    labels.insert(
+                        *mint,
+                        TokenLabel {
+                            ticker: WRAPPED_SOL_TICKER.to_string(),
+                            name: Some("Wrapped SOL".to_string()),
+                            logo_uri: None,
+                        },
+                    );
+                }
+                None => match self.token_list.as_ref().and_then(|list| list.get(mint)) {
+                    Some(entry) => {
+                        labels.insert(
+                            *mint,
+                            TokenLabel {
+                                ticker: entry.symbol.clone(),
+                                name: Some(entry.name.clone()),
+                                logo_uri: entry.logo_uri.clone(),
+                            },
+                        );
+                    }
+                    None => unknown.push(*mint),
+                },
+            }
+        }
+
+        let metadata = self.get_token_metadata(&unknown).await?;
+        for mint in unknown {
+            let metadata = metadata.get(&mint);
+            let non_empty = |value: &String| (!value.is_empty()).then(|| value.clone());
+            let name = metadata.and_then(|metadata| non_empty(&metadata.name));
+            let ticker = metadata
+                .and_then(|metadata| non_empty(&metadata.symbol))
+                .or_else(|| name.clone())
+                .unwrap_or_else(|| short_address(&mint.to_string()));
+            labels.insert(
+                mint,
+                TokenLabel {
+                    ticker,
+                    name,
+                    logo_uri: None,
+                },
+            );
+        }
+
+        Ok(labels)
+    }
+
+    /// Fetches Metaplex metadata for `mints`, caching it (or its absence)
+    /// for the lifetime of the fetcher.
+    pub async fn get_token_metadata(
+        &self,
+        mints: &[Pubkey],
+    ) -> Result<HashMap<Pubkey, TokenMetadata>, anyhow::Error> {
+        let mut metadata = HashMap::new();
+        let mut missing = Vec::new();
+        {
+            let cache = self.metadata_cache.lock().unwrap();
+            for mint in mints {
+                match cache.get(mint) {
+                    Some(Some(value)) => {
+                        metadata.insert(*mint, value.clone());
+                    }
+                    Some(None) => {}
+                    None => missing.push(*mint),
+                }
+            }
+        }
+
+        for chunk in missing.chunks(MAX_MULTIPLE_ACCOUNTS) {
+            let addresses: Vec<Pubkey> = chunk.iter().map(metadata_address).collect();
+            let accounts = {
+                let _permit = self.permit().await?;
+                self.client.get_multiple_accounts(&addresses).await?
+            };
+            let mut cache = self.metadata_cache.lock().unwrap();
+            for (mint, account) in chunk.iter().zip(accounts) {
+                let value = account.and_then(|account| parse_metadata(&account.data));
+                if let Some(value) = &value {
+                    metadata.insert(*mint, value.clone());
+                }
+                cache.insert(*mint, value);
+            }
+        }
+        Ok(metadata)
+    }
+}
+
+/// What a mint is reported as.
+pub(crate) struct TokenLabel {
+    pub(crate) ticker: String,
+    name: Option<String>,
+    logo_uri: Option<String>,
+}
+
+/// Inserts `balance` under its ticker, adding a shortened mint address when
+/// another mint already uses the same ticker.
+fn insert_labeled(
+    balances: &mut HashMap<String, TokenBalance>,
+    label: &TokenLabel,
+    mut balance: TokenBalance,
+) {
+    balance.name = label.name.clone();
+    balance.logo_uri = label.logo_uri.clone();
+    let key = if balances.contains_key(&label.ticker) {
+        format!("{} ({})", label.ticker, short_address(&balance.mint))
+    } else {
+        label.ticker.clone()
+    };
+    balances.insert(key, balance);
+}
@@ -0,0 +1,42 @@
+use crate::report::TokenBalance;
+use serde::Deserialize;
+
+/// Rules for hiding discovered tokens, typically airdropped spam.
+/// Configured tokens are always reported.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct TokenFilter {
+    /// Only keep tokens on the token list (see `token_list`).
+    pub verified_only: bool,
+    /// Drop tokens worth less than this in USD, including unpriced ones.
+    /// Needs `pricing`.
+    pub min_usd_value: Option<f64>,
+    /// Mint addresses or tickers (case-insensitive) to always hide.
+    pub blocklist: Vec<String>,
+}
+
+impl TokenFilter {
+    pub fn is_empty(&self) -> bool {
+        !self.verified_only && self.min_usd_value.is_none() && self.blocklist.is_empty()
+    }
+
+    /// Whether a discovered token passes the verification and blocklist
+    /// rules; `listed` says whether its mint is on the token list.
+    pub fn allows(&self, ticker: &str, balance: &TokenBalance, listed: bool) -> bool {
+        if self.verified_only && !listed {
+            return false;
+        }
+        !self
+            .blocklist
+            .iter()
+            .any(|entry| *entry == balance.mint || entry.eq_ignore_ascii_case(ticker))
+    }
+
+    /// Whether a priced token is worth enough to report.
+    pub fn allows_value(&self, balance: &TokenBalance) -> bool {
+        match self.min_usd_value {
+            Some(min) => balance.usd_value.is_some_and(|value| value >= min),
+            None => true,
+        }
+    }
+}
@@ -0,0 +1,122 @@
+use crate::metadata::short_address;
+use crate::report::{BalanceReport, TokenBalance};
+use serde::{Deserialize, Serialize};
+use solana_sdk::native_token::lamports_to_sol;
+use std::collections::{BTreeMap, HashMap};
+
+/// Combined balances of a named group of wallets.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct GroupSummary {
+    /// Addresses of the members that were fetched successfully.
+    pub wallets: Vec<String>,
+    pub sol_balance: f64,
+    pub sol_lamports: u64,
+    /// SOL in stake accounts, when stake accounts were fetched.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub staked_sol: Option<f64>,
+    pub token_balances: BTreeMap<String, TokenBalance>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub total_usd: Option<f64>,
+}
+
+/// Sums each group's member wallets. Members are matched by address, `.sol`
+/// domain or label; members that failed to fetch are left out.
+pub fn summarize_groups(
+    groups: &BTreeMap<String, Vec<String>>,
+    report: &BalanceReport,
+) -> BTreeMap<String, GroupSummary> {
+    groups
+        .iter()
+        .map(|(name, members)| (name.clone(), summarize(members, report)))
+        .collect()
+}
+
+fn summarize(members: &[String], report: &BalanceReport) -> GroupSummary {
+    let mut summary = GroupSummary::default();
+    // Keyed by mint so tokens sharing a ticker are not added together.
+    let mut tokens: BTreeMap<String, (String, TokenBalance)> = BTreeMap::new();
+
+    for (address, result) in &report.wallets {
+        let is_member = members.iter().any(|member| {
+            member == address
+                || result
+                    .domain
+                    .as_ref()
+                    .is_some_and(|domain| domain == member)
+                || result.label.as_ref() == Some(member)
+                || result.multisig.as_ref() == Some(member)
+                || result.realm.as_ref() == Some(member)
+        });
+        if !is_member {
+            continue;
+        }
+
+        summary.wallets.push(address.clone());
+        summary.sol_lamports = summary.sol_lamports.saturating_add(result.sol_lamports);
+        if let Some(stake) = &result.stake {
+            *summary.staked_sol.get_or_insert(0.0) += stake.total;
+        }
+        if let Some(total) = result.total_usd {
+            *summary.total_usd.get_or_insert(0.0) += total;
+        }
+
+        for (ticker, balance) in &result.token_balances {
+            let (_, total) = tokens.entry(balance.mint.clone()).or_insert_with(|| {
+                let mut total = TokenBalance::new(balance.mint.clone(), 0, balance.decimals);
+                total.name = balance.name.clone();
+                total.logo_uri = balance.logo_uri.clone();
+                (ticker.clone(), total)
+            });
+            *total = sum(total, balance);
+        }
+    }
+
+    summary.wallets.sort();
+    summary.sol_balance = lamports_to_sol(summary.sol_lamports);
+
+    let mut tickers: HashMap<String, usize> = HashMap::new();
+    for (ticker, _) in tokens.values() {
+        *tickers.entry(ticker.clone()).or_default() += 1;
+    }
+    for (mint, (ticker, balance)) in tokens {
+        let key = if tickers[&ticker] > 1 {
+            format!("{} ({})", ticker, short_address(&mint))
+        } else {
+            ticker
+        };
+        summary.token_balances.insert(key, balance);
+    }
+
+    summary
+}
+
+fn sum(total: &TokenBalance, balance: &TokenBalance) -> TokenBalance {
+    let mut sum = TokenBalance::new(
+        total.mint.clone(),
+        total.raw_amount.saturating_add(balance.raw_amount),
+        total.decimals,
+    );
+    sum.name = total.name.clone();
+    sum.logo_uri = total.logo_uri.clone();
+    for frozen in [total.frozen_raw_amount, balance.frozen_raw_amount]
+        .into_iter()
+        .flatten()
+    {
+        sum.add_frozen(frozen);
+    }
+    sum.delegations = total
+        .delegations
+        .iter()
+        .chain(&balance.delegations)
+        .cloned()
+        .collect();
+    sum.sol_value = match (total.sol_value, balance.sol_value) {
+        (None, None) => None,
+        (a, b) => Some(a.unwrap_or(0.0) + b.unwrap_or(0.0)),
+    };
+    sum.usd_value = match (total.usd_value, balance.usd_value) {
+        (None, None) => None,
+        (a, b) => Some(a.unwrap_or(0.0) + b.unwrap_or(0.0)),
+    };
+    sum
+}
@@ -0,0 +1,164 @@
+use crate::fetcher::BalanceFetcher;
+use crate::rpc::{redact_urls, url_host};
+use serde::Serialize;
+use solana_sdk::native_token::lamports_to_sol;
+use solana_sdk::pubkey::Pubkey;
+use std::fmt;
+use std::future::Future;
+use std::time::Instant;
+
+/// The outcome of one request made to check on an RPC node.
+#[derive(Debug, Clone, Serialize)]
+pub struct HealthCheck {
+    /// The RPC method, e.g. `getHealth`.
+    pub check: &'static str,
+    pub ok: bool,
+    /// What the node answered, or why the request failed, with URLs cut
+    /// down to their hosts.
+    pub detail: String,
+    pub latency_ms: u64,
+}
+
+impl fmt::Display for HealthCheck {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} {} in {} ms: {}",
+            self.check,
+            if self.ok { "ok" } else { "failed" },
+            self.latency_ms,
+            self.detail
+        )
+    }
+}
+
+async fn timed<F>(check: &'static str, request: F) -> HealthCheck
+where
+    F: Future<Output = Result<String, anyhow::Error>>,
+{
+    let started = Instant::now();
+    let result = request.await;
+    let latency_ms = started.elapsed().as_millis() as u64;
+    let (ok, detail) = match result {
+        Ok(detail) => (true, detail),
+        Err(err) => (false, redact_urls(&err.to_string())),
+    };
+    HealthCheck {
+        check,
+        ok,
+        detail,
+        latency_ms,
+    }
+}
+
+impl BalanceFetcher {
+    /// Asks the node whether it is caught up with the cluster.
+    pub async fn check_health(&self) -> HealthCheck {
+        timed("getHealth", async {
+            let _permit = self.permit().await?;
+            self.client.get_health().await?;
+            Ok("node is healthy".to_string())
+        })
+        .await
+    }
+
+    pub async fn check_version(&self) -> HealthCheck {
+        timed("getVersion", async {
+            let _permit = self.permit().await?;
+            let version = self.client.get_version().await?;
+            Ok(format!("solana-core {}", version.solana_core))
+        })
+        .await
+    }
+
+    /// Reads the SOL balance of `wallet`, as every report does.
+    pub async fn check_balance(&self, wallet: &Pubkey) -> HealthCheck {
+        timed("getBalance", async {
+            let lamports = self.get_sol_balance(wallet, None).await?;
+            Ok(format!(
+                "{} holds {} SOL",
+                wallet,
+                lamports_to_sol(lamports)
+            ))
+        })
+        .await
+    }
+
+    /// Runs every check against the node at `endpoint`, reading the balance
+    /// of `sample`.
+    pub async fn diagnose(&self, endpoint: &str, sample: &Pubkey) -> Diagnosis {
+        let checks = vec![
+            self.check_health().await,
+            self.check_version().await,
+            self.check_balance(sample).await,
+        ];
+        Diagnosis {
+            endpoint: url_host(endpoint),
+            findings: Diagnosis::findings(&checks),
+            checks,
+        }
+    }
+}
+
+/// Responses slower than this are called out by [`Diagnosis::findings`].
+const SLOW_MS: u64 = 1000;
+
+/// The checks run against one RPC endpoint, by `doctor`.
+#[derive(Debug, Clone, Serialize)]
+pub struct Diagnosis {
+    /// The endpoint's host, as the full URL may carry an API key.
+    pub endpoint: String,
+    pub checks: Vec<HealthCheck>,
+    /// What the checks suggest is wrong, or that nothing is.
+    pub findings: Vec<String>,
+}
+
+impl Diagnosis {
+    pub fn is_healthy(&self) -> bool {
+        self.checks.iter().all(|check| check.ok)
+    }
+
+    fn findings(checks: &[HealthCheck]) -> Vec<String> {
+        let failed = |name: &str| checks.iter().find(|check| check.check == name && !check.ok);
+        let mut findings = Vec::new();
+        if checks.iter().all(|check| !check.ok) {
+            let detail = &checks[0].detail;
+            findings.push(if detail.contains("401") || detail.contains("403") {
+                "the node refused the requests: check the API key in the URL or `rpc_headers`"
+                    .to_string()
+            } else if detail.contains("429") {
+                "the node is rate limiting: lower `max_rps` or `max_concurrency`".to_string()
+            } else {
+                "the node cannot be reached: check the URL, the network and any proxy".to_string()
+            });
+            return findings;
+        }
+        if let Some(check) = failed("getHealth") {
+            findings.push(format!(
+                "the node answers but reports itself unhealthy, so balances may be stale: {}",
+                check.detail
+            ));
+        }
+        if let Some(check) = failed("getBalance") {
+            findings.push(format!(
+                "the node answers but could not read a balance: {}",
+                check.detail
+            ));
+        }
+        if let Some(slowest) = checks
+            .iter()
+            .filter(|check| check.ok)
+            .max_by_key(|check| check.latency_ms)
+            .filter(|check| check.latency_ms > SLOW_MS)
+        {
+            findings.push(format!(
+                "responses are slow ({} ms for {}): a closer or dedicated endpoint would help",
+                slowest.latency_ms, slowest.check
+            ));
+        }
+        if findings.is_empty() {
+            findings.push("no problems found".to_string());
+        }
+        findings
+    }
+}
@@ -0,0 +1,146 @@
+use crate::diff::{SOL_ASSET, STAKED_SOL_ASSET};
+use crate::report::BalanceReport;
+use chrono::{DateTime, Utc};
+use rusqlite::{params, Connection};
+use serde::Serialize;
+use std::path::Path;
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS balances (
+    timestamp INTEGER NOT NULL,
+    slot INTEGER,
+    wallet TEXT NOT NULL,
+    asset TEXT NOT NULL,
+    mint TEXT,
+    amount REAL NOT NULL
+);
+CREATE INDEX IF NOT EXISTS balances_wallet_asset_time
+    ON balances (wallet, asset, timestamp);
+";
+
+/// Balances of past runs, one row per wallet and asset, in a SQLite database.
+pub struct HistoryStore {
+    conn: Connection,
+}
+
+/// Filters for [`HistoryStore::query`]; unset fields match everything.
+#[derive(Debug, Default, Clone)]
+pub struct HistoryQuery {
+    pub wallet: Option<String>,
+    /// Matches either the asset name (ticker) or the token mint.
+    pub asset: Option<String>,
+    pub from: Option<DateTime<Utc>>,
+    pub to: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct HistoryEntry {
+    pub timestamp: DateTime<Utc>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub slot: Option<u64>,
+    pub wallet: String,
+    pub asset: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mint: Option<String>,
+    pub amount: f64,
+}
+
+impl HistoryStore {
+    /// Opens the database at `path`, creating it and its table if needed.
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, anyhow::Error> {
+        let path = path.as_ref();
+        let conn = Connection::open(path)
+            .map_err(|err| anyhow::anyhow!("opening {}: {}", path.display(), err))?;
+        conn.execute_batch(SCHEMA)?;
+        Ok(HistoryStore { conn })
+    }
+
+    /// Appends every balance in `report`, returning the number of rows written.
+    /// Wallets that failed to fetch are not recorded.
+    pub fn record(&mut self, report: &BalanceReport) -> Result<usize, anyhow::Error> {
+        let timestamp = report.timestamp.timestamp();
+        let slot = report.slot.and_then(|slot| i64::try_from(slot).ok());
+
+        let tx = self.conn.transaction()?;
+        let mut rows = 0;
+        {
+            let mut insert = tx.prepare(
+                "INSERT INTO balances (timestamp, slot, wallet, asset, mint, amount)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            )?;
+            for (wallet, result) in &report.wallets {
+                insert.execute(params![
+                    timestamp,
+                    slot,
+                    wallet,
+                    SOL_ASSET,
+                    None::<String>,
+                    result.sol_balance
+                ])?;
+                rows += 1;
+
+                if let Some(stake) = &result.stake {
+                    insert.execute(params![
+                        timestamp,
+                        slot,
+                        wallet,
+                        STAKED_SOL_ASSET,
+                        None::<String>,
+                        stake.total
+                    ])?;
+                    rows += 1;
+                }
+
+                for (ticker, token) in &result.token_balances {
+                    insert.execute(params![
+                        timestamp,
+                        slot,
+                        wallet,
+                        ticker,
+                        token.mint,
+                        token.amount
+                    ])?;
+                    rows += 1;
+                }
+            }
+        }
+        tx.commit()?;
+
+        Ok(rows)
+    }
+
+    /// Recorded balances matching `query`, oldest first.
+    pub fn query(&self, query: &HistoryQuery) -> Result<Vec<HistoryEntry>, anyhow::Error> {
+        let mut statement = self.conn.prepare(
+            "SELECT timestamp, slot, wallet, asset, mint, amount FROM balances
+             WHERE (?1 IS NULL OR wallet = ?1)
+               AND (?2 IS NULL OR asset = ?2 OR mint = ?2)
+               AND (?3 IS NULL OR timestamp >= ?3)
+               AND (?4 IS NULL OR timestamp <= ?4)
+             ORDER BY timestamp, wallet, asset",
+        )?;
+
+        let rows = statement.query_map(
+            params![
+                query.wallet,
+                query.asset,
+                query.from.map(|from| from.timestamp()),
+                query.to.map(|to| to.timestamp()),
+            ],
+            |row| {
+                let timestamp: i64 = row.get(0)?;
+                let slot: Option<i64> = row.get(1)?;
+                Ok(HistoryEntry {
+                    timestamp: DateTime::from_timestamp(timestamp, 0).unwrap_or_default(),
+                    slot: slot.and_then(|slot| u64::try_from(slot).ok()),
+                    wallet: row.get(2)?,
+                    asset: row.get(3)?,
+                    mint: row.get(4)?,
+                    amount: row.get(5)?,
+                })
+            },
+        )?;
+
+        Ok(rows.collect::<Result<_, _>>()?)
+    }
+}
@@ -0,0 +1,100 @@
+use solana_sdk::pubkey::Pubkey;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Addresses of the Solana CLI keypair files `pattern` names: a file, a
+/// directory (its `*.json` files) or a file name with `*` and `?`
+/// wildcards, such as `ids/validator-*.json`. Each address is labelled with
+/// its file name. Only the public half of each keypair is read.
+pub fn keypair_file_addresses(pattern: &Path) -> Result<Vec<(String, Pubkey)>, anyhow::Error> {
+    let paths = if pattern.is_dir() {
+        matching_files(pattern, "*.json")?
+    } else {
+        match pattern.file_name().and_then(|name| name.to_str()) {
+            Some(name) if name.contains(['*', '?']) => {
+                let dir = match pattern.parent() {
+                    Some(dir) if !dir.as_os_str().is_empty() => dir,
+                    _ => Path::new("."),
+                };
+                matching_files(dir, name)?
+            }
+            _ => vec![pattern.to_path_buf()],
+        }
+    };
+    if paths.is_empty() {
+        anyhow::bail!("no keypair files match {}", pattern.display());
+    }
+
+    paths
+        .iter()
+        .map(|path| {
+            let label = path
+                .file_stem()
+                .map_or_else(String::new, |stem| stem.to_string_lossy().into_owned());
+            Ok((label, read_public_key(path)?))
+        })
+        .collect()
+}
+
+/// Reads the public key from the last 32 of a keypair file's 64 bytes.
+fn read_public_key(path: &Path) -> Result<Pubkey, anyhow::Error> {
+    let content = fs::read_to_string(path)
+        .map_err(|err| anyhow::anyhow!("reading {}: {}", path.display(), err))?;
+    let bytes: Vec<u8> = serde_json::from_str(&content)
+        .map_err(|_| anyhow::anyhow!("{} is not a keypair file", path.display()))?;
+    if bytes.len() != 64 {
+        anyhow::bail!(
+            "{} is not a keypair file: {} bytes instead of 64",
+            path.display(),
+            bytes.len()
+        );
+    }
+    Ok(Pubkey::try_from(&bytes[32..]).expect("32 bytes"))
+}
+
+/// Files in `dir` whose names match `pattern`, sorted by name.
+fn matching_files(dir: &Path, pattern: &str) -> Result<Vec<PathBuf>, anyhow::Error> {
+    let entries =
+        fs::read_dir(dir).map_err(|err| anyhow::anyhow!("reading {}: {}", dir.display(), err))?;
+    let mut paths = Vec::new();
+    for entry in entries {
+        let entry = entry?;
+        let name = entry.file_name();
+        if entry.file_type()?.is_file() && wildcard_match(pattern, &name.to_string_lossy()) {
+            paths.push(entry.path());
+        }
+    }
+    paths.sort();
+    Ok(paths)
+}
+
+/// Matches `name` against `pattern`, where `*` stands for any run of
+/// characters and `?` for any one.
+fn wildcard_match(pattern: &str, name: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let name: Vec<char> = name.chars().collect();
+    let (mut p, mut n) = (0, 0);
+    // Where the last `*` was, and how much of `name` it has taken.
+    let mut star: Option<(usize, usize)> = None;
+    while n < name.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                star = Some((p, n));
+                p += 1;
+            }
+            Some(&c) if c == '?' || c == name[n] => {
+                p += 1;
+                n += 1;
+            }
+            _ => match star {
+                Some((star_p, star_n)) => {
+                    p = star_p + 1;
+                    n = star_n + 1;
+                    star = Some((star_p, star_n + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|&c| c == '*')
+}
@@ -0,0 +1,74 @@
+mod activity;
+mod alert;
+mod at_slot;
+mod cache;
+pub mod chain;
+mod cleanup;
+mod config;
+mod derive;
+pub mod diff;
+mod error;
+pub mod explorer;
+mod fetcher;
+mod filter;
+mod group;
+mod health;
+#[cfg(feature = "history")]
+pub mod history;
+mod keypair_file;
+mod lst;
+pub mod metadata;
+mod nft;
+pub mod notify;
+pub mod output;
+pub mod pricing;
+pub mod realms;
+mod reconcile;
+mod rent;
+mod report;
+mod rewards;
+pub mod rpc;
+mod schedule;
+pub mod sink;
+pub mod sns;
+pub mod squads;
+mod stake;
+mod subscribe;
+mod token_account;
+pub mod token_list;
+mod trend;
+mod vesting;
+
+pub use activity::{Transfer, WalletTransaction};
+pub use alert::{check_thresholds, Alert};
+pub use cache::{CacheConfig, DiskCache};
+pub use cleanup::{CleanupReport, WalletCleanup};
+pub use config::{
+    ClusterConfig, ConfigFormat, TokenConfig, TokenInfo, TokenSelection, WalletInfo,
+    WalletThresholds, ENV_PREFIX,
+};
+pub use derive::DerivedWallets;
+pub use diff::{diff_reports, BalanceChange};
+pub use error::BalanceError;
+pub use fetcher::{BalanceFetcher, BalanceFetcherBuilder, FetchProgress};
+pub use filter::TokenFilter;
+pub use group::{summarize_groups, GroupSummary};
+pub use health::{Diagnosis, HealthCheck};
+#[cfg(feature = "history")]
+pub use history::{HistoryEntry, HistoryQuery, HistoryStore};
+pub use keypair_file::keypair_file_addresses;
+pub use nft::{CollectionCount, NftConfig, NftSummary};
+pub use output::{OutputFormat, RenderOptions, SortOrder};
+pub use reconcile::{reconcile, Discrepancy, ExpectedBalance, Reconciliation};
+pub use rent::RentBreakdown;
+pub use report::{BalanceReport, BalanceResult, TokenBalance};
+pub use rewards::{RewardsReport, WalletRewards};
+pub use schedule::Schedule;
+pub use stake::{StakeContext, StakeSummary};
+pub use subscribe::GeyserConfig;
+pub use token_account::{
+    associated_token_address, format_ui_amount, Delegation, TokenAccountDetail, TokenAccountState,
+    SOL_DECIMALS, TOKEN_PROGRAM_IDS, WRAPPED_SOL_TICKER,
+};
+pub use trend::BalanceTrends;
+pub use vesting::{VestingBalance, STREAMFLOW_PROGRAM_ID};
@@ -0,0 +1,126 @@
+use crate::config::TokenConfig;
+use crate::fetcher::BalanceFetcher;
+use crate::report::BalanceReport;
+use crate::token_account::read_u64;
+use solana_sdk::pubkey;
+use solana_sdk::pubkey::Pubkey;
+use std::collections::HashMap;
+use std::str::FromStr;
+
+/// Offsets of `total_lamports` and `pool_token_supply` in an SPL stake pool
+/// account, after the account type, four authorities, a bump seed and five
+/// more addresses.
+const SPL_TOTAL_LAMPORTS_OFFSET: usize = 258;
+const SPL_POOL_TOKEN_SUPPLY_OFFSET: usize = 266;
+
+/// Offset of `msol_price` in Marinade's state account, stored as SOL per
+/// mSOL scaled by 2^32.
+const MARINADE_PRICE_OFFSET: usize = 512;
+const MARINADE_PRICE_DENOMINATOR: f64 = 4_294_967_296.0;
+
+/// How a liquid staking token's exchange rate is read.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum PoolKind {
+    /// An SPL stake pool, such as Jito's or BlazeStake's.
+    Spl,
+    Marinade,
+}
+
+/// Liquid staking tokens valued when `liquid_staking` is set: mint, the
+/// account holding its exchange rate, and how to read it.
+const KNOWN_POOLS: [(Pubkey, Pubkey, PoolKind); 3] = [
+    // mSOL
+    (
+        pubkey!("mSoLzYCxHdYgdzU16g5QSh3i5K3z3KZK7ytfqcJm7So"),
+        pubkey!("8szGkuLTAux9XMgZ2vtY39jVSowEcpBfFfD8hXSEqdGC"),
+        PoolKind::Marinade,
+    ),
+    // jitoSOL
+    (
+        pubkey!("J1toso1uCk3RLmjorhTtrVwY9HJ7X8V9yYac6Y7kGCPn"),
+        pubkey!("Jito4APyf642JPZPx3hGc6WWJ8zPKtRbRs4P815Awbb"),
+        PoolKind::Spl,
+    ),
+    // bSOL
+    (
+        pubkey!("bSo13r4TkiE4KumL71LsHTPpL2euBYLFx6h9HP3piy1"),
+        pubkey!("stk9ApL5HeVAwPLr3TLhDXdZS8ptVu7zp6ov8HFDuMi"),
+        PoolKind::Spl,
+    ),
+];
+
+impl BalanceFetcher {
+    /// Sets the SOL value of every liquid staking token balance in `report`
+    /// from its pool's current exchange rate.
+    pub(crate) async fn apply_lst_rates(
+        &self,
+        config: &TokenConfig,
+        report: &mut BalanceReport,
+    ) -> Result<(), anyhow::Error> {
+        let mut pools: Vec<(Pubkey, Pubkey, PoolKind)> = Vec::new();
+        if config.liquid_staking {
+            pools.extend(KNOWN_POOLS);
+        }
+        for (mint, pool) in &config.stake_pools {
+            let mint = Pubkey::from_str(mint)
+                .map_err(|err| anyhow::anyhow!("stake_pools: invalid mint `{}`: {}", mint, err))?;
+            let pool = Pubkey::from_str(pool)
+                .map_err(|err| anyhow::anyhow!("stake_pools: invalid pool `{}`: {}", pool, err))?;
+            pools.retain(|(known, _, _)| *known != mint);
+            pools.push((mint, pool, PoolKind::Spl));
+        }
+        let held = report.mints();
+        pools.retain(|(mint, _, _)| held.contains(mint));
+        if pools.is_empty() {
+            return Ok(());
+        }
+
+        let rates = self.get_lst_rates(&pools).await?;
+        for result in report.wallets.values_mut() {
+            for balance in result.token_balances.values_mut() {
+                let rate = Pubkey::from_str(&balance.mint)
+                    .ok()
+                    .and_then(|mint| rates.get(&mint));
+                if let Some(rate) = rate {
+                    balance.sol_value = Some(balance.amount * rate);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// SOL per token of each pool's mint. Pools whose account is missing or
+    /// cannot be read are left out.
+    async fn get_lst_rates(
+        &self,
+        pools: &[(Pubkey, Pubkey, PoolKind)],
+    ) -> Result<HashMap<Pubkey, f64>, anyhow::Error> {
+        let addresses: Vec<Pubkey> = pools.iter().map(|(_, pool, _)| *pool).collect();
+        let accounts = {
+            let _permit = self.permit().await?;
+            self.client.get_multiple_accounts(&addresses).await?
+        };
+
+        let mut rates = HashMap::new();
+        for ((mint, _, kind), account) in pools.iter().zip(accounts) {
+            let Some(account) = account else {
+                continue;
+            };
+            let rate = match kind {
+                PoolKind::Spl => {
+                    let total_lamports = read_u64(&account.data, SPL_TOTAL_LAMPORTS_OFFSET);
+                    let supply = read_u64(&account.data, SPL_POOL_TOKEN_SUPPLY_OFFSET);
+                    total_lamports
+                        .zip(supply.filter(|supply| *supply > 0))
+                        .map(|(lamports, supply)| lamports as f64 / supply as f64)
+                }
+                PoolKind::Marinade => read_u64(&account.data, MARINADE_PRICE_OFFSET)
+                    .map(|price| price as f64 / MARINADE_PRICE_DENOMINATOR),
+            };
+            if let Some(rate) = rate {
+                rates.insert(*mint, rate);
+            }
+        }
+        Ok(rates)
+    }
+}
@@ -0,0 +1,51 @@
+use serde::{Deserialize, Serialize};
+use solana_sdk::pubkey;
+use solana_sdk::pubkey::Pubkey;
+
+pub const METADATA_PROGRAM_ID: Pubkey = pubkey!("metaqbxxUerdq28cj1RbAWkYQm3ybzjb6a8bG518x1s");
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TokenMetadata {
+    pub name: String,
+    pub symbol: String,
+    pub uri: String,
+}
+
+/// Derives the Metaplex token metadata PDA for `mint`.
+pub fn metadata_address(mint: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(
+        &[b"metadata", METADATA_PROGRAM_ID.as_ref(), mint.as_ref()],
+        &METADATA_PROGRAM_ID,
+    )
+    .0
+}
+
+/// Decodes the leading fields of a Metaplex metadata account:
+/// key, update authority, mint, then the borsh-encoded name, symbol and uri.
+pub fn parse_metadata(data: &[u8]) -> Option<TokenMetadata> {
+    let mut rest = data.get(1 + 32 + 32..)?;
+    let name = read_string(&mut rest)?;
+    let symbol = read_string(&mut rest)?;
+    let uri = read_string(&mut rest)?;
+    Some(TokenMetadata { name, symbol, uri })
+}
+
+fn read_string(data: &mut &[u8]) -> Option<String> {
+    let len = u32::from_le_bytes(data.get(..4)?.try_into().ok()?) as usize;
+    let bytes = data.get(4..4 + len)?;
+    *data = &data[4 + len..];
+    Some(
+        String::from_utf8_lossy(bytes)
+            .trim_end_matches('\0')
+            .trim()
+            .to_string(),
+    )
+}
+
+/// Shortens a base58 address to `abcd…wxyz` for display.
+pub fn short_address(address: &str) -> String {
+    if address.len() <= 8 {
+        return address.to_string();
+    }
+    format!("{}…{}", &address[..4], &address[address.len() - 4..])
+}
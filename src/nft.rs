@@ -0,0 +1,264 @@
+use crate::fetcher::BalanceFetcher;
+use crate::pricing::FloorPriceConfig;
+use crate::report::BalanceReport;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use solana_sdk::pubkey::Pubkey;
+use std::collections::{BTreeSet, HashMap};
+
+/// Assets requested per `getAssetsByOwner` page, the most DAS providers
+/// allow.
+pub(crate) const ASSETS_PAGE_SIZE: usize = 1000;
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct NftConfig {
+    /// DAS-compatible endpoint, such as a Helius or Triton RPC URL
+    /// [default: the RPC endpoint].
+    #[serde(default)]
+    pub das_url: Option<String>,
+    /// Also count the NFTs held in each collection.
+    #[serde(default)]
+    pub list_collections: bool,
+    /// Estimates what the NFTs are worth from their collections' floor
+    /// prices when set.
+    #[serde(default)]
+    pub floor_prices: Option<FloorPriceConfig>,
+}
+
+impl NftConfig {
+    /// Whether NFTs need counting per collection.
+    fn by_collection(&self) -> bool {
+        self.list_collections || self.floor_prices.is_some()
+    }
+}
+
+/// NFTs a wallet owns, as reported by the DAS API.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct NftSummary {
+    /// Every NFT, compressed or not.
+    pub count: usize,
+    pub compressed: usize,
+    /// NFTs per collection, largest first, when `list_collections` is set.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub collections: Vec<CollectionCount>,
+    /// Estimated value in SOL at collection floor prices, leaving out NFTs
+    /// whose collection has no known floor.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub value: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub usd_value: Option<f64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CollectionCount {
+    pub address: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    pub count: usize,
+    /// Cheapest listing in the collection, in SOL.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub floor_price: Option<f64>,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum DasResponse<T> {
+    Result { result: T },
+    Error { error: DasError },
+}
+
+#[derive(Deserialize)]
+struct DasError {
+    code: i64,
+    message: String,
+}
+
+#[derive(Deserialize)]
+struct AssetPage {
+    items: Vec<Asset>,
+}
+
+#[derive(Deserialize)]
+struct Asset {
+    interface: String,
+    #[serde(default)]
+    compression: Option<Compression>,
+    #[serde(default)]
+    grouping: Vec<Grouping>,
+    #[serde(default)]
+    burnt: bool,
+}
+
+#[derive(Deserialize)]
+struct Compression {
+    compressed: bool,
+}
+
+#[derive(Deserialize)]
+struct Grouping {
+    group_key: String,
+    group_value: Option<String>,
+    #[serde(default)]
+    collection_metadata: Option<CollectionMetadata>,
+}
+
+#[derive(Deserialize)]
+struct CollectionMetadata {
+    name: Option<String>,
+}
+
+impl Asset {
+    /// Fungible tokens are listed too when a provider ignores
+    /// `showFungible`, and are already covered by token balances.
+    fn is_nft(&self) -> bool {
+        !self.burnt && !matches!(self.interface.as_str(), "FungibleToken" | "FungibleAsset")
+    }
+}
+
+impl BalanceFetcher {
+    /// Sends one DAS `getAssetsByOwner` request to the DAS endpoint.
+    pub(crate) async fn get_assets_by_owner<T: DeserializeOwned>(
+        &self,
+        params: serde_json::Value,
+    ) -> Result<T, anyhow::Error> {
+        let request = json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": "getAssetsByOwner",
+            "params": params,
+        });
+        let response: DasResponse<T> = {
+            let _permit = self.permit().await?;
+            self.rpc_calls()
+                .charge("getAssetsByOwner", self.max_rpc_calls)?;
+            self.http
+                .post(&self.das_url)
+                .json(&request)
+                .send()
+                .await?
+                .error_for_status()?
+                .json()
+                .await?
+        };
+        match response {
+            DasResponse::Result { result } => Ok(result),
+            DasResponse::Error { error } => {
+                anyhow::bail!("getAssetsByOwner: {} ({})", error.message, error.code)
+            }
+        }
+    }
+
+    /// Counts the NFTs `wallet_pubkey` owns with the DAS `getAssetsByOwner`
+    /// method, page by page.
+    pub async fn get_nft_summary(
+        &self,
+        wallet_pubkey: &Pubkey,
+        config: &NftConfig,
+    ) -> Result<NftSummary, anyhow::Error> {
+        let mut summary = NftSummary::default();
+        let mut collections: HashMap<String, CollectionCount> = HashMap::new();
+        for page in 1.. {
+            let params = json!({
+                "ownerAddress": wallet_pubkey.to_string(),
+                "page": page,
+                "limit": ASSETS_PAGE_SIZE,
+                "displayOptions": {
+                    "showFungible": false,
+                    "showCollectionMetadata": config.by_collection(),
+                },
+            });
+            let response: AssetPage = self.get_assets_by_owner(params).await?;
+
+            let full = response.items.len() == ASSETS_PAGE_SIZE;
+            for asset in response.items.into_iter().filter(Asset::is_nft) {
+                summary.count += 1;
+                if asset
+                    .compression
+                    .is_some_and(|compression| compression.compressed)
+                {
+                    summary.compressed += 1;
+                }
+                if !config.by_collection() {
+                    continue;
+                }
+                let collection = asset
+                    .grouping
+                    .into_iter()
+                    .find(|grouping| grouping.group_key == "collection");
+                if let Some(Grouping {
+                    group_value: Some(address),
+                    collection_metadata,
+                    ..
+                }) = collection
+                {
+                    collections
+                        .entry(address.clone())
+                        .or_insert_with(|| CollectionCount {
+                            address,
+                            name: collection_metadata
+                                .and_then(|metadata| metadata.name)
+                                .filter(|name| !name.is_empty()),
+                            count: 0,
+                            floor_price: None,
+                        })
+                        .count += 1;
+                }
+            }
+            if !full {
+                break;
+            }
+        }
+
+        summary.collections = collections.into_values().collect();
+        summary.collections.sort_by(|a, b| {
+            b.count
+                .cmp(&a.count)
+                .then_with(|| a.address.cmp(&b.address))
+        });
+        Ok(summary)
+    }
+
+    /// Values every wallet's NFTs at their collections' floor prices. The
+    /// per-collection counts are dropped again unless `list_collections` is
+    /// set.
+    pub(crate) async fn apply_floor_prices(
+        &self,
+        config: &NftConfig,
+        report: &mut BalanceReport,
+    ) -> Result<(), anyhow::Error> {
+        let Some(provider) = &self.floor_price_provider else {
+            return Ok(());
+        };
+        let collections: BTreeSet<String> = report
+            .wallets
+            .values()
+            .filter_map(|result| result.nfts.as_ref())
+            .flat_map(|nfts| nfts.collections.iter())
+            .map(|collection| collection.address.clone())
+            .collect();
+        let collections: Vec<String> = collections.into_iter().collect();
+        let floors = if collections.is_empty() {
+            HashMap::new()
+        } else {
+            provider.floor_prices(&collections).await?
+        };
+
+        for nfts in report
+            .wallets
+            .values_mut()
+            .filter_map(|result| result.nfts.as_mut())
+        {
+            let mut value = 0.0;
+            for collection in &mut nfts.collections {
+                collection.floor_price = floors.get(&collection.address).copied();
+                value += collection.floor_price.unwrap_or(0.0) * collection.count as f64;
+            }
+            nfts.value = Some(value);
+            if !config.list_collections {
+                nfts.collections.clear();
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,128 @@
+use crate::notify::{send_with_retry, Notification, Notifier, EXPLORER_ADDRESS_URL};
+use crate::rpc::RetryPolicy;
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+
+/// Discord accepts at most this many embeds per message.
+const MAX_EMBEDS_PER_MESSAGE: usize = 10;
+
+const COLOR_RED: u32 = 0xe74c3c;
+const COLOR_GREEN: u32 = 0x2ecc71;
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct DiscordConfig {
+    pub webhook_url: String,
+    /// Overrides the webhook's default bot name.
+    #[serde(default)]
+    pub username: Option<String>,
+}
+
+#[derive(Serialize)]
+struct Message<'a> {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    username: Option<&'a str>,
+    embeds: &'a [Embed],
+}
+
+#[derive(Serialize)]
+struct Embed {
+    title: String,
+    url: String,
+    color: u32,
+    timestamp: String,
+    fields: Vec<Field>,
+}
+
+#[derive(Serialize)]
+struct Field {
+    name: &'static str,
+    value: String,
+    inline: bool,
+}
+
+impl Field {
+    fn new(name: &'static str, value: impl ToString, inline: bool) -> Self {
+        Field {
+            name,
+            value: value.to_string(),
+            inline,
+        }
+    }
+}
+
+/// Posts alerts and changes to a Discord channel webhook, one embed each.
+pub struct DiscordNotifier {
+    http: reqwest::Client,
+    config: DiscordConfig,
+    retry: RetryPolicy,
+}
+
+impl DiscordNotifier {
+    pub fn new(config: DiscordConfig, retry: RetryPolicy) -> Self {
+        DiscordNotifier {
+            http: reqwest::Client::new(),
+            config,
+            retry,
+        }
+    }
+}
+
+#[async_trait]
+impl Notifier for DiscordNotifier {
+    fn name(&self) -> &str {
+        "discord"
+    }
+
+    async fn notify(&self, notification: &Notification) -> Result<(), anyhow::Error> {
+        let embeds = embeds(notification);
+        for chunk in embeds.chunks(MAX_EMBEDS_PER_MESSAGE) {
+            let message = Message {
+                username: self.config.username.as_deref(),
+                embeds: chunk,
+            };
+            send_with_retry(&self.retry, || {
+                self.http.post(&self.config.webhook_url).json(&message)
+            })
+            .await?;
+        }
+        Ok(())
+    }
+}
+
+fn embeds(notification: &Notification) -> Vec<Embed> {
+    let timestamp = notification.timestamp.to_rfc3339();
+    let explorer_link = |wallet: &str| format!("{}/{}", EXPLORER_ADDRESS_URL, wallet);
+
+    let alerts = notification.alerts.iter().map(|alert| Embed {
+        title: format!("{} below threshold", alert.asset),
+        url: explorer_link(&alert.wallet),
+        color: COLOR_RED,
+        timestamp: timestamp.clone(),
+        fields: vec![
+            Field::new("Wallet", &alert.wallet, false),
+            Field::new("Asset", &alert.asset, true),
+            Field::new("Balance", alert.balance, true),
+            Field::new("Threshold", alert.threshold, true),
+        ],
+    });
+
+    let changes = notification.changes.iter().map(|change| Embed {
+        title: format!("{} balance changed", change.asset),
+        url: explorer_link(&change.wallet),
+        color: if change.delta() < 0.0 {
+            COLOR_RED
+        } else {
+            COLOR_GREEN
+        },
+        timestamp: timestamp.clone(),
+        fields: vec![
+            Field::new("Wallet", &change.wallet, false),
+            Field::new("Asset", &change.asset, true),
+            Field::new("Old", format!("{:.4}", change.old), true),
+            Field::new("New", format!("{:.4}", change.new), true),
+            Field::new("Delta", format!("{:+.4}", change.delta()), true),
+        ],
+    });
+
+    alerts.chain(changes).collect()
+}
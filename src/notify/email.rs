@@ -0,0 +1,143 @@
+use crate::notify::{Notification, Notifier};
+use async_trait::async_trait;
+use lettre::message::header::ContentType;
+use lettre::message::Mailbox;
+use lettre::transport::smtp::authentication::Credentials;
+use lettre::{AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor};
+use serde::Deserialize;
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct EmailConfig {
+    pub smtp_server: String,
+    /// [default: 587 for `starttls`, 465 for `tls`, 25 for `none`]
+    #[serde(default)]
+    pub port: Option<u16>,
+    #[serde(default)]
+    pub security: SmtpSecurity,
+    #[serde(default)]
+    pub username: Option<String>,
+    #[serde(default)]
+    pub password: Option<String>,
+    /// Sender, e.g. `Balances <balances@example.com>`.
+    pub from: String,
+    pub to: Vec<String>,
+    /// Subject of report emails [default: "Solana balances"].
+    #[serde(default)]
+    pub subject: Option<String>,
+}
+
+/// How the connection to the SMTP server is secured.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SmtpSecurity {
+    /// Upgrade a plain connection with STARTTLS.
+    #[default]
+    StartTls,
+    /// TLS from the start (SMTPS).
+    Tls,
+    /// Unencrypted, for a relay on localhost.
+    None,
+}
+
+/// The format of an emailed body.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EmailBody {
+    Plain,
+    Html,
+}
+
+/// Emails notifications, and rendered reports with `--email-report`.
+pub struct EmailNotifier {
+    config: EmailConfig,
+}
+
+impl EmailNotifier {
+    pub fn new(config: EmailConfig) -> Self {
+        EmailNotifier { config }
+    }
+
+    fn transport(&self) -> Result<AsyncSmtpTransport<Tokio1Executor>, anyhow::Error> {
+        let config = &self.config;
+        let server = config.smtp_server.as_str();
+        let mut builder = match config.security {
+            SmtpSecurity::StartTls => AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(server)?,
+            SmtpSecurity::Tls => AsyncSmtpTransport::<Tokio1Executor>::relay(server)?,
+            SmtpSecurity::None => AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous(server),
+        };
+        if let Some(port) = config.port {
+            builder = builder.port(port);
+        }
+        if let Some(username) = &config.username {
+            builder = builder.credentials(Credentials::new(
+                username.clone(),
+                config.password.clone().unwrap_or_default(),
+            ));
+        }
+        Ok(builder.build())
+    }
+
+    /// Sends `body` to every recipient with the configured subject.
+    pub async fn send_report(&self, body: String, format: EmailBody) -> Result<(), anyhow::Error> {
+        let subject = self
+            .config
+            .subject
+            .clone()
+            .unwrap_or_else(|| "Solana balances".to_string());
+        self.send(&subject, body, format).await
+    }
+
+    async fn send(
+        &self,
+        subject: &str,
+        body: String,
+        format: EmailBody,
+    ) -> Result<(), anyhow::Error> {
+        let from: Mailbox = self.config.from.parse().map_err(|err| {
+            anyhow::anyhow!("invalid email sender `{}`: {}", self.config.from, err)
+        })?;
+        let mut message = Message::builder().from(from).subject(subject);
+        for to in &self.config.to {
+            let mailbox: Mailbox = to
+                .parse()
+                .map_err(|err| anyhow::anyhow!("invalid email recipient `{}`: {}", to, err))?;
+            message = message.to(mailbox);
+        }
+        let message = message
+            .header(match format {
+                EmailBody::Plain => ContentType::TEXT_PLAIN,
+                EmailBody::Html => ContentType::TEXT_HTML,
+            })
+            .body(body)?;
+        self.transport()?.send(message).await?;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl Notifier for EmailNotifier {
+    fn name(&self) -> &str {
+        "email"
+    }
+
+    async fn notify(&self, notification: &Notification) -> Result<(), anyhow::Error> {
+        let subject = if notification.alerts.is_empty() {
+            "Solana balances changed".to_string()
+        } else {
+            format!(
+                "{} Solana balance(s) below threshold",
+                notification.alerts.len()
+            )
+        };
+        let mut body = format!(
+            "Solana balances ({})\n\n",
+            notification
+                .timestamp
+                .to_rfc3339_opts(chrono::SecondsFormat::Secs, true)
+        );
+        for line in notification.lines() {
+            body.push_str(&line);
+            body.push('\n');
+        }
+        self.send(&subject, body, EmailBody::Plain).await
+    }
+}
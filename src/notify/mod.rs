@@ -0,0 +1,240 @@
+mod discord;
+mod email;
+mod ntfy;
+mod pagerduty;
+mod pushover;
+mod slack;
+mod telegram;
+mod webhook;
+
+use crate::alert::Alert;
+use crate::diff::BalanceChange;
+use crate::rpc::RetryPolicy;
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+pub use discord::{DiscordConfig, DiscordNotifier};
+pub use email::{EmailBody, EmailConfig, EmailNotifier, SmtpSecurity};
+pub use ntfy::{NtfyConfig, NtfyNotifier};
+pub use pagerduty::{PagerDutyConfig, PagerDutyNotifier, Severity};
+pub use pushover::{PushoverConfig, PushoverNotifier};
+pub use slack::{SlackConfig, SlackNotifier};
+pub use telegram::{ChatId, TelegramConfig, TelegramNotifier};
+pub use webhook::{WebhookConfig, WebhookNotifier, SIGNATURE_HEADER};
+
+/// Base URL for links to a wallet on a block explorer.
+pub const EXPLORER_ADDRESS_URL: &str = "https://explorer.solana.com/address";
+
+/// A destination for balance notifications.
+#[async_trait]
+pub trait Notifier: Send + Sync {
+    /// Short name used in log messages, e.g. `"telegram"`.
+    fn name(&self) -> &str;
+
+    async fn notify(&self, notification: &Notification) -> Result<(), anyhow::Error>;
+}
+
+/// What watch mode sends out when balances move or cross a threshold.
+#[derive(Debug, Clone, Serialize)]
+pub struct Notification {
+    pub timestamp: DateTime<Utc>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub slot: Option<u64>,
+    pub changes: Vec<BalanceChange>,
+    /// Thresholds newly breached since the previous refresh.
+    pub alerts: Vec<Alert>,
+    /// Thresholds breached at the previous refresh that no longer are, with
+    /// the balance as it was then.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub resolved: Vec<Alert>,
+}
+
+impl Notification {
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty() && self.alerts.is_empty() && self.resolved.is_empty()
+    }
+
+    /// One human-readable line per alert, then one per resolved alert and
+    /// one per change, with the signatures behind it.
+    pub fn lines(&self) -> Vec<String> {
+        self.alerts
+            .iter()
+            .map(|alert| format!("Below threshold: {}", alert))
+            .chain(
+                self.resolved
+                    .iter()
+                    .map(|alert| format!("Back above threshold: {}", alert)),
+            )
+            .chain(self.changes.iter().map(|change| {
+                let mut line = format!(
+                    "{}: {} {:.4} -> {:.4} ({:+.4})",
+                    change.wallet,
+                    change.asset,
+                    change.old,
+                    change.new,
+                    change.delta()
+                );
+                if !change.transactions.is_empty() {
+                    let signatures: Vec<&str> = change
+                        .transactions
+                        .iter()
+                        .map(|transaction| transaction.signature.as_str())
+                        .collect();
+                    line.push_str(&format!(" in {}", signatures.join(", ")));
+                }
+                line
+            }))
+            .collect()
+    }
+}
+
+/// A short push message about breached and recovered thresholds.
+pub(crate) struct Push {
+    pub title: String,
+    pub message: String,
+    /// Whether any threshold is newly breached.
+    pub urgent: bool,
+}
+
+impl Push {
+    /// `None` when no threshold was breached or recovered.
+    pub fn new(notification: &Notification) -> Option<Self> {
+        if notification.alerts.is_empty() && notification.resolved.is_empty() {
+            return None;
+        }
+        let title = match (notification.alerts.len(), notification.resolved.len()) {
+            (0, resolved) => format!("{} Solana balance(s) recovered", resolved),
+            (alerts, _) => format!("{} Solana balance(s) below threshold", alerts),
+        };
+        let message = notification
+            .alerts
+            .iter()
+            .map(|alert| format!("Below threshold: {}", alert))
+            .chain(
+                notification
+                    .resolved
+                    .iter()
+                    .map(|alert| format!("Back above threshold: {}", alert)),
+            )
+            .collect::<Vec<_>>()
+            .join("\n");
+        Some(Push {
+            title,
+            message,
+            urgent: !notification.alerts.is_empty(),
+        })
+    }
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct NotificationConfig {
+    /// Smallest absolute change in a balance worth notifying about.
+    #[serde(default)]
+    pub min_change: f64,
+    #[serde(default)]
+    pub webhooks: Vec<WebhookConfig>,
+    #[serde(default)]
+    pub telegram: Option<TelegramConfig>,
+    #[serde(default)]
+    pub discord: Option<DiscordConfig>,
+    #[serde(default)]
+    pub slack: Option<SlackConfig>,
+    /// Triggers incidents for breached thresholds.
+    #[serde(default)]
+    pub pagerduty: Option<PagerDutyConfig>,
+    /// Pushes breached thresholds to phones through ntfy.
+    #[serde(default)]
+    pub ntfy: Option<NtfyConfig>,
+    /// Pushes breached thresholds to phones through Pushover.
+    #[serde(default)]
+    pub pushover: Option<PushoverConfig>,
+    #[serde(default)]
+    pub email: Option<EmailConfig>,
+    /// Backoff between delivery attempts.
+    #[serde(default)]
+    pub retry: RetryPolicy,
+}
+
+impl NotificationConfig {
+    /// Every configured destination.
+    pub fn notifiers(&self) -> Vec<Box<dyn Notifier>> {
+        let mut notifiers: Vec<Box<dyn Notifier>> = Vec::new();
+        if !self.webhooks.is_empty() {
+            notifiers.push(Box::new(WebhookNotifier::new(
+                self.webhooks.clone(),
+                self.retry.clone(),
+            )));
+        }
+        if let Some(telegram) = &self.telegram {
+            notifiers.push(Box::new(TelegramNotifier::new(
+                telegram.clone(),
+                self.retry.clone(),
+            )));
+        }
+        if let Some(discord) = &self.discord {
+            notifiers.push(Box::new(DiscordNotifier::new(
+                discord.clone(),
+                self.retry.clone(),
+            )));
+        }
+        if let Some(slack) = &self.slack {
+            notifiers.push(Box::new(SlackNotifier::new(
+                slack.clone(),
+                self.retry.clone(),
+            )));
+        }
+        if let Some(pagerduty) = &self.pagerduty {
+            notifiers.push(Box::new(PagerDutyNotifier::new(
+                pagerduty.clone(),
+                self.retry.clone(),
+            )));
+        }
+        if let Some(ntfy) = &self.ntfy {
+            notifiers.push(Box::new(NtfyNotifier::new(
+                ntfy.clone(),
+                self.retry.clone(),
+            )));
+        }
+        if let Some(pushover) = &self.pushover {
+            notifiers.push(Box::new(PushoverNotifier::new(
+                pushover.clone(),
+                self.retry.clone(),
+            )));
+        }
+        if let Some(email) = &self.email {
+            notifiers.push(Box::new(EmailNotifier::new(email.clone())));
+        }
+        notifiers
+    }
+}
+
+/// Sends the request built by `build` until it succeeds, retrying timeouts,
+/// connection errors, 429s and 5xx responses with `retry`'s backoff.
+/// Errors leave out the URL, which may contain a token.
+pub(crate) async fn send_with_retry(
+    retry: &RetryPolicy,
+    build: impl Fn() -> reqwest::RequestBuilder,
+) -> Result<(), anyhow::Error> {
+    let mut attempt = 1;
+    loop {
+        let retryable = match build().send().await {
+            Ok(response) if response.status().is_success() => return Ok(()),
+            Ok(response) => {
+                let status = response.status();
+                if status.as_u16() != 429 && !status.is_server_error() {
+                    anyhow::bail!("HTTP {}", status);
+                }
+                anyhow::anyhow!("HTTP {}", status)
+            }
+            Err(err) if err.is_timeout() || err.is_connect() => err.without_url().into(),
+            Err(err) => return Err(err.without_url().into()),
+        };
+
+        if attempt >= retry.max_attempts {
+            return Err(retryable);
+        }
+        tokio::time::sleep(retry.backoff(attempt)).await;
+        attempt += 1;
+    }
+}
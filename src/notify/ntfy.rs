@@ -0,0 +1,85 @@
+use crate::notify::{send_with_retry, Notification, Notifier, Push};
+use crate::rpc::RetryPolicy;
+use async_trait::async_trait;
+use serde::Deserialize;
+
+const NTFY_URL: &str = "https://ntfy.sh";
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct NtfyConfig {
+    pub topic: String,
+    /// For self-hosted servers [default: https://ntfy.sh].
+    #[serde(default)]
+    pub server: Option<String>,
+    /// Access token for protected topics.
+    #[serde(default)]
+    pub token: Option<String>,
+    /// 1 (min) to 5 (max) [default: 4 for alerts, 3 for recoveries].
+    #[serde(default)]
+    pub priority: Option<u8>,
+}
+
+/// Pushes breached and recovered thresholds to an ntfy topic. Balance
+/// changes are not sent.
+pub struct NtfyNotifier {
+    http: reqwest::Client,
+    config: NtfyConfig,
+    retry: RetryPolicy,
+}
+
+impl NtfyNotifier {
+    pub fn new(config: NtfyConfig, retry: RetryPolicy) -> Self {
+        NtfyNotifier {
+            http: reqwest::Client::new(),
+            config,
+            retry,
+        }
+    }
+}
+
+#[async_trait]
+impl Notifier for NtfyNotifier {
+    fn name(&self) -> &str {
+        "ntfy"
+    }
+
+    async fn notify(&self, notification: &Notification) -> Result<(), anyhow::Error> {
+        let Some(push) = Push::new(notification) else {
+            return Ok(());
+        };
+        let url = format!(
+            "{}/{}",
+            self.config
+                .server
+                .as_deref()
+                .unwrap_or(NTFY_URL)
+                .trim_end_matches('/'),
+            self.config.topic
+        );
+        let priority = self
+            .config
+            .priority
+            .unwrap_or(if push.urgent { 4 } else { 3 });
+        send_with_retry(&self.retry, || {
+            let mut request = self
+                .http
+                .post(&url)
+                .header("Title", &push.title)
+                .header("Priority", priority.to_string())
+                .header(
+                    "Tags",
+                    if push.urgent {
+                        "warning"
+                    } else {
+                        "white_check_mark"
+                    },
+                )
+                .body(push.message.clone());
+            if let Some(token) = &self.config.token {
+                request = request.bearer_auth(token);
+            }
+            request
+        })
+        .await
+    }
+}
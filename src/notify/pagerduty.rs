@@ -0,0 +1,104 @@
+use crate::alert::Alert;
+use crate::notify::{send_with_retry, Notification, Notifier};
+use crate::rpc::RetryPolicy;
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+
+const EVENTS_API_URL: &str = "https://events.pagerduty.com/v2/enqueue";
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct PagerDutyConfig {
+    /// Integration key of an Events API v2 integration on the service.
+    pub routing_key: String,
+    #[serde(default)]
+    pub severity: Severity,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    #[default]
+    Critical,
+    Error,
+    Warning,
+    Info,
+}
+
+#[derive(Serialize)]
+struct Event<'a> {
+    routing_key: &'a str,
+    event_action: &'static str,
+    dedup_key: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    payload: Option<Payload<'a>>,
+}
+
+#[derive(Serialize)]
+struct Payload<'a> {
+    summary: String,
+    source: &'a str,
+    severity: Severity,
+    custom_details: &'a Alert,
+}
+
+/// Triggers a PagerDuty incident for each breached threshold and resolves
+/// it once the balance recovers. Balance changes are not sent.
+pub struct PagerDutyNotifier {
+    http: reqwest::Client,
+    config: PagerDutyConfig,
+    retry: RetryPolicy,
+}
+
+impl PagerDutyNotifier {
+    pub fn new(config: PagerDutyConfig, retry: RetryPolicy) -> Self {
+        PagerDutyNotifier {
+            http: reqwest::Client::new(),
+            config,
+            retry,
+        }
+    }
+
+    async fn send(&self, event: &Event<'_>) -> Result<(), anyhow::Error> {
+        send_with_retry(&self.retry, || self.http.post(EVENTS_API_URL).json(event)).await
+    }
+}
+
+#[async_trait]
+impl Notifier for PagerDutyNotifier {
+    fn name(&self) -> &str {
+        "pagerduty"
+    }
+
+    async fn notify(&self, notification: &Notification) -> Result<(), anyhow::Error> {
+        for alert in &notification.alerts {
+            self.send(&Event {
+                routing_key: &self.config.routing_key,
+                event_action: "trigger",
+                dedup_key: dedup_key(alert),
+                payload: Some(Payload {
+                    summary: format!("Below threshold: {}", alert),
+                    source: &alert.wallet,
+                    severity: self.config.severity,
+                    custom_details: alert,
+                }),
+            })
+            .await?;
+        }
+        for alert in &notification.resolved {
+            self.send(&Event {
+                routing_key: &self.config.routing_key,
+                event_action: "resolve",
+                dedup_key: dedup_key(alert),
+                payload: None,
+            })
+            .await?;
+        }
+        Ok(())
+    }
+}
+
+/// Identifies the incident of one wallet and asset, so repeated triggers
+/// update it and a resolve closes it.
+fn dedup_key(alert: &Alert) -> String {
+    format!("solana-balance:{}:{}", alert.wallet, alert.asset)
+}
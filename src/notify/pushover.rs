@@ -0,0 +1,74 @@
+use crate::notify::{send_with_retry, Notification, Notifier, Push};
+use crate::rpc::RetryPolicy;
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+
+const PUSHOVER_API_URL: &str = "https://api.pushover.net/1/messages.json";
+/// Pushover truncates messages longer than this many characters.
+const MAX_MESSAGE_LEN: usize = 1024;
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct PushoverConfig {
+    /// The application's API token.
+    pub token: String,
+    /// The user or group key to notify.
+    pub user: String,
+    /// Only notify this device of the user's.
+    #[serde(default)]
+    pub device: Option<String>,
+}
+
+#[derive(Serialize)]
+struct Message<'a> {
+    token: &'a str,
+    user: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    device: Option<&'a str>,
+    title: &'a str,
+    message: String,
+    /// 1 bypasses quiet hours, 0 is the default.
+    priority: i8,
+}
+
+/// Pushes breached and recovered thresholds through Pushover. Balance
+/// changes are not sent.
+pub struct PushoverNotifier {
+    http: reqwest::Client,
+    config: PushoverConfig,
+    retry: RetryPolicy,
+}
+
+impl PushoverNotifier {
+    pub fn new(config: PushoverConfig, retry: RetryPolicy) -> Self {
+        PushoverNotifier {
+            http: reqwest::Client::new(),
+            config,
+            retry,
+        }
+    }
+}
+
+#[async_trait]
+impl Notifier for PushoverNotifier {
+    fn name(&self) -> &str {
+        "pushover"
+    }
+
+    async fn notify(&self, notification: &Notification) -> Result<(), anyhow::Error> {
+        let Some(push) = Push::new(notification) else {
+            return Ok(());
+        };
+        let message = Message {
+            token: &self.config.token,
+            user: &self.config.user,
+            device: self.config.device.as_deref(),
+            title: &push.title,
+            message: push.message.chars().take(MAX_MESSAGE_LEN).collect(),
+            priority: if push.urgent { 1 } else { 0 },
+        };
+        send_with_retry(&self.retry, || {
+            self.http.post(PUSHOVER_API_URL).form(&message)
+        })
+        .await
+    }
+}
@@ -0,0 +1,195 @@
+use crate::notify::{send_with_retry, Notification, Notifier, EXPLORER_ADDRESS_URL};
+use crate::rpc::RetryPolicy;
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Slack rejects messages with more blocks than this.
+const MAX_BLOCKS_PER_MESSAGE: usize = 50;
+/// Slack rejects section blocks with more fields than this.
+const MAX_FIELDS_PER_SECTION: usize = 10;
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct SlackConfig {
+    /// An incoming webhook URL, `https://hooks.slack.com/services/...`.
+    pub webhook_url: String,
+}
+
+#[derive(Serialize)]
+struct Message<'a> {
+    /// Shown in notifications and by clients that cannot render blocks.
+    text: &'a str,
+    blocks: &'a [Block],
+}
+
+#[derive(Serialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+enum Block {
+    Header {
+        text: Text,
+    },
+    Section {
+        #[serde(skip_serializing_if = "Option::is_none")]
+        text: Option<Text>,
+        #[serde(skip_serializing_if = "Vec::is_empty")]
+        fields: Vec<Text>,
+    },
+    Context {
+        elements: Vec<Text>,
+    },
+}
+
+#[derive(Serialize)]
+struct Text {
+    #[serde(rename = "type")]
+    kind: &'static str,
+    text: String,
+}
+
+impl Text {
+    fn plain(text: impl Into<String>) -> Self {
+        Text {
+            kind: "plain_text",
+            text: text.into(),
+        }
+    }
+
+    fn markdown(text: impl Into<String>) -> Self {
+        Text {
+            kind: "mrkdwn",
+            text: text.into(),
+        }
+    }
+}
+
+/// Posts each notification to a Slack incoming webhook as Block Kit
+/// sections, one per wallet with a field per asset.
+pub struct SlackNotifier {
+    http: reqwest::Client,
+    config: SlackConfig,
+    retry: RetryPolicy,
+}
+
+impl SlackNotifier {
+    pub fn new(config: SlackConfig, retry: RetryPolicy) -> Self {
+        SlackNotifier {
+            http: reqwest::Client::new(),
+            config,
+            retry,
+        }
+    }
+}
+
+#[async_trait]
+impl Notifier for SlackNotifier {
+    fn name(&self) -> &str {
+        "slack"
+    }
+
+    async fn notify(&self, notification: &Notification) -> Result<(), anyhow::Error> {
+        let text = summary(notification);
+        let blocks = blocks(notification, &text);
+        for chunk in blocks.chunks(MAX_BLOCKS_PER_MESSAGE) {
+            let message = Message {
+                text: &text,
+                blocks: chunk,
+            };
+            send_with_retry(&self.retry, || {
+                self.http.post(&self.config.webhook_url).json(&message)
+            })
+            .await?;
+        }
+        Ok(())
+    }
+}
+
+fn summary(notification: &Notification) -> String {
+    let mut parts = Vec::new();
+    if !notification.alerts.is_empty() {
+        parts.push(format!("{} below threshold", notification.alerts.len()));
+    }
+    if !notification.resolved.is_empty() {
+        parts.push(format!("{} recovered", notification.resolved.len()));
+    }
+    if !notification.changes.is_empty() {
+        parts.push(format!("{} change(s)", notification.changes.len()));
+    }
+    format!("Solana balances: {}", parts.join(", "))
+}
+
+fn blocks(notification: &Notification, summary: &str) -> Vec<Block> {
+    // Fields per wallet, alerts first, with the wallet's label if known.
+    let mut wallets: BTreeMap<&str, (Option<&str>, Vec<Text>)> = BTreeMap::new();
+    for alert in &notification.alerts {
+        let entry = wallets.entry(&alert.wallet).or_default();
+        entry.0 = entry.0.or(alert.label.as_deref());
+        entry.1.push(Text::markdown(format!(
+            ":rotating_light: *{}*\n{:.4} (below {:.4})",
+            alert.asset, alert.balance, alert.threshold
+        )));
+    }
+    for alert in &notification.resolved {
+        let entry = wallets.entry(&alert.wallet).or_default();
+        entry.0 = entry.0.or(alert.label.as_deref());
+        entry.1.push(Text::markdown(format!(
+            ":white_check_mark: *{}*\nback above {:.4}",
+            alert.asset, alert.threshold
+        )));
+    }
+    for change in &notification.changes {
+        let arrow = if change.delta() < 0.0 {
+            ":small_red_triangle_down:"
+        } else {
+            ":small_green_triangle:"
+        };
+        wallets
+            .entry(&change.wallet)
+            .or_default()
+            .1
+            .push(Text::markdown(format!(
+                "{} *{}*\n{:.4} → {:.4} ({:+.4})",
+                arrow,
+                change.asset,
+                change.old,
+                change.new,
+                change.delta()
+            )));
+    }
+
+    let mut blocks = vec![
+        Block::Header {
+            text: Text::plain(summary),
+        },
+        Block::Context {
+            elements: vec![Text::markdown(
+                notification
+                    .timestamp
+                    .to_rfc3339_opts(chrono::SecondsFormat::Secs, true),
+            )],
+        },
+    ];
+    for (wallet, (label, mut fields)) in wallets {
+        let link = format!("<{}/{}|{}>", EXPLORER_ADDRESS_URL, wallet, wallet);
+        let title = match label {
+            Some(label) => format!("*{}*  {}", escape(label), link),
+            None => link,
+        };
+        let mut text = Some(Text::markdown(title));
+        while !fields.is_empty() {
+            let rest = fields.split_off(fields.len().min(MAX_FIELDS_PER_SECTION));
+            blocks.push(Block::Section {
+                text: text.take(),
+                fields,
+            });
+            fields = rest;
+        }
+    }
+    blocks
+}
+
+/// Escapes the characters Slack's mrkdwn treats as control sequences.
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
@@ -0,0 +1,102 @@
+use crate::notify::{send_with_retry, Notification, Notifier};
+use crate::rpc::RetryPolicy;
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+
+const TELEGRAM_API_URL: &str = "https://api.telegram.org";
+/// Telegram rejects messages longer than this many characters.
+const MAX_MESSAGE_LEN: usize = 4096;
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct TelegramConfig {
+    pub bot_token: String,
+    pub chat_id: ChatId,
+}
+
+/// A numeric chat id or a public `@channelusername`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum ChatId {
+    Id(i64),
+    Username(String),
+}
+
+#[derive(Serialize)]
+struct SendMessage<'a> {
+    chat_id: &'a ChatId,
+    text: &'a str,
+    parse_mode: &'static str,
+    disable_web_page_preview: bool,
+}
+
+/// Posts each notification to a Telegram chat as a single message.
+pub struct TelegramNotifier {
+    http: reqwest::Client,
+    config: TelegramConfig,
+    retry: RetryPolicy,
+}
+
+impl TelegramNotifier {
+    pub fn new(config: TelegramConfig, retry: RetryPolicy) -> Self {
+        TelegramNotifier {
+            http: reqwest::Client::new(),
+            config,
+            retry,
+        }
+    }
+}
+
+#[async_trait]
+impl Notifier for TelegramNotifier {
+    fn name(&self) -> &str {
+        "telegram"
+    }
+
+    async fn notify(&self, notification: &Notification) -> Result<(), anyhow::Error> {
+        let url = format!(
+            "{}/bot{}/sendMessage",
+            TELEGRAM_API_URL, self.config.bot_token
+        );
+        let text = format_message(notification);
+        let message = SendMessage {
+            chat_id: &self.config.chat_id,
+            text: &text,
+            parse_mode: "HTML",
+            disable_web_page_preview: true,
+        };
+
+        send_with_retry(&self.retry, || self.http.post(&url).json(&message)).await
+    }
+}
+
+/// Formats every alert and change into one HTML message, dropping lines
+/// that would push it past Telegram's length limit.
+fn format_message(notification: &Notification) -> String {
+    let mut message = format!(
+        "<b>Solana balances</b> ({})\n",
+        notification
+            .timestamp
+            .to_rfc3339_opts(chrono::SecondsFormat::Secs, true)
+    );
+
+    let lines = notification.lines();
+    for (index, line) in lines.iter().enumerate() {
+        let line = format!("{}\n", escape_html(line));
+        let omitted = format!("… and {} more", lines.len() - index);
+        if message.chars().count() + line.chars().count() + omitted.chars().count()
+            > MAX_MESSAGE_LEN
+        {
+            message.push_str(&omitted);
+            break;
+        }
+        message.push_str(&line);
+    }
+
+    message
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
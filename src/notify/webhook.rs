@@ -0,0 +1,86 @@
+use crate::notify::{send_with_retry, Notification, Notifier};
+use crate::rpc::{redact_urls, url_host, RetryPolicy};
+use async_trait::async_trait;
+use hmac::{Hmac, Mac};
+use serde::Deserialize;
+use sha2::Sha256;
+
+/// Header carrying `sha256=<hex HMAC of the body>` when a secret is set.
+pub const SIGNATURE_HEADER: &str = "X-Signature-256";
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct WebhookConfig {
+    pub url: String,
+    /// Key used to sign payloads with HMAC-SHA256.
+    #[serde(default)]
+    pub secret: Option<String>,
+}
+
+/// POSTs notifications as JSON to every configured webhook.
+pub struct WebhookNotifier {
+    http: reqwest::Client,
+    webhooks: Vec<WebhookConfig>,
+    retry: RetryPolicy,
+}
+
+impl WebhookNotifier {
+    pub fn new(webhooks: Vec<WebhookConfig>, retry: RetryPolicy) -> Self {
+        WebhookNotifier {
+            http: reqwest::Client::new(),
+            webhooks,
+            retry,
+        }
+    }
+
+    async fn post(&self, webhook: &WebhookConfig, body: &[u8]) -> Result<(), anyhow::Error> {
+        send_with_retry(&self.retry, || {
+            let request = self
+                .http
+                .post(&webhook.url)
+                .header(reqwest::header::CONTENT_TYPE, "application/json")
+                .body(body.to_vec());
+            match &webhook.secret {
+                Some(secret) => request.header(SIGNATURE_HEADER, signature(secret, body)),
+                None => request,
+            }
+        })
+        .await
+    }
+}
+
+#[async_trait]
+impl Notifier for WebhookNotifier {
+    fn name(&self) -> &str {
+        "webhook"
+    }
+
+    /// Delivers `notification` to every webhook, even if some fail.
+    async fn notify(&self, notification: &Notification) -> Result<(), anyhow::Error> {
+        let body = serde_json::to_vec(notification)?;
+
+        let mut failures = Vec::new();
+        for (index, webhook) in self.webhooks.iter().enumerate() {
+            if let Err(err) = self.post(webhook, &body).await {
+                // Only the host, as webhook URLs often carry a token.
+                failures.push(format!(
+                    "webhook {} ({}): {}",
+                    index + 1,
+                    url_host(&webhook.url),
+                    redact_urls(&err.to_string())
+                ));
+            }
+        }
+
+        if !failures.is_empty() {
+            anyhow::bail!("{}", failures.join("; "));
+        }
+        Ok(())
+    }
+}
+
+fn signature(secret: &str, body: &[u8]) -> String {
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts keys of any size");
+    mac.update(body);
+    format!("sha256={}", hex::encode(mac.finalize().into_bytes()))
+}
@@ -0,0 +1,85 @@
+use crate::diff::BalanceChange;
+use crate::output::{OutputFormat, RenderOptions};
+use crate::report::BalanceReport;
+use chrono::SecondsFormat;
+use std::io::Write;
+
+/// Renders the changes between two reports in the requested format.
+pub fn write_changes(
+    old: &BalanceReport,
+    new: &BalanceReport,
+    changes: &[BalanceChange],
+    options: &RenderOptions,
+    out: &mut dyn Write,
+) -> Result<(), anyhow::Error> {
+    match options.format {
+        OutputFormat::Json => {
+            if options.compact {
+                serde_json::to_writer(&mut *out, changes)?;
+            } else {
+                serde_json::to_writer_pretty(&mut *out, changes)?;
+            }
+            writeln!(out)?;
+        }
+        OutputFormat::Csv => {
+            let mut writer = csv::Writer::from_writer(out);
+            writer.write_record(["wallet", "asset", "old", "new", "delta"])?;
+            for change in changes {
+                let amount = |value: f64| {
+                    options
+                        .numbers
+                        .csv_amount(&change.asset, None, value, &value.to_string())
+                };
+                writer.write_record([
+                    &change.wallet,
+                    &change.asset,
+                    &amount(change.old),
+                    &amount(change.new),
+                    &amount(change.delta()),
+                ])?;
+            }
+            writer.flush()?;
+        }
+        _ => write_changes_text(old, new, changes, options, out)?,
+    }
+
+    Ok(())
+}
+
+fn write_changes_text(
+    old: &BalanceReport,
+    new: &BalanceReport,
+    changes: &[BalanceChange],
+    options: &RenderOptions,
+    out: &mut dyn Write,
+) -> Result<(), anyhow::Error> {
+    let numbers = &options.numbers;
+    writeln!(
+        out,
+        "Balance changes from {} to {}:",
+        old.timestamp.to_rfc3339_opts(SecondsFormat::Secs, true),
+        new.timestamp.to_rfc3339_opts(SecondsFormat::Secs, true)
+    )?;
+    if changes.is_empty() {
+        writeln!(out, "No balance changes.")?;
+        return Ok(());
+    }
+
+    let mut current_wallet = None;
+    for change in changes {
+        if current_wallet != Some(&change.wallet) {
+            writeln!(out, "Wallet: {}", change.wallet)?;
+            current_wallet = Some(&change.wallet);
+        }
+        writeln!(
+            out,
+            "  {}: {} -> {} ({})",
+            change.asset,
+            numbers.amount(&change.asset, None, change.old),
+            numbers.amount(&change.asset, None, change.new),
+            numbers.delta(&change.asset, None, change.delta())
+        )?;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,113 @@
+use crate::cleanup::CleanupReport;
+use crate::output::table::{paint, Align, Cell, Table, BOLD, CYAN, DIM, RED};
+use crate::output::{OutputFormat, RenderOptions};
+use solana_sdk::native_token::lamports_to_sol;
+use std::io::Write;
+
+/// Renders a cleanup report as a table, JSON or one CSV row per account.
+pub fn write_cleanup(
+    report: &CleanupReport,
+    options: &RenderOptions,
+    out: &mut dyn Write,
+) -> Result<(), anyhow::Error> {
+    match options.format {
+        OutputFormat::Json => {
+            if options.compact {
+                serde_json::to_writer(&mut *out, report)?;
+            } else {
+                serde_json::to_writer_pretty(&mut *out, report)?;
+            }
+            writeln!(out)?;
+        }
+        OutputFormat::Csv => {
+            let mut writer = csv::Writer::from_writer(out);
+            writer.write_record(["wallet", "label", "account", "mint", "program", "rent_sol"])?;
+            for (wallet, cleanup) in &report.wallets {
+                for account in &cleanup.accounts {
+                    writer.write_record([
+                        wallet,
+                        cleanup.label.as_deref().unwrap_or_default(),
+                        &account.address,
+                        &account.mint,
+                        account.program_name(),
+                        &options.numbers.csv_amount(
+                            "SOL",
+                            Some(9),
+                            lamports_to_sol(account.lamports),
+                            &lamports_to_sol(account.lamports).to_string(),
+                        ),
+                    ])?;
+                }
+            }
+            writer.flush()?;
+        }
+        _ => write_cleanup_text(report, options, out)?,
+    }
+    Ok(())
+}
+
+fn write_cleanup_text(
+    report: &CleanupReport,
+    options: &RenderOptions,
+    out: &mut dyn Write,
+) -> Result<(), anyhow::Error> {
+    let sol = |value: f64| options.numbers.amount_or("SOL", Some(9), 6, value);
+    if report.wallets.is_empty() {
+        writeln!(out, "No empty token accounts to close.")?;
+    } else {
+        let mut table = Table::new(&[
+            ("Wallet", Align::Left),
+            ("Token Account", Align::Left),
+            ("Mint", Align::Left),
+            ("Program", Align::Left),
+            ("Rent (SOL)", Align::Right),
+        ]);
+        for (index, (wallet, cleanup)) in report.wallets.iter().enumerate() {
+            if index > 0 {
+                table.rule();
+            }
+            let names: Vec<&String> = cleanup.label.iter().chain([wallet]).collect();
+            for line in 0..cleanup.accounts.len().max(names.len()) {
+                let name = match names.get(line) {
+                    Some(name) if line == 0 => Cell::styled(name.as_str(), CYAN),
+                    Some(name) => Cell::styled(name.as_str(), DIM),
+                    None => Cell::default(),
+                };
+                let mut cells = vec![name];
+                if let Some(account) = cleanup.accounts.get(line) {
+                    cells.extend([
+                        Cell::new(account.address.clone()),
+                        Cell::new(account.mint.clone()),
+                        Cell::new(account.program_name()),
+                        Cell::new(sol(lamports_to_sol(account.lamports))),
+                    ]);
+                }
+                table.row(cells);
+            }
+        }
+        table.write(options.color, out)?;
+        writeln!(out)?;
+        writeln!(
+            out,
+            "{}",
+            paint(
+                &format!(
+                    "Recoverable: {} SOL by closing {} account(s)",
+                    sol(report.recoverable_sol),
+                    report.accounts()
+                ),
+                BOLD,
+                options.color
+            )
+        )?;
+    }
+
+    if !report.errors.is_empty() {
+        writeln!(out)?;
+        writeln!(out, "{}", paint("Errors:", RED, options.color))?;
+        for (wallet, error) in &report.errors {
+            writeln!(out, "  {}: {}", wallet, error)?;
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,89 @@
+use crate::diff::{SOL_ASSET, STAKED_SOL_ASSET};
+use crate::output::RenderOptions;
+use crate::report::BalanceReport;
+use crate::token_account::{format_ui_amount, SOL_DECIMALS};
+use solana_sdk::native_token::sol_to_lamports;
+use std::io::Write;
+
+/// Writes one `wallet,label,asset,amount,usd_value` row per balance, with
+/// wallets and their tokens in `order` and SOL always listed first for each
+/// wallet. `usd_value` is left empty when no price is known. Amounts are
+/// exact unless `options.numbers` sets a locale or decimals, and are base
+/// units in a `raw_amount` column under `options.raw`.
+pub fn write_csv(
+    report: &BalanceReport,
+    options: &RenderOptions,
+    out: &mut dyn Write,
+) -> Result<(), anyhow::Error> {
+    let numbers = &options.numbers;
+    let order = options.sort_by;
+    let usd_field = |value: Option<f64>| {
+        value
+            .map(|value| numbers.csv_value(value))
+            .unwrap_or_default()
+    };
+    let mut writer = csv::Writer::from_writer(out);
+    let amount_header = if options.raw { "raw_amount" } else { "amount" };
+    writer.write_record(["wallet", "label", "asset", amount_header, "usd_value"])?;
+
+    for (wallet, balance_info) in report.sorted_wallets(order) {
+        let label = balance_info.label.as_deref().unwrap_or_default();
+        writer.write_record([
+            wallet,
+            label,
+            SOL_ASSET,
+            &if options.raw {
+                balance_info.sol_lamports.to_string()
+            } else {
+                numbers.csv_amount(
+                    SOL_ASSET,
+                    Some(SOL_DECIMALS),
+                    balance_info.sol_balance,
+                    &format_ui_amount(balance_info.sol_lamports, SOL_DECIMALS),
+                )
+            },
+            &usd_field(balance_info.sol_usd_value),
+        ])?;
+
+        if let Some(stake) = &balance_info.stake {
+            writer.write_record([
+                wallet,
+                label,
+                STAKED_SOL_ASSET,
+                &if options.raw {
+                    sol_to_lamports(stake.total).to_string()
+                } else {
+                    numbers.csv_amount(
+                        STAKED_SOL_ASSET,
+                        Some(SOL_DECIMALS),
+                        stake.total,
+                        &stake.total.to_string(),
+                    )
+                },
+                "",
+            ])?;
+        }
+
+        for (token, balance) in balance_info.sorted_tokens(order) {
+            writer.write_record([
+                wallet,
+                label,
+                token,
+                &if options.raw {
+                    balance.raw_amount.to_string()
+                } else {
+                    numbers.csv_amount(
+                        token,
+                        Some(balance.decimals),
+                        balance.amount,
+                        &balance.ui_amount_string,
+                    )
+                },
+                &usd_field(balance.usd_value),
+            ])?;
+        }
+    }
+
+    writer.flush()?;
+    Ok(())
+}
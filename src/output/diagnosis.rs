@@ -0,0 +1,90 @@
+use crate::health::Diagnosis;
+use crate::output::table::{paint, Align, Cell, Table, BOLD, GREEN, RED};
+use crate::output::{OutputFormat, RenderOptions};
+use std::io::Write;
+
+/// Renders RPC diagnoses as a table of checks per endpoint followed by the
+/// findings, as JSON or as one CSV row per check.
+pub fn write_diagnoses(
+    diagnoses: &[Diagnosis],
+    options: &RenderOptions,
+    out: &mut dyn Write,
+) -> Result<(), anyhow::Error> {
+    match options.format {
+        OutputFormat::Json => {
+            if options.compact {
+                serde_json::to_writer(&mut *out, diagnoses)?;
+            } else {
+                serde_json::to_writer_pretty(&mut *out, diagnoses)?;
+            }
+            writeln!(out)?;
+        }
+        OutputFormat::Csv => {
+            let mut writer = csv::Writer::from_writer(out);
+            writer.write_record(["endpoint", "check", "ok", "latency_ms", "detail"])?;
+            for diagnosis in diagnoses {
+                for check in &diagnosis.checks {
+                    writer.write_record([
+                        diagnosis.endpoint.as_str(),
+                        check.check,
+                        &check.ok.to_string(),
+                        &check.latency_ms.to_string(),
+                        &check.detail,
+                    ])?;
+                }
+            }
+            writer.flush()?;
+        }
+        _ => write_diagnoses_text(diagnoses, options, out)?,
+    }
+    Ok(())
+}
+
+fn write_diagnoses_text(
+    diagnoses: &[Diagnosis],
+    options: &RenderOptions,
+    out: &mut dyn Write,
+) -> Result<(), anyhow::Error> {
+    for (index, diagnosis) in diagnoses.iter().enumerate() {
+        if index > 0 {
+            writeln!(out)?;
+        }
+        writeln!(
+            out,
+            "{}",
+            paint(
+                &format!("Endpoint: {}", diagnosis.endpoint),
+                BOLD,
+                options.color
+            )
+        )?;
+        let mut table = Table::new(&[
+            ("Check", Align::Left),
+            ("Result", Align::Left),
+            ("Latency", Align::Right),
+            ("Detail", Align::Left),
+        ]);
+        for check in &diagnosis.checks {
+            table.row(vec![
+                Cell::new(check.check),
+                if check.ok {
+                    Cell::styled("ok", GREEN)
+                } else {
+                    Cell::styled("failed", RED)
+                },
+                Cell::new(format!("{} ms", check.latency_ms)),
+                Cell::new(&check.detail),
+            ]);
+        }
+        table.write(options.color, out)?;
+        let style = if diagnosis.is_healthy() { GREEN } else { RED };
+        for finding in &diagnosis.findings {
+            writeln!(
+                out,
+                "{}",
+                paint(&format!("=> {}", finding), style, options.color)
+            )?;
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,94 @@
+use crate::history::HistoryEntry;
+use crate::output::{OutputFormat, RenderOptions};
+use chrono::SecondsFormat;
+use std::io::Write;
+
+/// Renders recorded balances in the requested format. Text output groups
+/// entries by wallet and asset and shows the change from the previous run.
+pub fn write_history(
+    entries: &[HistoryEntry],
+    options: &RenderOptions,
+    out: &mut dyn Write,
+) -> Result<(), anyhow::Error> {
+    match options.format {
+        OutputFormat::Json => {
+            if options.compact {
+                serde_json::to_writer(&mut *out, entries)?;
+            } else {
+                serde_json::to_writer_pretty(&mut *out, entries)?;
+            }
+            writeln!(out)?;
+        }
+        OutputFormat::Csv => {
+            let mut writer = csv::Writer::from_writer(out);
+            writer.write_record(["timestamp", "slot", "wallet", "asset", "mint", "amount"])?;
+            for entry in entries {
+                writer.write_record([
+                    &entry.timestamp.to_rfc3339_opts(SecondsFormat::Secs, true),
+                    &entry.slot.map(|slot| slot.to_string()).unwrap_or_default(),
+                    &entry.wallet,
+                    &entry.asset,
+                    entry.mint.as_deref().unwrap_or_default(),
+                    &options.numbers.csv_amount(
+                        &entry.asset,
+                        None,
+                        entry.amount,
+                        &entry.amount.to_string(),
+                    ),
+                ])?;
+            }
+            writer.flush()?;
+        }
+        _ => write_history_text(entries, options, out)?,
+    }
+
+    Ok(())
+}
+
+fn write_history_text(
+    entries: &[HistoryEntry],
+    options: &RenderOptions,
+    out: &mut dyn Write,
+) -> Result<(), anyhow::Error> {
+    let numbers = &options.numbers;
+    if entries.is_empty() {
+        writeln!(out, "No recorded balances.")?;
+        return Ok(());
+    }
+
+    let mut sorted: Vec<&HistoryEntry> = entries.iter().collect();
+    sorted.sort_by(|a, b| (&a.wallet, &a.asset).cmp(&(&b.wallet, &b.asset)));
+
+    let mut current_wallet = None;
+    let mut previous: Option<&HistoryEntry> = None;
+    for entry in sorted {
+        if current_wallet != Some(&entry.wallet) {
+            writeln!(out, "Wallet: {}", entry.wallet)?;
+            current_wallet = Some(&entry.wallet);
+            previous = None;
+        }
+        if previous.map(|previous| &previous.asset) != Some(&entry.asset) {
+            writeln!(out, "  {}:", entry.asset)?;
+            previous = None;
+        }
+
+        write!(
+            out,
+            "    {}  {}",
+            entry.timestamp.to_rfc3339_opts(SecondsFormat::Secs, true),
+            numbers.amount(&entry.asset, None, entry.amount)
+        )?;
+        if let Some(previous) = previous {
+            write!(
+                out,
+                " ({})",
+                numbers.delta(&entry.asset, None, entry.amount - previous.amount)
+            )?;
+        }
+        writeln!(out)?;
+
+        previous = Some(entry);
+    }
+
+    Ok(())
+}
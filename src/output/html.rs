@@ -0,0 +1,337 @@
+use crate::diff::SOL_ASSET;
+use crate::metadata::short_address;
+use crate::output::table::format_number;
+use crate::output::{NumberFormat, RenderOptions};
+use crate::pricing::Currency;
+use crate::report::{BalanceReport, BalanceResult};
+use crate::token_account::SOL_DECIMALS;
+use chrono::{DateTime, SecondsFormat, Utc};
+use std::io::Write;
+
+const STYLE: &str = "\
+body { font-family: -apple-system, 'Segoe UI', Helvetica, Arial, sans-serif; color: #1f2328; max-width: 960px; margin: 2em auto; padding: 0 1em; }
+h1 { font-size: 1.6em; margin-bottom: 0.2em; }
+h2 { font-size: 1.2em; margin: 1.6em 0 0.4em; }
+.meta, .sub { color: #656d76; }
+.sub { font-size: 0.85em; font-weight: normal; }
+table { border-collapse: collapse; width: 100%; }
+th, td { padding: 0.35em 0.7em; border-bottom: 1px solid #d0d7de; }
+th { text-align: left; background: #f6f8fa; }
+td.num, th.num { text-align: right; font-variant-numeric: tabular-nums; }
+tr.total td { font-weight: bold; border-top: 2px solid #1f2328; }
+a { color: #0969da; text-decoration: none; }
+.errors { color: #cf222e; }
+svg { width: 100%; height: auto; }
+";
+
+/// Size of the history chart's drawing area, in SVG units.
+const CHART_WIDTH: f64 = 900.0;
+const CHART_HEIGHT: f64 = 220.0;
+const CHART_MARGIN: f64 = 30.0;
+
+pub fn write_html(
+    report: &BalanceReport,
+    options: &RenderOptions,
+    out: &mut dyn Write,
+) -> Result<(), anyhow::Error> {
+    write_html_with_history(report, &[], options, out)
+}
+
+/// Like [`write_html`], but also charts `sol_history`, the SOL held across
+/// all wallets at each recorded run, when it has at least two points.
+pub fn write_html_with_history(
+    report: &BalanceReport,
+    sol_history: &[(DateTime<Utc>, f64)],
+    options: &RenderOptions,
+    out: &mut dyn Write,
+) -> Result<(), anyhow::Error> {
+    let timestamp = report.timestamp.to_rfc3339_opts(SecondsFormat::Secs, true);
+    writeln!(out, "<!DOCTYPE html>")?;
+    writeln!(out, "<html lang=\"en\">")?;
+    writeln!(out, "<head>")?;
+    writeln!(out, "<meta charset=\"utf-8\">")?;
+    writeln!(out, "<title>Solana balances {}</title>", timestamp)?;
+    writeln!(out, "<style>\n{}</style>", STYLE)?;
+    writeln!(out, "</head>")?;
+    writeln!(out, "<body>")?;
+    writeln!(out, "<h1>Solana balances</h1>")?;
+    write!(out, "<p class=\"meta\">Fetched {}", timestamp)?;
+    if let Some(slot) = report.slot {
+        if report.historical {
+            write!(out, ", as of slot {}", slot)?;
+        } else {
+            write!(out, " at slot {}", slot)?;
+        }
+    }
+    writeln!(out, "</p>")?;
+
+    let currency = report.display_currency();
+    let numbers = &options.numbers;
+    write_totals(report, &currency, options, out)?;
+    if sol_history.len() > 1 {
+        writeln!(out, "<h2>SOL over time</h2>")?;
+        write_chart(sol_history, out)?;
+    }
+
+    for (wallet, result) in report.sorted_wallets(options.sort_by) {
+        write_wallet(wallet, result, &currency, options, out)?;
+    }
+
+    if !report.groups.is_empty() {
+        writeln!(out, "<h2>Groups</h2>")?;
+        writeln!(out, "<table>")?;
+        writeln!(
+            out,
+            "<tr><th>Group</th><th class=\"num\">Wallets</th><th class=\"num\">SOL</th><th class=\"num\">{} Value</th></tr>",
+            currency
+        )?;
+        for (name, group) in &report.groups {
+            writeln!(
+                out,
+                "<tr><td>{}</td><td class=\"num\">{}</td><td class=\"num\">{}</td><td class=\"num\">{}</td></tr>",
+                escape(name),
+                group.wallets.len(),
+                numbers.amount(SOL_ASSET, Some(SOL_DECIMALS), group.sol_balance),
+                money(numbers, &currency, group.total_usd)
+            )?;
+        }
+        writeln!(out, "</table>")?;
+    }
+
+    if !report.errors.is_empty() {
+        writeln!(out, "<h2 class=\"errors\">Errors</h2>")?;
+        writeln!(out, "<ul class=\"errors\">")?;
+        for (wallet, error) in &report.errors {
+            writeln!(out, "<li>{}: {}</li>", escape(wallet), escape(error))?;
+        }
+        writeln!(out, "</ul>")?;
+    }
+
+    writeln!(out, "</body>")?;
+    writeln!(out, "</html>")?;
+    Ok(())
+}
+
+/// One row per wallet with its SOL and currency totals, and the grand
+/// total.
+fn write_totals(
+    report: &BalanceReport,
+    currency: &Currency,
+    options: &RenderOptions,
+    out: &mut dyn Write,
+) -> Result<(), anyhow::Error> {
+    let numbers = &options.numbers;
+    writeln!(out, "<h2>Totals</h2>")?;
+    writeln!(out, "<table>")?;
+    writeln!(
+        out,
+        "<tr><th>Wallet</th><th class=\"num\">SOL</th><th class=\"num\">Tokens</th><th class=\"num\">{} Value</th></tr>",
+        currency
+    )?;
+    for (wallet, result) in report.sorted_wallets(options.sort_by) {
+        writeln!(
+            out,
+            "<tr><td>{}</td><td class=\"num\">{}</td><td class=\"num\">{}</td><td class=\"num\">{}</td></tr>",
+            escape(&wallet_name(wallet, result)),
+            numbers.amount(SOL_ASSET, Some(SOL_DECIMALS), result.sol_balance),
+            result.token_balances.len(),
+            money(numbers, currency, result.total_usd)
+        )?;
+    }
+    let sol: f64 = report
+        .wallets
+        .values()
+        .map(|result| result.sol_balance)
+        .sum();
+    writeln!(
+        out,
+        "<tr class=\"total\"><td>Total</td><td class=\"num\">{}</td><td></td><td class=\"num\">{}</td></tr>",
+        numbers.amount(SOL_ASSET, Some(SOL_DECIMALS), sol),
+        money(numbers, currency, report.total_usd)
+    )?;
+    writeln!(out, "</table>")?;
+    Ok(())
+}
+
+fn write_wallet(
+    wallet: &str,
+    result: &BalanceResult,
+    currency: &Currency,
+    options: &RenderOptions,
+    out: &mut dyn Write,
+) -> Result<(), anyhow::Error> {
+    let numbers = &options.numbers;
+    let links = options
+        .links
+        .unwrap_or_default()
+        .on(result.cluster.as_deref());
+    write!(out, "<h2>{}", escape(&wallet_name(wallet, result)))?;
+    writeln!(
+        out,
+        " <a class=\"sub\" href=\"{}\">{}</a></h2>",
+        escape(&links.account(wallet)),
+        escape(wallet)
+    )?;
+    writeln!(out, "<table>")?;
+    writeln!(
+        out,
+        "<tr><th>Asset</th><th class=\"num\">Balance</th><th class=\"num\">{} Value</th></tr>",
+        currency
+    )?;
+    writeln!(
+        out,
+        "<tr><td>SOL</td><td class=\"num\">{}</td><td class=\"num\">{}</td></tr>",
+        numbers.amount(SOL_ASSET, Some(SOL_DECIMALS), result.sol_balance),
+        money(numbers, currency, result.sol_usd_value)
+    )?;
+    if let Some(stake) = &result.stake {
+        writeln!(
+            out,
+            "<tr><td>staked SOL ({} accounts)</td><td class=\"num\">{}</td><td></td></tr>",
+            stake.accounts,
+            numbers.amount(SOL_ASSET, Some(SOL_DECIMALS), stake.total)
+        )?;
+    }
+    for (ticker, balance) in result.sorted_tokens(options.sort_by) {
+        writeln!(
+            out,
+            "<tr><td><a href=\"{}\">{}</a></td><td class=\"num\">{}</td><td class=\"num\">{}</td></tr>",
+            escape(&links.token(&balance.mint)),
+            escape(ticker),
+            numbers.amount(ticker, Some(balance.decimals), balance.amount),
+            money(numbers, currency, balance.usd_value)
+        )?;
+    }
+    if let Some(total) = result.total_usd {
+        writeln!(
+            out,
+            "<tr class=\"total\"><td>Total</td><td></td><td class=\"num\">{}</td></tr>",
+            escape(&numbers.money(currency, total))
+        )?;
+    }
+    writeln!(out, "</table>")?;
+    Ok(())
+}
+
+/// An inline SVG line chart of `points`, labelled with the first and last
+/// dates and the lowest and highest values.
+fn write_chart(points: &[(DateTime<Utc>, f64)], out: &mut dyn Write) -> Result<(), anyhow::Error> {
+    let (first, last) = (points[0].0, points[points.len() - 1].0);
+    let span = (last - first).num_seconds().max(1) as f64;
+    let low = points
+        .iter()
+        .map(|(_, value)| *value)
+        .fold(f64::INFINITY, f64::min);
+    let high = points
+        .iter()
+        .map(|(_, value)| *value)
+        .fold(f64::NEG_INFINITY, f64::max);
+    let range = if high > low { high - low } else { 1.0 };
+
+    let coordinates: Vec<String> = points
+        .iter()
+        .map(|(time, value)| {
+            let x = CHART_MARGIN
+                + (*time - first).num_seconds() as f64 / span * (CHART_WIDTH - 2.0 * CHART_MARGIN);
+            let y = CHART_HEIGHT
+                - CHART_MARGIN
+                - (value - low) / range * (CHART_HEIGHT - 2.0 * CHART_MARGIN);
+            format!("{:.1},{:.1}", x, y)
+        })
+        .collect();
+
+    writeln!(
+        out,
+        "<svg viewBox=\"0 0 {} {}\" role=\"img\" aria-label=\"SOL held over time\">",
+        CHART_WIDTH, CHART_HEIGHT
+    )?;
+    writeln!(
+        out,
+        "<polyline fill=\"none\" stroke=\"#0969da\" stroke-width=\"2\" points=\"{}\"/>",
+        coordinates.join(" ")
+    )?;
+    let label = |x: f64, y: f64, anchor: &str, text: String| {
+        format!(
+            "<text x=\"{:.1}\" y=\"{:.1}\" text-anchor=\"{}\" font-size=\"12\" fill=\"#656d76\">{}</text>",
+            x, y, anchor, text
+        )
+    };
+    let bottom = CHART_HEIGHT - 8.0;
+    writeln!(
+        out,
+        "{}",
+        label(
+            CHART_MARGIN,
+            bottom,
+            "start",
+            first.format("%Y-%m-%d").to_string()
+        )
+    )?;
+    writeln!(
+        out,
+        "{}",
+        label(
+            CHART_WIDTH - CHART_MARGIN,
+            bottom,
+            "end",
+            last.format("%Y-%m-%d").to_string()
+        )
+    )?;
+    writeln!(
+        out,
+        "{}",
+        label(
+            CHART_MARGIN,
+            16.0,
+            "start",
+            format!("{} SOL", format_number(high, 2))
+        )
+    )?;
+    writeln!(
+        out,
+        "{}",
+        label(
+            CHART_MARGIN,
+            CHART_HEIGHT - CHART_MARGIN - 4.0,
+            "start",
+            format!("{} SOL", format_number(low, 2))
+        )
+    )?;
+    writeln!(out, "</svg>")?;
+    Ok(())
+}
+
+/// The wallet's label or domain, or its shortened address, followed by its
+/// cluster if it is not on the main one.
+fn wallet_name(wallet: &str, result: &BalanceResult) -> String {
+    let name = result
+        .label
+        .clone()
+        .or_else(|| result.domain.clone())
+        .unwrap_or_else(|| short_address(wallet));
+    match &result.cluster {
+        Some(cluster) => format!("{} ({})", name, cluster),
+        None => name,
+    }
+}
+
+fn money(numbers: &NumberFormat, currency: &Currency, value: Option<f64>) -> String {
+    value
+        .map(|value| numbers.money(currency, value))
+        .unwrap_or_default()
+}
+
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
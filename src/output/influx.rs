@@ -0,0 +1,118 @@
+use crate::diff::{SOL_ASSET, STAKED_SOL_ASSET};
+use crate::report::{BalanceReport, BalanceResult};
+use std::io::Write;
+
+/// Writes one InfluxDB line protocol point per balance, all stamped with
+/// the report's timestamp in nanoseconds: `solana_balance` per wallet and
+/// asset, `solana_wallet` with each wallet's USD total, and
+/// `solana_group` per group and asset.
+pub fn write_influx(report: &BalanceReport, out: &mut dyn Write) -> Result<(), anyhow::Error> {
+    let timestamp = report
+        .timestamp
+        .timestamp_nanos_opt()
+        .ok_or_else(|| anyhow::anyhow!("report timestamp out of range"))?;
+
+    let mut wallets: Vec<_> = report.wallets.iter().collect();
+    wallets.sort_by(|a, b| a.0.cmp(b.0));
+    for (wallet, result) in wallets {
+        let tags = wallet_tags(wallet, result);
+        writeln!(
+            out,
+            "solana_balance,{},asset={} amount={}{} {}",
+            tags,
+            escape_tag(SOL_ASSET),
+            result.sol_balance,
+            usd_field(result.sol_usd_value),
+            timestamp
+        )?;
+        if let Some(stake) = &result.stake {
+            writeln!(
+                out,
+                "solana_balance,{},asset={} amount={} {}",
+                tags,
+                escape_tag(STAKED_SOL_ASSET),
+                stake.total,
+                timestamp
+            )?;
+        }
+        let mut tokens: Vec<_> = result.token_balances.iter().collect();
+        tokens.sort_by(|a, b| a.0.cmp(b.0));
+        for (ticker, balance) in tokens {
+            writeln!(
+                out,
+                "solana_balance,{},asset={},mint={} amount={}{} {}",
+                tags,
+                escape_tag(ticker),
+                escape_tag(&balance.mint),
+                balance.amount,
+                usd_field(balance.usd_value),
+                timestamp
+            )?;
+        }
+        if let Some(total) = result.total_usd {
+            writeln!(
+                out,
+                "solana_wallet,{} usd_value={} {}",
+                tags, total, timestamp
+            )?;
+        }
+    }
+
+    for (name, group) in &report.groups {
+        let tags = format!("group={}", escape_tag(name));
+        writeln!(
+            out,
+            "solana_group,{},asset={} amount={} {}",
+            tags,
+            escape_tag(SOL_ASSET),
+            group.sol_balance,
+            timestamp
+        )?;
+        for (ticker, balance) in &group.token_balances {
+            writeln!(
+                out,
+                "solana_group,{},asset={},mint={} amount={}{} {}",
+                tags,
+                escape_tag(ticker),
+                escape_tag(&balance.mint),
+                balance.amount,
+                usd_field(balance.usd_value),
+                timestamp
+            )?;
+        }
+    }
+    Ok(())
+}
+
+/// The `wallet` tag, plus `label` when the wallet has a nickname.
+fn wallet_tags(wallet: &str, result: &BalanceResult) -> String {
+    match &result.label {
+        Some(label) if !label.is_empty() => {
+            format!("wallet={},label={}", escape_tag(wallet), escape_tag(label))
+        }
+        _ => format!("wallet={}", escape_tag(wallet)),
+    }
+}
+
+fn usd_field(usd_value: Option<f64>) -> String {
+    usd_value
+        .map(|value| format!(",usd_value={}", value))
+        .unwrap_or_default()
+}
+
+/// Escapes the characters line protocol gives meaning to in tag keys and
+/// values.
+fn escape_tag(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            ',' | '=' | ' ' | '\\' => {
+                escaped.push('\\');
+                escaped.push(c);
+            }
+            '\n' => escaped.push(' '),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
@@ -0,0 +1,139 @@
+use crate::explorer::ExplorerLinks;
+use crate::output::RenderOptions;
+use crate::report::BalanceReport;
+use serde_json::{Map, Value};
+use std::io::Write;
+
+/// Writes the report as JSON. With `options.links` each wallet, token
+/// balance and token account gets an `explorer_url`; with `options.raw`
+/// balances are exact base units, as strings, in place of floats.
+pub fn write_json(
+    report: &BalanceReport,
+    options: &RenderOptions,
+    out: &mut dyn Write,
+) -> Result<(), anyhow::Error> {
+    if options.links.is_none() && !options.raw {
+        return write_value(report, options.compact, out);
+    }
+
+    let mut value = serde_json::to_value(report)?;
+    if let Some(links) = &options.links {
+        add_links(&mut value, links);
+    }
+    if options.raw {
+        raw_amounts(&mut value);
+    }
+    write_value(&value, options.compact, out)
+}
+
+fn write_value(
+    value: &impl serde::Serialize,
+    compact: bool,
+    out: &mut dyn Write,
+) -> Result<(), anyhow::Error> {
+    if compact {
+        serde_json::to_writer(&mut *out, value)?;
+    } else {
+        serde_json::to_writer_pretty(&mut *out, value)?;
+    }
+    writeln!(out)?;
+
+    Ok(())
+}
+
+fn add_links(value: &mut Value, links: &ExplorerLinks) {
+    let wallets = value
+        .get_mut("wallets")
+        .and_then(Value::as_object_mut)
+        .into_iter()
+        .flatten();
+    for (address, wallet) in wallets {
+        let Some(wallet) = wallet.as_object_mut() else {
+            continue;
+        };
+        let cluster = wallet.get("cluster").and_then(Value::as_str);
+        let links = links.on(cluster);
+        wallet.insert("explorer_url".into(), links.account(address).into());
+        let balances = wallet
+            .get_mut("token_balances")
+            .and_then(Value::as_object_mut)
+            .into_iter()
+            .flat_map(|balances| balances.values_mut())
+            .filter_map(Value::as_object_mut);
+        for balance in balances {
+            if let Some(mint) = balance.get("mint").and_then(Value::as_str) {
+                let url = links.token(mint);
+                balance.insert("explorer_url".into(), url.into());
+            }
+            let accounts = balance
+                .get_mut("accounts")
+                .and_then(Value::as_array_mut)
+                .into_iter()
+                .flatten()
+                .filter_map(Value::as_object_mut);
+            for account in accounts {
+                if let Some(address) = account.get("address").and_then(Value::as_str) {
+                    let url = links.account(address);
+                    account.insert("explorer_url".into(), url.into());
+                }
+            }
+        }
+    }
+}
+
+/// Replaces the balances of wallets and groups with their exact amounts in
+/// lamports or base units: `sol_lamports` and the `raw_amount` fields
+/// become strings, so no JSON parser rounds them, and the floats they
+/// duplicate are dropped.
+fn raw_amounts(value: &mut Value) {
+    for key in ["wallets", "groups"] {
+        let holders = value
+            .get_mut(key)
+            .and_then(Value::as_object_mut)
+            .into_iter()
+            .flat_map(|holders| holders.values_mut())
+            .filter_map(Value::as_object_mut);
+        for holder in holders {
+            raw_holder_amounts(holder);
+        }
+    }
+}
+
+fn raw_holder_amounts(holder: &mut Map<String, Value>) {
+    holder.remove("sol_balance");
+    stringify(holder, "sol_lamports");
+    let balances = holder
+        .get_mut("token_balances")
+        .and_then(Value::as_object_mut)
+        .into_iter()
+        .flat_map(|balances| balances.values_mut())
+        .filter_map(Value::as_object_mut);
+    for balance in balances {
+        balance.remove("amount");
+        balance.remove("frozen_amount");
+        stringify(balance, "raw_amount");
+        stringify(balance, "frozen_raw_amount");
+        for key in ["delegations", "accounts"] {
+            let entries = balance
+                .get_mut(key)
+                .and_then(Value::as_array_mut)
+                .into_iter()
+                .flatten()
+                .filter_map(Value::as_object_mut);
+            for entry in entries {
+                entry.remove("amount");
+                stringify(entry, "raw_amount");
+                stringify(entry, "lamports");
+            }
+        }
+    }
+}
+
+/// Turns the integer at `key`, if any, into a string.
+fn stringify(object: &mut Map<String, Value>, key: &str) {
+    if let Some(value) = object.get_mut(key) {
+        if value.is_u64() {
+            *value = Value::String(value.to_string());
+        }
+    }
+}
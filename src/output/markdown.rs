@@ -0,0 +1,159 @@
+use crate::metadata::short_address;
+use crate::output::table::format_number;
+use crate::output::RenderOptions;
+use crate::pricing::Currency;
+use crate::report::{BalanceReport, BalanceResult};
+use chrono::SecondsFormat;
+use std::io::Write;
+
+/// Renders a GitHub-flavored Markdown report: a totals table, then one
+/// table per wallet and one for the groups.
+pub fn write_markdown(
+    report: &BalanceReport,
+    options: &RenderOptions,
+    out: &mut dyn Write,
+) -> Result<(), anyhow::Error> {
+    writeln!(out, "# Solana balances")?;
+    writeln!(out)?;
+    write!(
+        out,
+        "Fetched {}",
+        report.timestamp.to_rfc3339_opts(SecondsFormat::Secs, true)
+    )?;
+    if let Some(slot) = report.slot {
+        if report.historical {
+            write!(out, ", as of slot {}", slot)?;
+        } else {
+            write!(out, " at slot {}", slot)?;
+        }
+    }
+    writeln!(out)?;
+
+    let currency = report.display_currency();
+    writeln!(out)?;
+    writeln!(out, "## Totals")?;
+    writeln!(out)?;
+    writeln!(out, "| Wallet | SOL | Tokens | {} Value |", currency)?;
+    writeln!(out, "| --- | ---: | ---: | ---: |")?;
+    for (wallet, result) in report.sorted_wallets(options.sort_by) {
+        writeln!(
+            out,
+            "| {} | {} | {} | {} |",
+            escape(&wallet_name(wallet, result)),
+            format_number(result.sol_balance, 4),
+            result.token_balances.len(),
+            money(&currency, result.total_usd)
+        )?;
+    }
+    let sol: f64 = report
+        .wallets
+        .values()
+        .map(|result| result.sol_balance)
+        .sum();
+    let total_usd = report
+        .total_usd
+        .map(|total| format!("**{}**", currency.format(total)))
+        .unwrap_or_default();
+    writeln!(
+        out,
+        "| **Total** | **{}** | | {} |",
+        format_number(sol, 4),
+        total_usd
+    )?;
+
+    for (wallet, result) in report.sorted_wallets(options.sort_by) {
+        writeln!(out)?;
+        writeln!(out, "## {}", escape(&wallet_name(wallet, result)))?;
+        writeln!(out)?;
+        writeln!(out, "`{}`", wallet)?;
+        writeln!(out)?;
+        writeln!(out, "| Asset | Balance | {} Value |", currency)?;
+        writeln!(out, "| --- | ---: | ---: |")?;
+        writeln!(
+            out,
+            "| SOL | {} | {} |",
+            format_number(result.sol_balance, 4),
+            money(&currency, result.sol_usd_value)
+        )?;
+        if let Some(stake) = &result.stake {
+            writeln!(
+                out,
+                "| staked SOL ({} accounts) | {} | |",
+                stake.accounts,
+                format_number(stake.total, 4)
+            )?;
+        }
+        for (ticker, balance) in result.sorted_tokens(options.sort_by) {
+            writeln!(
+                out,
+                "| {} | {} | {} |",
+                escape(ticker),
+                format_number(balance.amount, 4),
+                money(&currency, balance.usd_value)
+            )?;
+        }
+        if let Some(total) = result.total_usd {
+            writeln!(out, "| **Total** | | **{}** |", currency.format(total))?;
+        }
+    }
+
+    if !report.groups.is_empty() {
+        writeln!(out)?;
+        writeln!(out, "## Groups")?;
+        writeln!(out)?;
+        writeln!(out, "| Group | Wallets | SOL | {} Value |", currency)?;
+        writeln!(out, "| --- | ---: | ---: | ---: |")?;
+        for (name, group) in &report.groups {
+            writeln!(
+                out,
+                "| {} | {} | {} | {} |",
+                escape(name),
+                group.wallets.len(),
+                format_number(group.sol_balance, 4),
+                money(&currency, group.total_usd)
+            )?;
+        }
+    }
+
+    if !report.errors.is_empty() {
+        writeln!(out)?;
+        writeln!(out, "## Errors")?;
+        writeln!(out)?;
+        for (wallet, error) in &report.errors {
+            writeln!(out, "- `{}`: {}", wallet, escape(error))?;
+        }
+    }
+    Ok(())
+}
+
+/// The wallet's label or domain, or its shortened address, followed by its
+/// cluster if it is not on the main one.
+fn wallet_name(wallet: &str, result: &BalanceResult) -> String {
+    let name = result
+        .label
+        .clone()
+        .or_else(|| result.domain.clone())
+        .unwrap_or_else(|| short_address(wallet));
+    match &result.cluster {
+        Some(cluster) => format!("{} ({})", name, cluster),
+        None => name,
+    }
+}
+
+fn money(currency: &Currency, value: Option<f64>) -> String {
+    value
+        .map(|value| currency.format(value))
+        .unwrap_or_default()
+}
+
+/// Escapes characters that would end a table cell or start formatting.
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        if matches!(c, '|' | '\\' | '*' | '_' | '`' | '[' | ']' | '<' | '>') {
+            escaped.push('\\');
+        }
+        escaped.push(if c == '\n' { ' ' } else { c });
+    }
+    escaped
+}
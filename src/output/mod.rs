@@ -0,0 +1,195 @@
+mod changes;
+mod cleanup;
+mod csv;
+mod diagnosis;
+#[cfg(feature = "history")]
+mod history;
+mod html;
+mod influx;
+mod json;
+mod markdown;
+mod number;
+mod parquet;
+mod prometheus;
+mod reconcile;
+mod rewards;
+mod table;
+mod template;
+mod text;
+mod transactions;
+mod xlsx;
+
+use crate::explorer::ExplorerLinks;
+use crate::report::BalanceReport;
+use std::fmt;
+use std::io::Write;
+use std::path::PathBuf;
+use std::str::FromStr;
+
+pub use self::csv::write_csv;
+pub use changes::write_changes;
+pub use cleanup::write_cleanup;
+pub use diagnosis::write_diagnoses;
+#[cfg(feature = "history")]
+pub use history::write_history;
+pub use html::{write_html, write_html_with_history};
+pub use influx::write_influx;
+pub use json::write_json;
+pub use markdown::write_markdown;
+pub use number::{Decimals, NumberFormat, NumberLocale};
+pub use parquet::write_parquet;
+pub use prometheus::write_prometheus;
+pub use reconcile::write_reconciliation;
+pub use rewards::write_rewards;
+pub use table::format_number;
+pub use template::write_template;
+pub use text::{write_text, write_text_with_changes};
+pub use transactions::write_transactions;
+pub use xlsx::write_xlsx;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OutputFormat {
+    #[default]
+    Text,
+    Json,
+    Csv,
+    Prometheus,
+    /// A standalone page, for sharing or emailing.
+    Html,
+    /// GitHub-flavored Markdown tables.
+    Markdown,
+    /// InfluxDB line protocol.
+    Influx,
+    /// A columnar file with a row per wallet and asset, for analytics.
+    Parquet,
+    /// An Excel workbook with a summary sheet and a sheet per group.
+    Xlsx,
+}
+
+impl FromStr for OutputFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "text" => Ok(OutputFormat::Text),
+            "json" => Ok(OutputFormat::Json),
+            "csv" => Ok(OutputFormat::Csv),
+            "prometheus" => Ok(OutputFormat::Prometheus),
+            "html" => Ok(OutputFormat::Html),
+            "markdown" | "md" => Ok(OutputFormat::Markdown),
+            "influx" => Ok(OutputFormat::Influx),
+            "parquet" => Ok(OutputFormat::Parquet),
+            "xlsx" => Ok(OutputFormat::Xlsx),
+            other => anyhow::bail!(
+                "unknown output format `{}` (expected text, json, csv, prometheus, html, markdown, influx, parquet or xlsx)",
+                other
+            ),
+        }
+    }
+}
+
+impl fmt::Display for OutputFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            OutputFormat::Text => "text",
+            OutputFormat::Json => "json",
+            OutputFormat::Csv => "csv",
+            OutputFormat::Prometheus => "prometheus",
+            OutputFormat::Html => "html",
+            OutputFormat::Markdown => "markdown",
+            OutputFormat::Influx => "influx",
+            OutputFormat::Parquet => "parquet",
+            OutputFormat::Xlsx => "xlsx",
+        })
+    }
+}
+
+/// The order wallets are listed in by text and CSV output.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SortOrder {
+    /// As listed in the config; wallets of loaded snapshots by address.
+    #[default]
+    Config,
+    Address,
+    /// By label, then `.sol` domain, then address.
+    Label,
+    /// Largest USD total first.
+    Usd,
+}
+
+impl FromStr for SortOrder {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "config" => Ok(SortOrder::Config),
+            "address" => Ok(SortOrder::Address),
+            "label" => Ok(SortOrder::Label),
+            "usd" => Ok(SortOrder::Usd),
+            other => anyhow::bail!(
+                "unknown sort order `{}` (expected config, address, label or usd)",
+                other
+            ),
+        }
+    }
+}
+
+impl fmt::Display for SortOrder {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            SortOrder::Config => "config",
+            SortOrder::Address => "address",
+            SortOrder::Label => "label",
+            SortOrder::Usd => "usd",
+        })
+    }
+}
+
+/// Options controlling how balances are rendered.
+#[derive(Debug, Clone, Default)]
+pub struct RenderOptions {
+    pub format: OutputFormat,
+    /// Emit single-line JSON instead of pretty-printed output.
+    pub compact: bool,
+    /// Use ANSI colors in text output.
+    pub color: bool,
+    pub sort_by: SortOrder,
+    /// Render through this Tera template instead of `format`.
+    pub template: Option<PathBuf>,
+    /// Link wallets, mints and token accounts to an explorer: as terminal
+    /// hyperlinks in colored text and as `explorer_url` fields in JSON.
+    pub links: Option<ExplorerLinks>,
+    /// Locale and decimals of amounts and values in text, CSV and HTML.
+    pub numbers: NumberFormat,
+    /// Show balances as exact lamports and token base units in text, CSV
+    /// and JSON output.
+    pub raw: bool,
+}
+
+impl RenderOptions {
+    /// Whether reports come out as the built-in text table.
+    pub fn is_text(&self) -> bool {
+        self.format == OutputFormat::Text && self.template.is_none()
+    }
+}
+
+pub fn render(
+    report: &BalanceReport,
+    options: &RenderOptions,
+    out: &mut dyn Write,
+) -> Result<(), anyhow::Error> {
+    if let Some(template) = &options.template {
+        return write_template(report, template, options.sort_by, out);
+    }
+    match options.format {
+        OutputFormat::Text => write_text(report, options, out),
+        OutputFormat::Json => write_json(report, options, out),
+        OutputFormat::Csv => write_csv(report, options, out),
+        OutputFormat::Prometheus => write_prometheus(report, out),
+        OutputFormat::Html => write_html(report, options, out),
+        OutputFormat::Markdown => write_markdown(report, options, out),
+        OutputFormat::Influx => write_influx(report, out),
+        OutputFormat::Parquet => write_parquet(report, out),
+        OutputFormat::Xlsx => write_xlsx(report, options, out),
+    }
+}
@@ -0,0 +1,229 @@
+use crate::pricing::Currency;
+use serde::{Deserialize, Deserializer};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::str::FromStr;
+
+/// Balances get this many decimals unless configured otherwise.
+pub const DEFAULT_DECIMALS: usize = 4;
+
+/// How digits are grouped and the decimal point written.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum NumberLocale {
+    /// `1,234.56`
+    #[default]
+    En,
+    /// `1.234,56`
+    De,
+    /// `1 234,56`, with a narrow no-break space
+    Fr,
+    /// `1'234.56`
+    Ch,
+    /// `1234.56`, without grouping
+    Plain,
+}
+
+impl NumberLocale {
+    fn separators(self) -> (&'static str, char) {
+        match self {
+            NumberLocale::En => (",", '.'),
+            NumberLocale::De => (".", ','),
+            NumberLocale::Fr => ("\u{202f}", ','),
+            NumberLocale::Ch => ("'", '.'),
+            NumberLocale::Plain => ("", '.'),
+        }
+    }
+
+    /// Formats `value` with `decimals` digits and this locale's separators.
+    pub fn format(self, value: f64, decimals: usize) -> String {
+        let (thousands, point) = self.separators();
+        let formatted = format!("{:.*}", decimals, value.abs());
+        let (whole, fraction) = match formatted.split_once('.') {
+            Some((whole, fraction)) => (whole, Some(fraction)),
+            None => (formatted.as_str(), None),
+        };
+        let mut grouped = String::new();
+        for (index, digit) in whole.chars().enumerate() {
+            if index > 0 && (whole.len() - index) % 3 == 0 {
+                grouped.push_str(thousands);
+            }
+            grouped.push(digit);
+        }
+        if let Some(fraction) = fraction {
+            grouped.push(point);
+            grouped.push_str(fraction);
+        }
+        if value < 0.0
+            && formatted
+                .bytes()
+                .any(|digit| (b'1'..=b'9').contains(&digit))
+        {
+            grouped.insert(0, '-');
+        }
+        grouped
+    }
+}
+
+impl FromStr for NumberLocale {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "en" => Ok(NumberLocale::En),
+            "de" => Ok(NumberLocale::De),
+            "fr" => Ok(NumberLocale::Fr),
+            "ch" => Ok(NumberLocale::Ch),
+            "plain" => Ok(NumberLocale::Plain),
+            other => anyhow::bail!(
+                "unknown locale `{}` (expected en, de, fr, ch or plain)",
+                other
+            ),
+        }
+    }
+}
+
+impl fmt::Display for NumberLocale {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            NumberLocale::En => "en",
+            NumberLocale::De => "de",
+            NumberLocale::Fr => "fr",
+            NumberLocale::Ch => "ch",
+            NumberLocale::Plain => "plain",
+        })
+    }
+}
+
+/// How many decimals balances are shown with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Decimals {
+    /// As many as the mint has, e.g. 9 for SOL and 6 for USDC.
+    Auto,
+    Fixed(usize),
+}
+
+impl FromStr for Decimals {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.eq_ignore_ascii_case("auto") {
+            return Ok(Decimals::Auto);
+        }
+        s.parse()
+            .map(Decimals::Fixed)
+            .map_err(|_| anyhow::anyhow!("invalid decimals `{}` (expected a number or auto)", s))
+    }
+}
+
+impl<'de> Deserialize<'de> for Decimals {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum Repr {
+            Fixed(usize),
+            Name(String),
+        }
+        match Repr::deserialize(deserializer)? {
+            Repr::Fixed(decimals) => Ok(Decimals::Fixed(decimals)),
+            Repr::Name(name) => name.parse().map_err(serde::de::Error::custom),
+        }
+    }
+}
+
+/// How amounts and values are written in text, CSV and HTML output. CSV
+/// keeps exact, unformatted amounts unless something here is set.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+pub struct NumberFormat {
+    /// [default: en]
+    #[serde(default)]
+    pub locale: Option<NumberLocale>,
+    /// Decimals of balances [default: 4]
+    #[serde(default)]
+    pub decimals: Option<Decimals>,
+    /// Decimals of particular assets by ticker, e.g. `USDC: 2`.
+    #[serde(default)]
+    pub assets: BTreeMap<String, usize>,
+}
+
+impl NumberFormat {
+    /// Whether nothing is configured, so defaults apply.
+    pub fn is_default(&self) -> bool {
+        *self == NumberFormat::default()
+    }
+
+    /// The decimals to show a balance of `asset` with; `mint_decimals` is
+    /// what its mint has, if known.
+    pub fn decimals_for(&self, asset: &str, mint_decimals: Option<u8>) -> usize {
+        self.decimals_or(asset, mint_decimals, DEFAULT_DECIMALS)
+    }
+
+    /// Like [`NumberFormat::decimals_for`], with `default` unless decimals
+    /// are configured.
+    fn decimals_or(&self, asset: &str, mint_decimals: Option<u8>, default: usize) -> usize {
+        if let Some(decimals) = self.assets.get(asset) {
+            return *decimals;
+        }
+        match self.decimals {
+            Some(Decimals::Fixed(decimals)) => decimals,
+            Some(Decimals::Auto) => mint_decimals.map_or(default, usize::from),
+            None => default,
+        }
+    }
+
+    /// A balance of `asset`.
+    pub fn amount(&self, asset: &str, mint_decimals: Option<u8>, value: f64) -> String {
+        self.number(value, self.decimals_for(asset, mint_decimals))
+    }
+
+    /// A balance of `asset` shown with `default` decimals unless decimals
+    /// are configured, for amounts as small as rewards and rent.
+    pub fn amount_or(
+        &self,
+        asset: &str,
+        mint_decimals: Option<u8>,
+        default: usize,
+        value: f64,
+    ) -> String {
+        self.number(value, self.decimals_or(asset, mint_decimals, default))
+    }
+
+    /// A change in a balance of `asset`, with its sign.
+    pub fn delta(&self, asset: &str, mint_decimals: Option<u8>, value: f64) -> String {
+        let sign = if value > 0.0 { "+" } else { "" };
+        format!("{}{}", sign, self.amount(asset, mint_decimals, value))
+    }
+
+    pub fn number(&self, value: f64, decimals: usize) -> String {
+        self.locale.unwrap_or_default().format(value, decimals)
+    }
+
+    /// `value` in `currency`, with the currency's usual decimals.
+    pub fn money(&self, currency: &Currency, value: f64) -> String {
+        currency.with_symbol(&self.number(value, currency.decimals()))
+    }
+
+    /// A balance for CSV: `exact` as it is unless something is configured.
+    pub fn csv_amount(
+        &self,
+        asset: &str,
+        mint_decimals: Option<u8>,
+        value: f64,
+        exact: &str,
+    ) -> String {
+        if self.is_default() {
+            exact.to_string()
+        } else {
+            self.amount(asset, mint_decimals, value)
+        }
+    }
+
+    /// A value for CSV: unformatted unless something is configured.
+    pub fn csv_value(&self, value: f64) -> String {
+        if self.is_default() {
+            value.to_string()
+        } else {
+            self.number(value, 2)
+        }
+    }
+}
@@ -0,0 +1,125 @@
+use crate::diff::{SOL_ASSET, STAKED_SOL_ASSET};
+use crate::report::BalanceReport;
+use crate::token_account::SOL_DECIMALS;
+use arrow_array::{
+    ArrayRef, Float64Array, RecordBatch, StringArray, TimestampNanosecondArray, UInt64Array,
+    UInt8Array,
+};
+use arrow_schema::{DataType, Field, Schema, TimeUnit};
+use parquet::arrow::ArrowWriter;
+use parquet::basic::Compression;
+use parquet::file::properties::WriterProperties;
+use solana_sdk::native_token::sol_to_lamports;
+use std::io::Write;
+use std::sync::Arc;
+
+/// Columns of the rows built for a report, one row per wallet and asset.
+#[derive(Default)]
+struct Columns {
+    wallet: Vec<String>,
+    label: Vec<Option<String>>,
+    asset: Vec<String>,
+    mint: Vec<Option<String>>,
+    raw_amount: Vec<u64>,
+    decimals: Vec<u8>,
+    amount: Vec<f64>,
+    usd: Vec<Option<f64>>,
+}
+
+/// Writes a Parquet file with a row per wallet and asset: wallet, label,
+/// asset, mint, raw_amount, decimals, amount, usd, slot and ts. SOL and
+/// staked SOL rows have no mint.
+pub fn write_parquet(report: &BalanceReport, out: &mut dyn Write) -> Result<(), anyhow::Error> {
+    let mut columns = Columns::default();
+    let mut wallets: Vec<_> = report.wallets.iter().collect();
+    wallets.sort_by(|a, b| a.0.cmp(b.0));
+    for (wallet, result) in wallets {
+        let mut push =
+            |asset: &str, mint: Option<&str>, raw: u64, decimals: u8, amount: f64, usd| {
+                columns.wallet.push(wallet.clone());
+                columns.label.push(result.label.clone());
+                columns.asset.push(asset.to_string());
+                columns.mint.push(mint.map(str::to_string));
+                columns.raw_amount.push(raw);
+                columns.decimals.push(decimals);
+                columns.amount.push(amount);
+                columns.usd.push(usd);
+            };
+        push(
+            SOL_ASSET,
+            None,
+            result.sol_lamports,
+            SOL_DECIMALS,
+            result.sol_balance,
+            result.sol_usd_value,
+        );
+        if let Some(stake) = &result.stake {
+            push(
+                STAKED_SOL_ASSET,
+                None,
+                sol_to_lamports(stake.total),
+                SOL_DECIMALS,
+                stake.total,
+                None,
+            );
+        }
+        let mut tokens: Vec<_> = result.token_balances.iter().collect();
+        tokens.sort_by(|a, b| a.0.cmp(b.0));
+        for (ticker, balance) in tokens {
+            push(
+                ticker,
+                Some(&balance.mint),
+                balance.raw_amount,
+                balance.decimals,
+                balance.amount,
+                balance.usd_value,
+            );
+        }
+    }
+
+    let rows = columns.wallet.len();
+    let timestamp = report
+        .timestamp
+        .timestamp_nanos_opt()
+        .ok_or_else(|| anyhow::anyhow!("report timestamp out of range"))?;
+    let schema = Arc::new(Schema::new(vec![
+        Field::new("wallet", DataType::Utf8, false),
+        Field::new("label", DataType::Utf8, true),
+        Field::new("asset", DataType::Utf8, false),
+        Field::new("mint", DataType::Utf8, true),
+        Field::new("raw_amount", DataType::UInt64, false),
+        Field::new("decimals", DataType::UInt8, false),
+        Field::new("amount", DataType::Float64, false),
+        Field::new("usd", DataType::Float64, true),
+        Field::new("slot", DataType::UInt64, true),
+        Field::new(
+            "ts",
+            DataType::Timestamp(TimeUnit::Nanosecond, Some("UTC".into())),
+            false,
+        ),
+    ]));
+    let arrays: Vec<ArrayRef> = vec![
+        Arc::new(StringArray::from(columns.wallet)),
+        Arc::new(StringArray::from(columns.label)),
+        Arc::new(StringArray::from(columns.asset)),
+        Arc::new(StringArray::from(columns.mint)),
+        Arc::new(UInt64Array::from(columns.raw_amount)),
+        Arc::new(UInt8Array::from(columns.decimals)),
+        Arc::new(Float64Array::from(columns.amount)),
+        Arc::new(Float64Array::from(columns.usd)),
+        Arc::new(UInt64Array::from(vec![report.slot; rows])),
+        Arc::new(TimestampNanosecondArray::from(vec![timestamp; rows]).with_timezone("UTC")),
+    ];
+    let batch = RecordBatch::try_new(schema.clone(), arrays)?;
+
+    // The writer needs a `Send` sink, so the file is built in memory.
+    let mut buffer = Vec::new();
+    let properties = WriterProperties::builder()
+        .set_compression(Compression::SNAPPY)
+        .build();
+    let mut writer = ArrowWriter::try_new(&mut buffer, schema, Some(properties))?;
+    writer.write(&batch)?;
+    writer.close()?;
+    out.write_all(&buffer)?;
+    Ok(())
+}
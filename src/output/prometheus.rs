@@ -0,0 +1,158 @@
+use crate::report::{BalanceReport, BalanceResult};
+use std::io::Write;
+
+/// Writes balances in the Prometheus text exposition format.
+pub fn write_prometheus(report: &BalanceReport, out: &mut dyn Write) -> Result<(), anyhow::Error> {
+    let mut wallets: Vec<_> = report.wallets.iter().collect();
+    wallets.sort_by(|a, b| a.0.cmp(b.0));
+
+    writeln!(out, "# HELP solana_sol_balance SOL balance of a wallet.")?;
+    writeln!(out, "# TYPE solana_sol_balance gauge")?;
+    for (wallet, result) in &wallets {
+        writeln!(
+            out,
+            "solana_sol_balance{{{}}} {}",
+            wallet_labels(wallet, result),
+            result.sol_balance
+        )?;
+    }
+
+    writeln!(
+        out,
+        "# HELP solana_token_balance Token balance of a wallet, in UI units."
+    )?;
+    writeln!(out, "# TYPE solana_token_balance gauge")?;
+    for (wallet, result) in &wallets {
+        let mut tokens: Vec<_> = result.token_balances.iter().collect();
+        tokens.sort_by(|a, b| a.0.cmp(b.0));
+        for (ticker, balance) in tokens {
+            writeln!(
+                out,
+                "solana_token_balance{{{},ticker=\"{}\",mint=\"{}\"}} {}",
+                wallet_labels(wallet, result),
+                escape_label(ticker),
+                escape_label(&balance.mint),
+                balance.amount
+            )?;
+        }
+    }
+
+    writeln!(
+        out,
+        "# HELP solana_wallet_fetch_error 1 if the wallet could not be fetched."
+    )?;
+    writeln!(out, "# TYPE solana_wallet_fetch_error gauge")?;
+    for (wallet, result) in &wallets {
+        writeln!(
+            out,
+            "solana_wallet_fetch_error{{{}}} 0",
+            wallet_labels(wallet, result)
+        )?;
+    }
+    for wallet in report.errors.keys() {
+        writeln!(
+            out,
+            "solana_wallet_fetch_error{{wallet=\"{}\"}} 1",
+            escape_label(wallet)
+        )?;
+    }
+
+    if report.total_usd.is_some() {
+        writeln!(
+            out,
+            "# HELP solana_wallet_usd_value Total {} value of a wallet.",
+            report.display_currency()
+        )?;
+        writeln!(out, "# TYPE solana_wallet_usd_value gauge")?;
+        for (wallet, result) in &wallets {
+            if let Some(total) = result.total_usd {
+                writeln!(
+                    out,
+                    "solana_wallet_usd_value{{{}}} {}",
+                    wallet_labels(wallet, result),
+                    total
+                )?;
+            }
+        }
+    }
+
+    if !report.groups.is_empty() {
+        write_groups(report, out)?;
+    }
+
+    Ok(())
+}
+
+fn write_groups(report: &BalanceReport, out: &mut dyn Write) -> Result<(), anyhow::Error> {
+    writeln!(
+        out,
+        "# HELP solana_group_sol_balance SOL balance of a wallet group."
+    )?;
+    writeln!(out, "# TYPE solana_group_sol_balance gauge")?;
+    for (name, group) in &report.groups {
+        writeln!(
+            out,
+            "solana_group_sol_balance{{group=\"{}\"}} {}",
+            escape_label(name),
+            group.sol_balance
+        )?;
+    }
+
+    writeln!(
+        out,
+        "# HELP solana_group_token_balance Token balance of a wallet group, in UI units."
+    )?;
+    writeln!(out, "# TYPE solana_group_token_balance gauge")?;
+    for (name, group) in &report.groups {
+        for (ticker, balance) in &group.token_balances {
+            writeln!(
+                out,
+                "solana_group_token_balance{{group=\"{}\",ticker=\"{}\",mint=\"{}\"}} {}",
+                escape_label(name),
+                escape_label(ticker),
+                escape_label(&balance.mint),
+                balance.amount
+            )?;
+        }
+    }
+
+    if report.total_usd.is_some() {
+        writeln!(
+            out,
+            "# HELP solana_group_usd_value Total {} value of a wallet group.",
+            report.display_currency()
+        )?;
+        writeln!(out, "# TYPE solana_group_usd_value gauge")?;
+        for (name, group) in &report.groups {
+            if let Some(total) = group.total_usd {
+                writeln!(
+                    out,
+                    "solana_group_usd_value{{group=\"{}\"}} {}",
+                    escape_label(name),
+                    total
+                )?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// The `wallet` label, plus `label` when the wallet has a nickname.
+fn wallet_labels(wallet: &str, result: &BalanceResult) -> String {
+    match &result.label {
+        Some(label) => format!(
+            "wallet=\"{}\",label=\"{}\"",
+            escape_label(wallet),
+            escape_label(label)
+        ),
+        None => format!("wallet=\"{}\"", escape_label(wallet)),
+    }
+}
+
+fn escape_label(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
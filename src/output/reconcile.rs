@@ -0,0 +1,93 @@
+use crate::output::table::{paint, Align, Cell, Table, BOLD, CYAN, GREEN, RED};
+use crate::output::{OutputFormat, RenderOptions};
+use crate::reconcile::{ExpectedBalance, Reconciliation};
+use std::io::Write;
+
+/// Renders the balances that differ from the expected ones as a table, JSON
+/// or one CSV row per discrepancy.
+pub fn write_reconciliation(
+    reconciliation: &Reconciliation,
+    options: &RenderOptions,
+    out: &mut dyn Write,
+) -> Result<(), anyhow::Error> {
+    match options.format {
+        OutputFormat::Json => {
+            if options.compact {
+                serde_json::to_writer(&mut *out, reconciliation)?;
+            } else {
+                serde_json::to_writer_pretty(&mut *out, reconciliation)?;
+            }
+            writeln!(out)?;
+        }
+        OutputFormat::Csv => {
+            let mut writer = csv::Writer::from_writer(out);
+            writer.write_record(["wallet", "label", "asset", "balance", "min", "max"])?;
+            for discrepancy in &reconciliation.discrepancies {
+                let (min, max) = match discrepancy.expected {
+                    ExpectedBalance::Exact(amount) => (Some(amount), Some(amount)),
+                    ExpectedBalance::Range { min, max } => (min, max),
+                };
+                let number = |value: Option<f64>| value.map(|value| value.to_string());
+                writer.write_record([
+                    discrepancy.wallet.as_str(),
+                    discrepancy.label.as_deref().unwrap_or_default(),
+                    &discrepancy.asset,
+                    &number(discrepancy.balance).unwrap_or_default(),
+                    &number(min).unwrap_or_default(),
+                    &number(max).unwrap_or_default(),
+                ])?;
+            }
+            writer.flush()?;
+        }
+        _ => write_reconciliation_text(reconciliation, options, out)?,
+    }
+    Ok(())
+}
+
+fn write_reconciliation_text(
+    reconciliation: &Reconciliation,
+    options: &RenderOptions,
+    out: &mut dyn Write,
+) -> Result<(), anyhow::Error> {
+    if reconciliation.discrepancies.is_empty() {
+        let summary = format!("All {} expected balance(s) match.", reconciliation.checked);
+        writeln!(out, "{}", paint(&summary, GREEN, options.color))?;
+        return Ok(());
+    }
+
+    let mut table = Table::new(&[
+        ("Wallet", Align::Left),
+        ("Asset", Align::Left),
+        ("Balance", Align::Right),
+        ("Expected", Align::Right),
+    ]);
+    for discrepancy in &reconciliation.discrepancies {
+        let wallet = discrepancy.label.as_ref().unwrap_or(&discrepancy.wallet);
+        let balance = match discrepancy.balance {
+            Some(balance) => Cell::styled(
+                options.numbers.amount(&discrepancy.asset, None, balance),
+                RED,
+            ),
+            None => Cell::styled("not fetched", RED),
+        };
+        table.row(vec![
+            Cell::styled(wallet.as_str(), CYAN).linked(
+                options
+                    .links
+                    .map(|links| links.account(&discrepancy.wallet)),
+            ),
+            Cell::new(&discrepancy.asset),
+            balance,
+            Cell::new(discrepancy.expected.to_string()),
+        ]);
+    }
+    table.write(options.color, out)?;
+    writeln!(out)?;
+    let summary = format!(
+        "{} of {} expected balance(s) differ.",
+        reconciliation.discrepancies.len(),
+        reconciliation.checked
+    );
+    writeln!(out, "{}", paint(&summary, BOLD, options.color))?;
+    Ok(())
+}
@@ -0,0 +1,114 @@
+use crate::output::table::{paint, Align, Cell, Table, BOLD, CYAN, DIM, RED};
+use crate::output::{OutputFormat, RenderOptions};
+use crate::rewards::RewardsReport;
+use std::io::Write;
+
+/// Renders a rewards report as a table, JSON or one CSV row per wallet and
+/// epoch.
+pub fn write_rewards(
+    report: &RewardsReport,
+    options: &RenderOptions,
+    out: &mut dyn Write,
+) -> Result<(), anyhow::Error> {
+    match options.format {
+        OutputFormat::Json => {
+            if options.compact {
+                serde_json::to_writer(&mut *out, report)?;
+            } else {
+                serde_json::to_writer_pretty(&mut *out, report)?;
+            }
+            writeln!(out)?;
+        }
+        OutputFormat::Csv => {
+            let mut writer = csv::Writer::from_writer(out);
+            writer.write_record(["wallet", "label", "epoch", "rewards_sol"])?;
+            for (wallet, rewards) in &report.wallets {
+                for (epoch, amount) in &rewards.epochs {
+                    writer.write_record([
+                        wallet,
+                        rewards.label.as_deref().unwrap_or_default(),
+                        &epoch.to_string(),
+                        &options
+                            .numbers
+                            .csv_amount("SOL", Some(9), *amount, &amount.to_string()),
+                    ])?;
+                }
+            }
+            writer.flush()?;
+        }
+        _ => write_rewards_text(report, options, out)?,
+    }
+    Ok(())
+}
+
+fn write_rewards_text(
+    report: &RewardsReport,
+    options: &RenderOptions,
+    out: &mut dyn Write,
+) -> Result<(), anyhow::Error> {
+    let sol = |value: f64| options.numbers.amount_or("SOL", Some(9), 6, value);
+    let epochs = if report.first_epoch == report.last_epoch {
+        format!("epoch {}", report.first_epoch)
+    } else {
+        format!("epochs {}-{}", report.first_epoch, report.last_epoch)
+    };
+    if report.wallets.is_empty() {
+        writeln!(out, "No stake accounts found.")?;
+    } else {
+        let mut table = Table::new(&[
+            ("Wallet", Align::Left),
+            ("Epoch", Align::Right),
+            ("Rewards (SOL)", Align::Right),
+        ]);
+        for (index, (wallet, rewards)) in report.wallets.iter().enumerate() {
+            if index > 0 {
+                table.rule();
+            }
+            let accounts = format!("{} stake account(s)", rewards.stake_accounts);
+            let names: Vec<&str> = rewards
+                .label
+                .as_deref()
+                .into_iter()
+                .chain([wallet.as_str(), accounts.as_str()])
+                .collect();
+            let mut epochs = rewards.epochs.iter();
+            for line in 0..rewards.epochs.len().max(names.len()) {
+                let name = match names.get(line) {
+                    Some(name) if line == 0 => Cell::styled(*name, CYAN),
+                    Some(name) => Cell::styled(*name, DIM),
+                    None => Cell::default(),
+                };
+                let mut cells = vec![name];
+                if let Some((epoch, amount)) = epochs.next() {
+                    cells.extend([Cell::new(epoch.to_string()), Cell::new(sol(*amount))]);
+                }
+                table.row(cells);
+            }
+            table.row(vec![
+                Cell::default(),
+                Cell::styled("Total", BOLD),
+                Cell::styled(sol(rewards.total), BOLD),
+            ]);
+        }
+        table.write(options.color, out)?;
+        writeln!(out)?;
+        writeln!(
+            out,
+            "{}",
+            paint(
+                &format!("Earned: {} SOL over {}", sol(report.total), epochs),
+                BOLD,
+                options.color
+            )
+        )?;
+    }
+
+    if !report.errors.is_empty() {
+        writeln!(out)?;
+        writeln!(out, "{}", paint("Errors:", RED, options.color))?;
+        for (wallet, error) in &report.errors {
+            writeln!(out, "  {}: {}", wallet, error)?;
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,172 @@
+use crate::output::NumberLocale;
+use std::io::Write;
+
+pub(crate) const BOLD: &str = "\x1b[1m";
+pub(crate) const DIM: &str = "\x1b[2m";
+pub(crate) const GREEN: &str = "\x1b[32m";
+pub(crate) const RED: &str = "\x1b[31m";
+pub(crate) const CYAN: &str = "\x1b[36m";
+const RESET: &str = "\x1b[0m";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Align {
+    Left,
+    Right,
+}
+
+/// A table cell with an optional ANSI style, applied only when colors are on.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Cell {
+    text: String,
+    style: Option<&'static str>,
+    /// URL the text links to in terminals that support OSC 8 hyperlinks.
+    link: Option<String>,
+}
+
+impl Cell {
+    pub fn new(text: impl Into<String>) -> Self {
+        Cell {
+            text: text.into(),
+            style: None,
+            link: None,
+        }
+    }
+
+    pub fn styled(text: impl Into<String>, style: &'static str) -> Self {
+        Cell {
+            text: text.into(),
+            style: Some(style),
+            link: None,
+        }
+    }
+
+    /// Links the cell to `url`, if any, when colors are on.
+    pub fn linked(mut self, url: Option<String>) -> Self {
+        self.link = url;
+        self
+    }
+}
+
+enum Row {
+    Cells(Vec<Cell>),
+    Rule,
+}
+
+/// Plain-text table with columns padded to their widest cell.
+pub(crate) struct Table {
+    header: Vec<String>,
+    align: Vec<Align>,
+    rows: Vec<Row>,
+}
+
+impl Table {
+    pub fn new(columns: &[(&str, Align)]) -> Self {
+        Table {
+            header: columns.iter().map(|(name, _)| name.to_string()).collect(),
+            align: columns.iter().map(|(_, align)| *align).collect(),
+            rows: Vec::new(),
+        }
+    }
+
+    pub fn row(&mut self, cells: Vec<Cell>) {
+        self.rows.push(Row::Cells(cells));
+    }
+
+    /// A horizontal line across every column.
+    pub fn rule(&mut self) {
+        self.rows.push(Row::Rule);
+    }
+
+    pub fn write(&self, color: bool, out: &mut dyn Write) -> Result<(), anyhow::Error> {
+        let mut widths: Vec<usize> = self.header.iter().map(|name| width(name)).collect();
+        for row in &self.rows {
+            if let Row::Cells(cells) = row {
+                for (width_of, cell) in widths.iter_mut().zip(cells) {
+                    *width_of = (*width_of).max(width(&cell.text));
+                }
+            }
+        }
+
+        let header: Vec<Cell> = self
+            .header
+            .iter()
+            .map(|name| Cell::styled(name.clone(), BOLD))
+            .collect();
+        self.write_cells(&header, &widths, color, out)?;
+        let rule = widths
+            .iter()
+            .map(|width| "-".repeat(*width))
+            .collect::<Vec<_>>()
+            .join("  ");
+        writeln!(out, "{}", rule)?;
+        for row in &self.rows {
+            match row {
+                Row::Cells(cells) => self.write_cells(cells, &widths, color, out)?,
+                Row::Rule => writeln!(out, "{}", rule)?,
+            }
+        }
+        Ok(())
+    }
+
+    fn write_cells(
+        &self,
+        cells: &[Cell],
+        widths: &[usize],
+        color: bool,
+        out: &mut dyn Write,
+    ) -> Result<(), anyhow::Error> {
+        let mut line = String::new();
+        for (index, width_of) in widths.iter().enumerate() {
+            let empty = Cell::default();
+            let cell = cells.get(index).unwrap_or(&empty);
+            let padding = " ".repeat(width_of - width(&cell.text));
+            if index > 0 {
+                line.push_str("  ");
+            }
+            if self.align[index] == Align::Right {
+                line.push_str(&padding);
+            }
+            let link = cell
+                .link
+                .as_ref()
+                .filter(|_| color && !cell.text.is_empty());
+            if let Some(url) = link {
+                line.push_str(&format!("\x1b]8;;{}\x1b\\", url));
+            }
+            match cell.style {
+                Some(style) if color && !cell.text.is_empty() => {
+                    line.push_str(style);
+                    line.push_str(&cell.text);
+                    line.push_str(RESET);
+                }
+                _ => line.push_str(&cell.text),
+            }
+            if link.is_some() {
+                line.push_str("\x1b]8;;\x1b\\");
+            }
+            if self.align[index] == Align::Left {
+                line.push_str(&padding);
+            }
+        }
+        writeln!(out, "{}", line.trim_end())?;
+        Ok(())
+    }
+}
+
+fn width(text: &str) -> usize {
+    text.chars().count()
+}
+
+/// Wraps `text` in `style` when colors are on.
+pub(crate) fn paint(text: &str, style: &str, color: bool) -> String {
+    if color {
+        format!("{}{}{}", style, text, RESET)
+    } else {
+        text.to_string()
+    }
+}
+
+/// Formats `value` with `decimals` digits and commas between thousands.
+pub fn format_number(value: f64, decimals: usize) -> String {
+    NumberLocale::En.format(value, decimals)
+}
@@ -0,0 +1,42 @@
+use crate::output::SortOrder;
+use crate::report::BalanceReport;
+use anyhow::Context as _;
+use std::fs;
+use std::io::Write;
+use std::path::Path;
+use tera::{Context, Tera};
+
+/// Renders `report` through the Tera template at `path`. The template sees
+/// the report's JSON fields, plus `wallet_list`: the wallets in `order`,
+/// each with its `address`. Templates ending in `.html` or `.htm` have
+/// their values HTML-escaped.
+pub fn write_template(
+    report: &BalanceReport,
+    path: &Path,
+    order: SortOrder,
+    out: &mut dyn Write,
+) -> Result<(), anyhow::Error> {
+    let source = fs::read_to_string(path)
+        .map_err(|err| anyhow::anyhow!("reading {}: {}", path.display(), err))?;
+
+    let mut context = Context::from_serialize(report)?;
+    let wallet_list = report
+        .sorted_wallets(order)
+        .into_iter()
+        .map(|(address, result)| {
+            let mut wallet = serde_json::to_value(result)?;
+            wallet["address"] = address.as_str().into();
+            Ok(wallet)
+        })
+        .collect::<Result<Vec<_>, serde_json::Error>>()?;
+    context.insert("wallet_list", &wallet_list);
+
+    let html = path
+        .extension()
+        .and_then(|extension| extension.to_str())
+        .is_some_and(|extension| matches!(extension, "html" | "htm"));
+    let rendered = Tera::one_off(&source, &context, html)
+        .with_context(|| format!("rendering {}", path.display()))?;
+    out.write_all(rendered.as_bytes())?;
+    Ok(())
+}
@@ -0,0 +1,528 @@
+use crate::diff::{BalanceChange, SOL_ASSET};
+use crate::metadata::short_address;
+use crate::output::table::{paint, Align, Cell, Table, BOLD, CYAN, DIM, GREEN, RED};
+use crate::output::{NumberFormat, RenderOptions};
+use crate::pricing::Currency;
+use crate::report::{BalanceReport, BalanceResult};
+use crate::token_account::{TokenAccountDetail, SOL_DECIMALS};
+use crate::trend::BalanceTrends;
+use spl_token::native_mint;
+use std::io::Write;
+
+pub fn write_text(
+    report: &BalanceReport,
+    options: &RenderOptions,
+    out: &mut dyn Write,
+) -> Result<(), anyhow::Error> {
+    write_text_with_changes(report, &[], None, options, out)
+}
+
+/// Like [`write_text`], but colors each balance listed in `changes` green or
+/// red and appends its delta, and draws a sparkline of each balance when
+/// `trends` are given.
+pub fn write_text_with_changes(
+    report: &BalanceReport,
+    changes: &[BalanceChange],
+    trends: Option<&BalanceTrends>,
+    options: &RenderOptions,
+    out: &mut dyn Write,
+) -> Result<(), anyhow::Error> {
+    let change_for = |wallet: &str, asset: &str| {
+        changes
+            .iter()
+            .find(|change| change.wallet == wallet && change.asset == asset)
+    };
+
+    if let Some(slot) = report.slot {
+        if report.historical {
+            writeln!(out, "Balances as of Slot: {}", slot)?;
+        } else if report.same_slot {
+            writeln!(out, "Snapshot Slot: {}", slot)?;
+        }
+    }
+
+    let show_usd = report.total_usd.is_some()
+        || report
+            .wallets
+            .values()
+            .any(|result| result.total_usd.is_some());
+    let mut columns = vec![
+        ("Wallet", Align::Left),
+        ("Asset", Align::Left),
+        ("Balance", Align::Right),
+    ];
+    if trends.is_some() {
+        columns.push(("Trend", Align::Left));
+    }
+    let currency = report.display_currency();
+    let numbers = &options.numbers;
+    let sol = |value: f64| numbers.amount(SOL_ASSET, Some(SOL_DECIMALS), value);
+    // Balances that are exact base units under `--raw`.
+    let units = |asset: &str, decimals: u8, value: f64, raw: u64| {
+        if options.raw {
+            raw.to_string()
+        } else {
+            numbers.amount(asset, Some(decimals), value)
+        }
+    };
+    let value_header = format!("{} Value", currency);
+    if show_usd {
+        columns.push((&value_header, Align::Right));
+    }
+
+    let mut table = Table::new(&columns);
+    for (index, (wallet, result)) in report
+        .sorted_wallets(options.sort_by)
+        .into_iter()
+        .enumerate()
+    {
+        let links = options
+            .links
+            .map(|links| links.on(result.cluster.as_deref()));
+        if index > 0 {
+            table.rule();
+        }
+
+        // Each row with the asset its trend is tracked under, if any.
+        let mut rows = vec![(
+            Some(SOL_ASSET),
+            [
+                Cell::new("SOL"),
+                amount(
+                    options,
+                    SOL_ASSET,
+                    SOL_DECIMALS,
+                    result.sol_balance,
+                    result.sol_lamports,
+                    change_for(wallet, SOL_ASSET),
+                ),
+                money(numbers, &currency, result.sol_usd_value),
+            ],
+        )];
+        if let Some(wrapped) = result.wrapped_sol {
+            rows.push((
+                None,
+                [
+                    Cell::styled("  incl. wrapped SOL", DIM),
+                    Cell::styled(sol(wrapped), DIM),
+                    Cell::default(),
+                ],
+            ));
+        }
+        if let Some(rent) = &result.rent {
+            for (name, value) in [
+                ("spendable", rent.spendable),
+                ("rent reserve", rent.reserve),
+            ] {
+                rows.push((
+                    None,
+                    [
+                        Cell::styled(format!("  {}", name), DIM),
+                        Cell::styled(sol(value), DIM),
+                        Cell::default(),
+                    ],
+                ));
+            }
+            if rent.token_accounts > 0 {
+                rows.push((
+                    None,
+                    [
+                        Cell::styled(
+                            format!("  rent in {} token accounts", rent.token_accounts),
+                            DIM,
+                        ),
+                        Cell::styled(sol(rent.token_account_rent), DIM),
+                        Cell::default(),
+                    ],
+                ));
+            }
+        }
+        if let Some(stake) = &result.stake {
+            rows.push((
+                None,
+                [
+                    Cell::new(format!("staked SOL ({} accounts)", stake.accounts)),
+                    Cell::new(sol(stake.total)),
+                    Cell::default(),
+                ],
+            ));
+            for (state, value) in [
+                ("active", stake.active),
+                ("activating", stake.activating),
+                ("deactivating", stake.deactivating),
+            ] {
+                if value > 0.0 {
+                    rows.push((
+                        None,
+                        [
+                            Cell::styled(format!("  {}", state), DIM),
+                            Cell::styled(sol(value), DIM),
+                            Cell::default(),
+                        ],
+                    ));
+                }
+            }
+        }
+        for (ticker, balance) in result.sorted_tokens(options.sort_by) {
+            rows.push((
+                Some(ticker.as_str()),
+                [
+                    Cell::new(ticker.clone()).linked(links.map(|links| links.token(&balance.mint))),
+                    amount(
+                        options,
+                        ticker,
+                        balance.decimals,
+                        balance.amount,
+                        balance.raw_amount,
+                        change_for(wallet, ticker),
+                    ),
+                    money(numbers, &currency, balance.usd_value),
+                ],
+            ));
+            if let Some(sol_value) = balance.sol_value {
+                rows.push((
+                    None,
+                    [
+                        Cell::styled("  in SOL", DIM),
+                        Cell::styled(sol(sol_value), DIM),
+                        Cell::default(),
+                    ],
+                ));
+            }
+            if balance.mint == native_mint::ID.to_string() {
+                rows.push((
+                    None,
+                    [
+                        Cell::styled("  wrapped SOL, not in the SOL balance", DIM),
+                        Cell::default(),
+                        Cell::default(),
+                    ],
+                ));
+            }
+            if let Some(frozen) = balance.frozen_amount {
+                rows.push((
+                    None,
+                    [
+                        Cell::styled("  frozen", RED),
+                        Cell::styled(
+                            units(
+                                ticker,
+                                balance.decimals,
+                                frozen,
+                                balance.frozen_raw_amount.unwrap_or_default(),
+                            ),
+                            RED,
+                        ),
+                        Cell::default(),
+                    ],
+                ));
+            }
+            for delegation in &balance.delegations {
+                rows.push((
+                    None,
+                    [
+                        Cell::styled(format!("  delegated to {}", delegation.delegate), RED),
+                        Cell::styled(
+                            units(
+                                ticker,
+                                balance.decimals,
+                                delegation.amount,
+                                delegation.raw_amount,
+                            ),
+                            RED,
+                        ),
+                        Cell::default(),
+                    ],
+                ));
+            }
+            for account in &balance.accounts {
+                rows.push((
+                    None,
+                    [
+                        Cell::styled(format!("  {}", account_summary(account)), DIM)
+                            .linked(links.map(|links| links.account(&account.address))),
+                        Cell::styled(
+                            units(
+                                ticker,
+                                balance.decimals,
+                                account.ui_amount(),
+                                account.raw_amount,
+                            ),
+                            DIM,
+                        ),
+                        Cell::default(),
+                    ],
+                ));
+            }
+        }
+        for (ticker, vesting) in result.vesting.iter().flatten() {
+            rows.push((
+                None,
+                [
+                    Cell::new(format!("{} vesting ({} streams)", ticker, vesting.streams)),
+                    Cell::new(numbers.amount(ticker, None, vesting.locked + vesting.claimable)),
+                    Cell::default(),
+                ],
+            ));
+            for (state, value) in [("locked", vesting.locked), ("claimable", vesting.claimable)] {
+                rows.push((
+                    None,
+                    [
+                        Cell::styled(format!("  {}", state), DIM),
+                        Cell::styled(numbers.amount(ticker, None, value), DIM),
+                        Cell::default(),
+                    ],
+                ));
+            }
+        }
+        if let Some(nfts) = &result.nfts {
+            rows.push((
+                None,
+                [
+                    Cell::new("NFTs"),
+                    Cell::new(nfts.count.to_string()),
+                    money(numbers, &currency, nfts.usd_value),
+                ],
+            ));
+            if let Some(value) = nfts.value {
+                rows.push((
+                    None,
+                    [
+                        Cell::styled("  floor value in SOL", DIM),
+                        Cell::styled(sol(value), DIM),
+                        Cell::default(),
+                    ],
+                ));
+            }
+            let compressed = (nfts.compressed > 0)
+                .then(|| ("compressed".to_string(), nfts.compressed))
+                .into_iter();
+            let collections = nfts.collections.iter().map(|collection| {
+                let name = collection
+                    .name
+                    .clone()
+                    .unwrap_or_else(|| short_address(&collection.address));
+                (name, collection.count)
+            });
+            for (name, count) in compressed.chain(collections) {
+                rows.push((
+                    None,
+                    [
+                        Cell::styled(format!("  {}", name), DIM),
+                        Cell::styled(count.to_string(), DIM),
+                        Cell::default(),
+                    ],
+                ));
+            }
+        }
+        if result
+            .token_balances
+            .values()
+            .any(|balance| balance.sol_value.is_some())
+        {
+            rows.push((
+                None,
+                [
+                    Cell::styled("SOL exposure", BOLD),
+                    Cell::styled(sol(result.sol_exposure()), BOLD),
+                    Cell::default(),
+                ],
+            ));
+        }
+        if let Some(total) = result.total_usd {
+            rows.push((
+                None,
+                [
+                    Cell::styled("Total", BOLD),
+                    Cell::default(),
+                    Cell::styled(numbers.money(&currency, total), BOLD),
+                ],
+            ));
+        }
+
+        let names = wallet_names(wallet, result);
+        for line in 0..rows.len().max(names.len()) {
+            let name = match names.get(line) {
+                Some(name) if line == 0 => Cell::styled(name.clone(), CYAN),
+                Some(name) => Cell::styled(name.clone(), DIM),
+                None => Cell::default(),
+            }
+            .linked(links.map(|links| links.account(wallet)));
+            let (asset, [asset_cell, balance, usd_value]) =
+                rows.get(line).cloned().unwrap_or_default();
+            let mut cells = vec![name, asset_cell, balance];
+            if let Some(trends) = trends {
+                let sparkline = asset
+                    .map(|asset| trends.sparkline(wallet, asset))
+                    .unwrap_or_default();
+                cells.push(Cell::styled(sparkline, CYAN));
+            }
+            cells.push(usd_value);
+            table.row(cells);
+        }
+    }
+    table.write(options.color, out)?;
+
+    if !report.groups.is_empty() {
+        writeln!(out)?;
+        writeln!(out, "{}", paint("Group Subtotals:", BOLD, options.color))?;
+        let mut table = Table::new(
+            &[
+                ("Group", Align::Left),
+                ("Asset", Align::Left),
+                ("Balance", Align::Right),
+                (&value_header, Align::Right),
+            ][..if show_usd { 4 } else { 3 }],
+        );
+        for (index, (name, group)) in report.groups.iter().enumerate() {
+            if index > 0 {
+                table.rule();
+            }
+            let mut rows = vec![[
+                Cell::new("SOL"),
+                Cell::new(units(
+                    SOL_ASSET,
+                    SOL_DECIMALS,
+                    group.sol_balance,
+                    group.sol_lamports,
+                )),
+                Cell::default(),
+            ]];
+            if let Some(staked) = group.staked_sol {
+                rows.push([
+                    Cell::new("staked SOL"),
+                    Cell::new(sol(staked)),
+                    Cell::default(),
+                ]);
+            }
+            for (ticker, balance) in &group.token_balances {
+                rows.push([
+                    Cell::new(ticker.clone()),
+                    Cell::new(units(
+                        ticker,
+                        balance.decimals,
+                        balance.amount,
+                        balance.raw_amount,
+                    )),
+                    money(numbers, &currency, balance.usd_value),
+                ]);
+            }
+            if let Some(total) = group.total_usd {
+                rows.push([
+                    Cell::styled("Total", BOLD),
+                    Cell::default(),
+                    Cell::styled(numbers.money(&currency, total), BOLD),
+                ]);
+            }
+
+            let members = match group.wallets.len() {
+                1 => "1 wallet".to_string(),
+                count => format!("{} wallets", count),
+            };
+            let names = [name.clone(), members];
+            for line in 0..rows.len().max(names.len()) {
+                let name = match names.get(line) {
+                    Some(name) if line == 0 => Cell::styled(name.clone(), CYAN),
+                    Some(name) => Cell::styled(name.clone(), DIM),
+                    None => Cell::default(),
+                };
+                let mut cells = vec![name];
+                cells.extend(rows.get(line).cloned().unwrap_or_default());
+                table.row(cells);
+            }
+        }
+        table.write(options.color, out)?;
+    }
+
+    if let Some(total) = report.total_usd {
+        writeln!(out)?;
+        writeln!(
+            out,
+            "{}",
+            paint(
+                &format!("Grand Total: {}", numbers.money(&currency, total)),
+                BOLD,
+                options.color
+            )
+        )?;
+    }
+
+    if !report.errors.is_empty() {
+        writeln!(out)?;
+        writeln!(out, "{}", paint("Errors:", RED, options.color))?;
+        for (wallet, error) in &report.errors {
+            writeln!(out, "  {}: {}", wallet, error)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// The lines of the wallet column: the label or domain first, then
+/// whatever else identifies the wallet.
+fn wallet_names(wallet: &str, result: &BalanceResult) -> Vec<String> {
+    let mut names: Vec<String> = result.label.iter().chain(&result.domain).cloned().collect();
+    names.push(wallet.to_string());
+    if let Some(multisig) = &result.multisig {
+        names.push(format!("vault of {}", short_address(multisig)));
+    }
+    if let Some(realm) = &result.realm {
+        names.push(format!("treasury of realm {}", short_address(realm)));
+    }
+    if let Some(cluster) = &result.cluster {
+        names.push(format!("on {}", cluster));
+    }
+    names
+}
+
+/// A token account's address followed by what kind of account it is, e.g.
+/// `7xKX... (ATA, Token-2022, frozen)`.
+fn account_summary(account: &TokenAccountDetail) -> String {
+    let mut tags = Vec::new();
+    if account.associated {
+        tags.push("ATA".to_string());
+    }
+    tags.push(account.program_name().to_string());
+    tags.push(account.state.to_string());
+    format!("{} ({})", account.address, tags.join(", "))
+}
+
+/// A balance of `asset`, or its `raw` base units under `--raw`, with its
+/// change if it has one.
+fn amount(
+    options: &RenderOptions,
+    asset: &str,
+    decimals: u8,
+    value: f64,
+    raw: u64,
+    change: Option<&BalanceChange>,
+) -> Cell {
+    let numbers = &options.numbers;
+    let formatted = if options.raw {
+        raw.to_string()
+    } else {
+        numbers.amount(asset, Some(decimals), value)
+    };
+    match change {
+        Some(change) => {
+            let color = if change.delta() > 0.0 { GREEN } else { RED };
+            let delta = if options.raw {
+                (change.delta() * 10f64.powi(i32::from(decimals)))
+                    .round()
+                    .to_string()
+            } else {
+                numbers.amount(asset, Some(decimals), change.delta())
+            };
+            let sign = if change.delta() > 0.0 { "+" } else { "" };
+            Cell::styled(format!("{} ({}{})", formatted, sign, delta), color)
+        }
+        None => Cell::new(formatted),
+    }
+}
+
+fn money(numbers: &NumberFormat, currency: &Currency, value: Option<f64>) -> Cell {
+    match value {
+        Some(value) => Cell::new(numbers.money(currency, value)),
+        None => Cell::default(),
+    }
+}
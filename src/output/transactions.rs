@@ -0,0 +1,141 @@
+use crate::activity::WalletTransaction;
+use crate::output::table::{paint, Align, Cell, Table, BOLD, DIM, GREEN, RED};
+use crate::output::{OutputFormat, RenderOptions};
+use chrono::SecondsFormat;
+use std::io::Write;
+
+/// Renders a wallet's transactions as a table, JSON or one CSV row per
+/// transfer (transactions that moved nothing get a row without one).
+pub fn write_transactions(
+    wallet: &str,
+    transactions: &[WalletTransaction],
+    options: &RenderOptions,
+    out: &mut dyn Write,
+) -> Result<(), anyhow::Error> {
+    match options.format {
+        OutputFormat::Json => {
+            if options.compact {
+                serde_json::to_writer(&mut *out, transactions)?;
+            } else {
+                serde_json::to_writer_pretty(&mut *out, transactions)?;
+            }
+            writeln!(out)?;
+        }
+        OutputFormat::Csv => {
+            let mut writer = csv::Writer::from_writer(out);
+            writer.write_record([
+                "signature",
+                "slot",
+                "block_time",
+                "error",
+                "asset",
+                "mint",
+                "amount",
+            ])?;
+            for transaction in transactions {
+                let mut record = |asset: &str, mint: &str, amount: String| {
+                    writer.write_record([
+                        &transaction.signature,
+                        &transaction.slot.to_string(),
+                        &transaction
+                            .block_time
+                            .map(|time| time.to_rfc3339_opts(SecondsFormat::Secs, true))
+                            .unwrap_or_default(),
+                        transaction.error.as_deref().unwrap_or_default(),
+                        asset,
+                        mint,
+                        &amount,
+                    ])
+                };
+                if transaction.transfers.is_empty() {
+                    record("", "", String::new())?;
+                }
+                for transfer in &transaction.transfers {
+                    record(
+                        &transfer.asset,
+                        transfer.mint.as_deref().unwrap_or_default(),
+                        options.numbers.csv_amount(
+                            &transfer.asset,
+                            None,
+                            transfer.amount,
+                            &transfer.amount.to_string(),
+                        ),
+                    )?;
+                }
+            }
+            writer.flush()?;
+        }
+        _ => write_transactions_text(wallet, transactions, options, out)?,
+    }
+    Ok(())
+}
+
+fn write_transactions_text(
+    wallet: &str,
+    transactions: &[WalletTransaction],
+    options: &RenderOptions,
+    out: &mut dyn Write,
+) -> Result<(), anyhow::Error> {
+    writeln!(
+        out,
+        "{}",
+        paint(&format!("Wallet: {}", wallet), BOLD, options.color)
+    )?;
+    if transactions.is_empty() {
+        writeln!(out, "No transactions found.")?;
+        return Ok(());
+    }
+
+    let mut table = Table::new(&[
+        ("Time", Align::Left),
+        ("Signature", Align::Left),
+        ("Status", Align::Left),
+        ("Direction", Align::Left),
+        ("Amount", Align::Right),
+        ("Asset", Align::Left),
+    ]);
+    for transaction in transactions {
+        let time = transaction
+            .block_time
+            .map(|time| time.to_rfc3339_opts(SecondsFormat::Secs, true))
+            .unwrap_or_else(|| format!("slot {}", transaction.slot));
+        let status = match &transaction.error {
+            Some(error) => Cell::styled(format!("failed: {}", error), RED),
+            None => Cell::new("ok"),
+        };
+        let signature = Cell::new(&transaction.signature).linked(
+            options
+                .links
+                .map(|links| links.transaction(&transaction.signature)),
+        );
+        let mut cells = vec![Cell::new(time), signature, status];
+        if transaction.transfers.is_empty() {
+            cells.push(Cell::styled("none", DIM));
+            table.row(cells);
+            continue;
+        }
+        for transfer in &transaction.transfers {
+            let direction = if transfer.amount < 0.0 {
+                Cell::styled("out", RED)
+            } else {
+                Cell::styled("in", GREEN)
+            };
+            cells.extend([
+                direction,
+                Cell::new(options.numbers.amount_or(
+                    &transfer.asset,
+                    None,
+                    6,
+                    transfer.amount.abs(),
+                )),
+                Cell::new(&transfer.asset),
+            ]);
+            table.row(std::mem::replace(
+                &mut cells,
+                vec![Cell::default(), Cell::default(), Cell::default()],
+            ));
+        }
+    }
+    table.write(options.color, out)?;
+    Ok(())
+}
@@ -0,0 +1,271 @@
+use crate::output::RenderOptions;
+use crate::pricing::Currency;
+use crate::report::{BalanceReport, BalanceResult};
+use rust_xlsxwriter::{Format, FormatAlign, Workbook, Worksheet, XlsxError};
+use std::collections::HashSet;
+use std::io::Write;
+
+/// Excel's limit on the length of a sheet name.
+const MAX_SHEET_NAME: usize = 31;
+
+const AMOUNT_FORMAT: &str = "#,##0.0000####";
+
+/// Cell formats shared by every sheet, and the heading of value columns.
+struct Formats {
+    value_header: String,
+    header: Format,
+    amount: Format,
+    usd: Format,
+    total: Format,
+    total_amount: Format,
+    total_usd: Format,
+}
+
+impl Formats {
+    fn new(currency: &Currency) -> Self {
+        let number = match currency.decimals() {
+            0 => "#,##0".to_string(),
+            decimals => format!("#,##0.{}", "0".repeat(decimals)),
+        };
+        let value_format = match currency.symbol() {
+            Some(symbol) => format!("{}{}", symbol, number),
+            None => format!("{} \"{}\"", number, currency),
+        };
+        Formats {
+            value_header: format!("{} Value", currency),
+            header: Format::new().set_bold().set_align(FormatAlign::Center),
+            amount: Format::new().set_num_format(AMOUNT_FORMAT),
+            usd: Format::new().set_num_format(&value_format),
+            total: Format::new().set_bold(),
+            total_amount: Format::new().set_bold().set_num_format(AMOUNT_FORMAT),
+            total_usd: Format::new().set_bold().set_num_format(&value_format),
+        }
+    }
+}
+
+/// Writes an Excel workbook: a summary sheet of wallet and group totals,
+/// then one sheet per group listing its members' balances. Wallets in no
+/// group get a sheet of their own.
+pub fn write_xlsx(
+    report: &BalanceReport,
+    options: &RenderOptions,
+    out: &mut dyn Write,
+) -> Result<(), anyhow::Error> {
+    let formats = Formats::new(&report.display_currency());
+    let wallets = report.sorted_wallets(options.sort_by);
+    let mut workbook = Workbook::new();
+    let mut names = SheetNames::default();
+
+    let summary = workbook.add_worksheet();
+    summary.set_name(names.unique("Summary"))?;
+    write_summary(summary, report, &wallets, &formats)?;
+
+    for (name, group) in &report.groups {
+        let members: Vec<_> = wallets
+            .iter()
+            .filter(|(wallet, _)| group.wallets.contains(wallet))
+            .copied()
+            .collect();
+        let sheet = workbook.add_worksheet();
+        sheet.set_name(names.unique(name))?;
+        write_balances(sheet, &members, &formats)?;
+    }
+
+    let grouped: HashSet<&String> = report
+        .groups
+        .values()
+        .flat_map(|group| &group.wallets)
+        .collect();
+    let ungrouped: Vec<_> = wallets
+        .iter()
+        .filter(|(wallet, _)| !grouped.contains(wallet))
+        .copied()
+        .collect();
+    if !ungrouped.is_empty() {
+        let sheet = workbook.add_worksheet();
+        let name = if report.groups.is_empty() {
+            "Wallets"
+        } else {
+            "Ungrouped"
+        };
+        sheet.set_name(names.unique(name))?;
+        write_balances(sheet, &ungrouped, &formats)?;
+    }
+
+    out.write_all(&workbook.save_to_buffer()?)?;
+    Ok(())
+}
+
+fn write_summary(
+    sheet: &mut Worksheet,
+    report: &BalanceReport,
+    wallets: &[(&String, &BalanceResult)],
+    formats: &Formats,
+) -> Result<(), XlsxError> {
+    write_header(
+        sheet,
+        0,
+        &["Wallet", "Label", "SOL", "Tokens", &formats.value_header],
+        formats,
+    )?;
+    sheet.set_freeze_panes(1, 0)?;
+    let mut row = 1;
+    for (wallet, result) in wallets {
+        sheet.write_string(row, 0, wallet.as_str())?;
+        if let Some(label) = &result.label {
+            sheet.write_string(row, 1, label)?;
+        }
+        sheet.write_number_with_format(row, 2, result.sol_balance, &formats.amount)?;
+        sheet.write_number(row, 3, result.token_balances.len() as f64)?;
+        if let Some(total) = result.total_usd {
+            sheet.write_number_with_format(row, 4, total, &formats.usd)?;
+        }
+        row += 1;
+    }
+    let sol: f64 = wallets.iter().map(|(_, result)| result.sol_balance).sum();
+    sheet.write_string_with_format(row, 0, "Total", &formats.total)?;
+    sheet.write_number_with_format(row, 2, sol, &formats.total_amount)?;
+    if let Some(total) = report.total_usd {
+        sheet.write_number_with_format(row, 4, total, &formats.total_usd)?;
+    }
+
+    if !report.groups.is_empty() {
+        row += 2;
+        write_header(
+            sheet,
+            row,
+            &["Group", "Wallets", "SOL", "", &formats.value_header],
+            formats,
+        )?;
+        for (name, group) in &report.groups {
+            row += 1;
+            sheet.write_string(row, 0, name)?;
+            sheet.write_number(row, 1, group.wallets.len() as f64)?;
+            sheet.write_number_with_format(row, 2, group.sol_balance, &formats.amount)?;
+            if let Some(total) = group.total_usd {
+                sheet.write_number_with_format(row, 4, total, &formats.usd)?;
+            }
+        }
+    }
+
+    if !report.errors.is_empty() {
+        row += 2;
+        write_header(sheet, row, &["Failed wallet", "Error"], formats)?;
+        for (wallet, error) in &report.errors {
+            row += 1;
+            sheet.write_string(row, 0, wallet)?;
+            sheet.write_string(row, 1, error)?;
+        }
+    }
+    sheet.autofit();
+    Ok(())
+}
+
+/// A row per wallet and asset, with each wallet's total after its assets.
+fn write_balances(
+    sheet: &mut Worksheet,
+    wallets: &[(&String, &BalanceResult)],
+    formats: &Formats,
+) -> Result<(), XlsxError> {
+    write_header(
+        sheet,
+        0,
+        &[
+            "Wallet",
+            "Label",
+            "Asset",
+            "Mint",
+            "Amount",
+            &formats.value_header,
+        ],
+        formats,
+    )?;
+    sheet.set_freeze_panes(1, 0)?;
+    let mut row = 1;
+    for (wallet, result) in wallets {
+        let mut asset_row = |asset: &str,
+                             mint: Option<&str>,
+                             amount: f64,
+                             usd: Option<f64>|
+         -> Result<(), XlsxError> {
+            sheet.write_string(row, 0, wallet.as_str())?;
+            if let Some(label) = &result.label {
+                sheet.write_string(row, 1, label)?;
+            }
+            sheet.write_string(row, 2, asset)?;
+            if let Some(mint) = mint {
+                sheet.write_string(row, 3, mint)?;
+            }
+            sheet.write_number_with_format(row, 4, amount, &formats.amount)?;
+            if let Some(usd) = usd {
+                sheet.write_number_with_format(row, 5, usd, &formats.usd)?;
+            }
+            row += 1;
+            Ok(())
+        };
+        asset_row("SOL", None, result.sol_balance, result.sol_usd_value)?;
+        if let Some(stake) = &result.stake {
+            asset_row("staked SOL", None, stake.total, None)?;
+        }
+        let mut tokens: Vec<_> = result.token_balances.iter().collect();
+        tokens.sort_by(|a, b| a.0.cmp(b.0));
+        for (ticker, balance) in tokens {
+            asset_row(
+                ticker,
+                Some(&balance.mint),
+                balance.amount,
+                balance.usd_value,
+            )?;
+        }
+        if let Some(total) = result.total_usd {
+            sheet.write_string(row, 0, wallet.as_str())?;
+            sheet.write_string_with_format(row, 2, "Total", &formats.total)?;
+            sheet.write_number_with_format(row, 5, total, &formats.total_usd)?;
+            row += 1;
+        }
+    }
+    sheet.autofit();
+    Ok(())
+}
+
+fn write_header(
+    sheet: &mut Worksheet,
+    row: u32,
+    titles: &[&str],
+    formats: &Formats,
+) -> Result<(), XlsxError> {
+    for (col, title) in titles.iter().enumerate() {
+        sheet.write_string_with_format(row, col as u16, *title, &formats.header)?;
+    }
+    Ok(())
+}
+
+/// Hands out sheet names Excel accepts: without `[]:*?/\`, at most 31
+/// characters and unique regardless of case.
+#[derive(Default)]
+struct SheetNames {
+    used: HashSet<String>,
+}
+
+impl SheetNames {
+    fn unique(&mut self, name: &str) -> String {
+        let base: String = name
+            .chars()
+            .map(|c| if "[]:*?/\\".contains(c) { '_' } else { c })
+            .collect();
+        let base = base.trim_matches('\'');
+        let base = if base.is_empty() { "Sheet" } else { base };
+        let mut candidate: String = base.chars().take(MAX_SHEET_NAME).collect();
+        let mut n = 2;
+        while !self.used.insert(candidate.to_lowercase()) {
+            let suffix = format!(" ({})", n);
+            candidate = base
+                .chars()
+                .take(MAX_SHEET_NAME - suffix.len())
+                .chain(suffix.chars())
+                .collect();
+            n += 1;
+        }
+        candidate
+    }
+}
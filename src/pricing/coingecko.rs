@@ -0,0 +1,93 @@
+use crate::pricing::PriceProvider;
+use async_trait::async_trait;
+use serde::Deserialize;
+use solana_sdk::pubkey::Pubkey;
+use std::collections::HashMap;
+use std::str::FromStr;
+
+const COINGECKO_API_URL: &str = "https://api.coingecko.com/api/v3";
+const MAX_ADDRESSES_PER_REQUEST: usize = 30;
+
+pub struct CoinGeckoPriceProvider {
+    http: reqwest::Client,
+    api_key: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct UsdPrice {
+    usd: Option<f64>,
+}
+
+impl CoinGeckoPriceProvider {
+    pub fn new(api_key: Option<String>) -> Self {
+        CoinGeckoPriceProvider {
+            http: reqwest::Client::new(),
+            api_key,
+        }
+    }
+
+    async fn get<T: for<'de> Deserialize<'de>>(
+        &self,
+        path: &str,
+        query: &[(&str, String)],
+    ) -> Result<T, anyhow::Error> {
+        let mut request = self
+            .http
+            .get(format!("{}{}", COINGECKO_API_URL, path))
+            .query(query);
+        if let Some(api_key) = &self.api_key {
+            request = request.header("x-cg-demo-api-key", api_key);
+        }
+        Ok(request.send().await?.error_for_status()?.json().await?)
+    }
+}
+
+#[async_trait]
+impl PriceProvider for CoinGeckoPriceProvider {
+    async fn usd_prices(&self, mints: &[Pubkey]) -> Result<HashMap<Pubkey, f64>, anyhow::Error> {
+        let mut prices = HashMap::new();
+
+        let sol: HashMap<String, UsdPrice> = self
+            .get(
+                "/simple/price",
+                &[
+                    ("ids", "solana".to_string()),
+                    ("vs_currencies", "usd".to_string()),
+                ],
+            )
+            .await?;
+        if let Some(price) = sol.get("solana").and_then(|price| price.usd) {
+            prices.insert(spl_token::native_mint::ID, price);
+        }
+
+        let addresses: Vec<String> = mints
+            .iter()
+            .filter(|mint| **mint != spl_token::native_mint::ID)
+            .map(Pubkey::to_string)
+            .collect();
+        for chunk in addresses.chunks(MAX_ADDRESSES_PER_REQUEST) {
+            let response: HashMap<String, UsdPrice> = self
+                .get(
+                    "/simple/token_price/solana",
+                    &[
+                        ("contract_addresses", chunk.join(",")),
+                        ("vs_currencies", "usd".to_string()),
+                    ],
+                )
+                .await?;
+
+            // CoinGecko may echo addresses back in a different case.
+            for address in chunk {
+                let price = response
+                    .iter()
+                    .find(|(key, _)| key.eq_ignore_ascii_case(address))
+                    .and_then(|(_, price)| price.usd);
+                if let (Ok(mint), Some(price)) = (Pubkey::from_str(address), price) {
+                    prices.insert(mint, price);
+                }
+            }
+        }
+
+        Ok(prices)
+    }
+}
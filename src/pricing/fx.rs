@@ -0,0 +1,175 @@
+use crate::output::format_number;
+use crate::pricing::PriceProvider;
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use solana_sdk::pubkey::Pubkey;
+use std::collections::HashMap;
+use std::fmt;
+use std::str::FromStr;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+const FRANKFURTER_API_URL: &str = "https://api.frankfurter.app";
+/// Frankfurter publishes the ECB's rates once a working day.
+const RATE_TTL: Duration = Duration::from_secs(60 * 60);
+
+/// An ISO 4217 currency code that valuations are shown in, e.g. `EUR`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct Currency(String);
+
+impl Currency {
+    pub fn usd() -> Self {
+        Currency("USD".to_string())
+    }
+
+    pub fn is_usd(&self) -> bool {
+        self.0 == "USD"
+    }
+
+    pub fn code(&self) -> &str {
+        &self.0
+    }
+
+    /// The sign written before amounts, for the currencies that have a
+    /// well-known one.
+    pub fn symbol(&self) -> Option<&'static str> {
+        match self.0.as_str() {
+            "USD" => Some("$"),
+            "EUR" => Some("€"),
+            "GBP" => Some("£"),
+            "JPY" | "CNY" => Some("¥"),
+            "INR" => Some("₹"),
+            "KRW" => Some("₩"),
+            _ => None,
+        }
+    }
+
+    /// Decimal places amounts are shown with: none for currencies without
+    /// minor units.
+    pub fn decimals(&self) -> usize {
+        match self.0.as_str() {
+            "JPY" | "KRW" | "ISK" | "CLP" | "VND" => 0,
+            _ => 2,
+        }
+    }
+
+    /// `value` with the currency's symbol, e.g. `€1,234.50`, or its code
+    /// after the amount, e.g. `1,234.50 CHF`.
+    pub fn format(&self, value: f64) -> String {
+        self.with_symbol(&format_number(value, self.decimals()))
+    }
+
+    /// An already formatted `amount` with the currency's symbol or code.
+    pub fn with_symbol(&self, amount: &str) -> String {
+        match self.symbol() {
+            Some(symbol) => format!("{}{}", symbol, amount),
+            None => format!("{} {}", amount, self.0),
+        }
+    }
+}
+
+impl Default for Currency {
+    fn default() -> Self {
+        Currency::usd()
+    }
+}
+
+impl FromStr for Currency {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.len() != 3 || !s.chars().all(|c| c.is_ascii_alphabetic()) {
+            anyhow::bail!(
+                "invalid currency `{}` (expected a code such as EUR or GBP)",
+                s
+            );
+        }
+        Ok(Currency(s.to_ascii_uppercase()))
+    }
+}
+
+impl TryFrom<String> for Currency {
+    type Error = anyhow::Error;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+impl From<Currency> for String {
+    fn from(currency: Currency) -> Self {
+        currency.0
+    }
+}
+
+impl fmt::Display for Currency {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+#[derive(Deserialize)]
+struct RatesResponse {
+    rates: HashMap<String, f64>,
+}
+
+/// Prices from another provider converted from USD into `currency` at the
+/// rate from the Frankfurter API, which is refetched hourly.
+pub struct ConvertedPriceProvider {
+    inner: Box<dyn PriceProvider>,
+    currency: Currency,
+    http: reqwest::Client,
+    api_url: String,
+    rate: Mutex<Option<(Instant, f64)>>,
+}
+
+impl ConvertedPriceProvider {
+    /// Reads rates from `api_url`, e.g. a self-hosted Frankfurter, or else
+    /// the public instance.
+    pub fn new(inner: Box<dyn PriceProvider>, currency: Currency, api_url: Option<String>) -> Self {
+        ConvertedPriceProvider {
+            inner,
+            currency,
+            http: reqwest::Client::new(),
+            api_url: api_url.unwrap_or_else(|| FRANKFURTER_API_URL.to_string()),
+            rate: Mutex::new(None),
+        }
+    }
+
+    /// How many units of the currency one US dollar buys.
+    async fn rate(&self) -> Result<f64, anyhow::Error> {
+        if let Some((fetched, rate)) = *self.rate.lock().unwrap() {
+            if fetched.elapsed() < RATE_TTL {
+                return Ok(rate);
+            }
+        }
+        let response: RatesResponse = self
+            .http
+            .get(format!("{}/latest", self.api_url.trim_end_matches('/')))
+            .query(&[("from", "USD"), ("to", self.currency.code())])
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+        let rate = *response
+            .rates
+            .get(self.currency.code())
+            .ok_or_else(|| anyhow::anyhow!("no USD to {} exchange rate", self.currency))?;
+        *self.rate.lock().unwrap() = Some((Instant::now(), rate));
+        Ok(rate)
+    }
+}
+
+#[async_trait]
+impl PriceProvider for ConvertedPriceProvider {
+    /// Prices in the display currency, despite the name.
+    async fn usd_prices(&self, mints: &[Pubkey]) -> Result<HashMap<Pubkey, f64>, anyhow::Error> {
+        let (prices, rate) = futures::try_join!(self.inner.usd_prices(mints), self.rate())?;
+        Ok(prices
+            .into_iter()
+            .map(|(mint, price)| (mint, price * rate))
+            .collect())
+    }
+}
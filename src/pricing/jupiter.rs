@@ -0,0 +1,66 @@
+use crate::pricing::PriceProvider;
+use async_trait::async_trait;
+use serde::Deserialize;
+use solana_sdk::pubkey::Pubkey;
+use std::collections::HashMap;
+use std::str::FromStr;
+
+const JUPITER_PRICE_URL: &str = "https://api.jup.ag/price/v2";
+const MAX_IDS_PER_REQUEST: usize = 100;
+
+pub struct JupiterPriceProvider {
+    http: reqwest::Client,
+    api_key: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct PriceResponse {
+    data: HashMap<String, Option<PriceEntry>>,
+}
+
+#[derive(Deserialize)]
+struct PriceEntry {
+    price: Option<String>,
+}
+
+impl JupiterPriceProvider {
+    pub fn new(api_key: Option<String>) -> Self {
+        JupiterPriceProvider {
+            http: reqwest::Client::new(),
+            api_key,
+        }
+    }
+}
+
+#[async_trait]
+impl PriceProvider for JupiterPriceProvider {
+    async fn usd_prices(&self, mints: &[Pubkey]) -> Result<HashMap<Pubkey, f64>, anyhow::Error> {
+        let mut ids: Vec<String> = mints.iter().map(Pubkey::to_string).collect();
+        ids.push(spl_token::native_mint::ID.to_string());
+        ids.sort();
+        ids.dedup();
+
+        let mut prices = HashMap::new();
+        for chunk in ids.chunks(MAX_IDS_PER_REQUEST) {
+            let mut request = self
+                .http
+                .get(JUPITER_PRICE_URL)
+                .query(&[("ids", chunk.join(","))]);
+            if let Some(api_key) = &self.api_key {
+                request = request.header("x-api-key", api_key);
+            }
+            let response: PriceResponse = request.send().await?.error_for_status()?.json().await?;
+
+            for (id, entry) in response.data {
+                let price = entry
+                    .and_then(|entry| entry.price)
+                    .and_then(|price| price.parse::<f64>().ok());
+                if let (Ok(mint), Some(price)) = (Pubkey::from_str(&id), price) {
+                    prices.insert(mint, price);
+                }
+            }
+        }
+
+        Ok(prices)
+    }
+}
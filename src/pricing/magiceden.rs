@@ -0,0 +1,64 @@
+use crate::pricing::FloorPriceProvider;
+use async_trait::async_trait;
+use reqwest::StatusCode;
+use serde::Deserialize;
+use solana_sdk::native_token::lamports_to_sol;
+use std::collections::HashMap;
+
+const MAGIC_EDEN_API_URL: &str = "https://api-mainnet.magiceden.dev/v2";
+
+/// Floor prices from Magic Eden. Its API only knows collections by symbol,
+/// so collections without one in `symbols` are not valued.
+pub struct MagicEdenFloorProvider {
+    http: reqwest::Client,
+    api_key: Option<String>,
+    symbols: HashMap<String, String>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CollectionStats {
+    /// Lowest listing, in lamports.
+    floor_price: Option<u64>,
+}
+
+impl MagicEdenFloorProvider {
+    pub fn new(api_key: Option<String>, symbols: HashMap<String, String>) -> Self {
+        MagicEdenFloorProvider {
+            http: reqwest::Client::new(),
+            api_key,
+            symbols,
+        }
+    }
+}
+
+#[async_trait]
+impl FloorPriceProvider for MagicEdenFloorProvider {
+    async fn floor_prices(
+        &self,
+        collections: &[String],
+    ) -> Result<HashMap<String, f64>, anyhow::Error> {
+        let mut prices = HashMap::new();
+        for address in collections {
+            let Some(symbol) = self.symbols.get(address) else {
+                continue;
+            };
+            let mut request = self.http.get(format!(
+                "{}/collections/{}/stats",
+                MAGIC_EDEN_API_URL, symbol
+            ));
+            if let Some(api_key) = &self.api_key {
+                request = request.bearer_auth(api_key);
+            }
+            let response = request.send().await?;
+            if response.status() == StatusCode::NOT_FOUND {
+                continue;
+            }
+            let stats: CollectionStats = response.error_for_status()?.json().await?;
+            if let Some(lamports) = stats.floor_price {
+                prices.insert(address.clone(), lamports_to_sol(lamports));
+            }
+        }
+        Ok(prices)
+    }
+}
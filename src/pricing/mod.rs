@@ -0,0 +1,111 @@
+mod coingecko;
+mod fx;
+mod jupiter;
+mod magiceden;
+mod tensor;
+
+use async_trait::async_trait;
+use serde::Deserialize;
+use solana_sdk::pubkey::Pubkey;
+use std::collections::HashMap;
+
+pub use coingecko::CoinGeckoPriceProvider;
+pub use fx::{ConvertedPriceProvider, Currency};
+pub use jupiter::JupiterPriceProvider;
+pub use magiceden::MagicEdenFloorProvider;
+pub use tensor::TensorFloorProvider;
+
+/// A source of USD prices for SPL mints. SOL is priced under the native
+/// (wrapped SOL) mint.
+#[async_trait]
+pub trait PriceProvider: Send + Sync {
+    async fn usd_prices(&self, mints: &[Pubkey]) -> Result<HashMap<Pubkey, f64>, anyhow::Error>;
+}
+
+/// A source of NFT collection floor prices, in SOL, keyed by collection
+/// address.
+#[async_trait]
+pub trait FloorPriceProvider: Send + Sync {
+    async fn floor_prices(
+        &self,
+        collections: &[String],
+    ) -> Result<HashMap<String, f64>, anyhow::Error>;
+}
+
+#[derive(Debug, Clone, Copy, Deserialize, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum PriceSource {
+    #[default]
+    Jupiter,
+    CoinGecko,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct PricingConfig {
+    #[serde(default)]
+    pub provider: PriceSource,
+    pub api_key: Option<String>,
+    /// Shows valuations in this currency instead of USD, converted at the
+    /// day's rate. Every USD setting, e.g. `min_usd_value`, is then in it
+    /// too.
+    #[serde(default)]
+    pub display_currency: Option<Currency>,
+    /// A Frankfurter instance to read exchange rates from [default: the
+    /// public api.frankfurter.app]
+    #[serde(default)]
+    pub fx_api_url: Option<String>,
+}
+
+impl PricingConfig {
+    pub fn provider(&self) -> Box<dyn PriceProvider> {
+        let provider: Box<dyn PriceProvider> = match self.provider {
+            PriceSource::Jupiter => Box::new(JupiterPriceProvider::new(self.api_key.clone())),
+            PriceSource::CoinGecko => Box::new(CoinGeckoPriceProvider::new(self.api_key.clone())),
+        };
+        match self.currency() {
+            Some(currency) => Box::new(ConvertedPriceProvider::new(
+                provider,
+                currency,
+                self.fx_api_url.clone(),
+            )),
+            None => provider,
+        }
+    }
+
+    /// The display currency, unless it is USD.
+    pub fn currency(&self) -> Option<Currency> {
+        self.display_currency
+            .clone()
+            .filter(|currency| !currency.is_usd())
+    }
+}
+
+#[derive(Debug, Clone, Copy, Deserialize, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum FloorPriceSource {
+    #[default]
+    Tensor,
+    MagicEden,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct FloorPriceConfig {
+    #[serde(default)]
+    pub provider: FloorPriceSource,
+    pub api_key: Option<String>,
+    /// The marketplace's slug or symbol for collections, by collection
+    /// address. Magic Eden only values collections listed here.
+    #[serde(default)]
+    pub symbols: HashMap<String, String>,
+}
+
+impl FloorPriceConfig {
+    pub fn provider(&self) -> Box<dyn FloorPriceProvider> {
+        let api_key = self.api_key.clone();
+        let symbols = self.symbols.clone();
+        match self.provider {
+            FloorPriceSource::Tensor => Box::new(TensorFloorProvider::new(api_key, symbols)),
+            FloorPriceSource::MagicEden => Box::new(MagicEdenFloorProvider::new(api_key, symbols)),
+        }
+    }
+}
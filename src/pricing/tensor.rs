@@ -0,0 +1,72 @@
+use crate::pricing::FloorPriceProvider;
+use async_trait::async_trait;
+use reqwest::StatusCode;
+use serde::Deserialize;
+use solana_sdk::native_token::lamports_to_sol;
+use std::collections::HashMap;
+
+const TENSOR_API_URL: &str = "https://api.mainnet.tensordev.io/api/v1";
+
+/// Floor prices from Tensor, which looks collections up by their on-chain
+/// address or by slug.
+pub struct TensorFloorProvider {
+    http: reqwest::Client,
+    api_key: Option<String>,
+    slugs: HashMap<String, String>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Collection {
+    stats: Option<CollectionStats>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CollectionStats {
+    /// Lowest listing, in lamports, sent as a string.
+    buy_now_price: Option<String>,
+}
+
+impl TensorFloorProvider {
+    pub fn new(api_key: Option<String>, slugs: HashMap<String, String>) -> Self {
+        TensorFloorProvider {
+            http: reqwest::Client::new(),
+            api_key,
+            slugs,
+        }
+    }
+}
+
+#[async_trait]
+impl FloorPriceProvider for TensorFloorProvider {
+    async fn floor_prices(
+        &self,
+        collections: &[String],
+    ) -> Result<HashMap<String, f64>, anyhow::Error> {
+        let mut prices = HashMap::new();
+        for address in collections {
+            let filter = self.slugs.get(address).unwrap_or(address);
+            let mut request = self
+                .http
+                .get(format!("{}/collections/find_collection", TENSOR_API_URL))
+                .query(&[("filter", filter)]);
+            if let Some(api_key) = &self.api_key {
+                request = request.header("x-tensor-api-key", api_key);
+            }
+            let response = request.send().await?;
+            if response.status() == StatusCode::NOT_FOUND {
+                continue;
+            }
+            let collection: Collection = response.error_for_status()?.json().await?;
+            let floor = collection
+                .stats
+                .and_then(|stats| stats.buy_now_price)
+                .and_then(|price| price.parse::<u64>().ok());
+            if let Some(lamports) = floor {
+                prices.insert(address.clone(), lamports_to_sol(lamports));
+            }
+        }
+        Ok(prices)
+    }
+}
@@ -0,0 +1,71 @@
+use crate::cli::Cli;
+use indicatif::{ProgressBar, ProgressDrawTarget, ProgressStyle};
+use std::io::{self, IsTerminal};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+use test_solana::metadata::short_address;
+use test_solana::{FetchProgress, WalletInfo};
+
+/// Runs with fewer wallets finish too quickly for a bar to be worth drawing.
+const MIN_WALLETS: usize = 10;
+
+/// A progress bar on stderr counting fetched wallets, with an ETA and the
+/// last wallet to finish. Hidden with `--quiet`, when stdout or stderr is
+/// not a terminal, and for small runs.
+pub struct WalletProgress {
+    bar: ProgressBar,
+    enabled: bool,
+    failed: AtomicUsize,
+}
+
+impl WalletProgress {
+    pub fn new(cli: &Cli) -> Self {
+        WalletProgress {
+            bar: ProgressBar::hidden(),
+            enabled: !cli.quiet && io::stdout().is_terminal() && io::stderr().is_terminal(),
+            failed: AtomicUsize::new(0),
+        }
+    }
+}
+
+impl FetchProgress for WalletProgress {
+    fn start(&self, wallets: usize) {
+        if !self.enabled || wallets < MIN_WALLETS {
+            return;
+        }
+        self.bar.set_length(wallets as u64);
+        self.bar.set_style(
+            ProgressStyle::with_template(
+                "{spinner} [{bar:30}] {pos}/{len} wallets, ETA {eta} {wide_msg}",
+            )
+            .expect("valid progress template")
+            .progress_chars("=> "),
+        );
+        self.bar.set_draw_target(ProgressDrawTarget::stderr());
+        self.bar.enable_steady_tick(Duration::from_millis(100));
+    }
+
+    fn wallet_done(&self, wallet: &WalletInfo, error: Option<&anyhow::Error>) {
+        let name = wallet
+            .label
+            .clone()
+            .unwrap_or_else(|| short_address(&wallet.address));
+        let failed = if error.is_some() {
+            self.failed.fetch_add(1, Ordering::Relaxed) + 1
+        } else {
+            self.failed.load(Ordering::Relaxed)
+        };
+        self.bar.inc(1);
+        let status = if error.is_some() { "failed" } else { "done" };
+        if failed > 0 {
+            self.bar
+                .set_message(format!("{} {} ({} failed)", name, status, failed));
+        } else {
+            self.bar.set_message(format!("{} {}", name, status));
+        }
+    }
+
+    fn finish(&self) {
+        self.bar.finish_and_clear();
+    }
+}
@@ -0,0 +1,133 @@
+use crate::config::{Expansion, TokenConfig, WalletInfo};
+use crate::fetcher::BalanceFetcher;
+use crate::metadata::short_address;
+use solana_account_decoder_client_types::{UiAccountEncoding, UiDataSliceConfig};
+use solana_client::rpc_config::{RpcAccountInfoConfig, RpcProgramAccountsConfig};
+use solana_client::rpc_filter::{Memcmp, RpcFilterType};
+use solana_sdk::pubkey;
+use solana_sdk::pubkey::Pubkey;
+use std::str::FromStr;
+
+/// The SPL Governance program most realms, including those created in the
+/// Realms app, belong to.
+pub const GOVERNANCE_PROGRAM_ID: Pubkey = pubkey!("GovER5Lthms3bLBqWub97yVrMmEogzX7xNjdXpPPCVZw");
+
+/// Offset of the realm in a governance account, after its account type.
+const GOVERNANCE_REALM_OFFSET: usize = 1;
+
+/// Account types of governances, V1 and V2: account, program, mint and
+/// token governances.
+const GOVERNANCE_ACCOUNT_TYPES: [u8; 8] = [3, 4, 9, 10, 18, 19, 20, 21];
+
+/// The SOL treasury of `governance`, which proposals spend from.
+pub fn native_treasury_address(governance: &Pubkey, program_id: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"native-treasury", governance.as_ref()], program_id).0
+}
+
+impl BalanceFetcher {
+    /// Replaces each realm entry in `config` with its governances and their
+    /// native treasuries, all labelled after the realm, and adds a group
+    /// named after the realm that sums them.
+    pub async fn expand_realms(&self, config: &mut TokenConfig) -> Result<(), anyhow::Error> {
+        if !config
+            .wallets
+            .iter()
+            .any(|wallet| matches!(wallet.expand, Some(Expansion::Realm { .. })))
+        {
+            return Ok(());
+        }
+
+        let mut wallets = Vec::with_capacity(config.wallets.len());
+        for wallet in std::mem::take(&mut config.wallets) {
+            let Some(Expansion::Realm { program_id }) = &wallet.expand else {
+                wallets.push(wallet);
+                continue;
+            };
+            let realm = Pubkey::from_str(&wallet.address).map_err(|err| {
+                anyhow::anyhow!("invalid realm address `{}`: {}", wallet.address, err)
+            })?;
+            let program_id = match program_id {
+                Some(program_id) => Pubkey::from_str(program_id).map_err(|err| {
+                    anyhow::anyhow!("invalid governance program `{}`: {}", program_id, err)
+                })?,
+                None => GOVERNANCE_PROGRAM_ID,
+            };
+            let label = wallet
+                .label
+                .clone()
+                .unwrap_or_else(|| format!("Realm {}", short_address(&wallet.address)));
+
+            let governances = self.get_governances(&realm, &program_id).await?;
+            if governances.is_empty() {
+                anyhow::bail!("realm {} has no governances", wallet.address);
+            }
+            for governance in governances {
+                let treasury = native_treasury_address(&governance, &program_id);
+                for (address, kind) in [(treasury, "treasury"), (governance, "governance")] {
+                    wallets.push(WalletInfo {
+                        label: Some(format!(
+                            "{} {} {}",
+                            label,
+                            kind,
+                            short_address(&governance.to_string())
+                        )),
+                        realm: Some(wallet.address.clone()),
+                        tokens: wallet.tokens.clone(),
+                        cluster: wallet.cluster.clone(),
+                        ..WalletInfo::new(address.to_string())
+                    });
+                }
+            }
+            config
+                .groups
+                .entry(label)
+                .or_insert_with(|| vec![wallet.address.clone()]);
+        }
+        config.wallets = wallets;
+        Ok(())
+    }
+
+    /// Governance accounts of `realm`, which own its token treasuries.
+    async fn get_governances(
+        &self,
+        realm: &Pubkey,
+        program_id: &Pubkey,
+    ) -> Result<Vec<Pubkey>, anyhow::Error> {
+        // Token owner records also start with the realm; only the account
+        // type is fetched to tell them apart.
+        let config = RpcProgramAccountsConfig {
+            filters: Some(vec![RpcFilterType::Memcmp(Memcmp::new_base58_encoded(
+                GOVERNANCE_REALM_OFFSET,
+                realm.as_ref(),
+            ))]),
+            account_config: RpcAccountInfoConfig {
+                encoding: Some(UiAccountEncoding::Base64),
+                data_slice: Some(UiDataSliceConfig {
+                    offset: 0,
+                    length: 1,
+                }),
+                commitment: Some(self.client.commitment()),
+                ..RpcAccountInfoConfig::default()
+            },
+            ..RpcProgramAccountsConfig::default()
+        };
+        let accounts = {
+            let _permit = self.permit().await?;
+            self.client
+                .get_program_accounts_with_config(program_id, config)
+                .await?
+        };
+        let mut governances: Vec<Pubkey> = accounts
+            .into_iter()
+            .filter(|(_, account)| {
+                account
+                    .data
+                    .first()
+                    .is_some_and(|kind| GOVERNANCE_ACCOUNT_TYPES.contains(kind))
+            })
+            .map(|(address, _)| address)
+            .collect();
+        governances.sort();
+        Ok(governances)
+    }
+}
@@ -0,0 +1,151 @@
+use crate::config::TokenConfig;
+use crate::diff::SOL_ASSET;
+use crate::report::BalanceReport;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// The balance a wallet should hold of an asset: an exact amount, or a
+/// range with either end open.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum ExpectedBalance {
+    Exact(f64),
+    Range {
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        min: Option<f64>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        max: Option<f64>,
+    },
+}
+
+impl ExpectedBalance {
+    pub fn matches(&self, balance: f64) -> bool {
+        match *self {
+            ExpectedBalance::Exact(amount) => balance == amount,
+            ExpectedBalance::Range { min, max } => {
+                min.is_none_or(|min| balance >= min) && max.is_none_or(|max| balance <= max)
+            }
+        }
+    }
+}
+
+impl fmt::Display for ExpectedBalance {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            ExpectedBalance::Exact(amount) => write!(f, "{}", amount),
+            ExpectedBalance::Range {
+                min: Some(min),
+                max: Some(max),
+            } => write!(f, "{} to {}", min, max),
+            ExpectedBalance::Range {
+                min: Some(min),
+                max: None,
+            } => write!(f, "at least {}", min),
+            ExpectedBalance::Range {
+                min: None,
+                max: Some(max),
+            } => write!(f, "at most {}", max),
+            ExpectedBalance::Range {
+                min: None,
+                max: None,
+            } => f.write_str("any amount"),
+        }
+    }
+}
+
+/// A balance that is not what the config expects, or could not be read.
+#[derive(Debug, Clone, Serialize)]
+pub struct Discrepancy {
+    pub wallet: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub label: Option<String>,
+    pub asset: String,
+    /// `None` when the wallet could not be fetched.
+    pub balance: Option<f64>,
+    pub expected: ExpectedBalance,
+}
+
+impl fmt::Display for Discrepancy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.label {
+            Some(label) => write!(f, "{} ({})", label, self.wallet)?,
+            None => write!(f, "{}", self.wallet)?,
+        }
+        match self.balance {
+            Some(balance) => write!(
+                f,
+                ": {} balance {}, expected {}",
+                self.asset, balance, self.expected
+            ),
+            None => write!(
+                f,
+                ": {} balance could not be fetched, expected {}",
+                self.asset, self.expected
+            ),
+        }
+    }
+}
+
+/// The outcome of comparing a report against `expected_balances`.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct Reconciliation {
+    /// Number of wallet and asset pairs compared.
+    pub checked: usize,
+    pub discrepancies: Vec<Discrepancy>,
+}
+
+/// Compares the fetched balances against the config's `expected_balances`,
+/// whose wallets are keyed by address, `.sol` domain or label and assets by
+/// `SOL`, ticker or mint. Tokens a wallet holds none of count as zero, and
+/// wallets missing from the report are discrepancies too.
+pub fn reconcile(config: &TokenConfig, report: &BalanceReport) -> Reconciliation {
+    let mut names: Vec<&String> = config.expected_balances.keys().collect();
+    names.sort();
+
+    let mut reconciliation = Reconciliation::default();
+    for name in names {
+        let mut assets: Vec<_> = config.expected_balances[name].iter().collect();
+        assets.sort_by(|a, b| a.0.cmp(b.0));
+
+        let fetched = report.wallets.iter().find(|(address, result)| {
+            *address == name
+                || result.domain.as_ref() == Some(name)
+                || result.label.as_ref() == Some(name)
+        });
+        for (asset, expected) in assets {
+            reconciliation.checked += 1;
+            let Some((address, result)) = fetched else {
+                let info = config.wallets.iter().find(|info| info.is_named(name));
+                reconciliation.discrepancies.push(Discrepancy {
+                    wallet: info.map_or_else(|| name.clone(), |info| info.address.clone()),
+                    label: info.and_then(|info| info.label.clone()),
+                    asset: asset.clone(),
+                    balance: None,
+                    expected: *expected,
+                });
+                continue;
+            };
+
+            let balance = if asset == SOL_ASSET {
+                result.sol_balance
+            } else {
+                result
+                    .token_balances
+                    .iter()
+                    .find(|(ticker, balance)| *ticker == asset || &balance.mint == asset)
+                    .map_or(0.0, |(_, balance)| balance.amount)
+            };
+            if !expected.matches(balance) {
+                reconciliation.discrepancies.push(Discrepancy {
+                    wallet: address.clone(),
+                    label: result.label.clone(),
+                    asset: asset.clone(),
+                    balance: Some(balance),
+                    expected: *expected,
+                });
+            }
+        }
+    }
+
+    reconciliation
+}
@@ -0,0 +1,69 @@
+use crate::fetcher::BalanceFetcher;
+use crate::token_account::TOKEN_PROGRAM_IDS;
+use futures::future::try_join_all;
+use serde::{Deserialize, Serialize};
+use solana_client::rpc_request::TokenAccountsFilter;
+use solana_sdk::clock::Slot;
+use solana_sdk::native_token::lamports_to_sol;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::rent::Rent;
+
+/// Size of a token account without extensions, for accounts the RPC node
+/// does not report the size of.
+const TOKEN_ACCOUNT_LEN: u64 = 165;
+
+/// How much of a wallet's SOL is tied up in rent-exempt reserves.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RentBreakdown {
+    /// SOL the wallet's own account must keep to stay rent-exempt.
+    pub reserve: f64,
+    /// SOL the wallet can move without closing its account.
+    pub spendable: f64,
+    pub token_accounts: usize,
+    /// Rent-exempt reserves held by the wallet's token accounts, which come
+    /// back when they are closed. Not part of `sol_balance`.
+    pub token_account_rent: f64,
+}
+
+impl BalanceFetcher {
+    /// Splits `sol_lamports` into the rent-exempt reserve and the spendable
+    /// rest, and sums the rent locked in the wallet's token accounts.
+    /// Reserves use the default rent parameters, which every public cluster
+    /// runs with.
+    pub async fn get_rent_breakdown(
+        &self,
+        wallet_pubkey: &Pubkey,
+        sol_lamports: u64,
+        min_context_slot: Option<Slot>,
+    ) -> Result<RentBreakdown, anyhow::Error> {
+        let rent = Rent::default();
+        let token_accounts = try_join_all(TOKEN_PROGRAM_IDS.iter().map(|program_id| {
+            self.get_token_accounts(
+                wallet_pubkey,
+                TokenAccountsFilter::ProgramId(*program_id),
+                min_context_slot,
+            )
+        }))
+        .await?
+        .concat();
+
+        // A wrapped SOL account's lamports include the wrapped amount, so
+        // only the reserve its size requires counts as rent.
+        let token_account_rent = token_accounts
+            .iter()
+            .map(|account| {
+                let space = account.account.space.unwrap_or(TOKEN_ACCOUNT_LEN);
+                let reserve = rent.minimum_balance(space as usize);
+                account.account.lamports.min(reserve)
+            })
+            .fold(0u64, u64::saturating_add);
+
+        let reserve = sol_lamports.min(rent.minimum_balance(0));
+        Ok(RentBreakdown {
+            reserve: lamports_to_sol(reserve),
+            spendable: lamports_to_sol(sol_lamports - reserve),
+            token_accounts: token_accounts.len(),
+            token_account_rent: lamports_to_sol(token_account_rent),
+        })
+    }
+}
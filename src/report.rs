@@ -0,0 +1,473 @@
+use crate::group::GroupSummary;
+use crate::nft::NftSummary;
+use crate::output::SortOrder;
+use crate::pricing::Currency;
+use crate::rent::RentBreakdown;
+use crate::stake::StakeSummary;
+use crate::token_account::{format_ui_amount, ui_amount, Delegation, TokenAccountDetail};
+use crate::vesting::VestingBalance;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize, Serializer};
+use solana_sdk::native_token::lamports_to_sol;
+use solana_sdk::pubkey::Pubkey;
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::Path;
+use std::str::FromStr;
+
+/// Serializes `wallets` in `wallet_order`, then any others by address.
+#[derive(Debug, Clone, Deserialize)]
+pub struct BalanceReport {
+    /// When the report was fetched.
+    pub timestamp: DateTime<Utc>,
+    /// Slot observed at the start of the fetch, if the node reported one.
+    pub slot: Option<u64>,
+    /// Whether every balance was read at or after `slot`.
+    #[serde(default)]
+    pub same_slot: bool,
+    /// Whether balances were reconstructed as of `slot` from transaction
+    /// history instead of read live.
+    #[serde(default)]
+    pub historical: bool,
+    pub wallets: HashMap<String, BalanceResult>,
+    /// Addresses of the fetched wallets in the order the config lists them.
+    #[serde(skip)]
+    pub wallet_order: Vec<String>,
+    /// Wallets that could not be fetched, with the reason.
+    #[serde(default)]
+    pub errors: BTreeMap<String, String>,
+    /// Subtotals of the wallet groups from the config.
+    #[serde(default)]
+    pub groups: BTreeMap<String, GroupSummary>,
+    /// Sum of `total_usd` over all wallets, when prices were fetched.
+    pub total_usd: Option<f64>,
+    /// The currency every `usd` value is in when it is not USD, from
+    /// `pricing.display_currency`.
+    #[serde(default)]
+    pub currency: Option<Currency>,
+}
+
+#[derive(Serialize)]
+struct SerializedReport<'a> {
+    timestamp: &'a DateTime<Utc>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    slot: &'a Option<u64>,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    same_slot: bool,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    historical: bool,
+    wallets: OrderedWallets<'a>,
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    errors: &'a BTreeMap<String, String>,
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    groups: &'a BTreeMap<String, GroupSummary>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    total_usd: &'a Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    currency: &'a Option<Currency>,
+}
+
+impl Serialize for BalanceReport {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        SerializedReport {
+            timestamp: &self.timestamp,
+            slot: &self.slot,
+            same_slot: self.same_slot,
+            historical: self.historical,
+            wallets: OrderedWallets(self),
+            errors: &self.errors,
+            groups: &self.groups,
+            total_usd: &self.total_usd,
+            currency: &self.currency,
+        }
+        .serialize(serializer)
+    }
+}
+
+/// A report's wallets in config order, which stays put between runs so
+/// saved reports diff cleanly.
+struct OrderedWallets<'a>(&'a BalanceReport);
+
+impl Serialize for OrderedWallets<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_map(self.0.sorted_wallets(SortOrder::Config))
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BalanceResult {
+    /// Nickname given to the wallet in the config.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub label: Option<String>,
+    /// The `.sol` domain the wallet was configured as, or its primary
+    /// domain when reverse lookups are enabled.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub domain: Option<String>,
+    /// The Squads multisig the wallet is a vault of.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub multisig: Option<String>,
+    /// The SPL Governance realm the wallet is a treasury of.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub realm: Option<String>,
+    /// The entry of the config's `clusters` the wallet is on, if it is not
+    /// on the main one.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cluster: Option<String>,
+    pub sol_balance: f64,
+    pub sol_lamports: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sol_usd_value: Option<f64>,
+    /// Wrapped SOL included in `sol_balance`, when `merge_wrapped_sol` is
+    /// set.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub wrapped_sol: Option<f64>,
+    /// How much of the SOL is rent-exempt reserve, when requested with
+    /// `rent_breakdown`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rent: Option<RentBreakdown>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stake: Option<StakeSummary>,
+    /// NFTs the wallet owns, when `nfts` is configured.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub nfts: Option<NftSummary>,
+    /// Tokens vesting to the wallet in Streamflow streams, by ticker, for
+    /// `vesting` wallet entries.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub vesting: Option<BTreeMap<String, VestingBalance>>,
+    #[serde(serialize_with = "serialize_sorted")]
+    pub token_balances: HashMap<String, TokenBalance>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub total_usd: Option<f64>,
+}
+
+/// Serializes a map with its keys sorted, so saved reports diff cleanly.
+fn serialize_sorted<S: Serializer, V: Serialize>(
+    map: &HashMap<String, V>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    map.iter().collect::<BTreeMap<_, _>>().serialize(serializer)
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TokenBalance {
+    pub mint: String,
+    /// Token name from the token list or on-chain metadata, when the ticker
+    /// came from there.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub logo_uri: Option<String>,
+    /// UI amount as a float; convenient but lossy for large balances.
+    pub amount: f64,
+    /// Exact total in base units.
+    pub raw_amount: u64,
+    pub decimals: u8,
+    /// Exact UI amount, e.g. `"1234.000001"`.
+    pub ui_amount_string: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub usd_value: Option<f64>,
+    /// What a liquid staking token balance is worth in SOL at its pool's
+    /// current exchange rate.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sol_value: Option<f64>,
+    /// The part of the balance in frozen accounts, which cannot be moved
+    /// until the mint's freeze authority thaws them.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub frozen_amount: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub frozen_raw_amount: Option<u64>,
+    /// Approvals letting someone other than the owner move these tokens.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub delegations: Vec<Delegation>,
+    /// The token accounts holding the balance, when requested with
+    /// `token_account_details`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub accounts: Vec<TokenAccountDetail>,
+}
+
+impl TokenBalance {
+    pub fn new(mint: String, raw_amount: u64, decimals: u8) -> Self {
+        TokenBalance {
+            mint,
+            name: None,
+            logo_uri: None,
+            amount: ui_amount(raw_amount, decimals),
+            raw_amount,
+            decimals,
+            ui_amount_string: format_ui_amount(raw_amount, decimals),
+            usd_value: None,
+            sol_value: None,
+            frozen_amount: None,
+            frozen_raw_amount: None,
+            delegations: Vec::new(),
+            accounts: Vec::new(),
+        }
+    }
+
+    /// Counts `raw_amount` from a frozen token account as frozen.
+    pub(crate) fn add_frozen(&mut self, raw_amount: u64) {
+        if raw_amount == 0 {
+            return;
+        }
+        let frozen = self
+            .frozen_raw_amount
+            .unwrap_or(0)
+            .saturating_add(raw_amount);
+        self.frozen_raw_amount = Some(frozen);
+        self.frozen_amount = Some(ui_amount(frozen, self.decimals));
+    }
+
+    /// Adds a delegation found on one of the balance's token accounts.
+    pub(crate) fn add_delegation(&mut self, account: String, delegate: Pubkey, raw_amount: u64) {
+        self.delegations.push(Delegation {
+            account,
+            delegate: delegate.to_string(),
+            raw_amount,
+            amount: ui_amount(raw_amount, self.decimals),
+        });
+    }
+
+    /// How much delegates can pull from the balance, in UI units.
+    pub fn delegated_amount(&self) -> f64 {
+        let raw_amount = self.delegations.iter().fold(0u64, |total, delegation| {
+            total.saturating_add(delegation.raw_amount)
+        });
+        ui_amount(raw_amount, self.decimals)
+    }
+}
+
+impl BalanceResult {
+    /// The token balances, keyed by ticker: largest USD value first for
+    /// [`SortOrder::Usd`], otherwise alphabetically.
+    pub fn sorted_tokens(&self, order: SortOrder) -> Vec<(&String, &TokenBalance)> {
+        let mut tokens: Vec<_> = self.token_balances.iter().collect();
+        tokens.sort_by_cached_key(|(ticker, _)| (ticker.to_lowercase(), ticker.to_string()));
+        if order == SortOrder::Usd {
+            // Stable, so unpriced tokens stay alphabetical after the rest.
+            tokens.sort_by(|a, b| match (a.1.usd_value, b.1.usd_value) {
+                (Some(x), Some(y)) => y.partial_cmp(&x).unwrap_or(Ordering::Equal),
+                (Some(_), None) => Ordering::Less,
+                (None, Some(_)) => Ordering::Greater,
+                (None, None) => Ordering::Equal,
+            });
+        }
+        tokens
+    }
+
+    /// SOL held directly, staked, wrapped and as liquid staking tokens.
+    pub fn sol_exposure(&self) -> f64 {
+        let native_mint = spl_token::native_mint::ID.to_string();
+        let tokens: f64 = self
+            .token_balances
+            .values()
+            .map(|balance| {
+                if balance.mint == native_mint {
+                    balance.amount
+                } else {
+                    balance.sol_value.unwrap_or(0.0)
+                }
+            })
+            .sum();
+        self.sol_balance + self.stake.as_ref().map_or(0.0, |stake| stake.total) + tokens
+    }
+}
+
+impl BalanceReport {
+    /// The wallets, keyed by address, in the requested order.
+    pub fn sorted_wallets(&self, order: SortOrder) -> Vec<(&String, &BalanceResult)> {
+        let mut wallets: Vec<_> = self.wallets.iter().collect();
+        match order {
+            SortOrder::Config => wallets.sort_by_cached_key(|(wallet, _)| {
+                let position = self.wallet_order.iter().position(|known| known == *wallet);
+                (position.unwrap_or(usize::MAX), wallet.to_string())
+            }),
+            SortOrder::Address => wallets.sort_by(|a, b| a.0.cmp(b.0)),
+            SortOrder::Label => wallets.sort_by_cached_key(|(wallet, result)| {
+                let name = result
+                    .label
+                    .as_ref()
+                    .or(result.domain.as_ref())
+                    .unwrap_or(wallet);
+                (name.to_lowercase(), wallet.to_string())
+            }),
+            // Largest first; wallets without prices go last.
+            SortOrder::Usd => wallets.sort_by(|a, b| {
+                match (a.1.total_usd, b.1.total_usd) {
+                    (Some(x), Some(y)) => y.partial_cmp(&x).unwrap_or(Ordering::Equal),
+                    (Some(_), None) => Ordering::Less,
+                    (None, Some(_)) => Ordering::Greater,
+                    (None, None) => Ordering::Equal,
+                }
+                .then_with(|| a.0.cmp(b.0))
+            }),
+        }
+        wallets
+    }
+
+    pub fn new(wallets: HashMap<String, BalanceResult>) -> Self {
+        BalanceReport {
+            timestamp: Utc::now(),
+            slot: None,
+            same_slot: false,
+            historical: false,
+            wallets,
+            wallet_order: Vec::new(),
+            errors: BTreeMap::new(),
+            groups: BTreeMap::new(),
+            total_usd: None,
+            currency: None,
+        }
+    }
+
+    /// The currency values are in: `currency`, or else USD.
+    pub fn display_currency(&self) -> Currency {
+        self.currency.clone().unwrap_or_default()
+    }
+
+    /// Adds the wallets and errors of `other`, a report of other wallets
+    /// fetched at the same time.
+    pub(crate) fn merge(&mut self, other: BalanceReport) {
+        self.wallets.extend(other.wallets);
+        self.wallet_order.extend(other.wallet_order);
+        self.errors.extend(other.errors);
+        self.currency = self.currency.take().or(other.currency);
+        self.total_usd = match (self.total_usd, other.total_usd) {
+            (None, None) => None,
+            (ours, theirs) => Some(ours.unwrap_or(0.0) + theirs.unwrap_or(0.0)),
+        };
+    }
+
+    /// Reads a report previously written with [`BalanceReport::save`].
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, anyhow::Error> {
+        let path = path.as_ref();
+        let content = fs::read_to_string(path)
+            .map_err(|err| anyhow::anyhow!("reading {}: {}", path.display(), err))?;
+        serde_json::from_str(&content)
+            .map_err(|err| anyhow::anyhow!("parsing {}: {}", path.display(), err))
+    }
+
+    /// Writes the report as pretty-printed JSON.
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), anyhow::Error> {
+        fs::write(path, serde_json::to_string_pretty(self)? + "\n")?;
+        Ok(())
+    }
+
+    /// True when at least one wallet was requested and none succeeded.
+    pub fn all_failed(&self) -> bool {
+        self.wallets.is_empty() && !self.errors.is_empty()
+    }
+
+    /// Moves wrapped SOL balances into `sol_balance`, remembering the
+    /// wrapped part in `wrapped_sol`.
+    pub fn merge_wrapped_sol(&mut self) {
+        let native_mint = spl_token::native_mint::ID.to_string();
+        for result in self.wallets.values_mut() {
+            let mut wrapped = 0u64;
+            result.token_balances.retain(|_, balance| {
+                if balance.mint != native_mint {
+                    return true;
+                }
+                wrapped = wrapped.saturating_add(balance.raw_amount);
+                false
+            });
+            if wrapped > 0 {
+                result.sol_lamports = result.sol_lamports.saturating_add(wrapped);
+                result.sol_balance = lamports_to_sol(result.sol_lamports);
+                result.wrapped_sol = Some(lamports_to_sol(wrapped));
+            }
+        }
+    }
+
+    /// Every token mint appearing in the report.
+    pub fn mints(&self) -> Vec<Pubkey> {
+        let mut mints: Vec<Pubkey> = self
+            .wallets
+            .values()
+            .flat_map(|result| result.token_balances.values())
+            .filter_map(|token| Pubkey::from_str(&token.mint).ok())
+            .collect();
+        mints.sort();
+        mints.dedup();
+        mints
+    }
+
+    /// Drops token balances for which `keep` returns false, updating the USD
+    /// totals if the report has been priced.
+    pub fn retain_tokens(&mut self, mut keep: impl FnMut(&str, &TokenBalance) -> bool) {
+        for result in self.wallets.values_mut() {
+            result
+                .token_balances
+                .retain(|ticker, balance| keep(ticker, balance));
+            if result.total_usd.is_some() {
+                let tokens_usd: f64 = result
+                    .token_balances
+                    .values()
+                    .filter_map(|token| token.usd_value)
+                    .sum();
+                result.total_usd = Some(result.sol_usd_value.unwrap_or(0.0) + tokens_usd);
+            }
+        }
+        if self.total_usd.is_some() {
+            self.total_usd = Some(
+                self.wallets
+                    .values()
+                    .filter_map(|result| result.total_usd)
+                    .sum(),
+            );
+        }
+    }
+
+    /// Fills in USD values from `prices`, keyed by mint (SOL under the
+    /// native mint). Assets without a price are left unvalued and do not
+    /// contribute to the totals.
+    pub fn apply_prices(&mut self, prices: &HashMap<Pubkey, f64>) {
+        let sol_price = prices.get(&spl_token::native_mint::ID).copied();
+        let mut grand_total = 0.0;
+
+        for result in self.wallets.values_mut() {
+            result.sol_usd_value = sol_price.map(|price| price * result.sol_balance);
+            let mut wallet_total = result.sol_usd_value.unwrap_or(0.0);
+
+            for token in result.token_balances.values_mut() {
+                token.usd_value = Pubkey::from_str(&token.mint)
+                    .ok()
+                    .and_then(|mint| prices.get(&mint))
+                    .map(|price| price * token.amount);
+                wallet_total += token.usd_value.unwrap_or(0.0);
+            }
+
+            // An estimate, so kept out of the wallet's total.
+            if let Some(nfts) = &mut result.nfts {
+                nfts.usd_value = sol_price
+                    .zip(nfts.value)
+                    .map(|(price, value)| price * value);
+            }
+
+            result.total_usd = Some(wallet_total);
+            grand_total += wallet_total;
+        }
+
+        self.total_usd = Some(grand_total);
+    }
+
+    /// The USD price per token (and per SOL) implied by the priced, non-zero
+    /// balances, for revaluing balances without asking the provider again.
+    pub(crate) fn unit_prices(&self) -> HashMap<Pubkey, f64> {
+        let mut prices = HashMap::new();
+        for result in self.wallets.values() {
+            if let Some(value) = result.sol_usd_value.filter(|_| result.sol_balance > 0.0) {
+                prices.insert(spl_token::native_mint::ID, value / result.sol_balance);
+            }
+            for token in result.token_balances.values() {
+                let Some(value) = token.usd_value.filter(|_| token.amount > 0.0) else {
+                    continue;
+                };
+                if let Ok(mint) = Pubkey::from_str(&token.mint) {
+                    prices.insert(mint, value / token.amount);
+                }
+            }
+        }
+        prices
+    }
+}
@@ -0,0 +1,140 @@
+use crate::config::TokenConfig;
+use crate::fetcher::{BalanceFetcher, MAX_MULTIPLE_ACCOUNTS};
+use futures::future::join_all;
+use serde::Serialize;
+use solana_sdk::clock::Epoch;
+use solana_sdk::native_token::lamports_to_sol;
+use solana_sdk::pubkey::Pubkey;
+use std::collections::BTreeMap;
+use std::ops::RangeInclusive;
+
+/// Staking rewards credited to each wallet's stake accounts over a range of
+/// epochs.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct RewardsReport {
+    pub first_epoch: Epoch,
+    pub last_epoch: Epoch,
+    /// Wallets with at least one stake account, keyed by address.
+    pub wallets: BTreeMap<String, WalletRewards>,
+    /// SOL earned by every wallet, per epoch.
+    pub epochs: BTreeMap<Epoch, f64>,
+    pub total: f64,
+    /// Wallets that could not be fetched, with the reason.
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub errors: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct WalletRewards {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub label: Option<String>,
+    pub stake_accounts: usize,
+    /// SOL earned per epoch. Epochs without rewards are left out.
+    pub epochs: BTreeMap<Epoch, f64>,
+    pub total: f64,
+}
+
+impl BalanceFetcher {
+    /// The last `count` epochs that have finished, which are the ones
+    /// rewards have been paid for.
+    pub async fn completed_epochs(
+        &self,
+        count: u64,
+    ) -> Result<RangeInclusive<Epoch>, anyhow::Error> {
+        let epoch = {
+            let _permit = self.permit().await?;
+            self.client.get_epoch_info().await?.epoch
+        };
+        if epoch == 0 {
+            anyhow::bail!("no epoch has finished yet");
+        }
+        Ok(epoch.saturating_sub(count.max(1))..=epoch - 1)
+    }
+
+    /// Sums the inflation rewards credited to the stake accounts of every
+    /// wallet in `epochs`.
+    pub async fn fetch_rewards_report(
+        &self,
+        config: &TokenConfig,
+        epochs: RangeInclusive<Epoch>,
+    ) -> Result<RewardsReport, anyhow::Error> {
+        let results = join_all(config.wallets.iter().map(|wallet| async {
+            let pubkey = self.resolve_wallet(&wallet.address).await?;
+            let accounts = self.get_stake_accounts(&pubkey, None).await?;
+            Ok::<_, anyhow::Error>((pubkey, accounts.into_keys().collect::<Vec<_>>()))
+        }))
+        .await;
+
+        let mut report = RewardsReport {
+            first_epoch: *epochs.start(),
+            last_epoch: *epochs.end(),
+            ..RewardsReport::default()
+        };
+        let mut owners: Vec<(String, Pubkey)> = Vec::new();
+        for (wallet, result) in config.wallets.iter().zip(results) {
+            let (pubkey, accounts) = match result {
+                Ok(result) => result,
+                Err(err) => {
+                    report
+                        .errors
+                        .insert(wallet.address.clone(), format!("{:#}", err));
+                    continue;
+                }
+            };
+            if accounts.is_empty() {
+                continue;
+            }
+            report.wallets.entry(pubkey.to_string()).or_default().label = wallet.label.clone();
+            owners.extend(
+                accounts
+                    .into_iter()
+                    .map(|account| (pubkey.to_string(), account)),
+            );
+        }
+        // An account found through both its staker and withdrawer wallets
+        // counts once, for the first of them.
+        owners.sort_by_key(|(_, account)| *account);
+        owners.dedup_by_key(|(_, account)| *account);
+        for (wallet, _) in &owners {
+            if let Some(rewards) = report.wallets.get_mut(wallet) {
+                rewards.stake_accounts += 1;
+            }
+        }
+        report
+            .wallets
+            .retain(|_, rewards| rewards.stake_accounts > 0);
+        if owners.is_empty() {
+            return Ok(report);
+        }
+
+        let addresses: Vec<Pubkey> = owners.iter().map(|(_, account)| *account).collect();
+        for epoch in epochs {
+            let mut rewards = Vec::with_capacity(addresses.len());
+            for chunk in addresses.chunks(MAX_MULTIPLE_ACCOUNTS) {
+                let _permit = self.permit().await?;
+                rewards.extend(
+                    self.client
+                        .get_inflation_reward(chunk, Some(epoch))
+                        .await
+                        .map_err(|err| {
+                            anyhow::anyhow!("fetching rewards for epoch {}: {}", epoch, err)
+                        })?,
+                );
+            }
+            for ((wallet, _), reward) in owners.iter().zip(rewards) {
+                let Some(reward) = reward.filter(|reward| reward.amount > 0) else {
+                    continue;
+                };
+                let Some(wallet) = report.wallets.get_mut(wallet) else {
+                    continue;
+                };
+                let amount = lamports_to_sol(reward.amount);
+                *wallet.epochs.entry(epoch).or_default() += amount;
+                wallet.total += amount;
+                *report.epochs.entry(epoch).or_default() += amount;
+                report.total += amount;
+            }
+        }
+        Ok(report)
+    }
+}
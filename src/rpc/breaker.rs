@@ -0,0 +1,199 @@
+use crate::rpc::url_host;
+use serde::Deserialize;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// When [`PoolSender`](crate::rpc::PoolSender) stops sending to a failing
+/// endpoint.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct CircuitBreakerConfig {
+    /// Consecutive transient failures that open an endpoint's breaker;
+    /// 0 turns breakers off.
+    pub failure_threshold: u32,
+    /// How long an open breaker skips its endpoint before letting one
+    /// request through to test it.
+    #[serde(with = "humantime_serde")]
+    pub cooldown: Duration,
+}
+
+impl Default for CircuitBreakerConfig {
+    fn default() -> Self {
+        CircuitBreakerConfig {
+            failure_threshold: 5,
+            cooldown: Duration::from_secs(30),
+        }
+    }
+}
+
+/// Where an endpoint's breaker stands.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BreakerState {
+    /// Requests go through.
+    Closed,
+    /// The endpoint is skipped until the cooldown ends.
+    Open,
+    /// The cooldown ended; the next request tests the endpoint.
+    HalfOpen,
+}
+
+impl BreakerState {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            BreakerState::Closed => "closed",
+            BreakerState::Open => "open",
+            BreakerState::HalfOpen => "half_open",
+        }
+    }
+}
+
+/// A breaker's state, for logs and metrics.
+#[derive(Debug, Clone)]
+pub struct EndpointStatus {
+    /// The endpoint's host, as the full URL may carry an API key.
+    pub host: String,
+    pub state: BreakerState,
+    pub consecutive_failures: u32,
+    /// Times the breaker opened.
+    pub trips: u64,
+}
+
+#[derive(Debug, Default)]
+struct Counts {
+    consecutive_failures: u32,
+    open_until: Option<Instant>,
+    /// Whether the request testing a half-open endpoint is in flight.
+    probing: bool,
+    trips: u64,
+}
+
+/// Counts one endpoint's consecutive transient failures and opens once
+/// there are too many, so the pool skips the endpoint for a cooldown.
+#[derive(Debug)]
+pub(crate) struct CircuitBreaker {
+    host: String,
+    config: CircuitBreakerConfig,
+    counts: Mutex<Counts>,
+}
+
+impl CircuitBreaker {
+    pub fn new(url: &str, config: CircuitBreakerConfig) -> Self {
+        CircuitBreaker {
+            host: url_host(url),
+            config,
+            counts: Mutex::default(),
+        }
+    }
+
+    pub fn state(&self) -> BreakerState {
+        match self.counts.lock().unwrap().open_until {
+            Some(until) if Instant::now() < until => BreakerState::Open,
+            Some(_) => BreakerState::HalfOpen,
+            None => BreakerState::Closed,
+        }
+    }
+
+    /// Lets a request go to the endpoint, unless the breaker is open. Once
+    /// the cooldown has passed, one request at a time is let through to test
+    /// it; the others are refused until it is answered.
+    pub fn try_acquire(&self) -> Option<Permit<'_>> {
+        let mut counts = self.counts.lock().unwrap();
+        let probe = match counts.open_until {
+            None => false,
+            Some(until) if Instant::now() < until => return None,
+            Some(_) if counts.probing => return None,
+            Some(_) => {
+                counts.probing = true;
+                true
+            }
+        };
+        Some(Permit {
+            breaker: self,
+            probe,
+        })
+    }
+
+    fn succeeded(&self) {
+        let mut counts = self.counts.lock().unwrap();
+        counts.probing = false;
+        if counts.open_until.take().is_some() {
+            tracing::info!(host = %self.host, "rpc endpoint recovered; circuit breaker closed");
+        }
+        counts.consecutive_failures = 0;
+    }
+
+    fn failed(&self) {
+        if self.config.failure_threshold == 0 {
+            return;
+        }
+        let mut counts = self.counts.lock().unwrap();
+        counts.probing = false;
+        counts.consecutive_failures += 1;
+        let half_open = counts
+            .open_until
+            .is_some_and(|until| Instant::now() >= until);
+        if half_open || counts.consecutive_failures == self.config.failure_threshold {
+            counts.open_until = Some(Instant::now() + self.config.cooldown);
+            counts.trips += 1;
+            tracing::warn!(
+                host = %self.host,
+                failures = counts.consecutive_failures,
+                cooldown = %humantime::format_duration(self.config.cooldown),
+                "rpc endpoint keeps failing; circuit breaker open"
+            );
+        }
+    }
+
+    pub fn status(&self) -> EndpointStatus {
+        let state = self.state();
+        let counts = self.counts.lock().unwrap();
+        EndpointStatus {
+            host: self.host.clone(),
+            state,
+            consecutive_failures: counts.consecutive_failures,
+            trips: counts.trips,
+        }
+    }
+}
+
+/// Permission from a [`CircuitBreaker`] to send one request, to be told how
+/// the request went.
+pub(crate) struct Permit<'a> {
+    breaker: &'a CircuitBreaker,
+    /// Whether the request tests a half-open endpoint, and is still
+    /// unanswered.
+    probe: bool,
+}
+
+impl Permit<'_> {
+    pub fn succeeded(mut self) {
+        self.probe = false;
+        self.breaker.succeeded();
+    }
+
+    pub fn failed(mut self) {
+        self.probe = false;
+        self.breaker.failed();
+    }
+}
+
+impl Drop for Permit<'_> {
+    /// Lets another request test the endpoint when this one was cancelled.
+    fn drop(&mut self) {
+        if self.probe {
+            self.breaker.counts.lock().unwrap().probing = false;
+        }
+    }
+}
+
+/// A handle on the breakers of a [`PoolSender`](crate::rpc::PoolSender)'s
+/// endpoints, which stays usable once the pool is inside an `RpcClient`.
+#[derive(Debug, Clone)]
+pub struct Breakers(pub(crate) Arc<[CircuitBreaker]>);
+
+impl Breakers {
+    /// Each endpoint's breaker, in pool order.
+    pub fn statuses(&self) -> Vec<EndpointStatus> {
+        self.0.iter().map(CircuitBreaker::status).collect()
+    }
+}
@@ -0,0 +1,108 @@
+use crate::rpc::BoxedSender;
+use async_trait::async_trait;
+use solana_rpc_client::rpc_sender::{RpcSender, RpcTransportStats};
+use solana_rpc_client_api::client_error::{Error as ClientError, ErrorKind, Result};
+use solana_rpc_client_api::request::RpcRequest;
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex};
+
+/// A request was refused because the run's RPC requests reached
+/// `max_rpc_calls`.
+#[derive(Debug, Clone, thiserror::Error)]
+#[error("the budget of {max} RPC requests is used up; raise `max_rpc_calls` (or --max-rpc-calls) to make more")]
+pub struct BudgetExceeded {
+    pub max: u64,
+}
+
+#[derive(Debug, Default)]
+struct Counts {
+    by_method: BTreeMap<String, u64>,
+    /// Requests since [`RpcCalls::start_run`], which the budget limits.
+    spent: u64,
+    refused: Option<BudgetExceeded>,
+}
+
+/// Counts the RPC requests sent, by method, and refuses the ones over a
+/// budget. One is shared by every fetcher of a command, so it can report
+/// the requests of all of them.
+#[derive(Debug, Default)]
+pub struct RpcCalls {
+    counts: Mutex<Counts>,
+}
+
+impl RpcCalls {
+    /// Counts a request to `method`, unless `max` requests were already
+    /// made this run.
+    pub fn charge(
+        &self,
+        method: &str,
+        max: Option<u64>,
+    ) -> std::result::Result<(), BudgetExceeded> {
+        let mut counts = self.counts.lock().unwrap();
+        if let Some(max) = max.filter(|&max| counts.spent >= max) {
+            let err = BudgetExceeded { max };
+            counts.refused = Some(err.clone());
+            return Err(err);
+        }
+        counts.spent += 1;
+        *counts.by_method.entry(method.to_string()).or_default() += 1;
+        Ok(())
+    }
+
+    /// Starts a new run with the whole budget, as each report gets.
+    pub fn start_run(&self) {
+        let mut counts = self.counts.lock().unwrap();
+        counts.spent = 0;
+        counts.refused = None;
+    }
+
+    /// Whether a request was refused this run.
+    pub fn exceeded(&self) -> Option<BudgetExceeded> {
+        self.counts.lock().unwrap().refused.clone()
+    }
+
+    /// Requests made so far, by method.
+    pub fn by_method(&self) -> BTreeMap<String, u64> {
+        self.counts.lock().unwrap().by_method.clone()
+    }
+
+    pub fn total(&self) -> u64 {
+        self.counts.lock().unwrap().by_method.values().sum()
+    }
+}
+
+/// Counts each request to the wrapped sender in an [`RpcCalls`], failing
+/// the ones over the budget without sending them.
+pub struct BudgetSender {
+    inner: BoxedSender,
+    calls: Arc<RpcCalls>,
+    max: Option<u64>,
+}
+
+impl BudgetSender {
+    pub fn new(inner: BoxedSender, calls: Arc<RpcCalls>, max: Option<u64>) -> Self {
+        BudgetSender { inner, calls, max }
+    }
+}
+
+#[async_trait]
+impl RpcSender for BudgetSender {
+    async fn send(
+        &self,
+        request: RpcRequest,
+        params: serde_json::Value,
+    ) -> Result<serde_json::Value> {
+        self.calls
+            .charge(&request.to_string(), self.max)
+            .map_err(|err| ClientError::from(ErrorKind::Custom(err.to_string())))?;
+        self.inner.send(request, params).await
+    }
+
+    fn get_transport_stats(&self) -> RpcTransportStats {
+        self.inner.get_transport_stats()
+    }
+
+    fn url(&self) -> String {
+        self.inner.url()
+    }
+}
@@ -0,0 +1,134 @@
+use crate::rpc::BoxedSender;
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use solana_rpc_client::rpc_sender::{RpcSender, RpcTransportStats};
+use solana_rpc_client_api::client_error::{Error as ClientError, ErrorKind, Result};
+use solana_rpc_client_api::request::RpcRequest;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Where a fetcher's RPC responses come from besides the network.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Fixtures {
+    /// Answer every request from the fixtures in this directory.
+    Replay(PathBuf),
+    /// Send requests as usual and save each response to this directory.
+    Record(PathBuf),
+}
+
+/// One recorded request and its response, `<method>-<hash>.json` in a
+/// fixture directory.
+#[derive(Serialize, Deserialize)]
+struct Fixture {
+    method: String,
+    params: serde_json::Value,
+    result: serde_json::Value,
+}
+
+/// The fixture file answering `request` with `params`.
+fn fixture_path(dir: &Path, request: RpcRequest, params: &serde_json::Value) -> PathBuf {
+    let method = request.to_string();
+    let mut hasher = Sha256::new();
+    hasher.update(method.as_bytes());
+    hasher.update(params.to_string().as_bytes());
+    let digest = hex::encode(hasher.finalize());
+    dir.join(format!("{}-{}.json", method, &digest[..16]))
+}
+
+/// Answers requests from recorded fixtures, failing those without one, so
+/// runs are deterministic and need no network.
+pub struct FixtureSender {
+    dir: PathBuf,
+}
+
+impl FixtureSender {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        FixtureSender { dir: dir.into() }
+    }
+}
+
+#[async_trait]
+impl RpcSender for FixtureSender {
+    async fn send(
+        &self,
+        request: RpcRequest,
+        params: serde_json::Value,
+    ) -> Result<serde_json::Value> {
+        let path = fixture_path(&self.dir, request, &params);
+        let content = fs::read_to_string(&path).map_err(|err| {
+            ClientError::from(ErrorKind::Custom(format!(
+                "no fixture for {} {} ({}: {})",
+                request,
+                params,
+                path.display(),
+                err
+            )))
+        })?;
+        let fixture: Fixture = serde_json::from_str(&content)?;
+        Ok(fixture.result)
+    }
+
+    fn get_transport_stats(&self) -> RpcTransportStats {
+        RpcTransportStats::default()
+    }
+
+    fn url(&self) -> String {
+        format!("fixture:{}", self.dir.display())
+    }
+}
+
+/// Saves each successful response of the wrapped sender as a fixture that
+/// [`FixtureSender`] can replay.
+pub struct RecordingSender {
+    inner: BoxedSender,
+    dir: PathBuf,
+}
+
+impl RecordingSender {
+    pub fn new(inner: BoxedSender, dir: impl Into<PathBuf>) -> Self {
+        RecordingSender {
+            inner,
+            dir: dir.into(),
+        }
+    }
+
+    fn save(&self, request: RpcRequest, fixture: &Fixture) -> std::io::Result<()> {
+        fs::create_dir_all(&self.dir)?;
+        let path = fixture_path(&self.dir, request, &fixture.params);
+        fs::write(path, serde_json::to_string_pretty(fixture)?)
+    }
+}
+
+#[async_trait]
+impl RpcSender for RecordingSender {
+    async fn send(
+        &self,
+        request: RpcRequest,
+        params: serde_json::Value,
+    ) -> Result<serde_json::Value> {
+        let result = self.inner.send(request, params.clone()).await?;
+        let fixture = Fixture {
+            method: request.to_string(),
+            params,
+            result,
+        };
+        // Not an I/O error, which would be retried as a transport failure.
+        self.save(request, &fixture).map_err(|err| {
+            ClientError::from(ErrorKind::Custom(format!(
+                "recording fixture in {}: {}",
+                self.dir.display(),
+                err
+            )))
+        })?;
+        Ok(fixture.result)
+    }
+
+    fn get_transport_stats(&self) -> RpcTransportStats {
+        self.inner.get_transport_stats()
+    }
+
+    fn url(&self) -> String {
+        self.inner.url()
+    }
+}
@@ -0,0 +1,51 @@
+mod breaker;
+mod budget;
+mod fixture;
+mod pool;
+mod rate_limit;
+mod retry;
+mod trace;
+
+use solana_rpc_client::rpc_sender::RpcSender;
+
+pub use breaker::{BreakerState, Breakers, CircuitBreakerConfig, EndpointStatus};
+pub use budget::{BudgetExceeded, BudgetSender, RpcCalls};
+pub use fixture::{FixtureSender, Fixtures, RecordingSender};
+pub use pool::{PoolSender, RotationStrategy};
+pub use rate_limit::{RateLimitedSender, RateLimiter};
+pub use retry::{is_retryable, RetryPolicy, RetrySender};
+pub use trace::TracingSender;
+
+/// A type-erased transport, so sender layers can be stacked freely.
+pub type BoxedSender = Box<dyn RpcSender + Send + Sync + 'static>;
+
+/// The host of `url`, which is safe to show where the full URL, which may
+/// carry an API key, is not.
+pub fn url_host(url: &str) -> String {
+    reqwest::Url::parse(url)
+        .ok()
+        .and_then(|url| url.host_str().map(str::to_string))
+        .unwrap_or_default()
+}
+
+/// `text` with every URL in it, such as those in reqwest's errors, cut down
+/// to its host.
+pub fn redact_urls(text: &str) -> String {
+    let mut redacted = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = ["http://", "https://", "ws://", "wss://"]
+        .iter()
+        .filter_map(|scheme| rest.find(scheme))
+        .min()
+    {
+        redacted.push_str(&rest[..start]);
+        let url = &rest[start..];
+        let end = url
+            .find(|c: char| c.is_whitespace() || matches!(c, ')' | '"' | '\'' | '>' | ','))
+            .unwrap_or(url.len());
+        redacted.push_str(&url_host(&url[..end]));
+        rest = &url[end..];
+    }
+    redacted.push_str(rest);
+    redacted
+}
@@ -0,0 +1,136 @@
+use crate::rpc::breaker::{Breakers, CircuitBreaker, CircuitBreakerConfig};
+use crate::rpc::retry::is_retryable;
+use crate::rpc::BoxedSender;
+use async_trait::async_trait;
+use serde::Deserialize;
+use solana_rpc_client::rpc_sender::{RpcSender, RpcTransportStats};
+use solana_rpc_client_api::client_error::{Error as ClientError, ErrorKind, Result};
+use solana_rpc_client_api::request::RpcRequest;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RotationStrategy {
+    /// Stick with one endpoint and move to the next only when it fails.
+    #[default]
+    Failover,
+    /// Start each request at the next endpoint to spread load.
+    RoundRobin,
+}
+
+/// Sends each request to one of several endpoints, moving on to the next
+/// endpoint when one fails with a transient error.
+///
+/// Each endpoint has a circuit breaker: once it fails too many times in a
+/// row, requests skip it until a cooldown has passed, and then one request
+/// tests it. While no endpoint takes requests, they fail without being
+/// sent.
+pub struct PoolSender {
+    endpoints: Vec<BoxedSender>,
+    strategy: RotationStrategy,
+    current: AtomicUsize,
+    breakers: Breakers,
+}
+
+impl PoolSender {
+    pub fn new(endpoints: Vec<BoxedSender>, strategy: RotationStrategy) -> Self {
+        assert!(
+            !endpoints.is_empty(),
+            "PoolSender needs at least one endpoint"
+        );
+        let breakers = Self::new_breakers(&endpoints, CircuitBreakerConfig::default());
+        PoolSender {
+            endpoints,
+            strategy,
+            current: AtomicUsize::new(0),
+            breakers,
+        }
+    }
+
+    pub fn circuit_breaker(mut self, config: CircuitBreakerConfig) -> Self {
+        self.breakers = Self::new_breakers(&self.endpoints, config);
+        self
+    }
+
+    pub fn breakers(&self) -> Breakers {
+        self.breakers.clone()
+    }
+
+    fn new_breakers(endpoints: &[BoxedSender], config: CircuitBreakerConfig) -> Breakers {
+        Breakers(
+            endpoints
+                .iter()
+                .map(|endpoint| CircuitBreaker::new(&endpoint.url(), config.clone()))
+                .collect(),
+        )
+    }
+
+    fn start_index(&self) -> usize {
+        match self.strategy {
+            RotationStrategy::Failover => self.current.load(Ordering::Relaxed),
+            RotationStrategy::RoundRobin => self.current.fetch_add(1, Ordering::Relaxed),
+        }
+    }
+}
+
+#[async_trait]
+impl RpcSender for PoolSender {
+    async fn send(
+        &self,
+        request: RpcRequest,
+        params: serde_json::Value,
+    ) -> Result<serde_json::Value> {
+        let breakers = &self.breakers.0;
+        let start = self.start_index();
+        let mut last_error = None;
+
+        for offset in 0..self.endpoints.len() {
+            let index = (start + offset) % self.endpoints.len();
+            let Some(permit) = breakers[index].try_acquire() else {
+                continue;
+            };
+            match self.endpoints[index].send(request, params.clone()).await {
+                Err(error) if is_retryable(&error) => {
+                    permit.failed();
+                    if self.strategy == RotationStrategy::Failover {
+                        let _ = self.current.compare_exchange(
+                            index,
+                            (index + 1) % self.endpoints.len(),
+                            Ordering::Relaxed,
+                            Ordering::Relaxed,
+                        );
+                    }
+                    last_error = Some(error);
+                }
+                result => {
+                    permit.succeeded();
+                    return result;
+                }
+            }
+        }
+
+        Err(last_error.unwrap_or_else(|| {
+            ClientError::from(ErrorKind::Custom(
+                "every RPC endpoint failed repeatedly; their circuit breakers are open until the cooldown passes"
+                    .to_string(),
+            ))
+        }))
+    }
+
+    fn get_transport_stats(&self) -> RpcTransportStats {
+        self.endpoints
+            .iter()
+            .map(|endpoint| endpoint.get_transport_stats())
+            .fold(RpcTransportStats::default(), |mut total, stats| {
+                total.request_count += stats.request_count;
+                total.elapsed_time += stats.elapsed_time;
+                total.rate_limited_time += stats.rate_limited_time;
+                total
+            })
+    }
+
+    fn url(&self) -> String {
+        let index = self.current.load(Ordering::Relaxed) % self.endpoints.len();
+        self.endpoints[index].url()
+    }
+}
@@ -0,0 +1,83 @@
+use crate::rpc::BoxedSender;
+use async_trait::async_trait;
+use solana_rpc_client::rpc_sender::{RpcSender, RpcTransportStats};
+use solana_rpc_client_api::client_error::Result;
+use solana_rpc_client_api::request::RpcRequest;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// A token bucket allowing `max_rps` requests per second on average, with
+/// bursts of up to `max_rps` requests.
+pub struct RateLimiter {
+    max_rps: f64,
+    bucket: Mutex<Bucket>,
+}
+
+struct Bucket {
+    /// Goes negative when callers are queued waiting for tokens.
+    tokens: f64,
+    refilled_at: Instant,
+}
+
+impl RateLimiter {
+    pub fn new(max_rps: f64) -> Self {
+        let max_rps = max_rps.max(f64::MIN_POSITIVE);
+        RateLimiter {
+            max_rps,
+            bucket: Mutex::new(Bucket {
+                tokens: max_rps,
+                refilled_at: Instant::now(),
+            }),
+        }
+    }
+
+    /// Takes a token, waiting until one is available. Waiters are served in
+    /// the order they arrive.
+    pub async fn acquire(&self) {
+        let wait = {
+            let mut bucket = self.bucket.lock().unwrap();
+            let now = Instant::now();
+            let elapsed = now.duration_since(bucket.refilled_at).as_secs_f64();
+            bucket.tokens = (bucket.tokens + elapsed * self.max_rps).min(self.max_rps);
+            bucket.refilled_at = now;
+            bucket.tokens -= 1.0;
+            (bucket.tokens < 0.0).then(|| Duration::from_secs_f64(-bucket.tokens / self.max_rps))
+        };
+        if let Some(wait) = wait {
+            tokio::time::sleep(wait).await;
+        }
+    }
+}
+
+/// Delays requests to the wrapped sender so that, together with every other
+/// sender sharing the same limiter, they stay under its rate.
+pub struct RateLimitedSender {
+    inner: BoxedSender,
+    limiter: Arc<RateLimiter>,
+}
+
+impl RateLimitedSender {
+    pub fn new(inner: BoxedSender, limiter: Arc<RateLimiter>) -> Self {
+        RateLimitedSender { inner, limiter }
+    }
+}
+
+#[async_trait]
+impl RpcSender for RateLimitedSender {
+    async fn send(
+        &self,
+        request: RpcRequest,
+        params: serde_json::Value,
+    ) -> Result<serde_json::Value> {
+        self.limiter.acquire().await;
+        self.inner.send(request, params).await
+    }
+
+    fn get_transport_stats(&self) -> RpcTransportStats {
+        self.inner.get_transport_stats()
+    }
+
+    fn url(&self) -> String {
+        self.inner.url()
+    }
+}
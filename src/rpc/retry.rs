@@ -0,0 +1,112 @@
+use crate::rpc::BoxedSender;
+use async_trait::async_trait;
+use rand::Rng;
+use serde::Deserialize;
+use solana_rpc_client::rpc_sender::{RpcSender, RpcTransportStats};
+use solana_rpc_client_api::client_error::{Error as ClientError, ErrorKind, Result};
+use solana_rpc_client_api::custom_error::{
+    JSON_RPC_SERVER_ERROR_MIN_CONTEXT_SLOT_NOT_REACHED, JSON_RPC_SERVER_ERROR_NODE_UNHEALTHY,
+};
+use solana_rpc_client_api::request::{RpcError, RpcRequest};
+use std::time::Duration;
+
+/// Rate-limit codes some providers return inside a JSON-RPC error body
+/// instead of as an HTTP 429.
+const RATE_LIMITED_CODES: [i64; 2] = [429, -32429];
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct RetryPolicy {
+    /// Total attempts per request, including the first one.
+    pub max_attempts: u32,
+    #[serde(with = "humantime_serde")]
+    pub initial_backoff: Duration,
+    #[serde(with = "humantime_serde")]
+    pub max_backoff: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        RetryPolicy {
+            max_attempts: 4,
+            initial_backoff: Duration::from_millis(250),
+            max_backoff: Duration::from_secs(8),
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Exponential backoff for the given retry (1-based), with "equal
+    /// jitter": a random delay between half and all of the capped backoff.
+    pub(crate) fn backoff(&self, retry: u32) -> Duration {
+        let exponential = self
+            .initial_backoff
+            .saturating_mul(2u32.saturating_pow(retry.saturating_sub(1)));
+        let capped = exponential.min(self.max_backoff);
+        let half = capped / 2;
+        half + rand::thread_rng().gen_range(Duration::ZERO..=half)
+    }
+}
+
+/// Retries transient failures (timeouts, connection errors, rate limits,
+/// 5xx responses, unhealthy nodes, nodes behind the requested
+/// `minContextSlot`) of the wrapped sender.
+pub struct RetrySender {
+    inner: BoxedSender,
+    policy: RetryPolicy,
+}
+
+impl RetrySender {
+    pub fn new(inner: BoxedSender, policy: RetryPolicy) -> Self {
+        RetrySender { inner, policy }
+    }
+}
+
+/// Whether a failed request may succeed if sent again (or elsewhere).
+pub fn is_retryable(error: &ClientError) -> bool {
+    match &error.kind {
+        ErrorKind::Io(_) => true,
+        ErrorKind::Reqwest(error) => {
+            error.is_timeout()
+                || error.is_connect()
+                || error
+                    .status()
+                    .is_some_and(|status| status.as_u16() == 429 || status.is_server_error())
+        }
+        ErrorKind::RpcError(RpcError::RpcResponseError { code, .. }) => {
+            *code == JSON_RPC_SERVER_ERROR_NODE_UNHEALTHY
+                || *code == JSON_RPC_SERVER_ERROR_MIN_CONTEXT_SLOT_NOT_REACHED
+                || RATE_LIMITED_CODES.contains(code)
+        }
+        _ => false,
+    }
+}
+
+#[async_trait]
+impl RpcSender for RetrySender {
+    async fn send(
+        &self,
+        request: RpcRequest,
+        params: serde_json::Value,
+    ) -> Result<serde_json::Value> {
+        let mut attempt = 1;
+        loop {
+            match self.inner.send(request, params.clone()).await {
+                Err(error) if attempt < self.policy.max_attempts && is_retryable(&error) => {
+                    tracing::info!(method = %request, attempt, %error, "retrying rpc request");
+                    tokio::time::sleep(self.policy.backoff(attempt)).await;
+                    attempt += 1;
+                }
+                result => return result,
+            }
+        }
+    }
+
+    fn get_transport_stats(&self) -> RpcTransportStats {
+        self.inner.get_transport_stats()
+    }
+
+    fn url(&self) -> String {
+        self.inner.url()
+    }
+}
@@ -0,0 +1,54 @@
+use crate::rpc::{url_host, BoxedSender};
+use async_trait::async_trait;
+use solana_rpc_client::rpc_sender::{RpcSender, RpcTransportStats};
+use solana_rpc_client_api::client_error::Result;
+use solana_rpc_client_api::request::RpcRequest;
+use std::time::Instant;
+use tracing::Instrument;
+
+/// Runs each request to the wrapped sender in an `rpc` span and logs how
+/// long it took and whether it failed.
+pub struct TracingSender {
+    inner: BoxedSender,
+    /// The endpoint's host, as the full URL may carry an API key.
+    host: String,
+}
+
+impl TracingSender {
+    pub fn new(inner: BoxedSender) -> Self {
+        let host = url_host(&inner.url());
+        TracingSender { inner, host }
+    }
+}
+
+#[async_trait]
+impl RpcSender for TracingSender {
+    async fn send(
+        &self,
+        request: RpcRequest,
+        params: serde_json::Value,
+    ) -> Result<serde_json::Value> {
+        let span = tracing::debug_span!("rpc", method = %request, host = %self.host);
+        let started = Instant::now();
+        let result = self
+            .inner
+            .send(request, params)
+            .instrument(span.clone())
+            .await;
+        let elapsed_ms = started.elapsed().as_millis() as u64;
+        let _entered = span.enter();
+        match &result {
+            Ok(_) => tracing::debug!(elapsed_ms, "rpc request succeeded"),
+            Err(error) => tracing::debug!(elapsed_ms, %error, "rpc request failed"),
+        }
+        result
+    }
+
+    fn get_transport_stats(&self) -> RpcTransportStats {
+        self.inner.get_transport_stats()
+    }
+
+    fn url(&self) -> String {
+        self.inner.url()
+    }
+}
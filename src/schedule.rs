@@ -0,0 +1,332 @@
+use chrono::{DateTime, Datelike, Duration, DurationRound, Timelike, Utc};
+use std::fmt;
+use std::str::FromStr;
+
+/// How far ahead [`Schedule::next_after`] looks before deciding a schedule,
+/// such as `0 0 30 2 *`, never fires.
+const MAX_YEARS_AHEAD: i32 = 5;
+
+const MONTHS: [&str; 12] = [
+    "JAN", "FEB", "MAR", "APR", "MAY", "JUN", "JUL", "AUG", "SEP", "OCT", "NOV", "DEC",
+];
+const WEEKDAYS: [&str; 7] = ["SUN", "MON", "TUE", "WED", "THU", "FRI", "SAT"];
+
+/// A cron schedule: the five fields minute, hour, day of month, month and
+/// day of week, each `*`, a number, a range like `1-5`, a step like `*/15`
+/// or a list of those, or one of `@hourly`, `@daily`, `@weekly`, `@monthly`
+/// and `@yearly`. Times are in UTC.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Schedule {
+    source: String,
+    minutes: u64,
+    hours: u64,
+    days: u64,
+    months: u64,
+    weekdays: u64,
+    /// Whether the day of month or day of week is exactly `*`. As in cron,
+    /// when neither is, a day matching either fires; otherwise it must match
+    /// both.
+    any_day: bool,
+    any_weekday: bool,
+}
+
+impl Schedule {
+    /// The first time after `time` the schedule fires, or `None` if it never
+    /// does.
+    pub fn next_after(&self, time: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        let mut next = time.duration_trunc(Duration::minutes(1)).ok()? + Duration::minutes(1);
+        let last_year = time.year() + MAX_YEARS_AHEAD;
+        while next.year() <= last_year {
+            if !contains(self.months, next.month()) {
+                let (year, month) = match next.month() {
+                    12 => (next.year() + 1, 1),
+                    month => (next.year(), month + 1),
+                };
+                next = next
+                    .with_day(1)?
+                    .with_year(year)?
+                    .with_month(month)?
+                    .with_hour(0)?
+                    .with_minute(0)?;
+            } else if !self.day_matches(next) {
+                next = (next + Duration::days(1)).with_hour(0)?.with_minute(0)?;
+            } else if !contains(self.hours, next.hour()) {
+                next = (next + Duration::hours(1)).with_minute(0)?;
+            } else if !contains(self.minutes, next.minute()) {
+                next += Duration::minutes(1);
+            } else {
+                return Some(next);
+            }
+        }
+        None
+    }
+
+    fn day_matches(&self, time: DateTime<Utc>) -> bool {
+        let day = contains(self.days, time.day());
+        let weekday = contains(self.weekdays, time.weekday().num_days_from_sunday());
+        if self.any_day || self.any_weekday {
+            day && weekday
+        } else {
+            day || weekday
+        }
+    }
+}
+
+fn contains(set: u64, value: u32) -> bool {
+    set & (1 << value) != 0
+}
+
+/// Parses one field into a bit set of the values it allows, which must lie
+/// in `min..=max`. `names` spell out the values from `min` on.
+fn parse_field(field: &str, min: u32, max: u32, names: &[&str]) -> Result<u64, anyhow::Error> {
+    let value = |text: &str| -> Result<u32, anyhow::Error> {
+        let value = match names
+            .iter()
+            .position(|name| name.eq_ignore_ascii_case(text))
+        {
+            Some(index) => min + index as u32,
+            None => text
+                .parse()
+                .map_err(|_| anyhow::anyhow!("`{}` is not a number", text))?,
+        };
+        if !(min..=max).contains(&value) {
+            anyhow::bail!("{} is outside {}-{}", value, min, max);
+        }
+        Ok(value)
+    };
+
+    let mut set = 0;
+    for part in field.split(',') {
+        let (range, step) = match part.split_once('/') {
+            Some((range, step)) => {
+                let step: u32 = step
+                    .parse()
+                    .ok()
+                    .filter(|step| *step > 0)
+                    .ok_or_else(|| anyhow::anyhow!("invalid step `{}`", step))?;
+                (range, Some(step))
+            }
+            None => (part, None),
+        };
+        let (start, end) = match range.split_once('-') {
+            _ if range == "*" => (min, max),
+            Some((start, end)) => (value(start)?, value(end)?),
+            // `5/15` means every 15 from 5 on.
+            None if step.is_some() => (value(range)?, max),
+            None => {
+                let value = value(range)?;
+                (value, value)
+            }
+        };
+        if start > end {
+            anyhow::bail!("range `{}` is backwards", range);
+        }
+        for value in (start..=end).step_by(step.unwrap_or(1) as usize) {
+            set |= 1 << value;
+        }
+    }
+    Ok(set)
+}
+
+impl FromStr for Schedule {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let expression = match s.trim() {
+            "@yearly" | "@annually" => "0 0 1 1 *",
+            "@monthly" => "0 0 1 * *",
+            "@weekly" => "0 0 * * 0",
+            "@daily" | "@midnight" => "0 0 * * *",
+            "@hourly" => "0 * * * *",
+            other => other,
+        };
+        let fields: Vec<&str> = expression.split_whitespace().collect();
+        let [minute, hour, day, month, weekday] = fields[..] else {
+            anyhow::bail!(
+                "invalid schedule `{}`: expected five fields (minute hour day month weekday)",
+                s
+            );
+        };
+        let invalid = |field: &str, err: anyhow::Error| {
+            anyhow::anyhow!("invalid schedule `{}`: {}: {}", s, field, err)
+        };
+        // Both 0 and 7 are Sunday.
+        let weekdays =
+            parse_field(weekday, 0, 7, &WEEKDAYS).map_err(|err| invalid("weekday", err))?;
+        Ok(Schedule {
+            source: s.trim().to_string(),
+            minutes: parse_field(minute, 0, 59, &[]).map_err(|err| invalid("minute", err))?,
+            hours: parse_field(hour, 0, 23, &[]).map_err(|err| invalid("hour", err))?,
+            days: parse_field(day, 1, 31, &[]).map_err(|err| invalid("day", err))?,
+            months: parse_field(month, 1, 12, &MONTHS).map_err(|err| invalid("month", err))?,
+            weekdays: (weekdays | weekdays >> 7) & 0x7f,
+            any_day: day == "*",
+            any_weekday: weekday == "*",
+        })
+    }
+}
+
+impl fmt::Display for Schedule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.source)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn at(year: i32, month: u32, day: u32, hour: u32, minute: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(year, month, day, hour, minute, 0)
+            .unwrap()
+    }
+
+    fn next(schedule: &str, time: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        schedule.parse::<Schedule>().unwrap().next_after(time)
+    }
+
+    #[test]
+    fn parses_fields() {
+        let schedule: Schedule = "*/15 9-17 1,15 JAN-mar 1-5".parse().unwrap();
+        assert_eq!(schedule.minutes, 1 | 1 << 15 | 1 << 30 | 1 << 45);
+        assert_eq!(
+            schedule.hours,
+            (9..=17).fold(0, |set, hour| set | 1 << hour)
+        );
+        assert_eq!(schedule.days, 1 << 1 | 1 << 15);
+        assert_eq!(schedule.months, 1 << 1 | 1 << 2 | 1 << 3);
+        assert_eq!(schedule.weekdays, 0b0111110);
+        assert!(!schedule.any_day);
+        assert!(!schedule.any_weekday);
+        assert_eq!(schedule.to_string(), "*/15 9-17 1,15 JAN-mar 1-5");
+    }
+
+    #[test]
+    fn parses_steps_from_a_start() {
+        let schedule: Schedule = "5/20 * * * *".parse().unwrap();
+        assert_eq!(schedule.minutes, 1 << 5 | 1 << 25 | 1 << 45);
+    }
+
+    #[test]
+    fn treats_seven_as_sunday() {
+        let schedule: Schedule = "0 0 * * 7".parse().unwrap();
+        assert_eq!(schedule.weekdays, 1);
+        assert_eq!("0 0 * * SUN".parse::<Schedule>().unwrap().weekdays, 1);
+    }
+
+    #[test]
+    fn expands_macros() {
+        assert_eq!(
+            next("@daily", at(2024, 3, 10, 12, 0)),
+            Some(at(2024, 3, 11, 0, 0))
+        );
+        assert_eq!(
+            next("@hourly", at(2024, 3, 10, 12, 0)),
+            Some(at(2024, 3, 10, 13, 0))
+        );
+        assert_eq!(
+            next("@yearly", at(2024, 3, 10, 12, 0)),
+            Some(at(2025, 1, 1, 0, 0))
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_schedules() {
+        for schedule in [
+            "",
+            "* * * *",
+            "60 * * * *",
+            "* 24 * * *",
+            "* * 0 * *",
+            "* * * 13 *",
+            "* * * * 8",
+            "*/0 * * * *",
+            "30-10 * * * *",
+            "x * * * *",
+        ] {
+            assert!(
+                schedule.parse::<Schedule>().is_err(),
+                "`{}` should not parse",
+                schedule
+            );
+        }
+    }
+
+    #[test]
+    fn next_after_is_strictly_later() {
+        assert_eq!(
+            next("* * * * *", at(2024, 3, 10, 12, 0)),
+            Some(at(2024, 3, 10, 12, 1))
+        );
+        assert_eq!(
+            next("*/15 * * * *", at(2024, 3, 10, 12, 15)),
+            Some(at(2024, 3, 10, 12, 30))
+        );
+    }
+
+    #[test]
+    fn next_after_rolls_over_days_months_and_years() {
+        assert_eq!(
+            next("30 8 * * *", at(2024, 3, 10, 9, 0)),
+            Some(at(2024, 3, 11, 8, 30))
+        );
+        assert_eq!(
+            next("0 0 1 * *", at(2024, 1, 31, 12, 0)),
+            Some(at(2024, 2, 1, 0, 0))
+        );
+        assert_eq!(
+            next("0 0 1 1 *", at(2024, 12, 31, 23, 59)),
+            Some(at(2025, 1, 1, 0, 0))
+        );
+    }
+
+    #[test]
+    fn day_steps_do_not_fire_daily() {
+        // Odd days of the month.
+        assert_eq!(
+            next("0 0 */2 * *", at(2024, 3, 1, 0, 0)),
+            Some(at(2024, 3, 3, 0, 0))
+        );
+        // Sundays, Tuesdays, Thursdays and Saturdays; 2024-03-10 is a Sunday.
+        assert_eq!(
+            next("0 0 * * */2", at(2024, 3, 10, 0, 0)),
+            Some(at(2024, 3, 12, 0, 0))
+        );
+    }
+
+    #[test]
+    fn restricted_day_and_weekday_match_either() {
+        // The 15th, or any Monday; 2024-03-10 is a Sunday.
+        assert_eq!(
+            next("0 0 15 * MON", at(2024, 3, 10, 0, 0)),
+            Some(at(2024, 3, 11, 0, 0))
+        );
+        assert_eq!(
+            next("0 0 15 * MON", at(2024, 3, 11, 0, 0)),
+            Some(at(2024, 3, 15, 0, 0))
+        );
+    }
+
+    #[test]
+    fn weekday_with_any_day_must_match() {
+        // Fridays only; 2024-03-10 is a Sunday.
+        assert_eq!(
+            next("0 9 * * FRI", at(2024, 3, 10, 0, 0)),
+            Some(at(2024, 3, 15, 9, 0))
+        );
+    }
+
+    #[test]
+    fn leap_days_wait_for_a_leap_year() {
+        assert_eq!(
+            next("0 0 29 2 *", at(2024, 3, 1, 0, 0)),
+            Some(at(2028, 2, 29, 0, 0))
+        );
+    }
+
+    #[test]
+    fn impossible_dates_never_fire() {
+        assert_eq!(next("0 0 30 2 *", at(2024, 1, 1, 0, 0)), None);
+    }
+}
@@ -0,0 +1,60 @@
+use crate::notify::send_with_retry;
+use crate::output::write_influx;
+use crate::report::BalanceReport;
+use crate::rpc::RetryPolicy;
+use crate::sink::Sink;
+use async_trait::async_trait;
+use serde::Deserialize;
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct InfluxConfig {
+    /// Base URL of the server, e.g. `http://localhost:8086`.
+    pub url: String,
+    pub org: String,
+    pub bucket: String,
+    /// An API token with write access to `bucket`.
+    pub token: String,
+}
+
+/// Writes reports as line protocol through the InfluxDB v2 write API.
+pub struct InfluxSink {
+    http: reqwest::Client,
+    config: InfluxConfig,
+    retry: RetryPolicy,
+}
+
+impl InfluxSink {
+    pub fn new(config: InfluxConfig, retry: RetryPolicy) -> Self {
+        InfluxSink {
+            http: reqwest::Client::new(),
+            config,
+            retry,
+        }
+    }
+}
+
+#[async_trait]
+impl Sink for InfluxSink {
+    fn name(&self) -> &str {
+        "influxdb"
+    }
+
+    async fn publish(&self, report: &BalanceReport) -> Result<(), anyhow::Error> {
+        let mut body = Vec::new();
+        write_influx(report, &mut body)?;
+        let url = format!("{}/api/v2/write", self.config.url.trim_end_matches('/'));
+        send_with_retry(&self.retry, || {
+            self.http
+                .post(&url)
+                .query(&[
+                    ("org", self.config.org.as_str()),
+                    ("bucket", self.config.bucket.as_str()),
+                    ("precision", "ns"),
+                ])
+                .header("Authorization", format!("Token {}", self.config.token))
+                .header("Content-Type", "text/plain; charset=utf-8")
+                .body(body.clone())
+        })
+        .await
+    }
+}
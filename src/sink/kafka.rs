@@ -0,0 +1,98 @@
+use serde::Deserialize;
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct KafkaConfig {
+    /// Bootstrap brokers, `host:port`.
+    pub brokers: Vec<String>,
+    pub topic: String,
+    /// Partition every snapshot is written to.
+    #[serde(default)]
+    pub partition: i32,
+    #[serde(default)]
+    pub client_id: Option<String>,
+}
+
+#[cfg(feature = "kafka")]
+pub use self::producer::KafkaSink;
+
+#[cfg(feature = "kafka")]
+mod producer {
+    use super::KafkaConfig;
+    use crate::report::{BalanceReport, BalanceResult};
+    use crate::sink::Sink;
+    use async_trait::async_trait;
+    use chrono::{DateTime, Utc};
+    use rskafka::client::partition::{Compression, UnknownTopicHandling};
+    use rskafka::client::ClientBuilder;
+    use rskafka::record::Record;
+    use serde::Serialize;
+    use std::collections::BTreeMap;
+
+    /// What each record's value holds: one wallet's balances at one run.
+    #[derive(Serialize)]
+    struct WalletSnapshot<'a> {
+        timestamp: DateTime<Utc>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        slot: Option<u64>,
+        wallet: &'a str,
+        #[serde(flatten)]
+        balances: &'a BalanceResult,
+    }
+
+    /// Publishes a JSON record per wallet to a Kafka topic, keyed by the
+    /// wallet's address.
+    pub struct KafkaSink {
+        config: KafkaConfig,
+    }
+
+    impl KafkaSink {
+        pub fn new(config: KafkaConfig) -> Self {
+            KafkaSink { config }
+        }
+    }
+
+    #[async_trait]
+    impl Sink for KafkaSink {
+        fn name(&self) -> &str {
+            "kafka"
+        }
+
+        async fn publish(&self, report: &BalanceReport) -> Result<(), anyhow::Error> {
+            let mut records = Vec::new();
+            for (wallet, balances) in &report.wallets {
+                let snapshot = WalletSnapshot {
+                    timestamp: report.timestamp,
+                    slot: report.slot,
+                    wallet,
+                    balances,
+                };
+                records.push(Record {
+                    key: Some(wallet.clone().into_bytes()),
+                    value: Some(serde_json::to_vec(&snapshot)?),
+                    headers: BTreeMap::new(),
+                    timestamp: report.timestamp,
+                });
+            }
+            if records.is_empty() {
+                return Ok(());
+            }
+
+            let mut builder = ClientBuilder::new(self.config.brokers.clone());
+            if let Some(client_id) = &self.config.client_id {
+                builder = builder.client_id(client_id.as_str());
+            }
+            let client = builder.build().await?;
+            let partition = client
+                .partition_client(
+                    self.config.topic.as_str(),
+                    self.config.partition,
+                    UnknownTopicHandling::Error,
+                )
+                .await?;
+            partition
+                .produce(records, Compression::NoCompression)
+                .await?;
+            Ok(())
+        }
+    }
+}
@@ -0,0 +1,125 @@
+use crate::report::BalanceReport;
+use crate::sink::{sanitize, values, Sink};
+use async_trait::async_trait;
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use tokio::io::AsyncWriteExt;
+use tokio::net::{TcpStream, UdpSocket};
+
+/// Keeps StatsD datagrams within a typical network's MTU.
+const MAX_DATAGRAM_SIZE: usize = 1432;
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct MetricsConfig {
+    /// `host:port` of the StatsD (UDP) or Graphite plaintext (TCP) listener.
+    pub address: String,
+    /// Prepended to every metric name [default: "solana.balance"].
+    #[serde(default)]
+    pub prefix: Option<String>,
+    /// Added to every metric, as DogStatsD tags or Graphite tags.
+    #[serde(default)]
+    pub tags: BTreeMap<String, String>,
+}
+
+/// Pushes `<prefix>.<wallet>.<ticker>` gauges to a StatsD server.
+pub struct StatsdSink {
+    config: MetricsConfig,
+}
+
+impl StatsdSink {
+    pub fn new(config: MetricsConfig) -> Self {
+        StatsdSink { config }
+    }
+}
+
+#[async_trait]
+impl Sink for StatsdSink {
+    fn name(&self) -> &str {
+        "statsd"
+    }
+
+    async fn publish(&self, report: &BalanceReport) -> Result<(), anyhow::Error> {
+        let tags = if self.config.tags.is_empty() {
+            String::new()
+        } else {
+            let tags: Vec<_> = self
+                .config
+                .tags
+                .iter()
+                .map(|(key, value)| format!("{}:{}", key, value))
+                .collect();
+            format!("|#{}", tags.join(","))
+        };
+        let socket = UdpSocket::bind("0.0.0.0:0").await?;
+        socket.connect(&self.config.address).await?;
+
+        let mut datagram = String::new();
+        for (name, value) in gauges(&self.config, report) {
+            let line = format!("{}:{}|g{}", name, value, tags);
+            if !datagram.is_empty() && datagram.len() + 1 + line.len() > MAX_DATAGRAM_SIZE {
+                socket.send(datagram.as_bytes()).await?;
+                datagram.clear();
+            }
+            if !datagram.is_empty() {
+                datagram.push('\n');
+            }
+            datagram.push_str(&line);
+        }
+        if !datagram.is_empty() {
+            socket.send(datagram.as_bytes()).await?;
+        }
+        Ok(())
+    }
+}
+
+/// Writes `<prefix>.<wallet>.<ticker>` metrics to a Graphite plaintext
+/// listener, stamped with the report's timestamp.
+pub struct GraphiteSink {
+    config: MetricsConfig,
+}
+
+impl GraphiteSink {
+    pub fn new(config: MetricsConfig) -> Self {
+        GraphiteSink { config }
+    }
+}
+
+#[async_trait]
+impl Sink for GraphiteSink {
+    fn name(&self) -> &str {
+        "graphite"
+    }
+
+    async fn publish(&self, report: &BalanceReport) -> Result<(), anyhow::Error> {
+        let tags: String = self
+            .config
+            .tags
+            .iter()
+            .map(|(key, value)| format!(";{}={}", key, value))
+            .collect();
+        let timestamp = report.timestamp.timestamp();
+        let mut body = String::new();
+        for (name, value) in gauges(&self.config, report) {
+            body.push_str(&format!("{}{} {} {}\n", name, tags, value, timestamp));
+        }
+
+        let mut stream = TcpStream::connect(&self.config.address).await?;
+        stream.write_all(body.as_bytes()).await?;
+        stream.shutdown().await?;
+        Ok(())
+    }
+}
+
+/// `<prefix>.<wallet>.<asset>` metric names and their values.
+fn gauges(config: &MetricsConfig, report: &BalanceReport) -> Vec<(String, f64)> {
+    let prefix = config.prefix.as_deref().unwrap_or("solana.balance");
+    values(report)
+        .into_iter()
+        .map(|(wallet, asset, value)| {
+            (
+                format!("{}.{}.{}", prefix, sanitize(wallet), sanitize(&asset)),
+                value,
+            )
+        })
+        .collect()
+}
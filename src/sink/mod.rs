@@ -0,0 +1,118 @@
+mod influx;
+mod kafka;
+mod metrics;
+mod mqtt;
+mod sheets;
+
+use crate::diff::{SOL_ASSET, STAKED_SOL_ASSET};
+use crate::report::BalanceReport;
+use crate::rpc::RetryPolicy;
+use async_trait::async_trait;
+use serde::Deserialize;
+
+pub use influx::{InfluxConfig, InfluxSink};
+pub use kafka::KafkaConfig;
+#[cfg(feature = "kafka")]
+pub use kafka::KafkaSink;
+pub use metrics::{GraphiteSink, MetricsConfig, StatsdSink};
+pub use mqtt::{MqttConfig, MqttSink};
+pub use sheets::{GoogleSheetsConfig, GoogleSheetsSink};
+
+/// A time-series store every fetched report is written to.
+#[async_trait]
+pub trait Sink: Send + Sync {
+    /// Short name used in log messages, e.g. `"influxdb"`.
+    fn name(&self) -> &str;
+
+    async fn publish(&self, report: &BalanceReport) -> Result<(), anyhow::Error>;
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct SinkConfig {
+    #[serde(default)]
+    pub influxdb: Option<InfluxConfig>,
+    #[serde(default)]
+    pub statsd: Option<MetricsConfig>,
+    #[serde(default)]
+    pub graphite: Option<MetricsConfig>,
+    #[serde(default)]
+    pub mqtt: Option<MqttConfig>,
+    /// Appends a row per wallet and asset to a spreadsheet.
+    #[serde(default)]
+    pub google_sheets: Option<GoogleSheetsConfig>,
+    /// Needs a build with the `kafka` feature.
+    #[serde(default)]
+    pub kafka: Option<KafkaConfig>,
+    /// Backoff between write attempts.
+    #[serde(default)]
+    pub retry: RetryPolicy,
+}
+
+impl SinkConfig {
+    /// Every configured sink.
+    pub fn sinks(&self) -> Vec<Box<dyn Sink>> {
+        let mut sinks: Vec<Box<dyn Sink>> = Vec::new();
+        if let Some(influxdb) = &self.influxdb {
+            sinks.push(Box::new(InfluxSink::new(
+                influxdb.clone(),
+                self.retry.clone(),
+            )));
+        }
+        if let Some(statsd) = &self.statsd {
+            sinks.push(Box::new(StatsdSink::new(statsd.clone())));
+        }
+        if let Some(graphite) = &self.graphite {
+            sinks.push(Box::new(GraphiteSink::new(graphite.clone())));
+        }
+        if let Some(mqtt) = &self.mqtt {
+            sinks.push(Box::new(MqttSink::new(mqtt.clone())));
+        }
+        if let Some(google_sheets) = &self.google_sheets {
+            sinks.push(Box::new(GoogleSheetsSink::new(
+                google_sheets.clone(),
+                self.retry.clone(),
+            )));
+        }
+        #[cfg(feature = "kafka")]
+        if let Some(kafka) = &self.kafka {
+            sinks.push(Box::new(KafkaSink::new(kafka.clone())));
+        }
+        sinks
+    }
+}
+
+/// Each wallet's SOL, staked SOL and token balances, and its USD total as
+/// `total_usd` when prices are known, sorted by wallet and asset. Wallets
+/// are named by label when they have one.
+fn values(report: &BalanceReport) -> Vec<(&str, String, f64)> {
+    let mut values = Vec::new();
+    for (address, result) in &report.wallets {
+        let wallet = result.label.as_deref().unwrap_or(address);
+        values.push((wallet, SOL_ASSET.to_string(), result.sol_balance));
+        if let Some(stake) = &result.stake {
+            values.push((wallet, STAKED_SOL_ASSET.to_string(), stake.total));
+        }
+        for (ticker, balance) in &result.token_balances {
+            values.push((wallet, ticker.clone(), balance.amount));
+        }
+        if let Some(total) = result.total_usd {
+            values.push((wallet, "total_usd".to_string(), total));
+        }
+    }
+    values.sort_by(|a, b| (a.0, &a.1).cmp(&(b.0, &b.1)));
+    values
+}
+
+/// Turns `value` into one metric or topic path component: runs of
+/// characters other than letters, digits, `-` and `_` become a single `_`.
+fn sanitize(value: &str) -> String {
+    let mut sanitized = String::with_capacity(value.len());
+    for c in value.chars() {
+        if c.is_ascii_alphanumeric() || c == '-' || c == '_' {
+            sanitized.push(c);
+        } else if !sanitized.ends_with('_') {
+            sanitized.push('_');
+        }
+    }
+    sanitized.trim_matches('_').to_string()
+}
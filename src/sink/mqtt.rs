@@ -0,0 +1,136 @@
+use crate::report::BalanceReport;
+use crate::sink::{sanitize, values, Sink};
+use async_trait::async_trait;
+use rumqttc::{
+    AsyncClient, Event, MqttOptions, Outgoing, Packet, QoS, TlsConfiguration, Transport,
+};
+use serde::Deserialize;
+use std::path::PathBuf;
+use std::time::Duration;
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct MqttConfig {
+    pub host: String,
+    /// [default: 1883, or 8883 with `tls`]
+    #[serde(default)]
+    pub port: Option<u16>,
+    /// [default: "solana-balance"]
+    #[serde(default)]
+    pub client_id: Option<String>,
+    #[serde(default)]
+    pub username: Option<String>,
+    #[serde(default)]
+    pub password: Option<String>,
+    #[serde(default)]
+    pub tls: bool,
+    /// PEM certificate of a private CA to trust instead of the system's.
+    #[serde(default)]
+    pub ca_file: Option<PathBuf>,
+    /// Topic of each balance, with `{wallet}` and `{ticker}` placeholders
+    /// [default: "solana/{wallet}/{ticker}"].
+    #[serde(default)]
+    pub topic: Option<String>,
+    /// Have the broker keep the last balance for new subscribers.
+    #[serde(default = "default_retain")]
+    pub retain: bool,
+    /// 0, 1 or 2.
+    #[serde(default = "default_qos")]
+    pub qos: u8,
+}
+
+fn default_retain() -> bool {
+    true
+}
+
+fn default_qos() -> u8 {
+    1
+}
+
+/// Publishes each balance to its own topic, as a bare number.
+pub struct MqttSink {
+    config: MqttConfig,
+}
+
+impl MqttSink {
+    pub fn new(config: MqttConfig) -> Self {
+        MqttSink { config }
+    }
+
+    fn options(&self) -> Result<MqttOptions, anyhow::Error> {
+        let config = &self.config;
+        let port = config.port.unwrap_or(if config.tls { 8883 } else { 1883 });
+        let client_id = config.client_id.as_deref().unwrap_or("solana-balance");
+        let mut options = MqttOptions::new(client_id, &config.host, port);
+        options.set_keep_alive(Duration::from_secs(30));
+        if let Some(username) = &config.username {
+            options.set_credentials(username, config.password.as_deref().unwrap_or_default());
+        }
+        if config.tls {
+            let tls = match &config.ca_file {
+                Some(path) => TlsConfiguration::SimpleNative {
+                    ca: std::fs::read(path).map_err(|err| {
+                        anyhow::anyhow!("reading {} failed: {}", path.display(), err)
+                    })?,
+                    client_auth: None,
+                },
+                None => TlsConfiguration::Native,
+            };
+            options.set_transport(Transport::Tls(tls));
+        }
+        Ok(options)
+    }
+}
+
+#[async_trait]
+impl Sink for MqttSink {
+    fn name(&self) -> &str {
+        "mqtt"
+    }
+
+    async fn publish(&self, report: &BalanceReport) -> Result<(), anyhow::Error> {
+        let qos = match self.config.qos {
+            0 => QoS::AtMostOnce,
+            1 => QoS::AtLeastOnce,
+            2 => QoS::ExactlyOnce,
+            other => anyhow::bail!("invalid MQTT qos {} (expected 0, 1 or 2)", other),
+        };
+        let scheme = self
+            .config
+            .topic
+            .as_deref()
+            .unwrap_or("solana/{wallet}/{ticker}");
+        let values = values(report);
+        let mut pending = values.len();
+
+        let (client, mut events) = AsyncClient::new(self.options()?, values.len() + 1);
+        for (wallet, asset, value) in values {
+            let topic = scheme
+                .replace("{wallet}", &sanitize(wallet))
+                .replace("{ticker}", &sanitize(&asset));
+            client
+                .publish(topic, qos, self.config.retain, value.to_string())
+                .await?;
+        }
+
+        // The event loop connects and sends the queued publishes. Disconnect
+        // once the broker has them all, or they are sent for qos 0.
+        if pending == 0 {
+            client.disconnect().await?;
+        }
+        loop {
+            let delivered = match events.poll().await? {
+                Event::Outgoing(Outgoing::Disconnect) => return Ok(()),
+                Event::Outgoing(Outgoing::Publish(_)) => qos == QoS::AtMostOnce,
+                Event::Incoming(Packet::PubAck(_)) => qos == QoS::AtLeastOnce,
+                Event::Incoming(Packet::PubComp(_)) => qos == QoS::ExactlyOnce,
+                _ => false,
+            };
+            if delivered {
+                pending -= 1;
+                if pending == 0 {
+                    client.disconnect().await?;
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,175 @@
+use crate::diff::{SOL_ASSET, STAKED_SOL_ASSET};
+use crate::notify::send_with_retry;
+use crate::report::BalanceReport;
+use crate::rpc::RetryPolicy;
+use crate::sink::Sink;
+use async_trait::async_trait;
+use chrono::SecondsFormat;
+use jsonwebtoken::{Algorithm, EncodingKey, Header};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::path::PathBuf;
+
+const SHEETS_API_URL: &str = "https://sheets.googleapis.com/v4/spreadsheets";
+const SHEETS_SCOPE: &str = "https://www.googleapis.com/auth/spreadsheets";
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct GoogleSheetsConfig {
+    /// JSON key of a service account the spreadsheet is shared with.
+    pub credentials_file: PathBuf,
+    /// The id in the spreadsheet's URL, `/spreadsheets/d/<id>/edit`.
+    pub spreadsheet_id: String,
+    /// Tab rows are appended to [default: "Sheet1"].
+    #[serde(default)]
+    pub sheet: Option<String>,
+}
+
+/// The fields of a service account key file that are needed to sign in.
+#[derive(Deserialize)]
+struct ServiceAccountKey {
+    client_email: String,
+    private_key: String,
+    #[serde(default = "default_token_uri")]
+    token_uri: String,
+}
+
+fn default_token_uri() -> String {
+    "https://oauth2.googleapis.com/token".to_string()
+}
+
+#[derive(Serialize)]
+struct Claims<'a> {
+    iss: &'a str,
+    scope: &'a str,
+    aud: &'a str,
+    iat: i64,
+    exp: i64,
+}
+
+#[derive(Deserialize)]
+struct TokenResponse {
+    access_token: String,
+}
+
+/// Appends a row per wallet and asset to a Google Sheet: timestamp,
+/// wallet, label, asset, amount and USD value.
+pub struct GoogleSheetsSink {
+    http: reqwest::Client,
+    config: GoogleSheetsConfig,
+    retry: RetryPolicy,
+}
+
+impl GoogleSheetsSink {
+    pub fn new(config: GoogleSheetsConfig, retry: RetryPolicy) -> Self {
+        GoogleSheetsSink {
+            http: reqwest::Client::new(),
+            config,
+            retry,
+        }
+    }
+
+    /// Exchanges a JWT signed with the service account's key for an
+    /// access token.
+    async fn access_token(&self) -> Result<String, anyhow::Error> {
+        let path = &self.config.credentials_file;
+        let contents = std::fs::read_to_string(path)
+            .map_err(|err| anyhow::anyhow!("reading {} failed: {}", path.display(), err))?;
+        let key: ServiceAccountKey = serde_json::from_str(&contents).map_err(|err| {
+            anyhow::anyhow!("invalid service account key {}: {}", path.display(), err)
+        })?;
+
+        let now = chrono::Utc::now().timestamp();
+        let claims = Claims {
+            iss: &key.client_email,
+            scope: SHEETS_SCOPE,
+            aud: &key.token_uri,
+            iat: now,
+            exp: now + 3600,
+        };
+        let assertion = jsonwebtoken::encode(
+            &Header::new(Algorithm::RS256),
+            &claims,
+            &EncodingKey::from_rsa_pem(key.private_key.as_bytes())?,
+        )?;
+        let response: TokenResponse = self
+            .http
+            .post(&key.token_uri)
+            .form(&[
+                ("grant_type", "urn:ietf:params:oauth:grant-type:jwt-bearer"),
+                ("assertion", assertion.as_str()),
+            ])
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+        Ok(response.access_token)
+    }
+}
+
+#[async_trait]
+impl Sink for GoogleSheetsSink {
+    fn name(&self) -> &str {
+        "google_sheets"
+    }
+
+    async fn publish(&self, report: &BalanceReport) -> Result<(), anyhow::Error> {
+        let values = rows(report);
+        if values.is_empty() {
+            return Ok(());
+        }
+        let token = self.access_token().await?;
+
+        let sheet = self.config.sheet.as_deref().unwrap_or("Sheet1");
+        let mut url = reqwest::Url::parse(SHEETS_API_URL)?;
+        url.path_segments_mut()
+            .map_err(|_| anyhow::anyhow!("invalid Sheets API URL"))?
+            .push(&self.config.spreadsheet_id)
+            .push("values")
+            .push(&format!("{}!A:F:append", sheet));
+        let body = json!({ "values": values });
+        send_with_retry(&self.retry, || {
+            self.http
+                .post(url.clone())
+                .query(&[
+                    ("valueInputOption", "RAW"),
+                    ("insertDataOption", "INSERT_ROWS"),
+                ])
+                .bearer_auth(&token)
+                .json(&body)
+        })
+        .await
+    }
+}
+
+/// One row per wallet and asset, in address then asset order.
+fn rows(report: &BalanceReport) -> Vec<Vec<Value>> {
+    let timestamp = report.timestamp.to_rfc3339_opts(SecondsFormat::Secs, true);
+    let mut wallets: Vec<_> = report.wallets.iter().collect();
+    wallets.sort_by(|a, b| a.0.cmp(b.0));
+
+    let mut rows = Vec::new();
+    for (wallet, result) in wallets {
+        let label = result.label.clone().unwrap_or_default();
+        let mut push = |asset: &str, amount: f64, usd_value: Option<f64>| {
+            rows.push(vec![
+                json!(timestamp),
+                json!(wallet),
+                json!(label),
+                json!(asset),
+                json!(amount),
+                usd_value.map_or(json!(""), |value| json!(value)),
+            ]);
+        };
+        push(SOL_ASSET, result.sol_balance, result.sol_usd_value);
+        if let Some(stake) = &result.stake {
+            push(STAKED_SOL_ASSET, stake.total, None);
+        }
+        let mut tokens: Vec<_> = result.token_balances.iter().collect();
+        tokens.sort_by(|a, b| a.0.cmp(b.0));
+        for (ticker, balance) in tokens {
+            push(ticker, balance.amount, balance.usd_value);
+        }
+    }
+    rows
+}
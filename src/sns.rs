@@ -0,0 +1,88 @@
+use solana_sdk::hash::hashv;
+use solana_sdk::pubkey;
+use solana_sdk::pubkey::Pubkey;
+
+/// The SPL Name Service program that Bonfida's SNS is built on.
+pub const NAME_PROGRAM_ID: Pubkey = pubkey!("namesLPneVptA9Z5rqUDD9tMTWEJwofgaYwp8cawRkX");
+/// Parent of every `.sol` domain.
+pub const SOL_TLD_AUTHORITY: Pubkey = pubkey!("58PwtjSDuFHuUkYjH9BYnnQKHfwo9reZhC2zMJv9JPkx");
+/// Class of the reverse records mapping a name account back to its name.
+pub const REVERSE_LOOKUP_CLASS: Pubkey = pubkey!("33m47vH6Eav6jr5Ry86XjhRft2jRBLDnDgPSHoquXi2Z");
+/// Program holding each wallet's primary ("favourite") domain.
+pub const NAME_OFFERS_PROGRAM_ID: Pubkey = pubkey!("85iDfUvr3HJyLM2LffCRdZJt7WifrSYTWqq7H9aWyGzm");
+
+const HASH_PREFIX: &str = "SPL Name Service";
+/// Parent, owner and class, in that order, precede a name record's data.
+const NAME_RECORD_HEADER_LEN: usize = 96;
+
+pub fn is_sol_domain(wallet: &str) -> bool {
+    wallet.len() > ".sol".len() && wallet.ends_with(".sol")
+}
+
+/// Derives the name account for `domain`, e.g. `bonfida.sol` or
+/// `dex.bonfida.sol`.
+pub fn domain_address(domain: &str) -> Result<Pubkey, anyhow::Error> {
+    let name = domain.strip_suffix(".sol").unwrap_or(domain);
+    let labels: Vec<&str> = name.split('.').collect();
+    if labels.len() > 2 || labels.iter().any(|label| label.is_empty()) {
+        anyhow::bail!("invalid .sol domain `{}`", domain);
+    }
+
+    let parent = name_account(
+        labels[labels.len() - 1],
+        &Pubkey::default(),
+        &SOL_TLD_AUTHORITY,
+    );
+    Ok(match labels.as_slice() {
+        [subdomain, _] => name_account(&format!("\0{}", subdomain), &Pubkey::default(), &parent),
+        _ => parent,
+    })
+}
+
+/// The owner recorded in a name account's header.
+pub fn parse_owner(data: &[u8]) -> Option<Pubkey> {
+    if data.len() < NAME_RECORD_HEADER_LEN {
+        return None;
+    }
+    Pubkey::try_from(&data[32..64]).ok()
+}
+
+/// The account recording `wallet`'s primary domain.
+pub fn primary_domain_address(wallet: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(
+        &[b"favourite_domain", wallet.as_ref()],
+        &NAME_OFFERS_PROGRAM_ID,
+    )
+    .0
+}
+
+/// The name account a primary domain record points to.
+pub fn parse_primary_domain(data: &[u8]) -> Option<Pubkey> {
+    Pubkey::try_from(data.get(1..33)?).ok()
+}
+
+/// The reverse record storing the name of `name_account`.
+pub fn reverse_address(name_account: &Pubkey) -> Pubkey {
+    self::name_account(
+        &name_account.to_string(),
+        &REVERSE_LOOKUP_CLASS,
+        &Pubkey::default(),
+    )
+}
+
+/// The domain, with its `.sol` suffix, stored in a reverse record.
+pub fn parse_reverse_name(data: &[u8]) -> Option<String> {
+    let data = data.get(NAME_RECORD_HEADER_LEN..)?;
+    let len = u32::from_le_bytes(data.get(..4)?.try_into().ok()?) as usize;
+    let name = std::str::from_utf8(data.get(4..4 + len)?).ok()?;
+    (!name.is_empty()).then(|| format!("{}.sol", name))
+}
+
+fn name_account(name: &str, class: &Pubkey, parent: &Pubkey) -> Pubkey {
+    let hashed = hashv(&[HASH_PREFIX.as_bytes(), name.as_bytes()]);
+    Pubkey::find_program_address(
+        &[hashed.as_ref(), class.as_ref(), parent.as_ref()],
+        &NAME_PROGRAM_ID,
+    )
+    .0
+}
@@ -0,0 +1,15 @@
+use solana_sdk::pubkey;
+use solana_sdk::pubkey::Pubkey;
+
+/// The Squads v4 multisig program.
+pub const SQUADS_PROGRAM_ID: Pubkey = pubkey!("SQDS4ep65T869zMMBKyuUq6aD6EgTu8psMjkvj52pCf");
+
+/// Derives the vault with `index` of a Squads v4 multisig. Vaults hold the
+/// multisig's funds; the multisig account itself only holds its settings.
+pub fn vault_address(multisig: &Pubkey, index: u8) -> Pubkey {
+    Pubkey::find_program_address(
+        &[b"multisig", multisig.as_ref(), b"vault", &[index]],
+        &SQUADS_PROGRAM_ID,
+    )
+    .0
+}
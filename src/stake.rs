@@ -0,0 +1,145 @@
+use crate::fetcher::BalanceFetcher;
+use serde::{Deserialize, Serialize};
+use solana_account_decoder_client_types::UiAccountEncoding;
+use solana_client::rpc_config::{RpcAccountInfoConfig, RpcProgramAccountsConfig};
+use solana_client::rpc_filter::{Memcmp, RpcFilterType};
+use solana_sdk::account::Account;
+use solana_sdk::clock::{Epoch, Slot};
+use solana_sdk::feature_set::reduce_stake_warmup_cooldown;
+use solana_sdk::native_token::lamports_to_sol;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::stake::state::StakeStateV2;
+use solana_sdk::stake_history::StakeHistory;
+use solana_sdk::{feature, stake, sysvar};
+use std::collections::HashMap;
+
+/// Offsets of `Meta.authorized.staker` and `Meta.authorized.withdrawer` in a
+/// stake account: a 4-byte enum tag followed by the 8-byte rent reserve.
+const STAKER_OFFSET: usize = 12;
+const WITHDRAWER_OFFSET: usize = 44;
+
+/// Stake held in accounts for which a wallet is the staker or withdrawer.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct StakeSummary {
+    pub accounts: usize,
+    /// Total SOL held by the stake accounts, including rent reserves and
+    /// undelegated lamports.
+    pub total: f64,
+    pub active: f64,
+    pub activating: f64,
+    pub deactivating: f64,
+}
+
+/// Cluster-wide state needed to compute stake activation, fetched once per run.
+pub struct StakeContext {
+    epoch: Epoch,
+    history: StakeHistory,
+    new_rate_activation_epoch: Option<Epoch>,
+}
+
+impl BalanceFetcher {
+    pub async fn get_stake_context(&self) -> Result<StakeContext, anyhow::Error> {
+        let _permit = self.permit().await?;
+        let epoch_info = self.client.get_epoch_info().await?;
+        let history: StakeHistory = self
+            .client
+            .get_account(&sysvar::stake_history::id())
+            .await?
+            .deserialize_data()?;
+
+        let feature_account = self
+            .client
+            .get_account_with_commitment(
+                &reduce_stake_warmup_cooldown::id(),
+                self.client.commitment(),
+            )
+            .await?
+            .value;
+        let activated_slot = feature_account
+            .as_ref()
+            .and_then(feature::from_account)
+            .and_then(|feature| feature.activated_at);
+        let new_rate_activation_epoch = match activated_slot {
+            Some(slot) => Some(self.client.get_epoch_schedule().await?.get_epoch(slot)),
+            None => None,
+        };
+
+        Ok(StakeContext {
+            epoch: epoch_info.epoch,
+            history,
+            new_rate_activation_epoch,
+        })
+    }
+
+    /// Finds stake accounts whose staker or withdrawer authority is
+    /// `wallet_pubkey` and sums them by activation state.
+    pub async fn get_stake_summary(
+        &self,
+        wallet_pubkey: &Pubkey,
+        context: &StakeContext,
+        min_context_slot: Option<Slot>,
+    ) -> Result<StakeSummary, anyhow::Error> {
+        let accounts = self
+            .get_stake_accounts(wallet_pubkey, min_context_slot)
+            .await?;
+        let mut summary = StakeSummary {
+            accounts: accounts.len(),
+            ..StakeSummary::default()
+        };
+        for account in accounts.values() {
+            summary.total += lamports_to_sol(account.lamports);
+
+            let Some(delegation) = account
+                .deserialize_data::<StakeStateV2>()
+                .ok()
+                .and_then(|state| state.delegation())
+            else {
+                continue;
+            };
+            let status = delegation.stake_activating_and_deactivating(
+                context.epoch,
+                &context.history,
+                context.new_rate_activation_epoch,
+            );
+            summary.active += lamports_to_sol(status.effective);
+            summary.activating += lamports_to_sol(status.activating);
+            summary.deactivating += lamports_to_sol(status.deactivating);
+        }
+
+        Ok(summary)
+    }
+
+    /// Stake accounts whose staker or withdrawer authority is `wallet_pubkey`.
+    pub(crate) async fn get_stake_accounts(
+        &self,
+        wallet_pubkey: &Pubkey,
+        min_context_slot: Option<Slot>,
+    ) -> Result<HashMap<Pubkey, Account>, anyhow::Error> {
+        let mut accounts = HashMap::new();
+        for offset in [STAKER_OFFSET, WITHDRAWER_OFFSET] {
+            let config = RpcProgramAccountsConfig {
+                filters: Some(vec![
+                    RpcFilterType::DataSize(StakeStateV2::size_of() as u64),
+                    RpcFilterType::Memcmp(Memcmp::new_base58_encoded(
+                        offset,
+                        wallet_pubkey.as_ref(),
+                    )),
+                ]),
+                account_config: RpcAccountInfoConfig {
+                    encoding: Some(UiAccountEncoding::Base64),
+                    commitment: Some(self.client.commitment()),
+                    min_context_slot,
+                    ..RpcAccountInfoConfig::default()
+                },
+                ..RpcProgramAccountsConfig::default()
+            };
+            let _permit = self.permit().await?;
+            accounts.extend(
+                self.client
+                    .get_program_accounts_with_config(&stake::program::id(), config)
+                    .await?,
+            );
+        }
+        Ok(accounts)
+    }
+}
@@ -0,0 +1,428 @@
+use crate::config::TokenConfig;
+use crate::fetcher::BalanceFetcher;
+use crate::group::summarize_groups;
+use crate::report::BalanceReport;
+use crate::token_account::{
+    associated_token_address, decode_token_account_data, format_ui_amount, ui_amount,
+    TOKEN_PROGRAM_IDS,
+};
+use anyhow::Context;
+use chrono::Utc;
+use futures::stream::{select_all, BoxStream, StreamExt};
+use serde::Deserialize;
+use solana_account_decoder_client_types::UiAccountEncoding;
+use solana_client::nonblocking::pubsub_client::PubsubClient;
+use solana_client::rpc_config::RpcAccountInfoConfig;
+use solana_sdk::native_token::lamports_to_sol;
+use solana_sdk::pubkey::Pubkey;
+use std::collections::{BTreeSet, HashMap};
+use std::str::FromStr;
+use std::time::Duration;
+use tokio::sync::mpsc;
+
+const INITIAL_BACKOFF: Duration = Duration::from_secs(1);
+const MAX_BACKOFF: Duration = Duration::from_secs(60);
+
+/// A Yellowstone gRPC (Geyser) endpoint to stream account updates from in
+/// `watch --subscribe`, instead of the RPC node's WebSocket.
+#[derive(Debug, Clone, Deserialize)]
+pub struct GeyserConfig {
+    /// e.g. `https://example.rpcpool.com:443`.
+    pub endpoint: String,
+    /// Sent as the `x-token` header, for providers that need one.
+    #[serde(default)]
+    pub x_token: Option<String>,
+}
+
+/// Where account updates come from.
+#[derive(Clone, Copy)]
+enum Source<'a> {
+    PubSub(&'a str),
+    #[cfg_attr(not(feature = "geyser"), allow(dead_code))]
+    Geyser(&'a GeyserConfig),
+}
+
+impl Source<'_> {
+    fn endpoint(&self) -> &str {
+        match self {
+            Source::PubSub(ws_url) => ws_url,
+            Source::Geyser(config) => &config.endpoint,
+        }
+    }
+}
+
+/// What a subscribed account holds for the report.
+enum Watched {
+    /// A wallet's system account.
+    Wallet { wallet: String },
+    /// One of a wallet's token accounts, existing or not yet created.
+    Token { wallet: String, mint: Pubkey },
+}
+
+impl Watched {
+    fn is_token_of(&self, wallet: &str, mint: &Pubkey) -> bool {
+        matches!(self, Watched::Token { wallet: w, mint: m } if w == wallet && m == mint)
+    }
+}
+
+/// A new state of a watched account.
+struct AccountUpdate {
+    address: Pubkey,
+    lamports: u64,
+    data: Vec<u8>,
+    slot: u64,
+}
+
+impl BalanceFetcher {
+    /// Fetches a full report, then keeps it up to date from account
+    /// notifications on `ws_url`, sending a copy to `updates` after each
+    /// change. Each wallet's system account and token accounts (including
+    /// associated token accounts not created yet) are subscribed to.
+    ///
+    /// When the connection drops, the error is sent and the report is
+    /// fetched and subscribed to again, backing off between attempts.
+    /// Returns once `updates` is closed.
+    pub async fn subscribe_reports(
+        &self,
+        config: &TokenConfig,
+        ws_url: &str,
+        updates: mpsc::Sender<Result<BalanceReport, anyhow::Error>>,
+    ) -> Result<(), anyhow::Error> {
+        self.subscribe(config, Source::PubSub(ws_url), updates)
+            .await
+    }
+
+    /// Like [`subscribe_reports`](Self::subscribe_reports), streaming the
+    /// account updates from a Yellowstone gRPC endpoint.
+    #[cfg(feature = "geyser")]
+    pub async fn subscribe_reports_geyser(
+        &self,
+        config: &TokenConfig,
+        geyser: &GeyserConfig,
+        updates: mpsc::Sender<Result<BalanceReport, anyhow::Error>>,
+    ) -> Result<(), anyhow::Error> {
+        self.subscribe(config, Source::Geyser(geyser), updates)
+            .await
+    }
+
+    async fn subscribe(
+        &self,
+        config: &TokenConfig,
+        source: Source<'_>,
+        updates: mpsc::Sender<Result<BalanceReport, anyhow::Error>>,
+    ) -> Result<(), anyhow::Error> {
+        let mut backoff = INITIAL_BACKOFF;
+        loop {
+            let outcome = match self.fetch_report(config).await {
+                Ok(report) => self.follow_report(config, source, report, &updates).await,
+                Err(err) => Err(err.into()),
+            };
+            let err = match outcome {
+                _ if updates.is_closed() => return Ok(()),
+                Ok(notified) => {
+                    // Notifications arrived, so the connection was healthy
+                    // until now.
+                    if notified {
+                        backoff = INITIAL_BACKOFF;
+                    }
+                    anyhow::anyhow!("subscription to {} closed", source.endpoint())
+                }
+                Err(err) => err,
+            };
+            if updates.send(Err(err)).await.is_err() {
+                return Ok(());
+            }
+            tokio::time::sleep(backoff).await;
+            backoff = (backoff * 2).min(MAX_BACKOFF);
+        }
+    }
+
+    /// Lists the accounts behind `report`, with the current amount of every
+    /// token account that exists.
+    async fn watched_accounts(
+        &self,
+        report: &BalanceReport,
+    ) -> Result<(HashMap<Pubkey, Watched>, HashMap<Pubkey, u64>), anyhow::Error> {
+        let mut watched: HashMap<Pubkey, Watched> = HashMap::new();
+        let mut amounts: HashMap<Pubkey, u64> = HashMap::new();
+        for (wallet, result) in &report.wallets {
+            let owner = Pubkey::from_str(wallet)?;
+            watched.insert(
+                owner,
+                Watched::Wallet {
+                    wallet: wallet.clone(),
+                },
+            );
+
+            let mints: BTreeSet<Pubkey> = result
+                .token_balances
+                .values()
+                .filter_map(|balance| Pubkey::from_str(&balance.mint).ok())
+                .collect();
+            if mints.is_empty() {
+                continue;
+            }
+            for detail in self.get_token_account_details(&owner, None).await? {
+                let mint = Pubkey::from_str(&detail.mint)?;
+                if mints.contains(&mint) {
+                    let address = Pubkey::from_str(&detail.address)?;
+                    amounts.insert(address, detail.raw_amount);
+                    watched.insert(
+                        address,
+                        Watched::Token {
+                            wallet: wallet.clone(),
+                            mint,
+                        },
+                    );
+                }
+            }
+            for mint in mints {
+                for program_id in &TOKEN_PROGRAM_IDS {
+                    let address = associated_token_address(&owner, program_id, &mint);
+                    watched.entry(address).or_insert_with(|| Watched::Token {
+                        wallet: wallet.clone(),
+                        mint,
+                    });
+                }
+            }
+        }
+        Ok((watched, amounts))
+    }
+
+    /// Subscribes to the WebSocket notifications of every watched account.
+    async fn pubsub_updates<'a>(
+        &self,
+        client: &'a PubsubClient,
+        watched: &HashMap<Pubkey, Watched>,
+    ) -> Result<BoxStream<'a, Result<AccountUpdate, anyhow::Error>>, anyhow::Error> {
+        let account_config = RpcAccountInfoConfig {
+            encoding: Some(UiAccountEncoding::Base64),
+            commitment: Some(self.client.commitment()),
+            ..RpcAccountInfoConfig::default()
+        };
+        let mut streams = Vec::with_capacity(watched.len());
+        for address in watched.keys() {
+            let (stream, _unsubscribe) = client
+                .account_subscribe(address, Some(account_config.clone()))
+                .await
+                .with_context(|| format!("subscribing to {}", address))?;
+            let address = *address;
+            streams.push(stream.map(move |response| {
+                Ok(AccountUpdate {
+                    address,
+                    lamports: response.value.lamports,
+                    data: response.value.data.decode().unwrap_or_default(),
+                    slot: response.context.slot,
+                })
+            }));
+        }
+        Ok(select_all(streams).boxed())
+    }
+
+    /// Subscribes to the accounts behind `report` and applies notifications
+    /// until the stream ends or `updates` is closed. Returns whether any
+    /// notification arrived.
+    async fn follow_report(
+        &self,
+        config: &TokenConfig,
+        source: Source<'_>,
+        mut report: BalanceReport,
+        updates: &mpsc::Sender<Result<BalanceReport, anyhow::Error>>,
+    ) -> Result<bool, anyhow::Error> {
+        let (watched, mut amounts) = self.watched_accounts(&report).await?;
+
+        let pubsub;
+        let mut notifications = match source {
+            Source::PubSub(ws_url) => {
+                pubsub = PubsubClient::new(ws_url)
+                    .await
+                    .with_context(|| format!("connecting to {}", ws_url))?;
+                self.pubsub_updates(&pubsub, &watched).await?
+            }
+            #[cfg(feature = "geyser")]
+            Source::Geyser(geyser) => {
+                let addresses = watched.keys().copied().collect();
+                geyser::account_updates(geyser, addresses, self.client.commitment()).await?
+            }
+            #[cfg(not(feature = "geyser"))]
+            Source::Geyser(geyser) => anyhow::bail!(
+                "cannot stream from {}; this build lacks the `geyser` feature",
+                geyser.endpoint
+            ),
+        };
+
+        if updates.send(Ok(report.clone())).await.is_err() {
+            return Ok(false);
+        }
+
+        let prices = report.unit_prices();
+        let mut notified = false;
+        while let Some(update) = notifications.next().await {
+            let update = update?;
+            let Some(entry) = watched.get(&update.address) else {
+                continue;
+            };
+            match entry {
+                Watched::Wallet { wallet } => {
+                    let Some(result) = report.wallets.get_mut(wallet) else {
+                        continue;
+                    };
+                    result.sol_lamports = update.lamports;
+                    result.sol_balance = lamports_to_sol(update.lamports);
+                }
+                Watched::Token { wallet, mint } => {
+                    let owner = Pubkey::from_str(wallet)?;
+                    // A closed or transferred account no longer counts.
+                    let amount = decode_token_account_data(&update.data)
+                        .filter(|(account_owner, account_mint, _)| {
+                            *account_owner == owner && account_mint == mint
+                        })
+                        .map_or(0, |(_, _, amount)| amount);
+                    amounts.insert(update.address, amount);
+
+                    let total = watched
+                        .iter()
+                        .filter(|(_, entry)| entry.is_token_of(wallet, mint))
+                        .filter_map(|(address, _)| amounts.get(address))
+                        .fold(0u64, |total, amount| total.saturating_add(*amount));
+                    let Some(balance) = report.wallets.get_mut(wallet).and_then(|result| {
+                        let mint = mint.to_string();
+                        result
+                            .token_balances
+                            .values_mut()
+                            .find(|balance| balance.mint == mint)
+                    }) else {
+                        continue;
+                    };
+                    // Liquid staking tokens keep the exchange rate they
+                    // were last fetched at.
+                    let sol_rate = balance
+                        .sol_value
+                        .filter(|_| balance.amount > 0.0)
+                        .map(|sol_value| sol_value / balance.amount);
+                    balance.raw_amount = total;
+                    balance.amount = ui_amount(total, balance.decimals);
+                    if let Some(rate) = sol_rate {
+                        balance.sol_value = Some(balance.amount * rate);
+                    }
+                    balance.ui_amount_string = format_ui_amount(total, balance.decimals);
+                }
+            }
+
+            if report.total_usd.is_some() {
+                report.apply_prices(&prices);
+            }
+            report.groups = summarize_groups(&config.groups, &report);
+            report.timestamp = Utc::now();
+            report.slot = Some(update.slot);
+            report.same_slot = false;
+            notified = true;
+            if updates.send(Ok(report.clone())).await.is_err() {
+                break;
+            }
+        }
+        Ok(notified)
+    }
+}
+
+#[cfg(feature = "geyser")]
+mod geyser {
+    use super::{AccountUpdate, GeyserConfig};
+    use anyhow::Context;
+    use futures::stream::{self, BoxStream, StreamExt};
+    use futures::SinkExt;
+    use solana_sdk::commitment_config::CommitmentConfig;
+    use solana_sdk::pubkey::Pubkey;
+    use std::collections::HashMap;
+    use yellowstone_grpc_client::{ClientTlsConfig, GeyserGrpcClient};
+    use yellowstone_grpc_proto::prelude::{
+        subscribe_update::UpdateOneof, CommitmentLevel, SubscribeRequest,
+        SubscribeRequestFilterAccounts, SubscribeRequestPing,
+    };
+
+    /// Streams updates of `addresses` at `commitment`, answering the
+    /// server's pings so idle connections are kept open.
+    pub(super) async fn account_updates(
+        config: &GeyserConfig,
+        addresses: Vec<Pubkey>,
+        commitment: CommitmentConfig,
+    ) -> Result<BoxStream<'static, Result<AccountUpdate, anyhow::Error>>, anyhow::Error> {
+        let mut builder = GeyserGrpcClient::build_from_shared(config.endpoint.clone())?
+            .x_token(config.x_token.clone())?;
+        if config.endpoint.starts_with("https://") {
+            builder = builder.tls_config(ClientTlsConfig::new().with_native_roots())?;
+        }
+        let mut client = builder
+            .connect()
+            .await
+            .with_context(|| format!("connecting to {}", config.endpoint))?;
+
+        let commitment = if commitment.is_finalized() {
+            CommitmentLevel::Finalized
+        } else if commitment.is_confirmed() {
+            CommitmentLevel::Confirmed
+        } else {
+            CommitmentLevel::Processed
+        };
+        let request = SubscribeRequest {
+            accounts: HashMap::from([(
+                "wallets".to_string(),
+                SubscribeRequestFilterAccounts {
+                    account: addresses.iter().map(Pubkey::to_string).collect(),
+                    ..Default::default()
+                },
+            )]),
+            commitment: Some(commitment as i32),
+            ..Default::default()
+        };
+        let (requests, responses) = client
+            .subscribe_with_request(Some(request))
+            .await
+            .with_context(|| format!("subscribing on {}", config.endpoint))?;
+
+        // The client is kept alongside the streams, which end with it.
+        let state = (client, Box::pin(requests), Box::pin(responses));
+        Ok(
+            stream::unfold(state, |(client, mut requests, mut responses)| async move {
+                loop {
+                    let update = match responses.next().await? {
+                        Ok(update) => update,
+                        Err(status) => {
+                            let err = anyhow::Error::from(status).context("geyser stream");
+                            return Some((Err(err), (client, requests, responses)));
+                        }
+                    };
+                    match update.update_oneof {
+                        Some(UpdateOneof::Account(account)) => {
+                            let Some(info) = account.account else {
+                                continue;
+                            };
+                            let Ok(address) = Pubkey::try_from(info.pubkey.as_slice()) else {
+                                continue;
+                            };
+                            let update = AccountUpdate {
+                                address,
+                                lamports: info.lamports,
+                                data: info.data,
+                                slot: account.slot,
+                            };
+                            return Some((Ok(update), (client, requests, responses)));
+                        }
+                        Some(UpdateOneof::Ping(_)) => {
+                            let ping = SubscribeRequest {
+                                ping: Some(SubscribeRequestPing { id: 1 }),
+                                ..Default::default()
+                            };
+                            if let Err(err) = requests.send(ping).await {
+                                let err = anyhow::Error::from(err).context("geyser ping");
+                                return Some((Err(err), (client, requests, responses)));
+                            }
+                        }
+                        _ => {}
+                    }
+                }
+            })
+            .boxed(),
+        )
+    }
+}
@@ -0,0 +1,257 @@
+use serde::{Deserialize, Serialize};
+use solana_account_decoder_client_types::UiAccountData;
+use solana_client::rpc_response::RpcKeyedAccount;
+use solana_sdk::account::Account;
+use solana_sdk::program_pack::Pack;
+use solana_sdk::pubkey;
+use solana_sdk::pubkey::Pubkey;
+use spl_token_2022::extension::StateWithExtensions;
+use std::fmt;
+use std::str::FromStr;
+
+/// Token programs whose accounts are reported: legacy SPL Token and Token-2022.
+pub const TOKEN_PROGRAM_IDS: [Pubkey; 2] = [spl_token::ID, spl_token_2022::ID];
+
+pub const SOL_DECIMALS: u8 = 9;
+
+/// Ticker given to the native mint, so wrapped SOL is not mistaken for the
+/// wallet's own SOL.
+pub const WRAPPED_SOL_TICKER: &str = "wSOL";
+
+const ASSOCIATED_TOKEN_PROGRAM_ID: Pubkey = pubkey!("ATokenGPvbdGVxr1b2hvZbsiqW5xWH25efTNsLJA8knL");
+
+/// A single token account and its balance.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TokenAccountDetail {
+    pub address: String,
+    pub mint: String,
+    /// The token program owning the account.
+    pub program_id: String,
+    /// Whether this is the wallet's associated token account for the mint.
+    #[serde(default)]
+    pub associated: bool,
+    #[serde(default)]
+    pub state: TokenAccountState,
+    pub raw_amount: u64,
+    pub decimals: u8,
+    /// The account's rent-exempt reserve, returned when it is closed.
+    #[serde(default)]
+    pub lamports: u64,
+}
+
+impl TokenAccountDetail {
+    pub fn ui_amount(&self) -> f64 {
+        ui_amount(self.raw_amount, self.decimals)
+    }
+
+    /// `"Token"` or `"Token-2022"`.
+    pub fn program_name(&self) -> &'static str {
+        if Pubkey::from_str(&self.program_id).ok() == Some(TOKEN_PROGRAM_IDS[1]) {
+            "Token-2022"
+        } else {
+            "Token"
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TokenAccountState {
+    #[default]
+    Initialized,
+    /// The mint's freeze authority has frozen the account; its tokens
+    /// cannot be moved.
+    Frozen,
+}
+
+impl fmt::Display for TokenAccountState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            TokenAccountState::Initialized => "initialized",
+            TokenAccountState::Frozen => "frozen",
+        })
+    }
+}
+
+/// An approval letting `delegate` transfer tokens out of a token account
+/// without the owner signing.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Delegation {
+    pub account: String,
+    pub delegate: String,
+    /// What the delegate can still pull, in base units: the approved
+    /// amount, capped at the account's balance.
+    pub raw_amount: u64,
+    pub amount: f64,
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct DecodedTokenAccount {
+    pub mint: Pubkey,
+    pub amount: u64,
+    /// Known when the RPC returned `jsonParsed` data; binary fallbacks need
+    /// the mint to be looked up.
+    pub decimals: Option<u8>,
+    pub state: TokenAccountState,
+    /// The delegate and what it can still pull, when there is one.
+    pub delegate: Option<(Pubkey, u64)>,
+}
+
+#[derive(Deserialize, Debug)]
+struct ParsedInfo {
+    info: AccountInfo,
+}
+
+#[derive(Deserialize, Debug)]
+struct AccountInfo {
+    mint: String,
+    #[serde(default)]
+    state: TokenAccountState,
+    #[serde(rename = "tokenAmount")]
+    token_amount: TokenAmount,
+    #[serde(default)]
+    delegate: Option<String>,
+    #[serde(default, rename = "delegatedAmount")]
+    delegated_amount: Option<TokenAmount>,
+}
+
+#[derive(Deserialize, Debug)]
+struct TokenAmount {
+    amount: String,
+    decimals: u8,
+}
+
+/// Decodes a token account returned by `getTokenAccountsByOwner`.
+///
+/// The RPC node falls back to base64 when it cannot `jsonParse` an account
+/// (e.g. a Token-2022 account with an extension it does not know), so raw
+/// data is unpacked locally with extension support.
+pub(crate) fn decode_token_account(account: &RpcKeyedAccount) -> Option<DecodedTokenAccount> {
+    match &account.account.data {
+        UiAccountData::Json(parsed_account) => {
+            let info = serde_json::from_value::<ParsedInfo>(parsed_account.parsed.clone())
+                .ok()?
+                .info;
+            let amount = info.token_amount.amount.parse().ok()?;
+            let delegate = info
+                .delegate
+                .and_then(|delegate| Pubkey::from_str(&delegate).ok())
+                .zip(info.delegated_amount)
+                .and_then(|(delegate, delegated)| {
+                    at_risk(delegate, delegated.amount.parse().ok()?, amount)
+                });
+            Some(DecodedTokenAccount {
+                mint: Pubkey::from_str(&info.mint).ok()?,
+                amount,
+                decimals: Some(info.token_amount.decimals),
+                state: info.state,
+                delegate,
+            })
+        }
+        data => {
+            let bytes = data.decode()?;
+            let state =
+                StateWithExtensions::<spl_token_2022::state::Account>::unpack(&bytes).ok()?;
+            Some(DecodedTokenAccount {
+                mint: state.base.mint,
+                amount: state.base.amount,
+                decimals: None,
+                state: decode_state(&bytes),
+                delegate: decode_delegate(&bytes),
+            })
+        }
+    }
+}
+
+/// The associated token account of `wallet` for `mint` under `token_program`.
+pub fn associated_token_address(wallet: &Pubkey, token_program: &Pubkey, mint: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(
+        &[wallet.as_ref(), token_program.as_ref(), mint.as_ref()],
+        &ASSOCIATED_TOKEN_PROGRAM_ID,
+    )
+    .0
+}
+
+/// Reads the owner, mint and amount of a raw legacy or Token-2022 token
+/// account.
+pub(crate) fn decode_token_account_data(data: &[u8]) -> Option<(Pubkey, Pubkey, u64)> {
+    let state = StateWithExtensions::<spl_token_2022::state::Account>::unpack(data).ok()?;
+    Some((state.base.owner, state.base.mint, state.base.amount))
+}
+
+/// Reads whether a raw token account is frozen.
+pub(crate) fn decode_state(data: &[u8]) -> TokenAccountState {
+    match StateWithExtensions::<spl_token_2022::state::Account>::unpack(data) {
+        Ok(state) if state.base.is_frozen() => TokenAccountState::Frozen,
+        _ => TokenAccountState::Initialized,
+    }
+}
+
+/// Reads the delegate of a raw token account and what it can still pull.
+pub(crate) fn decode_delegate(data: &[u8]) -> Option<(Pubkey, u64)> {
+    let state = StateWithExtensions::<spl_token_2022::state::Account>::unpack(data).ok()?;
+    let delegate = Option::<Pubkey>::from(state.base.delegate)?;
+    at_risk(delegate, state.base.delegated_amount, state.base.amount)
+}
+
+/// A delegation worth reporting: one that can still move tokens.
+fn at_risk(delegate: Pubkey, delegated_amount: u64, amount: u64) -> Option<(Pubkey, u64)> {
+    let at_risk = delegated_amount.min(amount);
+    (at_risk > 0).then_some((delegate, at_risk))
+}
+
+/// Reads `decimals` from a legacy or Token-2022 mint account.
+pub(crate) fn decode_mint_decimals(data: &[u8]) -> Option<u8> {
+    StateWithExtensions::<spl_token_2022::state::Mint>::unpack(data)
+        .ok()
+        .map(|state| state.base.decimals)
+}
+
+/// Why `account` is not an initialized legacy or Token-2022 mint, if it
+/// is not one.
+pub(crate) fn mint_problem(account: Option<&Account>) -> Option<&'static str> {
+    let Some(account) = account else {
+        return Some("does not exist on-chain");
+    };
+    if !TOKEN_PROGRAM_IDS.contains(&account.owner) {
+        return Some("is not owned by a token program");
+    }
+    if account.data.len() == spl_token::state::Account::LEN
+        || decode_token_account_data(&account.data).is_some()
+    {
+        return Some("is a token account, not a mint");
+    }
+    if decode_mint_decimals(&account.data).is_none() {
+        return Some("is not an initialized token mint");
+    }
+    None
+}
+
+/// A little-endian `u64` at `offset` in raw account data.
+pub(crate) fn read_u64(data: &[u8], offset: usize) -> Option<u64> {
+    let bytes = data.get(offset..offset + 8)?;
+    Some(u64::from_le_bytes(bytes.try_into().ok()?))
+}
+
+pub(crate) fn ui_amount(amount: u64, decimals: u8) -> f64 {
+    amount as f64 / 10f64.powi(decimals as i32)
+}
+
+/// Formats a base-unit amount as an exact decimal string, e.g.
+/// `(1_500_000, 6)` as `"1.5"`.
+pub fn format_ui_amount(amount: u64, decimals: u8) -> String {
+    let digits = amount.to_string();
+    let decimals = decimals as usize;
+    if decimals == 0 {
+        return digits;
+    }
+
+    let padded = format!("{:0>width$}", digits, width = decimals + 1);
+    let (whole, fraction) = padded.split_at(padded.len() - decimals);
+    let fraction = fraction.trim_end_matches('0');
+    if fraction.is_empty() {
+        whole.to_string()
+    } else {
+        format!("{}.{}", whole, fraction)
+    }
+}
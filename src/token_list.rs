@@ -0,0 +1,136 @@
+use crate::cache::cache_dir;
+use serde::{Deserialize, Serialize};
+use solana_sdk::pubkey::Pubkey;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::time::{Duration, SystemTime};
+
+const JUPITER_TOKEN_LIST_URL: &str = "https://tokens.jup.ag/tokens?tags=verified";
+const MAINNET_CHAIN_ID: u32 = 101;
+const SOLANA_LABS_TOKEN_LIST_URL: &str =
+    "https://raw.githubusercontent.com/solana-labs/token-list/main/src/tokens/solana.tokenlist.json";
+
+#[derive(Debug, Clone, Copy, Deserialize, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum TokenListSource {
+    #[default]
+    Jupiter,
+    SolanaLabs,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct TokenListConfig {
+    #[serde(default)]
+    pub source: TokenListSource,
+    /// Where the downloaded list is kept [default: the user cache directory].
+    #[serde(default)]
+    pub cache_path: Option<PathBuf>,
+    /// How long a downloaded list is reused before fetching it again.
+    #[serde(default = "default_max_age", with = "humantime_serde")]
+    pub max_age: Duration,
+}
+
+fn default_max_age() -> Duration {
+    Duration::from_secs(24 * 60 * 60)
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TokenListEntry {
+    pub address: String,
+    pub symbol: String,
+    pub name: String,
+    pub decimals: u8,
+    #[serde(rename = "logoURI", default, skip_serializing_if = "Option::is_none")]
+    pub logo_uri: Option<String>,
+    /// Set by the Solana Labs list, which also covers devnet and testnet.
+    #[serde(rename = "chainId", default, skip_serializing_if = "Option::is_none")]
+    pub chain_id: Option<u32>,
+}
+
+#[derive(Deserialize)]
+struct SolanaLabsTokenList {
+    tokens: Vec<TokenListEntry>,
+}
+
+/// Symbols, names, decimals and logos of known mints.
+#[derive(Debug, Clone, Default)]
+pub struct TokenList {
+    tokens: HashMap<Pubkey, TokenListEntry>,
+}
+
+impl TokenList {
+    /// Reads the cached list, downloading it first when the cache is missing,
+    /// older than `max_age`, or `refresh` is set. A stale cache is still used
+    /// if the download fails.
+    pub async fn load(config: &TokenListConfig, refresh: bool) -> Result<Self, anyhow::Error> {
+        let path = config.cache_path.clone().unwrap_or_else(default_cache_path);
+
+        let age = fs::metadata(&path)
+            .and_then(|metadata| metadata.modified())
+            .ok()
+            .and_then(|modified| SystemTime::now().duration_since(modified).ok());
+        let fresh = age.is_some_and(|age| age < config.max_age);
+
+        if refresh || !fresh {
+            match download(config.source).await {
+                Ok(entries) => {
+                    save(&path, &entries)?;
+                    return Ok(Self::from_entries(entries));
+                }
+                Err(err) if age.is_none() => {
+                    return Err(anyhow::anyhow!("downloading token list: {}", err));
+                }
+                Err(_) => {}
+            }
+        }
+
+        let content = fs::read_to_string(&path)
+            .map_err(|err| anyhow::anyhow!("reading {}: {}", path.display(), err))?;
+        Ok(Self::from_entries(serde_json::from_str(&content)?))
+    }
+
+    pub fn from_entries(entries: Vec<TokenListEntry>) -> Self {
+        TokenList {
+            tokens: entries
+                .into_iter()
+                .filter_map(|entry| Some((Pubkey::from_str(&entry.address).ok()?, entry)))
+                .collect(),
+        }
+    }
+
+    pub fn get(&self, mint: &Pubkey) -> Option<&TokenListEntry> {
+        self.tokens.get(mint)
+    }
+}
+
+async fn download(source: TokenListSource) -> Result<Vec<TokenListEntry>, anyhow::Error> {
+    let url = match source {
+        TokenListSource::Jupiter => JUPITER_TOKEN_LIST_URL,
+        TokenListSource::SolanaLabs => SOLANA_LABS_TOKEN_LIST_URL,
+    };
+    let response = reqwest::get(url).await?.error_for_status()?;
+    Ok(match source {
+        TokenListSource::Jupiter => response.json().await?,
+        TokenListSource::SolanaLabs => response
+            .json::<SolanaLabsTokenList>()
+            .await?
+            .tokens
+            .into_iter()
+            .filter(|entry| entry.chain_id == Some(MAINNET_CHAIN_ID))
+            .collect(),
+    })
+}
+
+fn save(path: &Path, entries: &[TokenListEntry]) -> Result<(), anyhow::Error> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(path, serde_json::to_string(entries)?)
+        .map_err(|err| anyhow::anyhow!("writing {}: {}", path.display(), err))
+}
+
+fn default_cache_path() -> PathBuf {
+    cache_dir().join("token-list.json")
+}
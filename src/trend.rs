@@ -0,0 +1,71 @@
+use crate::diff::SOL_ASSET;
+use crate::report::BalanceReport;
+use std::collections::{HashMap, VecDeque};
+
+const BARS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+
+/// The last few polled values of every wallet's SOL and token balances,
+/// for drawing sparklines in long-running modes.
+#[derive(Debug, Clone)]
+pub struct BalanceTrends {
+    length: usize,
+    series: HashMap<(String, String), VecDeque<f64>>,
+}
+
+impl BalanceTrends {
+    /// Keeps up to `length` values per balance.
+    pub fn new(length: usize) -> Self {
+        BalanceTrends {
+            length: length.max(1),
+            series: HashMap::new(),
+        }
+    }
+
+    /// Appends the balances of `report`. Wallets that failed to fetch keep
+    /// their series unchanged.
+    pub fn record(&mut self, report: &BalanceReport) {
+        for (wallet, result) in &report.wallets {
+            self.push(wallet, SOL_ASSET, result.sol_balance);
+            for (ticker, balance) in &result.token_balances {
+                self.push(wallet, ticker, balance.amount);
+            }
+        }
+    }
+
+    fn push(&mut self, wallet: &str, asset: &str, value: f64) {
+        let series = self
+            .series
+            .entry((wallet.to_string(), asset.to_string()))
+            .or_default();
+        if series.len() == self.length {
+            series.pop_front();
+        }
+        series.push_back(value);
+    }
+
+    /// The recorded values of one balance, oldest first.
+    pub fn values(&self, wallet: &str, asset: &str) -> Option<&VecDeque<f64>> {
+        self.series.get(&(wallet.to_string(), asset.to_string()))
+    }
+
+    /// A sparkline such as `▁▂▄█▆` of one balance, scaled between its lowest
+    /// and highest recorded value. Empty until two values are recorded.
+    pub fn sparkline(&self, wallet: &str, asset: &str) -> String {
+        let Some(values) = self.values(wallet, asset).filter(|values| values.len() > 1) else {
+            return String::new();
+        };
+        let low = values.iter().copied().fold(f64::INFINITY, f64::min);
+        let high = values.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+        values
+            .iter()
+            .map(|value| {
+                if high > low {
+                    let level = (value - low) / (high - low) * (BARS.len() - 1) as f64;
+                    BARS[level.round() as usize]
+                } else {
+                    BARS[0]
+                }
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,157 @@
+use crate::config::TokenInfo;
+use crate::fetcher::BalanceFetcher;
+use crate::token_account::{read_u64, ui_amount};
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use solana_account_decoder_client_types::UiAccountEncoding;
+use solana_client::rpc_config::{RpcAccountInfoConfig, RpcProgramAccountsConfig};
+use solana_client::rpc_filter::{Memcmp, RpcFilterType};
+use solana_sdk::clock::Slot;
+use solana_sdk::pubkey;
+use solana_sdk::pubkey::Pubkey;
+use std::collections::{BTreeMap, HashMap};
+
+/// Streamflow's token vesting and payment stream program.
+pub const STREAMFLOW_PROGRAM_ID: Pubkey = pubkey!("strmRqUCoQUgGUan5YhzUZa6KqdzwX5L6FpUxfmKg5m");
+
+/// Offsets of the fields read from a Streamflow stream (`Contract`) account.
+const AMOUNT_WITHDRAWN_OFFSET: usize = 17;
+const CANCELED_AT_OFFSET: usize = 25;
+const RECIPIENT_OFFSET: usize = 113;
+const MINT_OFFSET: usize = 177;
+const START_TIME_OFFSET: usize = 409;
+const NET_AMOUNT_DEPOSITED_OFFSET: usize = 417;
+const PERIOD_OFFSET: usize = 425;
+const AMOUNT_PER_PERIOD_OFFSET: usize = 433;
+const CLIFF_OFFSET: usize = 441;
+const CLIFF_AMOUNT_OFFSET: usize = 449;
+
+/// Tokens of one mint vesting to a wallet, summed over its streams.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct VestingBalance {
+    pub mint: String,
+    pub streams: usize,
+    /// Not vested yet.
+    pub locked: f64,
+    /// Vested but not withdrawn, which the wallet can claim now.
+    pub claimable: f64,
+    pub decimals: u8,
+}
+
+/// The parts of a Streamflow stream that say how much has vested.
+struct Stream {
+    mint: Pubkey,
+    amount_withdrawn: u64,
+    start_time: u64,
+    net_amount_deposited: u64,
+    period: u64,
+    amount_per_period: u64,
+    cliff: u64,
+    cliff_amount: u64,
+}
+
+impl Stream {
+    /// Reads a stream, or `None` if the account is too short or the stream
+    /// was cancelled, which returns the unvested rest to the sender.
+    fn parse(data: &[u8]) -> Option<Self> {
+        if read_u64(data, CANCELED_AT_OFFSET)? > 0 {
+            return None;
+        }
+        Some(Stream {
+            mint: Pubkey::try_from(data.get(MINT_OFFSET..MINT_OFFSET + 32)?).ok()?,
+            amount_withdrawn: read_u64(data, AMOUNT_WITHDRAWN_OFFSET)?,
+            start_time: read_u64(data, START_TIME_OFFSET)?,
+            net_amount_deposited: read_u64(data, NET_AMOUNT_DEPOSITED_OFFSET)?,
+            period: read_u64(data, PERIOD_OFFSET)?,
+            amount_per_period: read_u64(data, AMOUNT_PER_PERIOD_OFFSET)?,
+            cliff: read_u64(data, CLIFF_OFFSET)?,
+            cliff_amount: read_u64(data, CLIFF_AMOUNT_OFFSET)?,
+        })
+    }
+
+    /// Base units vested by `now`: the cliff amount at the cliff, then
+    /// `amount_per_period` every full period after it.
+    fn vested(&self, now: u64) -> u64 {
+        let cliff = self.cliff.max(self.start_time);
+        if now < cliff {
+            return 0;
+        }
+        let periods = (now - cliff).checked_div(self.period).unwrap_or(0);
+        self.cliff_amount
+            .saturating_add(periods.saturating_mul(self.amount_per_period))
+            .min(self.net_amount_deposited)
+    }
+}
+
+impl BalanceFetcher {
+    /// Sums the Streamflow streams paying `recipient` into locked and
+    /// claimable amounts per token, keyed by ticker.
+    pub async fn get_vesting(
+        &self,
+        recipient: &Pubkey,
+        known_tokens: &[TokenInfo],
+        min_context_slot: Option<Slot>,
+    ) -> Result<BTreeMap<String, VestingBalance>, anyhow::Error> {
+        let config = RpcProgramAccountsConfig {
+            filters: Some(vec![RpcFilterType::Memcmp(Memcmp::new_base58_encoded(
+                RECIPIENT_OFFSET,
+                recipient.as_ref(),
+            ))]),
+            account_config: RpcAccountInfoConfig {
+                encoding: Some(UiAccountEncoding::Base64),
+                commitment: Some(self.client.commitment()),
+                min_context_slot,
+                ..RpcAccountInfoConfig::default()
+            },
+            ..RpcProgramAccountsConfig::default()
+        };
+        let accounts = {
+            let _permit = self.permit().await?;
+            self.client
+                .get_program_accounts_with_config(&STREAMFLOW_PROGRAM_ID, config)
+                .await?
+        };
+
+        let now = Utc::now().timestamp().max(0) as u64;
+        let mut totals: HashMap<Pubkey, (usize, u64, u64)> = HashMap::new();
+        for (_, account) in accounts {
+            let Some(stream) = Stream::parse(&account.data) else {
+                continue;
+            };
+            let vested = stream.vested(now);
+            let total = totals.entry(stream.mint).or_default();
+            total.0 += 1;
+            total.1 = total
+                .1
+                .saturating_add(stream.net_amount_deposited.saturating_sub(vested));
+            total.2 = total
+                .2
+                .saturating_add(vested.saturating_sub(stream.amount_withdrawn));
+        }
+        if totals.is_empty() {
+            return Ok(BTreeMap::new());
+        }
+
+        let mints: Vec<Pubkey> = totals.keys().copied().collect();
+        let decimals = self.get_mint_decimals(&mints).await?;
+        let labels = self.resolve_tickers(mints.iter(), known_tokens).await?;
+        let mut vesting = BTreeMap::new();
+        for (mint, (streams, locked, claimable)) in totals {
+            let decimals = decimals.get(&mint).copied().unwrap_or(0);
+            let ticker = labels
+                .get(&mint)
+                .map_or_else(|| mint.to_string(), |label| label.ticker.clone());
+            vesting.insert(
+                ticker,
+                VestingBalance {
+                    mint: mint.to_string(),
+                    streams,
+                    locked: ui_amount(locked, decimals),
+                    claimable: ui_amount(claimable, decimals),
+                    decimals,
+                },
+            );
+        }
+        Ok(vesting)
+    }
+}
@@ -0,0 +1,98 @@
+//! Replays a recorded `check` from `tests/fixtures/check`, so the whole
+//! fetch and output path runs without a network, through the binary and
+//! through the library.
+
+use serde_json::Value;
+use std::path::PathBuf;
+use std::process::{Command, Output};
+use test_solana::rpc::FixtureSender;
+use test_solana::{BalanceFetcher, TokenConfig};
+
+const WALLET: &str = "NSeNsegABZxxJF2fEmCpGGXu2kK3yTWcMsgyTLb2puP";
+
+fn fixtures() -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/check")
+}
+
+/// Runs `check` with `config` from the fixture directory in JSON.
+fn check(config: &str) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_test_solana"))
+        .arg("--config")
+        .arg(fixtures().join(config))
+        .arg("--fixture")
+        .arg(fixtures().join("rpc"))
+        .args(["--format", "json", "check"])
+        .output()
+        .expect("running test_solana")
+}
+
+#[test]
+fn replays_recorded_check() {
+    let output = check("config.yaml");
+    assert!(
+        output.status.success(),
+        "check failed: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    let report: Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(report["slot"], 300_000_000);
+    let wallet = &report["wallets"][WALLET];
+    assert_eq!(wallet["label"], "treasury");
+    assert_eq!(wallet["sol_lamports"], 1_234_567_890);
+
+    let tokens = &wallet["token_balances"];
+    // Only the associated token accounts are read: BONK's is funded, the
+    // others take their decimals from the mints.
+    assert_eq!(tokens["BONK"]["raw_amount"], 4_200_000);
+    assert_eq!(tokens["BONK"]["decimals"], 5);
+    assert_eq!(tokens["SPX6900"]["raw_amount"], 0);
+    assert_eq!(tokens["SPX6900"]["decimals"], 8);
+    assert_eq!(tokens["USDT"]["ui_amount_string"], "0");
+}
+
+#[test]
+fn scans_every_token_account() {
+    let output = check("scan.yaml");
+    assert!(
+        output.status.success(),
+        "check failed: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    let report: Value = serde_json::from_slice(&output.stdout).unwrap();
+    let tokens = &report["wallets"][WALLET]["token_balances"];
+    assert_eq!(tokens["BONK"]["raw_amount"], 123_456_789_012u64);
+    assert_eq!(
+        tokens["BONK"]["delegations"][0]["raw_amount"],
+        61_728_394_506u64
+    );
+    assert_eq!(tokens["SPX6900"]["raw_amount"], 123_456_789_012u64);
+    assert_eq!(tokens["SPX6900"]["decimals"], 8);
+    assert_eq!(tokens["USDT"]["raw_amount"], 0);
+}
+
+#[tokio::test]
+async fn fetches_through_a_custom_sender() {
+    let config = TokenConfig::from_file(fixtures().join("config.yaml")).unwrap();
+    let fetcher = BalanceFetcher::config_builder(&config)
+        .sender(Box::new(FixtureSender::new(fixtures().join("rpc"))))
+        .build();
+
+    let report = fetcher.fetch_report(&config).await.unwrap();
+    assert_eq!(report.slot, Some(300_000_000));
+    let wallet = &report.wallets[WALLET];
+    assert_eq!(wallet.sol_lamports, 1_234_567_890);
+    assert_eq!(wallet.token_balances["BONK"].raw_amount, 4_200_000);
+    assert_eq!(fetcher.rpc_calls().by_method()["getMultipleAccounts"], 2);
+}
+
+#[test]
+fn exits_2_below_a_recorded_threshold() {
+    let output = check("thresholds.yaml");
+    assert_eq!(output.status.code(), Some(2));
+    // The report is still written before the exit.
+    let report: Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(report["wallets"][WALLET]["sol_lamports"], 1_234_567_890);
+    assert!(String::from_utf8_lossy(&output.stderr).contains("warning:"));
+}